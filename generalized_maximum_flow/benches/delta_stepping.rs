@@ -0,0 +1,104 @@
+// the first calculate_distance_to_sink of RoundedPrimalDual on large random instances, by the dijkstra and by delta
+// stepping on thread pools of 1 up to all the cores, and whole solves with both
+// cargo bench -p generalized_maximum_flow --features rayon --bench delta_stepping [-- GROUP_FILTER]
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use generalized_maximum_flow::generators::random_graph_with_gains;
+use generalized_maximum_flow::graph::{Flow, ScalingGraph};
+use generalized_maximum_flow::instances::GraphInstance;
+use generalized_maximum_flow::RoundedPrimalDual;
+
+const EPSILON: Flow = 0.01;
+
+// gains of at least 0.99, with lower ones build drops most edges of graphs this large (see max_edge_dist)
+fn instances() -> Vec<(String, GraphInstance)> {
+    [(100_000, 1_000_000), (1_000_000, 10_000_000)]
+        .into_iter()
+        .map(|(num_nodes, num_edges)| {
+            (
+                format!("{}x{}", num_nodes, num_edges),
+                random_graph_with_gains(num_nodes, num_edges, 99..=100, 1),
+            )
+        })
+        .collect()
+}
+
+// 1, 2, 4, ... threads and all of them
+fn thread_counts() -> Vec<usize> {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut counts: Vec<usize> = std::iter::successors(Some(1), |&t| Some(t * 2))
+        .take_while(|&t| t < cores)
+        .collect();
+    counts.push(cores);
+    counts
+}
+
+fn scaling_graph(instance: &GraphInstance, min_edges: usize) -> ScalingGraph {
+    let mut graph = ScalingGraph::new(instance.num_nodes, EPSILON);
+    for &(from, to, capacity, gain) in instance.edges.iter() {
+        graph.add_directed_edge(from, to, capacity, gain).unwrap();
+    }
+    graph.build();
+    graph.delta_stepping_min_edges = min_edges;
+    graph
+}
+
+fn bench_distance_to_sink(c: &mut Criterion) {
+    let mut group = c.benchmark_group("distance_to_sink");
+    group.sample_size(10);
+    for (name, instance) in instances().iter() {
+        group.bench_with_input(
+            BenchmarkId::new("dijkstra", name),
+            instance,
+            |b, instance| {
+                b.iter_batched(
+                    || scaling_graph(instance, usize::MAX),
+                    |mut graph| graph.calculate_distance_to_sink(instance.sink),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        for threads in thread_counts() {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            group.bench_with_input(
+                BenchmarkId::new(format!("delta_stepping_{}_threads", threads), name),
+                instance,
+                |b, instance| {
+                    b.iter_batched(
+                        || scaling_graph(instance, 0),
+                        |mut graph| {
+                            pool.install(|| graph.calculate_distance_to_sink(instance.sink))
+                        },
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_solve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+    let (name, instance) = &instances()[0];
+    for (label, min_edges) in [("dijkstra", usize::MAX), ("delta_stepping", 0)] {
+        group.bench_with_input(BenchmarkId::new(label, name), instance, |b, instance| {
+            b.iter_batched(
+                || {
+                    let mut solver: RoundedPrimalDual = instance.build_solver(EPSILON);
+                    solver.graph.delta_stepping_min_edges = min_edges;
+                    solver
+                },
+                |mut solver| solver.solve(instance.source, instance.sink),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_distance_to_sink, bench_solve);
+criterion_main!(benches);
@@ -0,0 +1,243 @@
+// compares HighestGainPathMethod, alone and with blocking_flow, with RoundedPrimalDual on seeded random instances
+// over node count, edges per node, gain range and epsilon: first the rounds, shortest path computations and
+// augmentations of each, then the time, also of GainScalingPushRelabel (see the solvers bench for its pushes and
+// relabels), and the same for chain, where HighestGainPathMethod sends along one path per round, and last build and
+// RoundedPrimalDual on an instance of 10^6 edges
+// cargo bench -p generalized_maximum_flow --bench generated [-- GROUP_FILTER]
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use generalized_maximum_flow::generators::{random_graph_with_gains, Rng};
+use generalized_maximum_flow::graph::{Flow, ScalingGraph, SolveStats};
+use generalized_maximum_flow::instances::GraphInstance;
+use generalized_maximum_flow::{GainScalingPushRelabel, HighestGainPathMethod, RoundedPrimalDual};
+
+const SEED: u64 = 1;
+
+// (name, gains in hundredths), the mixed instances only get edges from lower to higher ids, a cycle of gains above 1
+// would make HighestGainPathMethod fail
+const GAINS: [(&str, u64, u64); 2] = [("lossy", 50, 100), ("mixed", 50, 150)];
+
+fn random(num_nodes: usize, density: usize, gains: (&str, u64, u64)) -> GraphInstance {
+    let (name, low, high) = gains;
+    let mut instance = random_graph_with_gains(num_nodes, num_nodes * density, low..=high, SEED);
+    if name == "mixed" {
+        for (from, to, _, _) in instance.edges.iter_mut() {
+            if from > to {
+                std::mem::swap(from, to);
+            }
+        }
+    }
+    instance
+}
+
+// width chains of length edges of gain 0.99 between source and sink, each node also linked to the next node of the
+// neighbouring chain, so that a huge number of paths have nearly the same gain
+fn chain(length: usize, width: usize) -> GraphInstance {
+    let mut rng = Rng::new(SEED);
+    let node = |i: usize, j: usize| {
+        if i == 0 {
+            0
+        } else if i == length {
+            1
+        } else {
+            2 + (i - 1) * width + j
+        }
+    };
+    let mut edges = Vec::new();
+    for i in 0..length {
+        for j in 0..width {
+            edges.push((
+                node(i, j),
+                node(i + 1, j),
+                rng.gen_range(1, 101) as Flow,
+                0.99,
+            ));
+            edges.push((
+                node(i, j),
+                node(i + 1, (j + 1) % width),
+                rng.gen_range(1, 101) as Flow,
+                0.99,
+            ));
+        }
+    }
+    GraphInstance {
+        num_nodes: 2 + (length - 1) * width,
+        edges,
+        source: 0,
+        sink: 1,
+    }
+}
+
+fn instances() -> Vec<(String, GraphInstance)> {
+    let mut instances = Vec::new();
+    for num_nodes in [100, 1000] {
+        for density in [8, 32] {
+            for gains in GAINS {
+                instances.push((
+                    format!("{}_{}x{}", gains.0, num_nodes, density),
+                    random(num_nodes, density, gains),
+                ));
+            }
+        }
+    }
+    instances
+}
+
+fn print_stats(name: &str, epsilon: Flow, solver: &str, value: Flow, stats: &SolveStats) {
+    let augmentations = stats
+        .augmentations
+        .map_or("-".to_string(), |a| a.to_string());
+    println!(
+        "{:<18} {:>6} {:<26} {:>14.4} {:>8} {:>10} {:>14}",
+        name, epsilon, solver, value, stats.rounds, stats.dijkstras, augmentations
+    );
+}
+
+fn blocking_flow(instance: &GraphInstance, epsilon: Flow) -> HighestGainPathMethod {
+    let mut solver = instance.build_solver::<HighestGainPathMethod>(epsilon);
+    solver.blocking_flow = true;
+    solver
+}
+
+fn print_iterations(instances: &[(String, GraphInstance)], epsilons: &[Flow]) {
+    println!(
+        "{:<18} {:>6} {:<26} {:>14} {:>8} {:>10} {:>14}",
+        "instance", "eps", "solver", "value", "rounds", "dijkstras", "augmentations"
+    );
+    for (name, instance) in instances.iter() {
+        for &epsilon in epsilons {
+            let mut highest_gain = instance.build_solver::<HighestGainPathMethod>(epsilon);
+            let value = highest_gain.solve(instance.source, instance.sink);
+            print_stats(
+                name,
+                epsilon,
+                "highest_gain_path_method",
+                value,
+                highest_gain.stats(),
+            );
+            let mut blocking = blocking_flow(instance, epsilon);
+            let value = blocking.solve(instance.source, instance.sink);
+            print_stats(
+                name,
+                epsilon,
+                "highest_gain_blocking_flow",
+                value,
+                blocking.stats(),
+            );
+            let mut primal_dual = instance.build_solver::<RoundedPrimalDual>(epsilon);
+            let value = primal_dual.solve(instance.source, instance.sink);
+            print_stats(
+                name,
+                epsilon,
+                "rounded_primal_dual",
+                value,
+                primal_dual.stats(),
+            );
+        }
+    }
+}
+
+fn bench_group(
+    c: &mut Criterion,
+    group_name: &str,
+    instances: &[(String, GraphInstance)],
+    epsilons: &[Flow],
+) {
+    print_iterations(instances, epsilons);
+    let mut group = c.benchmark_group(group_name);
+    group.sample_size(10);
+    for (name, instance) in instances.iter() {
+        for &epsilon in epsilons {
+            let id = format!("{}_eps{}", name, epsilon);
+            group.bench_with_input(
+                BenchmarkId::new("highest_gain_path_method", &id),
+                instance,
+                |b, instance| {
+                    b.iter_batched(
+                        || instance.build_solver::<HighestGainPathMethod>(epsilon),
+                        |mut solver| solver.solve(instance.source, instance.sink),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new("highest_gain_blocking_flow", &id),
+                instance,
+                |b, instance| {
+                    b.iter_batched(
+                        || blocking_flow(instance, epsilon),
+                        |mut solver| solver.solve(instance.source, instance.sink),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new("rounded_primal_dual", &id),
+                instance,
+                |b, instance| {
+                    b.iter_batched(
+                        || instance.build_solver::<RoundedPrimalDual>(epsilon),
+                        |mut solver| solver.solve(instance.source, instance.sink),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new("gain_scaling_push_relabel", &id),
+                instance,
+                |b, instance| {
+                    b.iter_batched(
+                        || instance.build_solver::<GainScalingPushRelabel>(epsilon),
+                        |mut solver| solver.solve(instance.source, instance.sink),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn bench_random(c: &mut Criterion) {
+    bench_group(c, "random", &instances(), &[0.1, 0.01]);
+}
+
+fn bench_chain(c: &mut Criterion) {
+    bench_group(
+        c,
+        "chain",
+        &[("chain_100x10".to_string(), chain(100, 10))],
+        &[0.01],
+    );
+}
+
+// the labels and the gains of the edges are powers of the base: rescale rounds every gain and tabulates the powers,
+// which the dijkstras then look up
+fn bench_large(c: &mut Criterion) {
+    let instance = random_graph_with_gains(100_000, 1_000_000, 50..=100, SEED);
+    let mut group = c.benchmark_group("large");
+    group.sample_size(10);
+    group.bench_function("build", |b| {
+        b.iter_batched(
+            || {
+                let mut graph = ScalingGraph::new(instance.num_nodes, 0.01);
+                for &(from, to, capacity, gain) in instance.edges.iter() {
+                    graph.add_directed_edge(from, to, capacity, gain).unwrap();
+                }
+                graph
+            },
+            |mut graph| graph.build(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("rounded_primal_dual", |b| {
+        b.iter_batched(
+            || instance.build_solver::<RoundedPrimalDual>(0.01),
+            |mut solver| solver.solve(instance.source, instance.sink),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_random, bench_chain, bench_large);
+criterion_main!(benches);
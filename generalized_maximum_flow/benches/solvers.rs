@@ -0,0 +1,252 @@
+// compares RoundedPrimalDual with GainScalingPushRelabel: first the iteration counts of both on each
+// instance (shortest path computations and augmentations against pushes and relabels), then the time
+// and the label correcting of calculate_distance_to_sink_with_negative_edge with and without queue_heuristics,
+// and RoundedPrimalDual with the heap and with the buckets of bucket_queue
+// cargo bench -p generalized_maximum_flow --bench solvers [-- GROUP_FILTER]
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use generalized_maximum_flow::graph::{Flow, ScalingGraph};
+use generalized_maximum_flow::instances::{read_graph_instance, GraphInstance};
+use generalized_maximum_flow::test_utilities::shortcut_chain;
+use generalized_maximum_flow::{GainScalingPushRelabel, RoundedPrimalDual};
+use std::path::PathBuf;
+
+const EPSILON: Flow = 0.01;
+
+// layers of width nodes between a source and a sink, every node linked to 3 random nodes of the next
+// layer, so that many paths of similar gain compete, the gains are in [1 - loss / 2, 1)
+// with much more nodes, build drops the edges of the lowest gains (see max_edge_dist)
+fn layered(layers: usize, width: usize, seed: u64, loss: Flow) -> GraphInstance {
+    let mut state = seed;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+    let num_nodes = layers * width + 2;
+    let (source, sink) = (num_nodes - 2, num_nodes - 1);
+    let mut edges = Vec::new();
+    for u in 0..width {
+        edges.push((source, u, 1000.0, 1.0));
+        edges.push(((layers - 1) * width + u, sink, 1000.0, 1.0));
+    }
+    for layer in 0..layers - 1 {
+        for u in 0..width {
+            for _ in 0..3 {
+                let v = (layer + 1) * width + next() % width;
+                let capacity = (1 + next() % 100) as Flow;
+                let gain = 1.0 - loss * (500 - next() % 500) as Flow / 1000.0;
+                edges.push((layer * width + u, v, capacity, gain));
+            }
+        }
+    }
+    GraphInstance {
+        num_nodes,
+        edges,
+        source,
+        sink,
+    }
+}
+
+fn instances() -> Vec<(String, GraphInstance)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test_cases/gain_random");
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == "in"))
+        .collect();
+    paths.sort();
+    let mut instances: Vec<(String, GraphInstance)> = paths
+        .iter()
+        .map(|p| {
+            (
+                p.file_stem().unwrap().to_string_lossy().into_owned(),
+                read_graph_instance(p).unwrap(),
+            )
+        })
+        .collect();
+    instances.push(("layered_10x10".to_string(), layered(10, 10, 1, 1.0)));
+    instances.push(("layered_20x20".to_string(), layered(20, 20, 1, 1.0)));
+    instances.push(("layered_30x30".to_string(), layered(30, 30, 1, 1.0)));
+    instances
+}
+
+fn print_iterations(instances: &[(String, GraphInstance)]) {
+    println!(
+        "{:<16} {:>12} {:>8} {:>14} {:>10} {:>8} {:>8}",
+        "instance", "value", "phases", "augmentations", "pushes", "relabels", "global"
+    );
+    for (name, instance) in instances.iter() {
+        let mut primal_dual = instance.build_solver::<RoundedPrimalDual>(EPSILON);
+        let value = primal_dual.solve(instance.source, instance.sink);
+        let mut push_relabel = instance.build_solver::<GainScalingPushRelabel>(EPSILON);
+        push_relabel.solve(instance.source, instance.sink);
+        println!(
+            "{:<16} {:>12.4} {:>8} {:>14} {:>10} {:>8} {:>8}",
+            name,
+            value,
+            primal_dual.phases(),
+            primal_dual.augmentations(),
+            push_relabel.pushes(),
+            push_relabel.relabels(),
+            push_relabel.global_relabels()
+        );
+    }
+}
+
+fn bench(c: &mut Criterion) {
+    let instances = instances();
+    print_iterations(&instances);
+
+    let mut group = c.benchmark_group("generalized");
+    group.sample_size(10);
+    for (name, instance) in instances
+        .iter()
+        .filter(|(name, _)| name.starts_with("layered"))
+    {
+        group.bench_with_input(
+            BenchmarkId::new("rounded_primal_dual", name),
+            instance,
+            |b, instance| {
+                b.iter_batched(
+                    || instance.build_solver::<RoundedPrimalDual>(EPSILON),
+                    |mut solver| solver.solve(instance.source, instance.sink),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("gain_scaling_push_relabel", name),
+            instance,
+            |b, instance| {
+                b.iter_batched(
+                    || instance.build_solver::<GainScalingPushRelabel>(EPSILON),
+                    |mut solver| solver.solve(instance.source, instance.sink),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+// built, with the potentials 0 like for a network with a flow generating cycle
+fn scaling_graph(instance: &GraphInstance, queue_heuristics: bool) -> ScalingGraph {
+    let mut graph = ScalingGraph::new(instance.num_nodes, EPSILON);
+    for &(from, to, capacity, gain) in instance.edges.iter() {
+        graph.add_directed_edge(from, to, capacity, gain).unwrap();
+    }
+    graph.build();
+    graph.clear_potentials();
+    graph.queue_heuristics = queue_heuristics;
+    graph
+}
+
+fn bench_negative_edge(c: &mut Criterion) {
+    let instances: Vec<(String, GraphInstance)> = [300, 1000]
+        .iter()
+        .map(|&n| (format!("shortcut_chain_{}", n), shortcut_chain(n)))
+        .collect();
+    println!("{:<20} {:>12} {:>12}", "instance", "fifo", "heuristics");
+    for (name, instance) in instances.iter() {
+        let relaxations = [false, true].map(|queue_heuristics| {
+            let mut graph = scaling_graph(instance, queue_heuristics);
+            graph.calculate_distance_to_sink_with_negative_edge(instance.sink);
+            graph.relaxations()
+        });
+        println!("{:<20} {:>12} {:>12}", name, relaxations[0], relaxations[1]);
+    }
+
+    let mut group = c.benchmark_group("negative_edge");
+    group.sample_size(10);
+    for (name, instance) in instances.iter() {
+        for (label, queue_heuristics) in [("fifo", false), ("heuristics", true)] {
+            group.bench_with_input(BenchmarkId::new(label, name), instance, |b, instance| {
+                b.iter_batched(
+                    || scaling_graph(instance, queue_heuristics),
+                    |mut graph| graph.calculate_distance_to_sink_with_negative_edge(instance.sink),
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    group.finish();
+}
+
+// the gain_random suite in one iteration (each instance is too small to time alone), and the layered instances
+// one by one, the last nearly lossless: the dist of an edge is about num_nodes * (1 - gain) / epsilon, the
+// buckets only span distances of the order of num_nodes
+fn bench_bucket_queue(c: &mut Criterion) {
+    let (mut layered_instances, gain_random): (Vec<_>, Vec<_>) = instances()
+        .into_iter()
+        .partition(|(name, _)| name.starts_with("layered"));
+    layered_instances.push((
+        "layered_30x30_lossless".to_string(),
+        layered(30, 30, 1, 0.001),
+    ));
+    let mut suites: Vec<(String, Vec<GraphInstance>)> = vec![(
+        "gain_random".to_string(),
+        gain_random
+            .into_iter()
+            .map(|(_, instance)| instance)
+            .collect(),
+    )];
+    suites.extend(
+        layered_instances
+            .into_iter()
+            .map(|(name, instance)| (name, vec![instance])),
+    );
+
+    let mut group = c.benchmark_group("bucket_queue");
+    group.sample_size(10);
+    for (name, instances) in suites.iter() {
+        for (label, bucket_queue) in [("heap", false), ("buckets", true)] {
+            group.bench_with_input(BenchmarkId::new(label, name), instances, |b, instances| {
+                b.iter_batched(
+                    || {
+                        let mut solvers: Vec<RoundedPrimalDual> = instances
+                            .iter()
+                            .map(|instance| instance.build_solver(EPSILON))
+                            .collect();
+                        solvers
+                            .iter_mut()
+                            .for_each(|solver| solver.graph.bucket_queue = bucket_queue);
+                        solvers
+                    },
+                    |mut solvers| {
+                        solvers
+                            .iter_mut()
+                            .zip(instances.iter())
+                            .map(|(solver, instance)| solver.solve(instance.source, instance.sink))
+                            .sum::<Flow>()
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+    // the first shortest path tree alone
+    for (name, instances) in suites.iter().skip(1) {
+        for (label, bucket_queue) in [("heap", false), ("buckets", true)] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("distance_to_sink_{}", label), name),
+                &instances[0],
+                |b, instance| {
+                    b.iter_batched(
+                        || {
+                            let mut graph = scaling_graph(instance, true);
+                            graph.bucket_queue = bucket_queue;
+                            graph
+                        },
+                        |mut graph| graph.calculate_distance_to_sink(instance.sink),
+                        BatchSize::LargeInput,
+                    )
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench, bench_negative_edge, bench_bucket_queue);
+criterion_main!(benches);
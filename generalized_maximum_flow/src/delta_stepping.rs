@@ -0,0 +1,169 @@
+// update_distance_to_sink by delta stepping: each bucket holds the nodes of DELTA consecutive distances, the arcs of
+// the nodes of the first one are relaxed on the threads of rayon, lowering the distances with fetch_min, until no node
+// of its distances is left to relax, then its nodes have their distances and the next bucket starts
+// the shortest distances do not depend on the order of the relaxations, and the distances to the sink and the new
+// potentials follow from them, so they are those of the dijkstra
+use crate::graph::{refill, Dist, ScalingGraph, DIST_MAX};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+// the distances of a bucket, the dists of the edges reduced by the potentials are small
+const DELTA: Dist = 32;
+
+impl ScalingGraph {
+    pub(crate) fn update_distance_to_sink_delta_stepping(&mut self, sink: usize) {
+        let tentative = self.delta_stepping(sink);
+        let mut distance = std::mem::take(&mut self.distance);
+        let mut distance_to_sink = std::mem::take(&mut self.distance_to_sink);
+        let mut visited = std::mem::take(&mut self.visited);
+        distance.clear();
+        distance.par_extend(tentative.into_par_iter().map(AtomicI32::into_inner));
+        // along the path of the dijkstra, distance_to_sink - distance - potential stays that of the sink
+        let potential_of_sink = self.potentials[sink];
+        refill(&mut distance_to_sink, self.num_nodes, DIST_MAX);
+        distance_to_sink
+            .par_iter_mut()
+            .zip(distance.par_iter().zip(self.potentials.par_iter()))
+            .for_each(|(to_sink, (&d, &p))| {
+                if d != DIST_MAX {
+                    *to_sink = d + p - potential_of_sink;
+                }
+            });
+        refill(&mut visited, self.num_nodes, false);
+        visited
+            .par_iter_mut()
+            .zip(distance.par_iter())
+            .for_each(|(v, &d)| *v = d != DIST_MAX);
+
+        // update potentials
+        let farthest = distance
+            .par_iter()
+            .copied()
+            .filter(|&d| d != DIST_MAX)
+            .max()
+            .unwrap_or(0);
+        self.potentials
+            .par_iter_mut()
+            .zip(distance.par_iter())
+            .for_each(|(p, &d)| *p += d.min(farthest));
+        self.distance = distance;
+        self.distance_to_sink = distance_to_sink;
+        self.visited = visited;
+    }
+
+    // the distances to the sink, DIST_MAX where it is not reached
+    fn delta_stepping(&self, sink: usize) -> Vec<AtomicI32> {
+        let distance: Vec<AtomicI32> = (0..self.num_nodes)
+            .map(|_| AtomicI32::new(DIST_MAX))
+            .collect();
+        distance[sink].store(0, Ordering::Relaxed);
+        let tentative = &distance;
+        let load = |u: usize| tentative[u].load(Ordering::Relaxed);
+
+        // the arcs of update_distance_to_sink into u, the nodes whose distance they lowered
+        let relax = |u: usize| {
+            let d = load(u);
+            (self.start[u]..self.start[u + 1]).filter_map(move |i| {
+                let e = &self.inside_edge_list[i];
+                if self.inside_edge_list[e.rev].residual_capacity() <= 0.0 {
+                    return None;
+                }
+                let dist = -e.dist - self.potentials[e.to] + self.potentials[u];
+                assert!(dist >= 0);
+                let new_dist = d + dist;
+                (new_dist < tentative[e.to].fetch_min(new_dist, Ordering::Relaxed)).then_some(e.to)
+            })
+        };
+
+        // a node is left in the buckets it was in before its distance decreased, and comes once per decrease
+        let mut buckets: BTreeMap<Dist, Vec<usize>> = BTreeMap::from([(0, vec![sink])]);
+        while let Some((b, mut nodes)) = buckets.pop_first() {
+            loop {
+                nodes.retain(|&u| load(u) / DELTA == b);
+                nodes.sort_unstable();
+                nodes.dedup();
+                if nodes.is_empty() {
+                    break;
+                }
+                let lowered: Vec<usize> = nodes.par_iter().flat_map_iter(|&u| relax(u)).collect();
+                nodes.clear();
+                for v in lowered {
+                    let c = load(v) / DELTA;
+                    if c == b {
+                        nodes.push(v);
+                    } else {
+                        buckets.entry(c).or_default().push(v);
+                    }
+                }
+            }
+        }
+        distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generators::random_graph_with_gains;
+    use crate::graph::{Dist, ScalingGraph};
+    use crate::instances::{read_graph_instance, GraphInstance};
+    use crate::rounded_primal_dual::RoundedPrimalDual;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    // (distances to the sink, potentials, visited) of the first calculate_distance_to_sink after build
+    fn distances(instance: &GraphInstance, min_edges: usize) -> (Vec<Dist>, Vec<Dist>, Vec<bool>) {
+        let mut graph = ScalingGraph::new(instance.num_nodes, 0.01);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            graph.add_directed_edge(from, to, capacity, gain).unwrap();
+        }
+        graph.build();
+        graph.delta_stepping_min_edges = min_edges;
+        let distance_to_sink = graph.calculate_distance_to_sink(instance.sink);
+        (distance_to_sink, graph.potentials, graph.visited)
+    }
+
+    // every round of a solve, through the flows and potentials it leaves
+    fn assert_same_solve(instance: &GraphInstance, epsilon: f64) {
+        let mut dijkstra: RoundedPrimalDual = instance.build_solver(epsilon);
+        dijkstra.graph.delta_stepping_min_edges = usize::MAX;
+        let mut delta_stepping: RoundedPrimalDual = instance.build_solver(epsilon);
+        delta_stepping.graph.delta_stepping_min_edges = 0;
+        let value = dijkstra.solve(instance.source, instance.sink);
+        assert_eq!(
+            delta_stepping
+                .solve(instance.source, instance.sink)
+                .to_bits(),
+            value.to_bits()
+        );
+        assert_eq!(delta_stepping.stats(), dijkstra.stats());
+        assert_eq!(delta_stepping.graph.potentials, dijkstra.graph.potentials);
+        for i in 0..instance.edges.len() {
+            let (expected, actual) = (dijkstra.get_flow(i), delta_stepping.get_flow(i));
+            assert_eq!(
+                (actual.0.to_bits(), actual.1.to_bits()),
+                (expected.0.to_bits(), expected.1.to_bits()),
+                "edge {}",
+                i
+            );
+        }
+    }
+
+    #[rstest]
+    fn same_as_dijkstra(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        let instance = read_graph_instance(&path).unwrap();
+        assert_eq!(distances(&instance, 0), distances(&instance, usize::MAX));
+        assert_same_solve(&instance, 0.01);
+    }
+
+    #[test]
+    fn same_as_dijkstra_random() {
+        for seed in 0..4 {
+            let instance = random_graph_with_gains(2000, 16000, 50..=100, seed);
+            assert_eq!(distances(&instance, 0), distances(&instance, usize::MAX));
+            for epsilon in [0.1, 0.001] {
+                assert_same_solve(&instance, epsilon);
+            }
+        }
+    }
+}
@@ -0,0 +1,627 @@
+use crate::graph::{cycle_along, normalize_cycle, EdgeError, GeneralizedFlowError};
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+// capacity is what may enter the edge, flow what enters it
+// edge i ^ 1 is the reverse of edge i, with gain 1 / gain and capacity capacity * gain
+#[derive(Debug, Clone)]
+pub struct ExactEdge {
+    pub from: usize,
+    pub to: usize,
+    pub flow: BigRational,
+    pub capacity: BigRational,
+    pub gain: BigRational,
+}
+
+impl ExactEdge {
+    pub fn residual_capacity(&self) -> BigRational {
+        &self.capacity - &self.flow
+    }
+}
+
+// the augmenting highest gain path method without rounding: the labels are exact gain products, so the
+// answer is the optimum itself, e.g. to validate the approximate solvers on small instances
+// the number of augmentations is not polynomial, and the rationals grow with every one
+pub struct ExactHighestGainPath {
+    pub num_nodes: usize,
+    pub num_edges: usize,
+    // forward edges at even, reverse edges at odd positions
+    pub edge_list: Vec<ExactEdge>,
+    pub excesses: Vec<BigRational>,
+}
+
+#[allow(dead_code)]
+impl ExactHighestGainPath {
+    pub fn new(num_nodes: usize) -> Self {
+        ExactHighestGainPath {
+            num_nodes,
+            num_edges: 0,
+            edge_list: Vec::new(),
+            excesses: Vec::new(),
+        }
+    }
+
+    pub fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: BigRational,
+        gain: BigRational,
+    ) -> Result<usize, EdgeError> {
+        if !capacity.is_positive() {
+            return Err(EdgeError::NonPositiveCapacity);
+        }
+        if !gain.is_positive() {
+            return Err(EdgeError::NonPositiveGain);
+        }
+
+        self.num_nodes = self.num_nodes.max(from.max(to) + 1);
+        let rev_capacity = &capacity * &gain;
+        let rev_gain = gain.recip();
+        self.edge_list.push(ExactEdge {
+            from,
+            to,
+            flow: BigRational::zero(),
+            capacity,
+            gain,
+        });
+        self.edge_list.push(ExactEdge {
+            from: to,
+            to: from,
+            flow: rev_capacity.clone(),
+            capacity: rev_capacity,
+            gain: rev_gain,
+        });
+
+        self.num_edges += 1;
+        Ok(self.num_edges - 1)
+    }
+
+    // (flow entering, flow leaving) the edge
+    pub fn get_flow(&self, edge_index: usize) -> (BigRational, BigRational) {
+        let edge = &self.edge_list[2 * edge_index];
+        (edge.flow.clone(), &edge.flow * &edge.gain)
+    }
+
+    // 0 if try_solve fails
+    pub fn solve(&mut self, source: usize, sink: usize) -> BigRational {
+        self.try_solve(source, sink)
+            .unwrap_or_else(|_| BigRational::zero())
+    }
+
+    pub fn try_solve(
+        &mut self,
+        source: usize,
+        sink: usize,
+    ) -> Result<BigRational, GeneralizedFlowError> {
+        if self.num_edges == 0 {
+            return Err(GeneralizedFlowError::EmptyGraph);
+        }
+        for u in [source, sink] {
+            if u >= self.num_nodes {
+                return Err(GeneralizedFlowError::InvalidTerminal(u));
+            }
+        }
+        if source == sink {
+            return Err(GeneralizedFlowError::SourceEqualsSink);
+        }
+
+        // start without flow
+        for edge in self.edge_list.iter_mut().skip(1).step_by(2) {
+            edge.flow = edge.capacity.clone();
+        }
+        for edge in self.edge_list.iter_mut().step_by(2) {
+            edge.flow = BigRational::zero();
+        }
+        self.excesses = vec![BigRational::zero(); self.num_nodes];
+
+        while let Some(path) = self.find_highest_gain_path(source, sink)? {
+            self.augment(&path, sink);
+        }
+        Ok(self.excesses[sink].clone())
+    }
+
+    // bellman-ford on the gain products from the source over the residual edges
+    fn find_highest_gain_path(
+        &self,
+        source: usize,
+        sink: usize,
+    ) -> Result<Option<Vec<usize>>, GeneralizedFlowError> {
+        let mut labels: Vec<Option<BigRational>> = vec![None; self.num_nodes];
+        let mut prev = vec![usize::MAX; self.num_nodes];
+        labels[source] = Some(BigRational::one());
+
+        let mut relaxed = Some(source);
+        let mut rounds = 0;
+        while let Some(u) = relaxed {
+            if rounds == self.num_nodes {
+                // the prevs from a node relaxed in the last round run into the cycle, against the flow
+                let cycle = cycle_along(u, self.num_nodes, |v| {
+                    (prev[v] != usize::MAX).then(|| self.edge_list[prev[v]].from)
+                });
+                return Err(GeneralizedFlowError::FlowGeneratingCycle(
+                    cycle
+                        .map(|mut cycle| {
+                            cycle.reverse();
+                            normalize_cycle(cycle)
+                        })
+                        .unwrap_or_default(),
+                ));
+            }
+            relaxed = None;
+            for (i, e) in self.edge_list.iter().enumerate() {
+                let Some(label) = &labels[e.from] else {
+                    continue;
+                };
+                if !e.residual_capacity().is_positive() {
+                    continue;
+                }
+                let candidate = label * &e.gain;
+                if labels[e.to].as_ref().is_none_or(|l| candidate > *l) {
+                    labels[e.to] = Some(candidate);
+                    prev[e.to] = i;
+                    relaxed = Some(e.to);
+                }
+            }
+            rounds += 1;
+        }
+
+        if labels[sink].is_none() {
+            return Ok(None);
+        }
+        let mut path = Vec::new();
+        let mut v = sink;
+        while v != source {
+            path.push(prev[v]);
+            v = self.edge_list[prev[v]].from;
+        }
+        path.reverse();
+        Ok(Some(path))
+    }
+
+    // sends as much as the path allows, measured at the source
+    fn augment(&mut self, path: &[usize], sink: usize) {
+        // multipliers[k]: flow entering path[k] per unit leaving the source
+        let mut multipliers = vec![BigRational::one()];
+        for &i in path {
+            let next = multipliers.last().unwrap() * &self.edge_list[i].gain;
+            multipliers.push(next);
+        }
+        let delta = path
+            .iter()
+            .zip(multipliers.iter())
+            .map(|(&i, m)| self.edge_list[i].residual_capacity() / m)
+            .min()
+            .unwrap();
+
+        for (&i, m) in path.iter().zip(multipliers.iter()) {
+            let sent = &delta * m;
+            let received = &sent * &self.edge_list[i].gain;
+            self.edge_list[i].flow += sent;
+            self.edge_list[i ^ 1].flow -= received;
+        }
+        self.excesses[sink] += &delta * multipliers.last().unwrap();
+    }
+}
+
+// the flows x entering the edges (from, to, lower, capacity, gain) above their lower bounds, the slacks of the
+// capacities and the delivered amount d: x + slack = capacity - lower, and what arrives at each node other than the
+// source leaves it again, or reaches the sink as d
+fn flow_rows(
+    num_nodes: usize,
+    edges: &[(usize, usize, &BigRational, &BigRational, &BigRational)],
+    source: usize,
+    sink: usize,
+) -> (Vec<Vec<BigRational>>, Vec<BigRational>) {
+    let m = edges.len();
+    let d = 2 * m;
+    let mut rows = Vec::new();
+    let mut rhs = Vec::new();
+    for (i, &(_, _, lower, capacity, _)) in edges.iter().enumerate() {
+        let mut row = vec![BigRational::zero(); d + 1];
+        row[i] = BigRational::one();
+        row[m + i] = BigRational::one();
+        rows.push(row);
+        rhs.push(capacity - lower);
+    }
+    for v in (0..num_nodes).filter(|&v| v != source) {
+        let mut row = vec![BigRational::zero(); d + 1];
+        let mut b = BigRational::zero();
+        for (i, &(from, to, lower, _, gain)) in edges.iter().enumerate() {
+            if to == v {
+                row[i] += gain;
+                b -= gain * lower;
+            }
+            if from == v {
+                row[i] -= BigRational::one();
+                b += lower;
+            }
+        }
+        if v == sink {
+            row[d] = -BigRational::one();
+        }
+        rows.push(row);
+        rhs.push(b);
+    }
+    (rows, rhs)
+}
+
+// the maximum flow from a source of unlimited supply over the edges (from, to, capacity, gain), as a linear
+// program, e.g. as the oracle the approximate solvers are validated against
+pub fn generalized_max_flow_lp(
+    num_nodes: usize,
+    edges: &[(usize, usize, BigRational, BigRational)],
+    source: usize,
+    sink: usize,
+) -> BigRational {
+    let zero = BigRational::zero();
+    let edges: Vec<_> = edges
+        .iter()
+        .map(|(from, to, capacity, gain)| (*from, *to, &zero, capacity, gain))
+        .collect();
+    max_flow_lp(num_nodes, &edges, source, sink).expect("no flow is feasible")
+}
+
+// generalized_max_flow_lp over the edges (from, to, lower, capacity, gain), each carrying at least lower, None if no
+// flow meets the lower bounds, e.g. to validate the lower bounds of RoundedPrimalDual on small instances
+pub fn generalized_max_flow_lp_with_lower_bounds(
+    num_nodes: usize,
+    edges: &[(usize, usize, BigRational, BigRational, BigRational)],
+    source: usize,
+    sink: usize,
+) -> Option<BigRational> {
+    let edges: Vec<_> = edges
+        .iter()
+        .map(|(from, to, lower, capacity, gain)| (*from, *to, lower, capacity, gain))
+        .collect();
+    max_flow_lp(num_nodes, &edges, source, sink)
+}
+
+fn max_flow_lp(
+    num_nodes: usize,
+    edges: &[(usize, usize, &BigRational, &BigRational, &BigRational)],
+    source: usize,
+    sink: usize,
+) -> Option<BigRational> {
+    let (rows, rhs) = flow_rows(num_nodes, edges, source, sink);
+    let d = 2 * edges.len();
+    let mut most = vec![BigRational::zero(); d + 1];
+    most[d] = -BigRational::one();
+    simplex(rows, rhs, most).map(|(maximum, _)| -maximum)
+}
+
+// the cheapest flow that delivers target at the sink, or as much as reaches it, from a source of unlimited
+// supply, as a linear program over the flows entering the edges (from, to, capacity, gain, cost)
+// returns (delivered, cost), e.g. to validate MinCostGeneralizedFlow on small instances
+pub fn min_cost_generalized_flow_lp(
+    num_nodes: usize,
+    edges: &[(usize, usize, BigRational, BigRational, BigRational)],
+    source: usize,
+    sink: usize,
+    target: &BigRational,
+) -> (BigRational, BigRational) {
+    let zero = BigRational::zero();
+    let flow_edges: Vec<_> = edges
+        .iter()
+        .map(|(from, to, capacity, gain, _)| (*from, *to, &zero, capacity, gain))
+        .collect();
+    let (mut rows, mut rhs) = flow_rows(num_nodes, &flow_edges, source, sink);
+    let d = 2 * edges.len();
+
+    let mut most = vec![BigRational::zero(); d + 1];
+    most[d] = -BigRational::one();
+    let (maximum, _) = simplex(rows.clone(), rhs.clone(), most).expect("no flow is feasible");
+    let delivered = target.clone().min(-maximum);
+
+    let mut row = vec![BigRational::zero(); d + 1];
+    row[d] = BigRational::one();
+    rows.push(row);
+    rhs.push(delivered.clone());
+    let mut costs = vec![BigRational::zero(); d + 1];
+    for (i, (_, _, _, _, cost)) in edges.iter().enumerate() {
+        costs[i] = cost.clone();
+    }
+    let (cost, _) = simplex(rows, rhs, costs).expect("delivering at most the maximum is feasible");
+    (delivered, cost)
+}
+
+// minimizes costs * x subject to rows * x = rhs and x >= 0, two phases on a dense tableau with the rule of
+// bland against cycling, (value, x) or None if infeasible or unbounded
+fn simplex(
+    mut rows: Vec<Vec<BigRational>>,
+    mut rhs: Vec<BigRational>,
+    costs: Vec<BigRational>,
+) -> Option<(BigRational, Vec<BigRational>)> {
+    let (m, n) = (rows.len(), costs.len());
+    for (row, b) in rows.iter_mut().zip(rhs.iter_mut()) {
+        if b.is_negative() {
+            row.iter_mut().for_each(|a| *a = -a.clone());
+            *b = -b.clone();
+        }
+    }
+
+    // the columns of the variables, of one artificial variable per row, and the right hand side
+    let mut tableau: Vec<Vec<BigRational>> = rows
+        .into_iter()
+        .zip(rhs)
+        .enumerate()
+        .map(|(i, (mut row, b))| {
+            row.extend((0..m).map(|j| {
+                if i == j {
+                    BigRational::one()
+                } else {
+                    BigRational::zero()
+                }
+            }));
+            row.push(b);
+            row
+        })
+        .collect();
+    let mut basis: Vec<usize> = (n..n + m).collect();
+
+    // phase 1 minimizes the sum of the artificial variables, the objective row holds the reduced costs and
+    // the negated value
+    let mut objective = vec![BigRational::zero(); n + m + 1];
+    for row in tableau.iter() {
+        for (o, a) in objective.iter_mut().zip(row.iter()) {
+            *o -= a;
+        }
+    }
+    for o in objective[n..n + m].iter_mut() {
+        *o = BigRational::zero();
+    }
+    run_simplex(&mut tableau, &mut objective, &mut basis, n + m)?;
+    if !objective[n + m].is_zero() {
+        return None;
+    }
+
+    // artificial variables left in the basis at 0 leave it, unless their row is redundant
+    for r in 0..m {
+        if basis[r] >= n {
+            if let Some(c) = (0..n).find(|&j| !tableau[r][j].is_zero()) {
+                pivot(&mut tableau, &mut objective, &mut basis, r, c);
+            }
+        }
+    }
+
+    let mut objective = vec![BigRational::zero(); n + m + 1];
+    objective[..n].clone_from_slice(&costs);
+    for (r, row) in tableau.iter().enumerate() {
+        if basis[r] < n && !costs[basis[r]].is_zero() {
+            let factor = costs[basis[r]].clone();
+            for (o, a) in objective.iter_mut().zip(row.iter()) {
+                *o -= &factor * a;
+            }
+        }
+    }
+    run_simplex(&mut tableau, &mut objective, &mut basis, n)?;
+
+    let mut x = vec![BigRational::zero(); n];
+    for (r, &j) in basis.iter().enumerate() {
+        if j < n {
+            x[j] = tableau[r][n + m].clone();
+        }
+    }
+    Some((-objective[n + m].clone(), x))
+}
+
+// pivots until no column below allowed has a negative reduced cost, None if the objective is unbounded
+fn run_simplex(
+    tableau: &mut [Vec<BigRational>],
+    objective: &mut [BigRational],
+    basis: &mut [usize],
+    allowed: usize,
+) -> Option<()> {
+    let last = objective.len() - 1;
+    while let Some(c) = (0..allowed).find(|&j| objective[j].is_negative()) {
+        let mut best: Option<(BigRational, usize)> = None;
+        for (r, row) in tableau.iter().enumerate() {
+            if !row[c].is_positive() {
+                continue;
+            }
+            let ratio = &row[last] / &row[c];
+            let better = match &best {
+                None => true,
+                Some((b, s)) => ratio < *b || (ratio == *b && basis[r] < basis[*s]),
+            };
+            if better {
+                best = Some((ratio, r));
+            }
+        }
+        let (_, r) = best?;
+        pivot(tableau, objective, basis, r, c);
+    }
+    Some(())
+}
+
+fn pivot(
+    tableau: &mut [Vec<BigRational>],
+    objective: &mut [BigRational],
+    basis: &mut [usize],
+    r: usize,
+    c: usize,
+) {
+    let divisor = tableau[r][c].clone();
+    tableau[r].iter_mut().for_each(|a| *a /= &divisor);
+    let pivot_row = tableau[r].clone();
+    for (i, row) in tableau.iter_mut().enumerate() {
+        if i != r && !row[c].is_zero() {
+            let factor = row[c].clone();
+            for (a, p) in row.iter_mut().zip(pivot_row.iter()) {
+                *a -= &factor * p;
+            }
+        }
+    }
+    if !objective[c].is_zero() {
+        let factor = objective[c].clone();
+        for (o, p) in objective.iter_mut().zip(pivot_row.iter()) {
+            *o -= &factor * p;
+        }
+    }
+    basis[r] = c;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExactHighestGainPath;
+    use crate::graph::{EdgeError, Flow, GeneralizedFlowError};
+    use crate::highest_gain_path_method::HighestGainPathMethod;
+    use crate::instances::{read_graph_instance, GraphInstance};
+    use crate::rounded_primal_dual::RoundedPrimalDual;
+    use crate::test_utilities::{assert_certified_case, read_expected, sample_instance};
+    use num_rational::BigRational;
+    use num_traits::ToPrimitive;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    fn rational(x: Flow) -> BigRational {
+        BigRational::from_float(x).unwrap()
+    }
+
+    fn exact_solver(instance: &GraphInstance) -> ExactHighestGainPath {
+        let mut solver = ExactHighestGainPath::new(instance.num_nodes);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            solver
+                .add_directed_edge(from, to, rational(capacity), rational(gain))
+                .unwrap();
+        }
+        solver
+    }
+
+    #[test]
+    fn sample() {
+        let instance = sample_instance();
+        let mut solver = exact_solver(&instance);
+        let actual = solver
+            .solve(instance.source, instance.sink)
+            .to_f64()
+            .unwrap();
+        assert!((actual - 7.363).abs() < 1e-9, "{}", actual);
+
+        // exact conservation at the inner nodes
+        let mut balance = vec![BigRational::from_integer(0.into()); instance.num_nodes];
+        for (i, &(from, to, _, _)) in instance.edges.iter().enumerate() {
+            let (sent, received) = solver.get_flow(i);
+            balance[from] -= sent;
+            balance[to] += received;
+        }
+        for (u, b) in balance.iter().enumerate() {
+            if u != instance.source && u != instance.sink {
+                assert_eq!(*b, BigRational::from_integer(0.into()), "{}", u);
+            }
+        }
+        assert_eq!(balance[instance.sink], solver.excesses[instance.sink]);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut solver = ExactHighestGainPath::new(3);
+        assert_eq!(
+            solver.try_solve(0, 2),
+            Err(GeneralizedFlowError::EmptyGraph)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, rational(0.0), rational(1.0)),
+            Err(EdgeError::NonPositiveCapacity)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, rational(1.0), rational(-1.0)),
+            Err(EdgeError::NonPositiveGain)
+        );
+
+        // 1 -> 2 -> 1 doubles the flow
+        solver
+            .add_directed_edge(0, 1, rational(1.0), rational(1.0))
+            .unwrap();
+        solver
+            .add_directed_edge(1, 2, rational(1.0), rational(2.0))
+            .unwrap();
+        solver
+            .add_directed_edge(2, 1, rational(1.0), rational(1.0))
+            .unwrap();
+        assert_eq!(
+            solver.try_solve(0, 2),
+            Err(GeneralizedFlowError::FlowGeneratingCycle(vec![1, 2]))
+        );
+        assert_eq!(
+            solver.try_solve(0, 0),
+            Err(GeneralizedFlowError::SourceEqualsSink)
+        );
+        assert_eq!(
+            solver.try_solve(0, 3),
+            Err(GeneralizedFlowError::InvalidTerminal(3))
+        );
+    }
+
+    #[test]
+    fn linear_program() {
+        use super::min_cost_generalized_flow_lp;
+        // 0 -> 1 -> 3 costs 2 per unit delivered, 0 -> 2 -> 3 costs 3
+        let edges: Vec<_> = [
+            (0, 1, 10.0, 1.0, 1.0),
+            (1, 3, 10.0, 0.5, 0.0),
+            (0, 2, 4.0, 1.0, 3.0),
+            (2, 3, 10.0, 1.0, 0.0),
+        ]
+        .iter()
+        .map(|&(from, to, capacity, gain, cost)| {
+            (from, to, rational(capacity), rational(gain), rational(cost))
+        })
+        .collect();
+        assert_eq!(
+            min_cost_generalized_flow_lp(4, &edges, 0, 3, &rational(6.0)),
+            (rational(6.0), rational(13.0))
+        );
+        assert_eq!(
+            min_cost_generalized_flow_lp(4, &edges, 0, 3, &rational(100.0)),
+            (rational(9.0), rational(22.0))
+        );
+    }
+
+    // the instances with up to 30 edges
+    #[rstest]
+    fn aoj_grl_6_a(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        assert_certified_case(&path, 0.01);
+        let instance = read_graph_instance(&path).unwrap();
+        if instance.edges.len() > 30 {
+            return;
+        }
+        let mut expected_file_path = path.clone();
+        expected_file_path.set_extension("out");
+        let expected = read_expected(&expected_file_path);
+
+        let exact = exact_solver(&instance)
+            .solve(instance.source, instance.sink)
+            .to_f64()
+            .unwrap();
+        assert!(
+            (exact - expected).abs() <= 1e-6 * expected.max(1.0),
+            "{} {}",
+            exact,
+            expected
+        );
+
+        let epsilon: Flow = 0.01;
+        let mut rounded_primal_dual = RoundedPrimalDual::new(instance.num_nodes, epsilon);
+        let mut highest_gain_path = HighestGainPathMethod::new(instance.num_nodes, epsilon);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            rounded_primal_dual
+                .add_directed_edge(from, to, capacity, gain)
+                .unwrap();
+            highest_gain_path
+                .add_directed_edge(from, to, capacity, gain)
+                .unwrap();
+        }
+        for actual in [
+            rounded_primal_dual.solve(instance.source, instance.sink),
+            highest_gain_path.solve(instance.source, instance.sink),
+        ] {
+            assert!(
+                exact * (1.0 - epsilon) <= actual && actual <= exact * (1.0 + 1e-12),
+                "{} {}",
+                actual,
+                exact
+            );
+        }
+    }
+}
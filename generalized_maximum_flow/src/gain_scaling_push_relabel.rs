@@ -0,0 +1,446 @@
+use crate::graph::{
+    EdgeError, Flow, GeneralizedFlowError, InsideEdge, ScalingGraph, DIST_MAX, EPS, FLOW_MAX,
+};
+use crate::solver::GeneralizedMaxFlowSolver;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+// push-relabel on the network with the gains rounded like RoundedPrimalDual, so with the same (1 - epsilon)
+// guarantee, but without its shortest path phase per augmentation: each node keeps a distance label towards
+// the sink that only grows where it is stuck, and pushes its excess along the tight edges (whose gains are 1
+// in the units of the labels) in the order of the hops of a second label within them
+// once a node needs n hops, a global relabel computes the exact distances again
+pub struct GainScalingPushRelabel {
+    // the potentials are the distance labels: p(u) <= p(v) + dist for every residual edge u -> v, tight with equality
+    pub graph: ScalingGraph,
+    // hops to the sink over tight edges, a lower bound like the labels of push-relabel
+    hops: Vec<usize>,
+    // no residual path to the sink, the excess stays
+    stranded: Vec<bool>,
+    current: Vec<usize>,
+    pushes: usize,
+    relabels: usize,
+    global_relabels: usize,
+}
+
+#[allow(dead_code)]
+impl GainScalingPushRelabel {
+    // the base follows the nodes of the edges added, num_nodes is only a hint
+    pub fn new(num_nodes: usize, epsilon: Flow) -> Self {
+        assert!(epsilon > 0.0);
+        Self::with_graph(ScalingGraph::new(num_nodes, epsilon))
+    }
+
+    pub fn new_with_base(base: Flow) -> Self {
+        assert!(base > 1.0);
+        Self::with_graph(ScalingGraph::new_with_base(base))
+    }
+
+    fn with_graph(graph: ScalingGraph) -> Self {
+        GainScalingPushRelabel {
+            graph,
+            hops: Vec::new(),
+            stranded: Vec::new(),
+            current: Vec::new(),
+            pushes: 0,
+            relabels: 0,
+            global_relabels: 0,
+        }
+    }
+
+    pub fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.graph.try_add_directed_edge(from, to, capacity, gain)
+    }
+
+    // edges i and i + 1, see ScalingGraph::try_add_undirected_edge
+    pub fn add_undirected_edge(
+        &mut self,
+        u: usize,
+        v: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.graph.try_add_undirected_edge(u, v, capacity, gain)
+    }
+
+    // (flow entering, flow leaving) the edge, see ScalingGraph::get_flow
+    pub fn get_flow(&self, edge_index: usize) -> (Flow, Flow) {
+        self.graph.get_flow(edge_index)
+    }
+
+    // pushes of the last solve
+    pub fn pushes(&self) -> usize {
+        self.pushes
+    }
+
+    // local relabels of the last solve
+    pub fn relabels(&self) -> usize {
+        self.relabels
+    }
+
+    // shortest path computations of the last solve, one at the start and one whenever a node runs
+    // out of tight paths that others still lead into
+    pub fn global_relabels(&self) -> usize {
+        self.global_relabels
+    }
+
+    // 0 if try_solve fails
+    pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.try_solve(source, sink).unwrap_or(0.0)
+    }
+
+    pub fn try_solve(&mut self, source: usize, sink: usize) -> Result<Flow, GeneralizedFlowError> {
+        self.graph.validate(&[source], sink)?;
+        if self.graph.has_lower_bounds() {
+            return Err(GeneralizedFlowError::LowerBoundsUnsupported);
+        }
+        self.graph.build();
+        if !self.graph.is_lossy {
+            return Err(self.graph.flow_generating_cycle_error());
+        }
+        let source = self.graph.entry(source);
+
+        let n = self.graph.num_nodes;
+        self.pushes = 0;
+        self.relabels = 0;
+        self.global_relabels = 0;
+        self.stranded = vec![false; n];
+        self.global_relabel(sink);
+
+        self.graph.excesses[source] = FLOW_MAX;
+        let mut active = VecDeque::from([source]);
+        let mut in_queue = vec![false; n];
+        in_queue[source] = true;
+        while let Some(u) = active.pop_front() {
+            in_queue[u] = false;
+            self.discharge(u, &mut active, &mut in_queue, sink);
+        }
+
+        self.graph.cancel_opposite_flows();
+        Ok(self.graph.excesses[sink])
+    }
+
+    fn discharge(
+        &mut self,
+        u: usize,
+        active: &mut VecDeque<usize>,
+        in_queue: &mut [bool],
+        sink: usize,
+    ) {
+        while self.graph.excesses[u] > EPS && !self.stranded[u] {
+            if self.current[u] == self.graph.start[u + 1] {
+                // the local relabels raise the labels by one gain class at a time, a global relabel per n of them
+                // jumps to the exact distances
+                if !self.relabel(u) || self.relabels.is_multiple_of(self.graph.num_nodes) {
+                    self.global_relabel(sink);
+                }
+                self.current[u] = self.graph.start[u];
+                continue;
+            }
+
+            let i = self.current[u];
+            let edge = &self.graph.inside_edge_list[i];
+            let v = edge.to;
+            if self.is_admissible(u, edge) {
+                let sent = self.graph.excesses[u].min(edge.residual_capacity());
+                let received = sent * self.graph.scaled_gain(edge.dist);
+                self.graph.push_real_flow_with_excess(u, i, sent, received);
+                self.pushes += 1;
+                if v != sink && !in_queue[v] && self.graph.excesses[v] > EPS {
+                    in_queue[v] = true;
+                    active.push_back(v);
+                }
+            } else {
+                self.current[u] += 1;
+            }
+        }
+    }
+
+    #[inline]
+    fn is_tight(&self, u: usize, edge: &InsideEdge) -> bool {
+        edge.residual_capacity() > 0.0
+            && !self.stranded[edge.to]
+            && self.graph.potentials[u] == self.graph.potentials[edge.to] + edge.dist
+    }
+
+    #[inline]
+    fn is_admissible(&self, u: usize, edge: &InsideEdge) -> bool {
+        self.is_tight(u, edge) && self.hops[u] == self.hops[edge.to] + 1
+    }
+
+    // more hops over the tight edges, or without any, the smallest distance label the residual edges allow
+    // false if that needs n hops, the nodes of the tight edges have to be relabeled first
+    fn relabel(&mut self, u: usize) -> bool {
+        self.relabels += 1;
+        let n = self.graph.num_nodes;
+        let mut min_hops = usize::MAX;
+        let mut min_distance = DIST_MAX;
+        for e in self.graph.neighbors(u) {
+            if e.residual_capacity() <= 0.0 || self.stranded[e.to] {
+                continue;
+            }
+            if self.is_tight(u, e) {
+                min_hops = min_hops.min(self.hops[e.to] + 1);
+            } else {
+                min_distance = min_distance.min(self.graph.potentials[e.to] + e.dist);
+            }
+        }
+        if min_hops != usize::MAX {
+            self.hops[u] = min_hops;
+            return min_hops < n;
+        }
+        if min_distance == DIST_MAX {
+            self.stranded[u] = true;
+            return true;
+        }
+
+        self.graph.potentials[u] = min_distance;
+        let hops = self
+            .graph
+            .neighbors(u)
+            .filter(|e| self.is_tight(u, e))
+            .map(|e| self.hops[e.to] + 1)
+            .min()
+            .unwrap();
+        self.hops[u] = hops;
+        hops < n
+    }
+
+    // exact distances to the sink, and the hops along the tight edges by bfs from the sink
+    // dijkstra on the dist reduced by the labels, over the residual edges exactly as the pushes see them
+    fn global_relabel(&mut self, sink: usize) {
+        self.global_relabels += 1;
+        let n = self.graph.num_nodes;
+        let mut distance = vec![DIST_MAX; n];
+        distance[sink] = 0;
+        let mut heap = BinaryHeap::from([(Reverse(0), sink)]);
+        while let Some((Reverse(d), v)) = heap.pop() {
+            if d > distance[v] {
+                continue;
+            }
+            for e in self.graph.neighbors(v) {
+                // edge(u -> v) is the reverse of e
+                let u = e.to;
+                let edge = &self.graph.inside_edge_list[e.rev];
+                if edge.residual_capacity() <= 0.0 {
+                    continue;
+                }
+                let reduced = edge.dist - self.graph.potentials[u] + self.graph.potentials[v];
+                debug_assert!(reduced >= 0);
+                if d + reduced < distance[u] {
+                    distance[u] = d + reduced;
+                    heap.push((Reverse(distance[u]), u));
+                }
+            }
+        }
+        let sink_potential = self.graph.potentials[sink];
+        for (u, &d) in distance.iter().enumerate() {
+            self.stranded[u] = d == DIST_MAX;
+            if d != DIST_MAX {
+                self.graph.potentials[u] += d - sink_potential;
+            }
+        }
+
+        self.hops = vec![n; n];
+        self.hops[sink] = 0;
+        let mut que = VecDeque::from([sink]);
+        while let Some(v) = que.pop_front() {
+            for i in self.graph.start[v]..self.graph.start[v + 1] {
+                // edge(u -> v) is the reverse of edge i
+                let u = self.graph.inside_edge_list[i].to;
+                let rev = self.graph.inside_edge_list[i].rev;
+                if self.hops[u] == n
+                    && u != sink
+                    && self.is_tight(u, &self.graph.inside_edge_list[rev])
+                {
+                    self.hops[u] = self.hops[v] + 1;
+                    que.push_back(u);
+                }
+            }
+        }
+        self.current = (0..n).map(|u| self.graph.start[u]).collect();
+    }
+}
+
+impl GeneralizedMaxFlowSolver for GainScalingPushRelabel {
+    fn new(num_nodes: usize, epsilon: Flow) -> Self {
+        GainScalingPushRelabel::new(num_nodes, epsilon)
+    }
+
+    fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.add_directed_edge(from, to, capacity, gain)
+    }
+
+    fn add_undirected_edge(
+        &mut self,
+        u: usize,
+        v: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.add_undirected_edge(u, v, capacity, gain)
+    }
+
+    fn try_solve(&mut self, source: usize, sink: usize) -> Result<Flow, GeneralizedFlowError> {
+        self.try_solve(source, sink)
+    }
+
+    fn graph(&self) -> &ScalingGraph {
+        &self.graph
+    }
+
+    fn graph_mut(&mut self) -> &mut ScalingGraph {
+        &mut self.graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GainScalingPushRelabel;
+    use crate::graph::{Flow, GeneralizedFlowError, EPS};
+    use crate::instances::read_graph_instance;
+    use crate::rounded_primal_dual::RoundedPrimalDual;
+    use crate::test_utilities::{assert_certified_case, sample_instance};
+    use crate::verify::check;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn sample() {
+        let epsilon = 0.01;
+        let instance = sample_instance();
+        let mut solver = GainScalingPushRelabel::new(instance.num_nodes, epsilon);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
+        }
+        let actual = solver.solve(instance.source, instance.sink);
+        assert!(
+            7.363 * (1.0 - epsilon) <= actual && actual <= 7.363 + EPS,
+            "{}",
+            actual
+        );
+        assert!(solver.pushes() > 0 && solver.relabels() > 0);
+        assert_eq!(
+            check(&solver.graph, instance.source, instance.sink, 1e-9),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn invalid_instances() {
+        let mut solver = GainScalingPushRelabel::new(4, 0.01);
+        assert_eq!(
+            solver.try_solve(0, 1),
+            Err(GeneralizedFlowError::EmptyGraph)
+        );
+        solver.add_directed_edge(0, 1, 1.0, 1.0).unwrap();
+        solver.add_directed_edge(1, 2, 1.0, 2.0).unwrap();
+        solver.add_directed_edge(2, 1, 1.0, 1.0).unwrap();
+        solver.add_directed_edge(2, 3, 10.0, 1.0).unwrap();
+        assert_eq!(
+            solver.try_solve(0, 0),
+            Err(GeneralizedFlowError::SourceEqualsSink)
+        );
+        assert_eq!(
+            solver.try_solve(0, 3),
+            Err(GeneralizedFlowError::FlowGeneratingCycle(vec![1, 2]))
+        );
+    }
+
+    #[test]
+    fn gain_above_one_without_cycle() {
+        // 0 -> 1 -> 3 doubles the flow, 0 -> 2 -> 3 keeps it
+        let epsilon = 0.01;
+        let mut solver = GainScalingPushRelabel::new(4, epsilon);
+        for (from, to, capacity, gain) in [
+            (0, 1, 1.0, 2.0),
+            (1, 3, 1.5, 1.0),
+            (0, 2, 1.0, 1.0),
+            (2, 3, 1.0, 1.0),
+        ] {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
+        }
+        let actual = solver.solve(0, 3);
+        assert!(
+            2.5 * (1.0 - epsilon) <= actual && actual <= 2.5 + EPS,
+            "{}",
+            actual
+        );
+    }
+
+    #[test]
+    fn layered() {
+        // 13 layers of 13 nodes, every node linked to 3 pseudo random nodes of the next layer: pushes that
+        // nearly cancel leave flows of a few ulps, which the global relabel has to see like the pushes do
+        let (layers, width) = (13, 13);
+        let (source, sink) = (layers * width, layers * width + 1);
+        let mut state: u64 = 1;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize
+        };
+        let mut solver = GainScalingPushRelabel::new(layers * width + 2, 0.01);
+        let mut rounded_primal_dual = RoundedPrimalDual::new(layers * width + 2, 0.01);
+        let mut add = |from: usize, to: usize, capacity: Flow, gain: Flow| {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
+            rounded_primal_dual
+                .add_directed_edge(from, to, capacity, gain)
+                .unwrap();
+        };
+        for u in 0..width {
+            add(source, u, 1000.0, 1.0);
+            add((layers - 1) * width + u, sink, 1000.0, 1.0);
+        }
+        for layer in 0..layers - 1 {
+            for u in 0..width {
+                for _ in 0..3 {
+                    let v = (layer + 1) * width + next() % width;
+                    let capacity = (1 + next() % 100) as Flow;
+                    let gain = 0.5 + (next() % 500) as Flow / 1000.0;
+                    add(layer * width + u, v, capacity, gain);
+                }
+            }
+        }
+        let actual = solver.solve(source, sink);
+        let expected = rounded_primal_dual.solve(source, sink);
+        assert!(
+            expected > 0.0 && (actual - expected).abs() <= 1e-9 * expected,
+            "{} {}",
+            actual,
+            expected
+        );
+        check(&solver.graph, source, sink, 1e-6).unwrap();
+    }
+
+    // the optimum of the same rounded network
+    #[rstest]
+    fn same_as_rounded_primal_dual(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        let epsilon: Flow = 0.01;
+        let instance = read_graph_instance(&path).unwrap();
+        let mut solver: GainScalingPushRelabel = instance.build_solver(epsilon);
+        let mut rounded_primal_dual: RoundedPrimalDual = instance.build_solver(epsilon);
+        let actual = solver.solve(instance.source, instance.sink);
+        let other = rounded_primal_dual.solve(instance.source, instance.sink);
+        assert!(
+            (actual - other).abs() <= 1e-9 * other.max(1.0),
+            "{} {}",
+            actual,
+            other
+        );
+        assert_certified_case(&path, epsilon);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::graph::Flow;
+use crate::instances::GraphInstance;
+use std::ops::RangeInclusive;
+
+// SplitMix64, small and good enough for generating test instances (not for cryptography)
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [low, high)
+    pub fn gen_range(&mut self, low: u64, high: u64) -> u64 {
+        assert!(low < high);
+        // rejection sampling to avoid modulo bias
+        let range = high - low;
+        let zone = u64::MAX - u64::MAX % range;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return low + x % range;
+            }
+        }
+    }
+
+    pub fn gen_bool(&mut self) -> bool {
+        self.next_u64() >> 63 == 1
+    }
+}
+
+// num_nodes nodes and num_edges edges between distinct random endpoints, capacities in 1..=100, gains in [0.5, 1]
+// in steps of 0.01, source = 0, sink = num_nodes - 1
+pub fn random_graph(num_nodes: usize, num_edges: usize, seed: u64) -> GraphInstance {
+    random_graph_with_gains(num_nodes, num_edges, 50..=100, seed)
+}
+
+// random_graph with the gains in the given range of hundredths, above 100 there can be flow generating cycles
+pub fn random_graph_with_gains(
+    num_nodes: usize,
+    num_edges: usize,
+    gain_percents: RangeInclusive<u64>,
+    seed: u64,
+) -> GraphInstance {
+    assert!(num_nodes >= 2 && *gain_percents.start() > 0 && !gain_percents.is_empty());
+    let mut rng = Rng::new(seed);
+    let edges = (0..num_edges)
+        .map(|_| {
+            let from = rng.gen_range(0, num_nodes as u64) as usize;
+            // shift to skip self loops
+            let mut to = rng.gen_range(0, num_nodes as u64 - 1) as usize;
+            if to >= from {
+                to += 1;
+            }
+            let capacity = rng.gen_range(1, 101) as Flow;
+            let gain =
+                rng.gen_range(*gain_percents.start(), gain_percents.end() + 1) as Flow / 100.0;
+            (from, to, capacity, gain)
+        })
+        .collect();
+    GraphInstance {
+        num_nodes,
+        edges,
+        source: 0,
+        sink: num_nodes - 1,
+    }
+}
+
+// what arrives at the end of a two terminal network for x units sent into its start, concave and nondecreasing, as
+// its segments (length of x, units arriving per unit sent) with the slopes decreasing
+type Response = Vec<(Flow, Flow)>;
+
+// the two networks side by side: x goes to the steeper segments first
+fn parallel(mut a: Response, b: Response) -> Response {
+    a.extend(b);
+    a.sort_by(|x, y| y.1.total_cmp(&x.1));
+    a
+}
+
+// what arrives at the end of first enters second
+fn series(first: &Response, second: &Response) -> Response {
+    let mut result = Vec::new();
+    let mut next = second.iter().copied();
+    let mut current = next.next();
+    for &(mut length, slope) in first.iter() {
+        while length > 0.0 {
+            let Some((room, second_slope)) = current else {
+                return result;
+            };
+            // the input of first that fills the rest of the current segment of second
+            let fill = room / slope;
+            let used = length.min(fill);
+            result.push((used, slope * second_slope));
+            length -= used;
+            current = if used < fill {
+                Some((room - used * slope, second_slope))
+            } else {
+                next.next()
+            };
+        }
+    }
+    result
+}
+
+// a series parallel network of num_edges edges from source 0 to sink 1 and its maximum flow, exact up to the
+// rounding of the f64 arithmetic: capacities in 1..=100, gains in [0.5, 1.2] in steps of 0.01, no cycle so nothing
+// generates flow
+pub fn series_parallel(num_edges: usize, seed: u64) -> (GraphInstance, Flow) {
+    assert!(num_edges > 0);
+    let mut rng = Rng::new(seed);
+    let mut instance = GraphInstance {
+        num_nodes: 2,
+        edges: Vec::new(),
+        source: 0,
+        sink: 1,
+    };
+    let response = compose(&mut instance, &mut rng, 0, 1, num_edges);
+    let optimum = response.iter().map(|&(length, slope)| length * slope).sum();
+    (instance, optimum)
+}
+
+// num_edges edges between from and to, split in two parts in series (through a new node) or in parallel
+fn compose(
+    instance: &mut GraphInstance,
+    rng: &mut Rng,
+    from: usize,
+    to: usize,
+    num_edges: usize,
+) -> Response {
+    if num_edges == 1 {
+        let capacity = rng.gen_range(1, 101) as Flow;
+        let gain = rng.gen_range(50, 121) as Flow / 100.0;
+        instance.edges.push((from, to, capacity, gain));
+        return vec![(capacity, gain)];
+    }
+    let left = rng.gen_range(1, num_edges as u64) as usize;
+    if rng.gen_bool() {
+        let middle = instance.num_nodes;
+        instance.num_nodes += 1;
+        let first = compose(instance, rng, from, middle, left);
+        let second = compose(instance, rng, middle, to, num_edges - left);
+        series(&first, &second)
+    } else {
+        let a = compose(instance, rng, from, to, left);
+        let b = compose(instance, rng, from, to, num_edges - left);
+        parallel(a, b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parallel, random_graph, series, series_parallel};
+
+    #[test]
+    fn responses() {
+        // 10 units at gain 0.5 then 4 at gain 0.8, into an edge of capacity 5 and gain 0.9
+        let first = parallel(vec![(10.0, 0.5)], vec![(4.0, 0.8)]);
+        assert_eq!(first, vec![(4.0, 0.8), (10.0, 0.5)]);
+        let both = series(&first, &vec![(5.0, 0.9)]);
+        // 3.2 arrive from the first 4 units, the remaining room of 1.8 takes 3.6 more units at gain 0.5
+        assert_eq!(both.len(), 2);
+        assert!((both[0].0 - 4.0).abs() < 1e-12 && (both[0].1 - 0.72).abs() < 1e-12);
+        assert!((both[1].0 - 3.6).abs() < 1e-12 && (both[1].1 - 0.45).abs() < 1e-12);
+        let total: f64 = both.iter().map(|&(length, slope)| length * slope).sum();
+        assert!((total - 4.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reproducible() {
+        let (a, optimum_a) = series_parallel(30, 7);
+        let (b, optimum_b) = series_parallel(30, 7);
+        assert_eq!((a, optimum_a), (b, optimum_b));
+        let (c, _) = series_parallel(30, 8);
+        let (a, _) = series_parallel(30, 7);
+        assert_ne!(a, c);
+        assert_eq!(a.edges.len(), 30);
+        assert!(a
+            .edges
+            .iter()
+            .all(|&(from, to, _, _)| from < a.num_nodes && to < a.num_nodes && from != to));
+
+        let random = random_graph(8, 24, 3);
+        assert_eq!(random, random_graph(8, 24, 3));
+        assert_eq!(
+            (
+                random.num_nodes,
+                random.edges.len(),
+                random.source,
+                random.sink
+            ),
+            (8, 24, 0, 7)
+        );
+        assert!(random
+            .edges
+            .iter()
+            .all(|&(from, to, _, gain)| from < 8 && to < 8 && from != to && gain <= 1.0));
+    }
+
+    // the optimum of the lp over the rationals
+    #[cfg(feature = "exact")]
+    #[test]
+    fn exact_optimum() {
+        use crate::exact::ExactHighestGainPath;
+        use num_rational::BigRational;
+        use num_traits::ToPrimitive;
+
+        for seed in 0..10 {
+            let (instance, optimum) = series_parallel(12, seed);
+            let mut solver = ExactHighestGainPath::new(instance.num_nodes);
+            for &(from, to, capacity, gain) in instance.edges.iter() {
+                let capacity = BigRational::from_float(capacity).unwrap();
+                let gain = BigRational::from_float(gain).unwrap();
+                solver.add_directed_edge(from, to, capacity, gain).unwrap();
+            }
+            let exact = solver
+                .solve(instance.source, instance.sink)
+                .to_f64()
+                .unwrap();
+            assert!(
+                (exact - optimum).abs() <= 1e-9 * optimum,
+                "{} {} {}",
+                seed,
+                exact,
+                optimum
+            );
+        }
+    }
+}
@@ -1,5 +1,7 @@
+use network_flows_core::{CsrGraph, EdgePayload};
 use std::cmp::Reverse;
 use std::collections::{BinaryHeap, VecDeque};
+use std::time::{Duration, Instant};
 
 pub type Flow = f64;
 pub type Dist = i32;
@@ -7,23 +9,364 @@ pub type Dist = i32;
 pub const DIST_MAX: Dist = Dist::MAX / 2;
 pub const FLOW_MAX: Flow = Flow::MAX / 2.0;
 pub const EPS: Flow = Flow::EPSILON;
+// flow below this fraction of the capacity is treated as rounding noise by decompose
+pub const DECOMPOSITION_EPS: Flow = 1e-9;
+// the farthest distance past the smallest queued one dial's buckets span, see ScalingGraph::bucket_queue
+pub const BUCKET_QUEUE_MAX_SPAN: usize = 1 << 12;
+// the inside edges from which update_distance_to_sink runs delta stepping, see ScalingGraph::delta_stepping_min_edges
+pub const DELTA_STEPPING_MIN_EDGES: usize = 1 << 22;
+// the unit of the forward flows of ScalingGraph::integral_capacities
+pub const FLOW_UNIT: Flow = 1.0 / (1u64 << 32) as Flow;
+// the integer capacities up to which ScalingGraph::integral_capacities counts the flow in units of FLOW_UNIT, whose
+// counts up to 2^53 the f64 flows hold exactly
+pub const INTEGRAL_CAPACITY_MAX: Flow = (1u64 << 21) as Flow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeneralizedFlowError {
+    // a cycle whose gain product is greater than 1 exists in the residual network, with its nodes in the
+    // direction of flow from the smallest one (empty if it could not be traced)
+    // RoundedPrimalDual cancels such cycles instead
+    FlowGeneratingCycle(Vec<usize>),
+    // no edge was added
+    EmptyGraph,
+    // source or sink is not a node of the graph
+    InvalidTerminal(usize),
+    SourceEqualsSink,
+    // the supply is negative or NaN
+    InvalidSupply,
+    // the demand of a sink is negative or NaN
+    InvalidDemand,
+    // the lower bounds of the edges can't all be met, this node can't get rid of the flow they bring or can't send
+    // what they take out of it
+    Infeasible(usize),
+    // the solver has no support for lower bounds, only RoundedPrimalDual does
+    LowerBoundsUnsupported,
+    // with ScalingGraph::integral_capacities, the capacity of this edge less its lower bound isn't an integer up to
+    // INTEGRAL_CAPACITY_MAX
+    NonIntegralCapacity(usize),
+}
+
+impl std::fmt::Display for GeneralizedFlowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneralizedFlowError::FlowGeneratingCycle(cycle) => {
+                write!(f, "flow generating cycle detected: {:?}", cycle)
+            }
+            GeneralizedFlowError::EmptyGraph => write!(f, "the graph has no edges"),
+            GeneralizedFlowError::InvalidTerminal(u) => write!(f, "node {} is out of range", u),
+            GeneralizedFlowError::SourceEqualsSink => {
+                write!(f, "source and sink are the same node")
+            }
+            GeneralizedFlowError::InvalidSupply => {
+                write!(f, "supply needs to be a non-negative number")
+            }
+            GeneralizedFlowError::InvalidDemand => {
+                write!(f, "demand needs to be a non-negative number")
+            }
+            GeneralizedFlowError::Infeasible(u) => {
+                write!(f, "the lower bounds at node {} can't be met", u)
+            }
+            GeneralizedFlowError::LowerBoundsUnsupported => {
+                write!(f, "the solver doesn't support lower bounds")
+            }
+            GeneralizedFlowError::NonIntegralCapacity(i) => write!(
+                f,
+                "the capacity of edge {} isn't an integer up to {}",
+                i, INTEGRAL_CAPACITY_MAX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GeneralizedFlowError {}
+
+// reasons for rejecting an edge, NaN and infinite values would otherwise end up in the labels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeError {
+    NonFiniteCapacity,
+    NonFiniteGain,
+    NonPositiveCapacity,
+    NonPositiveGain,
+    NonFiniteCost,
+    NegativeCost,
+    // of an undirected edge, whose two directions would make a flow generating cycle
+    UndirectedGainAboveOne,
+    // negative, NaN or above the capacity
+    InvalidLowerBound,
+    // from == to, which no flow from a source to a sink can use
+    SelfLoop,
+    // from == to with a gain above 1, a flow generating cycle of its own
+    FlowGeneratingSelfLoop,
+}
+
+impl std::fmt::Display for EdgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeError::NonFiniteCapacity => write!(f, "capacity needs to be finite"),
+            EdgeError::NonFiniteGain => write!(f, "gain needs to be finite"),
+            EdgeError::NonPositiveCapacity => write!(f, "capacity needs to be greater than 0"),
+            EdgeError::NonPositiveGain => write!(f, "gain needs to be greater than 0"),
+            EdgeError::NonFiniteCost => write!(f, "cost needs to be finite"),
+            EdgeError::NegativeCost => write!(f, "cost needs to be non-negative"),
+            EdgeError::UndirectedGainAboveOne => {
+                write!(f, "gain of an undirected edge needs to be at most 1")
+            }
+            EdgeError::InvalidLowerBound => {
+                write!(f, "lower bound needs to be between 0 and the capacity")
+            }
+            EdgeError::SelfLoop => write!(f, "edge needs to join two different nodes"),
+            EdgeError::FlowGeneratingSelfLoop => write!(
+                f,
+                "edge from a node to itself with a gain above 1 generates flow"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EdgeError {}
+
+pub(crate) fn check_edge(capacity: Flow, gain: Flow) -> Result<(), EdgeError> {
+    if !capacity.is_finite() {
+        return Err(EdgeError::NonFiniteCapacity);
+    }
+    if !gain.is_finite() {
+        return Err(EdgeError::NonFiniteGain);
+    }
+    if capacity <= 0.0 {
+        return Err(EdgeError::NonPositiveCapacity);
+    }
+    if gain <= 0.0 {
+        return Err(EdgeError::NonPositiveGain);
+    }
+    Ok(())
+}
+
+// negative costs could close a cycle that pays for the flow it loses
+pub(crate) fn check_cost(cost: Flow) -> Result<(), EdgeError> {
+    if !cost.is_finite() {
+        return Err(EdgeError::NonFiniteCost);
+    }
+    if cost < 0.0 {
+        return Err(EdgeError::NegativeCost);
+    }
+    Ok(())
+}
+
+// the cycle that following next from start runs into, None if next runs out before
+pub(crate) fn cycle_along(
+    start: usize,
+    num_nodes: usize,
+    next: impl Fn(usize) -> Option<usize>,
+) -> Option<Vec<usize>> {
+    let mut seen = vec![false; num_nodes];
+    let mut v = start;
+    while !seen[v] {
+        seen[v] = true;
+        v = next(v)?;
+    }
+    let mut cycle = vec![v];
+    let mut w = next(v)?;
+    while w != v {
+        cycle.push(w);
+        w = next(w)?;
+    }
+    Some(cycle)
+}
+
+// a cycle as the error reports it: each node once in a row (a node with a gain is there twice, with its copy),
+// starting from the smallest
+pub(crate) fn normalize_cycle(mut cycle: Vec<usize>) -> Vec<usize> {
+    cycle.dedup();
+    while cycle.len() > 1 && cycle.first() == cycle.last() {
+        cycle.pop();
+    }
+    if let Some(first) = (0..cycle.len()).min_by_key(|&k| cycle[k]) {
+        cycle.rotate_left(first);
+    }
+    cycle
+}
+
+// kahan's compensated summation: compensation carries what the additions to sum lost so far
+#[inline]
+pub(crate) fn compensated_add(sum: &mut Flow, compensation: &mut Flow, x: Flow) {
+    let y = x - *compensation;
+    let t = *sum + y;
+    *compensation = (t - *sum) - y;
+    *sum = t;
+}
+
+// rounding every gain down to a power of this loses at most a factor 1 + epsilon along a path
+pub fn base_for(num_nodes: usize, epsilon: Flow) -> Flow {
+    (1.0 + epsilon).powf(1.0 / num_nodes.max(1) as Flow) as Flow
+}
+
+// the largest |dist| build gives an edge: distances along paths of up to num_nodes edges, and the
+// potentials, stay far from DIST_MAX, and base to the power of such a distance is a finite f64
+pub fn max_edge_dist(num_nodes: usize, base: Flow) -> Dist {
+    let n = num_nodes.max(1) as Flow;
+    let by_dist = DIST_MAX as Flow / (2.0 * n + 1.0);
+    let by_label = Flow::MAX.ln() / 2.0 / (n * base.ln());
+    by_dist.min(by_label).floor() as Dist
+}
+
+// the exponents up to which rescale tabulates the labels base^d and the gains base^-dist, which with a base close
+// to 1 can go far beyond
+const POWERS_RANGE: Dist = 1 << 16;
+
+// base.powf(d) for the labels and base.powi(-dist) for the gains of the edges, for the exponents rescale expects,
+// ScalingGraph::label and ScalingGraph::scaled_gain compute the others the same way, so that the tables change no
+// bit of the results
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Powers {
+    // base.powf(d) at d + label_range
+    labels: Vec<Flow>,
+    label_range: Dist,
+    // base.powi(-dist) at dist + gain_range
+    gains: Vec<Flow>,
+    gain_range: Dist,
+}
+
+impl Powers {
+    fn new(base: Flow, label_range: Dist, gain_range: Dist) -> Self {
+        Powers {
+            labels: (-label_range..=label_range)
+                .map(|d| base.powf(d as Flow))
+                .collect(),
+            label_range,
+            gains: (-gain_range..=gain_range)
+                .map(|dist| base.powi(-dist))
+                .collect(),
+            gain_range,
+        }
+    }
+
+    // below -range the index wraps around past the end
+    #[inline]
+    fn label(&self, d: Dist) -> Option<Flow> {
+        self.labels
+            .get((d as i64 + self.label_range as i64) as usize)
+            .copied()
+    }
+
+    #[inline]
+    fn gain(&self, dist: Dist) -> Option<Flow> {
+        self.gains
+            .get((dist as i64 + self.gain_range as i64) as usize)
+            .copied()
+    }
+}
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Edge {
     pub from: usize,
     pub to: usize,
     pub flow: Flow,
     pub capacity: Flow,
     pub gain: Flow,
+    // per unit entering the edge
+    pub cost: Flow,
+    // the flow entering the edge needs to be at least this, 0 unless added with a lower bound
+    pub lower: Flow,
+}
+
+// a path (or a cycle, starting and ending at the same node) of a flow decomposition
+// injected enters at the first node and delivered, injected times the (rounded) gains along it, arrives at the last
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneralizedPath {
+    pub nodes: Vec<usize>,
+    pub injected: Flow,
+    pub delivered: Flow,
+}
+
+// counters of the last solve of RoundedPrimalDual or HighestGainPathMethod, None for the ones the algorithm doesn't have
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolveStats {
+    // calls of argument_flow, the last one finds no path left
+    pub rounds: usize,
+    // calculate_distance_to_sink and find_shortest_path_from_any
+    pub dijkstras: usize,
+    // calculate_distance_to_sink_with_negative_edge, once for a network with a flow generating cycle
+    pub bellman_fords: usize,
+    // RoundedPrimalDual and HighestGainPathMethod::blocking_flow: augmenting paths of the blocking flows
+    pub augmentations: Option<usize>,
+    // what reached the sink in each round
+    pub flow_per_round: Vec<Flow>,
+    // rounds that brought at most EPS to the sink, many of them point at an epsilon too small for the capacities
+    pub stalled_rounds: usize,
+    // RoundedPrimalDual: the excess of the sink plus the capacity of the labeled cut before each round with a
+    // dijkstra, no flow with the rounded gains delivers more (see ScalingGraph::labeled_cut_capacity)
+    pub upper_bound_per_round: Vec<Flow>,
+}
+
+impl SolveStats {
+    pub(crate) fn record_round(&mut self, delivered: Flow) {
+        self.rounds += 1;
+        self.flow_per_round.push(delivered);
+        if delivered <= EPS {
+            self.stalled_rounds += 1;
+        }
+    }
+}
+
+// checked before each round of solve_with_limits, the defaults run to the end
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SolveLimits {
+    pub max_rounds: Option<usize>,
+    pub time_budget: Option<Duration>,
+    // RoundedPrimalDual only: stop once the labeled cut leaves at most this much flow to deliver, or this fraction of
+    // its upper bound, the value is then within the gap of the optimum with the rounded gains, so at least
+    // 1 - epsilon times the maximum minus the gap
+    pub absolute_gap: Option<Flow>,
+    pub relative_gap: Option<Flow>,
+}
+
+impl SolveLimits {
+    pub(crate) fn reached(&self, stats: &SolveStats, started: Instant) -> bool {
+        self.max_rounds
+            .is_some_and(|max_rounds| stats.rounds >= max_rounds)
+            || self
+                .time_budget
+                .is_some_and(|budget| started.elapsed() >= budget)
+    }
+
+    // at most remaining is left to deliver on top of delivered
+    pub(crate) fn gap_closed(&self, delivered: Flow, remaining: Flow) -> bool {
+        self.absolute_gap.is_some_and(|gap| remaining <= gap)
+            || self
+                .relative_gap
+                .is_some_and(|gap| remaining <= gap * (delivered + remaining))
+    }
+}
+
+// result of solve_with_limits, the flow in the graph is feasible either way, so value is a lower bound
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitedSolution {
+    pub value: Flow,
+    // a limit stopped the solve before a round found nothing left to send, value may then be below
+    // 1 - epsilon times the maximum
+    pub truncated: bool,
+}
+
+// adds one to a counter of SolveStats
+#[inline]
+pub(crate) fn count(counter: &mut Option<usize>) {
+    if let Some(c) = counter {
+        *c += 1;
+    }
 }
 
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InsideEdge {
     pub to: usize,
     pub flow: Flow,
     pub capacity: Flow,
     pub dist: Dist,
     pub rev: usize,
+    // per unit of flow, so the reverse edge refunds the cost of what arrived over the rounded gain
+    pub cost: Flow,
 }
 
 impl InsideEdge {
@@ -34,25 +377,178 @@ impl InsideEdge {
     }
 }
 
+impl EdgePayload for InsideEdge {
+    fn to(&self) -> usize {
+        self.to
+    }
+
+    fn rev(&self) -> usize {
+        self.rev
+    }
+
+    fn set_rev(&mut self, rev: usize) {
+        self.rev = rev;
+    }
+}
+
+// the queue of the dijkstras: dial's buckets hold the nodes of each distance modulo their number, until a
+// distance comes beyond the last of them, then a heap takes over
+// nodes of the same distance come in the order of their pushes from the buckets and from the largest index down from
+// the heap, the same on every run: the distances don't depend on it, which of several shortest paths
+// find_shortest_path_from_any takes does
+struct DistanceQueue {
+    buckets: Vec<Vec<usize>>,
+    current: Dist,
+    len: usize,
+    // empty until it takes over
+    heap: BinaryHeap<(Reverse<Dist>, usize)>,
+    is_heap: bool,
+}
+
+impl DistanceQueue {
+    fn push(&mut self, d: Dist, u: usize) {
+        let k = self.buckets.len();
+        if !self.is_heap && d - self.current >= k as Dist {
+            self.heap.reserve(self.len + 1);
+            for (j, bucket) in self.buckets.iter_mut().enumerate() {
+                let distance = self.current + ((j + k - self.current as usize % k) % k) as Dist;
+                self.heap
+                    .extend(bucket.drain(..).map(|v| (Reverse(distance), v)));
+            }
+            self.is_heap = true;
+        }
+        if self.is_heap {
+            self.heap.push((Reverse(d), u));
+        } else {
+            self.buckets[d as usize % k].push(u);
+            self.len += 1;
+        }
+    }
+
+    // a node of the smallest distance, nodes pushed again come more than once
+    fn pop(&mut self) -> Option<(Dist, usize)> {
+        if self.is_heap {
+            return self.heap.pop().map(|(Reverse(d), u)| (d, u));
+        }
+        if self.len == 0 {
+            return None;
+        }
+        let k = self.buckets.len();
+        loop {
+            if let Some(u) = self.buckets[self.current as usize % k].pop() {
+                self.len -= 1;
+                return Some((self.current, u));
+            }
+            self.current += 1;
+        }
+    }
+}
+
+// a node behind the sinks of a multi sink solve, numbered after the nodes of the user
+pub(crate) struct SuperSink {
+    pub node: usize,
+    num_edges: usize,
+    // the arc from each sink, None for the sinks without demand
+    arcs: Vec<Option<usize>>,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScalingGraph {
-    pub num_nodes: usize,
-    pub num_edges: usize,
-    pub base: Flow,
+    pub(crate) num_nodes: usize,
+    pub(crate) num_edges: usize,
+    // with an epsilon, build recomputes the base from the nodes the edges actually use
+    pub(crate) base: Flow,
+    epsilon: Option<Flow>,
     edge_list: Vec<Edge>,
-    pub is_lossy: bool,
+    // (node, gain) of set_node_gain
+    node_gains: Vec<(usize, Flow)>,
+    // the first of the two directed edges of each undirected edge
+    undirected_edges: Vec<usize>,
+    // no flow generating cycle after rounding the gains, set by build
+    pub(crate) is_lossy: bool,
+    has_gain_above_one: bool,
+    // every gain exactly 1, set by build
+    has_unit_gains: bool,
 
-    pub start: Vec<usize>,
-    pub inside_edge_list: Vec<InsideEdge>,
+    pub(crate) start: Vec<usize>,
+    pub(crate) inside_edge_list: Vec<InsideEdge>,
+    // position of the forward inside edge of each edge
+    pub(crate) inside_edge_index: Vec<usize>,
+    // and of the edge from the in copy of each node with a gain
+    node_gain_index: Vec<usize>,
+    // where the edges into each node end, its in copy if it has a gain
+    entries: Vec<usize>,
+    // in copies counted in num_nodes, numbered after the other nodes
+    num_copies: usize,
+    // the inside edges are laid out for the edges of edge_list
+    is_built: bool,
+    // and their gains rounded for the base, build then only resets the flow
+    is_scaled: bool,
 
-    pub excesses: Vec<Flow>,
-    pub potentials: Vec<Dist>,
+    pub(crate) excesses: Vec<Flow>,
+    pub(crate) potentials: Vec<Dist>,
+    // the potentials of the last rescale, which reset restores
+    initial_potentials: Vec<Dist>,
+
+    // see calculate_distance_to_sink_with_negative_edge, set by default
+    pub queue_heuristics: bool,
+    // of the last calculate_distance_to_sink_with_negative_edge
+    pub(crate) relaxations: usize,
+    // dial's buckets for the dijkstras of calculate_distance_to_sink and find_shortest_path while the queued
+    // distances stay within num_nodes of each other, the heap alone when unset, set by default
+    pub bucket_queue: bool,
+    // with the rayon feature, calculate_distance_to_sink runs delta stepping on the threads of rayon from this many
+    // inside edges on (two per edge), for the same distances and potentials: 0 for always, usize::MAX for never,
+    // DELTA_STEPPING_MIN_EDGES by default
+    pub delta_stepping_min_edges: usize,
+    // kept from one dijkstra to the next, with the heap that takes over from them
+    #[cfg_attr(feature = "serde", serde(skip))]
+    buckets: Vec<Vec<usize>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    heap: BinaryHeap<(Reverse<Dist>, usize)>,
+    // and so are the vectors of num_nodes of the dijkstras, refilled by each
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) distance: Vec<Dist>,
+    // the nodes the last dijkstra settled, whose potentials it updated
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) visited: Vec<bool>,
+    // of the last update_distance_to_sink
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) distance_to_sink: Vec<Dist>,
+    // of the last shortest_path_from_any
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) prev: Vec<(usize, usize)>,
+
+    // of the base of the last rescale, empty after deserializing, which only makes label and scaled_gain compute them
+    #[cfg_attr(feature = "serde", serde(skip))]
+    powers: Powers,
+
+    // kahan summation for the flows of the inside edges and the excesses of add_excess, unset by default
+    pub compensated: bool,
+    flow_compensation: Vec<Flow>,
+    excess_compensation: Vec<Flow>,
+    // what push_real_flow moved the flows by to keep them in range, since build
+    drift: Flow,
+    // once set, the edges keep their forward flow as an i64 count of FLOW_UNIT against their capacity, which validate
+    // requires to be an integer up to INTEGRAL_CAPACITY_MAX, push_real_flow clamps it into [0, capacity] by exact
+    // comparisons and derives both f64 flows of the inside edges from it, so that a saturated edge and its reverse
+    // edge agree exactly, unset by default, from the next build
+    // each push rounds what it sends and what it receives to FLOW_UNIT once, and the excesses take those amounts
+    // (see round_flow), the inside edges of the node gains and of the demands of fractional size keep f64 flows
+    pub integral_capacities: bool,
+    // (flow, capacity) in units of FLOW_UNIT at the forward inside edges of integer capacity, None elsewhere, empty
+    // when integral_capacities was unset at the last build
+    unit_flows: Vec<Option<(i64, i64)>>,
 }
 
 #[allow(dead_code)]
 impl<'a> ScalingGraph {
+    // num_nodes only gives the base until build
     pub fn new(num_nodes: usize, epsilon: Flow) -> Self {
-        let base = (1.0 + epsilon).powf(1.0 / num_nodes as Flow) as Flow;
-        Self::new_with_base(base)
+        ScalingGraph {
+            epsilon: Some(epsilon),
+            ..Self::new_with_base(base_for(num_nodes, epsilon))
+        }
     }
 
     pub fn new_with_base(base: Flow) -> Self {
@@ -60,58 +556,803 @@ impl<'a> ScalingGraph {
             num_nodes: 0,
             num_edges: 0,
             base,
+            epsilon: None,
             edge_list: Vec::new(),
+            node_gains: Vec::new(),
+            undirected_edges: Vec::new(),
             is_lossy: true,
+            has_gain_above_one: false,
+            has_unit_gains: false,
 
             start: Vec::new(),
             inside_edge_list: Vec::new(),
+            inside_edge_index: Vec::new(),
+            node_gain_index: Vec::new(),
+            entries: Vec::new(),
+            num_copies: 0,
+            is_built: false,
+            is_scaled: false,
 
             excesses: Vec::new(),
             potentials: Vec::new(),
+            initial_potentials: Vec::new(),
+
+            queue_heuristics: true,
+            relaxations: 0,
+            bucket_queue: true,
+            delta_stepping_min_edges: DELTA_STEPPING_MIN_EDGES,
+            buckets: Vec::new(),
+            heap: BinaryHeap::new(),
+            distance: Vec::new(),
+            visited: Vec::new(),
+            distance_to_sink: Vec::new(),
+            prev: Vec::new(),
+            powers: Powers::default(),
+
+            compensated: false,
+            flow_compensation: Vec::new(),
+            excess_compensation: Vec::new(),
+            drift: 0.0,
+            integral_capacities: false,
+            unit_flows: Vec::new(),
         }
     }
 
-    pub fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow, gain: Flow) -> Option<usize> {
-        if gain <= 0.0 {
-            eprintln!("warning. gain needs to be greater than 0");
-            return None;
+    // None (with a warning if the log feature is enabled) if try_add_directed_edge rejects the edge
+    pub fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Option<usize> {
+        let result = self.try_add_directed_edge(from, to, capacity, gain);
+        #[cfg(feature = "log")]
+        if let Err(error) = result {
+            log::warn!(
+                "edge {} -> {} (capacity {}, gain {}) is ignored: {}",
+                from,
+                to,
+                capacity,
+                gain,
+                error
+            );
         }
-        if capacity <= 0.0 {
-            eprintln!("warning. capacity needs to be greater than 0");
-            return None;
+        result.ok()
+    }
+
+    pub fn try_add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.try_add_directed_edge_with_cost(from, to, capacity, gain, 0.0)
+    }
+
+    // None (with a warning if the log feature is enabled) if try_add_directed_edge_with_cost rejects the edge
+    pub fn add_directed_edge_with_cost(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+        cost: Flow,
+    ) -> Option<usize> {
+        let result = self.try_add_directed_edge_with_cost(from, to, capacity, gain, cost);
+        #[cfg(feature = "log")]
+        if let Err(error) = result {
+            log::warn!(
+                "edge {} -> {} (capacity {}, gain {}, cost {}) is ignored: {}",
+                from,
+                to,
+                capacity,
+                gain,
+                cost,
+                error
+            );
+        }
+        result.ok()
+    }
+
+    // cost per unit entering the edge, only the minimum cost solvers look at it, and from != to: the rounded gain of
+    // a self loop would make its two arcs a cycle of their own
+    pub fn try_add_directed_edge_with_cost(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+        cost: Flow,
+    ) -> Result<usize, EdgeError> {
+        check_edge(capacity, gain)?;
+        check_cost(cost)?;
+        if from == to {
+            return Err(if gain > 1.0 {
+                EdgeError::FlowGeneratingSelfLoop
+            } else {
+                EdgeError::SelfLoop
+            });
         }
 
         if gain > 1.0 {
-            self.is_lossy = false;
+            self.has_gain_above_one = true;
         }
 
+        self.drop_copies();
         self.edge_list.push(Edge {
             from,
             to,
             flow: 0 as Flow,
             capacity,
             gain,
+            cost,
+            lower: 0.0,
         });
         self.num_nodes = self.num_nodes.max(from.max(to) + 1);
         self.num_edges += 1;
 
-        Some(self.num_edges - 1)
+        Ok(self.num_edges - 1)
+    }
+
+    // None (with a warning if the log feature is enabled) if try_add_undirected_edge rejects the edge
+    pub fn add_undirected_edge(
+        &mut self,
+        u: usize,
+        v: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Option<usize> {
+        let result = self.try_add_undirected_edge(u, v, capacity, gain);
+        #[cfg(feature = "log")]
+        if let Err(error) = result {
+            log::warn!(
+                "edge {} -- {} (capacity {}, gain {}) is ignored: {}",
+                u,
+                v,
+                capacity,
+                gain,
+                error
+            );
+        }
+        result.ok()
+    }
+
+    // a pipe between u and v that flow can take in either direction: x units entering it at one end leave gain * x
+    // at the other, whichever end that is, and the units entering it at both ends together are at most capacity
+    // it takes the indices i (u -> v) and i + 1 (v -> u) of two directed edges, whose get_flow reads what went each
+    // way: flow both ways at once only loses more than their difference sent one way, so the solvers leave at most
+    // one of them with flow (see cancel_opposite_flows), and a gain above 1, with which the two would make a flow
+    // generating cycle, is rejected
+    pub fn try_add_undirected_edge(
+        &mut self,
+        u: usize,
+        v: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        check_edge(capacity, gain)?;
+        if gain > 1.0 {
+            return Err(EdgeError::UndirectedGainAboveOne);
+        }
+        let i = self.try_add_directed_edge(u, v, capacity, gain)?;
+        self.try_add_directed_edge(v, u, capacity, gain)?;
+        self.undirected_edges.push(i);
+        Ok(i)
+    }
+
+    // None (with a warning if the log feature is enabled) if try_add_directed_edge_with_lower_bound rejects the edge
+    pub fn add_directed_edge_with_lower_bound(
+        &mut self,
+        from: usize,
+        to: usize,
+        lower: Flow,
+        upper: Flow,
+        gain: Flow,
+    ) -> Option<usize> {
+        let result = self.try_add_directed_edge_with_lower_bound(from, to, lower, upper, gain);
+        #[cfg(feature = "log")]
+        if let Err(error) = result {
+            log::warn!(
+                "edge {} -> {} (lower {}, capacity {}, gain {}) is ignored: {}",
+                from,
+                to,
+                lower,
+                upper,
+                gain,
+                error
+            );
+        }
+        result.ok()
+    }
+
+    // at least lower and at most upper units enter the edge, only RoundedPrimalDual solves with such edges: the
+    // lower units leave from as a demand and lower times the rounded gain arrives at to as a supply, the inside edge
+    // carries the flow above them, up to upper - lower
+    pub fn try_add_directed_edge_with_lower_bound(
+        &mut self,
+        from: usize,
+        to: usize,
+        lower: Flow,
+        upper: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        check_edge(upper, gain)?;
+        if !(0.0..=upper).contains(&lower) {
+            return Err(EdgeError::InvalidLowerBound);
+        }
+        let i = self.try_add_directed_edge(from, to, upper, gain)?;
+        self.edge_list[i].lower = lower;
+        Ok(i)
+    }
+
+    // a loss (or gain) on all flow through u, arriving along edges or as supply, before it leaves along edges
+    // or stays at a sink: build splits u into an in copy, which the edges into u end at, and u itself, joined by
+    // an edge of unbounded capacity and this gain, rounded like the ones of the edges; gain 1 undoes it
+    // the solvers take sources and sinks as nodes of the user, get_node_flow reads the flow through u back, only
+    // num_nodes counts the in copies after building
+    pub fn set_node_gain(&mut self, u: usize, gain: Flow) -> Result<(), EdgeError> {
+        check_edge(1.0, gain)?;
+        if gain > 1.0 {
+            self.has_gain_above_one = true;
+        }
+
+        self.drop_copies();
+        self.node_gains.retain(|&(v, _)| v != u);
+        if gain != 1.0 {
+            self.node_gains.push((u, gain));
+        }
+        self.num_nodes = self.num_nodes.max(u + 1);
+        Ok(())
+    }
+
+    // the nodes of the user only, until build numbers the in copies again
+    fn drop_copies(&mut self) {
+        self.num_nodes -= self.num_copies;
+        self.num_copies = 0;
+        self.is_built = false;
+    }
+
+    // the nodes without the in copies
+    pub(crate) fn num_user_nodes(&self) -> usize {
+        self.num_nodes - self.num_copies
+    }
+
+    // the node supply of u enters, after building
+    pub(crate) fn entry(&self, u: usize) -> usize {
+        self.entries[u]
+    }
+
+    // the node of the user a node of the built graph belongs to
+    pub(crate) fn user_node(&self, v: usize) -> usize {
+        let first_copy = self.num_user_nodes();
+        if v >= first_copy {
+            self.node_gains[v - first_copy].0
+        } else {
+            v
+        }
+    }
+
+    // the nodes of set_node_gain
+    pub(crate) fn nodes_with_gain(&self) -> impl Iterator<Item = usize> + '_ {
+        self.node_gains.iter().map(|&(u, _)| u)
+    }
+
+    // the checks shared by the solvers before building
+    pub fn validate(&self, sources: &[usize], sink: usize) -> Result<(), GeneralizedFlowError> {
+        if self.num_edges == 0 {
+            return Err(GeneralizedFlowError::EmptyGraph);
+        }
+        for &u in sources.iter().chain([&sink]) {
+            if u >= self.num_user_nodes() {
+                return Err(GeneralizedFlowError::InvalidTerminal(u));
+            }
+        }
+        if sources.contains(&sink) {
+            return Err(GeneralizedFlowError::SourceEqualsSink);
+        }
+        if self.integral_capacities {
+            if let Some(i) = self.edge_list.iter().position(|e| {
+                let capacity = e.capacity - e.lower;
+                capacity.fract() != 0.0 || capacity > INTEGRAL_CAPACITY_MAX
+            }) {
+                return Err(GeneralizedFlowError::NonIntegralCapacity(i));
+            }
+        }
+        Ok(())
+    }
+
+    // gain 1 arcs from each sink with capacity its demand, until remove_super_sink
+    pub(crate) fn add_super_sink(
+        &mut self,
+        source: usize,
+        sinks: &[(usize, Flow)],
+    ) -> Result<SuperSink, GeneralizedFlowError> {
+        self.validate(&[], source)?;
+        for &(sink, demand) in sinks {
+            self.validate(&[source], sink)?;
+            if demand.is_nan() || demand < 0.0 {
+                return Err(GeneralizedFlowError::InvalidDemand);
+            }
+        }
+
+        self.drop_copies();
+        let node = self.num_nodes;
+        let num_edges = self.num_edges;
+        let arcs = sinks
+            .iter()
+            .map(|&(sink, demand)| {
+                if demand > 0.0 {
+                    self.try_add_directed_edge(sink, node, demand.min(FLOW_MAX), 1.0)
+                        .ok()
+                } else {
+                    None
+                }
+            })
+            .collect();
+        // even without any arc
+        self.num_nodes = node + 1;
+        self.is_built = false;
+        Ok(SuperSink {
+            node,
+            num_edges,
+            arcs,
+        })
+    }
+
+    // what each sink passed on to the super sink, after solving
+    pub(crate) fn received(&self, super_sink: &SuperSink) -> Vec<Flow> {
+        super_sink
+            .arcs
+            .iter()
+            .map(|arc| arc.map_or(0.0, |i| self.get_flow(i).1))
+            .collect()
+    }
+
+    // keeps what the sinks passed on where it is for the rest of the solve: no path runs back out of the super sink,
+    // and its excess no longer counts
+    pub(crate) fn close_super_sink(&mut self, super_sink: &SuperSink) {
+        for &i in super_sink.arcs.iter().flatten() {
+            self.freeze(i);
+        }
+        self.excesses[super_sink.node] = 0.0;
+    }
+
+    // get_flow keeps working for the edges of the user until the next build
+    pub(crate) fn remove_super_sink(&mut self, super_sink: SuperSink) {
+        self.edge_list.truncate(super_sink.num_edges);
+        self.inside_edge_index.truncate(super_sink.num_edges);
+        self.num_edges = super_sink.num_edges;
+        self.num_nodes = super_sink.node;
+        self.num_copies = 0;
+        self.is_built = false;
+    }
+
+    // the first directed edge of each undirected edge, the second comes right after it
+    pub fn undirected_edges(&self) -> impl Iterator<Item = usize> + '_ {
+        self.undirected_edges.iter().copied()
+    }
+
+    // the nodes of the user, without the in copies of set_node_gain
+    pub fn num_nodes(&self) -> usize {
+        self.num_user_nodes()
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.num_edges
+    }
+
+    // as added, in the order of their indices, the flow of a solve is get_flow
+    pub fn edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edge_list.iter()
+    }
+
+    // the gains are rounded down to powers of base
+    pub fn base(&self) -> Flow {
+        self.base
+    }
+
+    // no flow generating cycle after rounding the gains, after building
+    pub fn is_lossy(&self) -> bool {
+        self.is_lossy
+    }
+
+    // an edge with a lower bound above 0
+    pub fn has_lower_bounds(&self) -> bool {
+        self.edge_list.iter().any(|e| e.lower > 0.0)
+    }
+
+    // (from, lower bound, to, what the lower bound brings there with the rounded gain, in the built graph) for the
+    // edges with one, after building
+    pub(crate) fn lower_bounds(&self) -> impl Iterator<Item = (usize, Flow, usize, Flow)> + '_ {
+        self.edge_list
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.lower > 0.0)
+            .map(|(i, e)| {
+                (
+                    e.from,
+                    e.lower,
+                    self.entry(e.to),
+                    e.lower * self.rounded_gain(i),
+                )
+            })
+    }
+
+    // an ordinary maximum flow problem, after building: the rounded gains and the labels are all 1
+    pub fn has_unit_gains(&self) -> bool {
+        self.has_unit_gains
+    }
+
+    // the reduced cost of an inside edge from u is its dist - potential(u) + potential(to), after building
+    pub fn potential(&self, u: usize) -> Dist {
+        self.potentials[u]
+    }
+
+    // of a node of the built graph, see excess_at
+    pub fn excess(&self, u: usize) -> Flow {
+        self.excesses[u]
+    }
+
+    // what the last solve left at node u of the user, with the excess at its in copy if it has a gain
+    pub fn excess_at(&self, u: usize) -> Flow {
+        let entry = self.entry(u);
+        if entry != u {
+            self.excesses[u] + self.excesses[entry]
+        } else {
+            self.excesses[u]
+        }
+    }
+
+    // of the last calculate_distance_to_sink_with_negative_edge
+    pub fn relaxations(&self) -> usize {
+        self.relaxations
+    }
+
+    // all 0, as build leaves them for a network with a flow generating cycle
+    pub fn clear_potentials(&mut self) {
+        self.potentials = vec![0; self.num_nodes];
     }
 
     pub fn get_directed_edge(&self, edge_index: usize) -> &Edge {
         &self.edge_list[edge_index]
     }
 
+    // (flow entering the edge at from, flow leaving it at to) in original units, after solving, with the lower bound
+    // the inside flows are already multiplied by the labels, the solver accounted for the gain
+    // rounded down to a power of base, so to may keep up to a factor base more than it sent on
+    pub fn get_flow(&self, edge_index: usize) -> (Flow, Flow) {
+        let edge = &self.edge_list[edge_index];
+        let flow = self.inside_edge_list[self.inside_edge_index[edge_index]].flow + edge.lower;
+        (flow, flow * edge.gain)
+    }
+
+    // the gain the solver worked with, rounded down to a power of base
+    // the inside edge of a lower bound carries only the rest of the capacity, which may be nothing
+    pub(crate) fn rounded_gain(&self, edge_index: usize) -> Flow {
+        let forward = &self.inside_edge_list[self.inside_edge_index[edge_index]];
+        let edge = &self.edge_list[edge_index];
+        if edge.lower > 0.0 {
+            return self.scaled_gain(forward.dist);
+        }
+        self.inside_edge_list[forward.rev].capacity / edge.capacity
+    }
+
+    // the flow through the inside edge of edge_index can no longer change, until the next build
+    pub(crate) fn freeze(&mut self, edge_index: usize) {
+        let forward = self.inside_edge_index[edge_index];
+        let rev = self.inside_edge_list[forward].rev;
+        self.inside_edge_list[forward].capacity = self.inside_edge_list[forward].flow;
+        self.inside_edge_list[rev].capacity = self.inside_edge_list[rev].flow;
+        if let Some(Some((flow, capacity))) = self.unit_flows.get_mut(forward) {
+            *capacity = *flow;
+        }
+    }
+
+    // (flow entering u, flow leaving it) through the gain of set_node_gain in original units, after solving,
+    // None for the nodes without one
+    pub fn get_node_flow(&self, u: usize) -> Option<(Flow, Flow)> {
+        let k = self.node_gains.iter().position(|&(v, _)| v == u)?;
+        let flow = self.inside_edge_list[self.node_gain_index[k]].flow;
+        Some((flow, flow * self.node_gains[k].1))
+    }
+
+    // base^d, the label of a node at distance d from the sink
+    #[inline]
+    pub(crate) fn label(&self, d: Dist) -> Flow {
+        self.powers
+            .label(d)
+            .unwrap_or_else(|| self.base.powf(d as Flow))
+    }
+
+    // base^-dist, the rounded gain of an inside edge of this dist
+    #[inline]
+    pub(crate) fn scaled_gain(&self, dist: Dist) -> Flow {
+        self.powers
+            .gain(dist)
+            .unwrap_or_else(|| self.base.powi(-dist))
+    }
+
+    // the gain of u the solver worked with, 1 without set_node_gain
+    pub(crate) fn rounded_node_gain(&self, u: usize) -> Flow {
+        match self.node_gains.iter().position(|&(v, _)| v == u) {
+            Some(k) => {
+                let edge = &self.inside_edge_list[self.node_gain_index[k]];
+                if edge.capacity > 0.0 {
+                    self.scaled_gain(edge.dist)
+                } else {
+                    0.0
+                }
+            }
+            None => 1.0,
+        }
+    }
+
+    // weak duality: prices of the nodes, 0 at the sources (of unlimited supply) and 1 at the sink, bound the flow of
+    // any solve by the sum over the edges of capacity * max(0, gain * price(to) - price(from)), here base^-d for the
+    // distances d to the sink of the last update_distance_to_sink and 0 out of its reach
+    // (bound with the rounded gains, bound with the original gains, where the in copies pay their gain times the
+    // price of their node so that the edges between them add nothing)
+    // the lower bound of an edge carries its units whatever the prices, so it adds lower * (gain * price(to) - price(from))
+    // and the capacity above it the rest
+    pub(crate) fn dual_bounds(&self, sources: &[usize], sink: usize) -> (Flow, Flow) {
+        let distance = &self.distance_to_sink;
+        let reachable = |u: usize| distance[u] != DIST_MAX && !sources.contains(&u);
+        let mut prices: Vec<Flow> = (0..self.num_nodes)
+            .map(|u| {
+                if reachable(u) {
+                    1.0 / self.label(distance[u])
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        prices[sink] = 1.0;
+
+        // on the distances, as a residual edge with a price difference of a rounding error could take up to FLOW_MAX
+        let mut rounded = 0.0;
+        for &i in self
+            .inside_edge_index
+            .iter()
+            .chain(self.node_gain_index.iter())
+        {
+            let edge = &self.inside_edge_list[i];
+            let from = self.inside_edge_list[edge.rev].to;
+            if reachable(edge.to)
+                && (!reachable(from) || distance[from] > distance[edge.to] + edge.dist)
+            {
+                rounded += edge.capacity
+                    * (1.0 / self.label(distance[edge.to] + edge.dist) - prices[from]);
+            }
+        }
+        for (from, lower, to, received) in self.lower_bounds() {
+            rounded += received * prices[to] - lower * prices[from];
+        }
+
+        let first_copy = self.num_user_nodes();
+        for (k, &(u, gain)) in self.node_gains.iter().enumerate() {
+            if !sources.contains(&(first_copy + k)) {
+                prices[first_copy + k] = gain * prices[u];
+            }
+        }
+        let mut original = 0.0;
+        for (edge, &i) in self.edge_list.iter().zip(self.inside_edge_index.iter()) {
+            let (to, from) = (
+                self.inside_edge_list[i].to,
+                self.inside_edge_list[self.inside_edge_list[i].rev].to,
+            );
+            let difference = edge.gain * prices[to] - prices[from];
+            original +=
+                (edge.capacity - edge.lower) * difference.max(0.0) + edge.lower * difference;
+        }
+        (rounded, original)
+    }
+
+    // the capacity of the labeled cut of the last update_distance_to_sink, with the prices of dual_bounds and 0 at the
+    // sources (their in copies included): what the residual edges out of the sources and the excesses elsewhere can
+    // still bring to the sink, as the residual edges between the other nodes reached lose at least what the distances
+    // say, an upper bound on what the rest of a solve adds to the excess of the sink
+    pub(crate) fn labeled_cut_capacity(&self, sources: &[usize], sink: usize) -> Flow {
+        let distance = &self.distance_to_sink;
+        let is_source = |v: usize| sources.contains(&self.user_node(v));
+        let mut capacity = 0.0;
+        for v in (0..self.num_nodes).filter(|&v| v != sink) {
+            if !is_source(v) {
+                if distance[v] != DIST_MAX && self.excesses[v] > 0.0 {
+                    capacity += self.excesses[v] / self.label(distance[v]);
+                }
+                continue;
+            }
+            for e in self.neighbors(v) {
+                if e.residual_capacity() > 0.0 && distance[e.to] != DIST_MAX && !is_source(e.to) {
+                    capacity += e.residual_capacity() * self.scaled_gain(e.dist)
+                        / self.label(distance[e.to]);
+                }
+            }
+        }
+        capacity
+    }
+
+    // splits the flow of get_flow into source-sink paths and cycles, after solving
+    // uses the rounded gains, which the flow is conserved with, so the delivered amounts add up to
+    // the solver's answer (the original gains would deliver up to a factor 1 + epsilon more)
+    pub fn decompose(
+        &self,
+        source: usize,
+        sink: usize,
+    ) -> (Vec<GeneralizedPath>, Vec<GeneralizedPath>) {
+        let mut remaining: Vec<Flow> = (0..self.num_edges).map(|i| self.get_flow(i).0).collect();
+        let is_zero = |flow: Flow, i: usize| flow <= DECOMPOSITION_EPS * self.edge_list[i].capacity;
+        let mut out_edges = vec![Vec::new(); self.num_nodes];
+        for (i, e) in self.edge_list.iter().enumerate() {
+            out_edges[e.from].push(i);
+        }
+
+        let mut paths = Vec::new();
+        let mut cycles = Vec::new();
+        loop {
+            // walk along edges with flow left until the sink or a node seen before
+            let mut position = vec![usize::MAX; self.num_nodes];
+            let mut nodes = vec![source];
+            let mut edges: Vec<usize> = Vec::new();
+            position[source] = 0;
+            let mut u = source;
+            while u != sink {
+                match out_edges[u].iter().find(|&&i| !is_zero(remaining[i], i)) {
+                    Some(&i) => {
+                        u = self.edge_list[i].to;
+                        edges.push(i);
+                        if position[u] != usize::MAX {
+                            break;
+                        }
+                        position[u] = nodes.len();
+                        nodes.push(u);
+                    }
+                    None => break,
+                }
+            }
+
+            if u == source && edges.is_empty() {
+                break;
+            }
+            let (start, is_cycle) = match u {
+                _ if u == sink => (0, false),
+                _ if position[u] != usize::MAX && edges.len() == nodes.len() => (position[u], true),
+                _ => {
+                    // u keeps what the rounding left over, drop the last edge into it
+                    let &i = edges.last().unwrap();
+                    remaining[i] = 0.0;
+                    continue;
+                }
+            };
+
+            let mut nodes = nodes[start..].to_vec();
+            let edges = &edges[start..];
+            if is_cycle {
+                nodes.push(u);
+            }
+
+            // multiplier[k]: flow at nodes[k] per unit injected, past the gain of the node
+            let mut multiplier = vec![
+                if is_cycle {
+                    1.0
+                } else {
+                    self.rounded_node_gain(nodes[0])
+                };
+                edges.len() + 1
+            ];
+            for (k, &i) in edges.iter().enumerate() {
+                multiplier[k + 1] = multiplier[k]
+                    * self.rounded_gain(i)
+                    * self.rounded_node_gain(self.edge_list[i].to);
+            }
+            let injected = edges
+                .iter()
+                .enumerate()
+                .map(|(k, &i)| remaining[i] / multiplier[k])
+                .fold(FLOW_MAX, Flow::min);
+            for (k, &i) in edges.iter().enumerate() {
+                remaining[i] -= injected * multiplier[k];
+                if is_zero(remaining[i], i) {
+                    remaining[i] = 0.0;
+                }
+            }
+
+            let delivered = injected * multiplier[edges.len()];
+            let path = GeneralizedPath {
+                nodes,
+                injected,
+                delivered,
+            };
+            if is_cycle {
+                cycles.push(path);
+            } else {
+                paths.push(path);
+            }
+        }
+        (paths, cycles)
+    }
+
     pub fn neighbors(&'a self, u: usize) -> std::slice::Iter<'a, InsideEdge> {
         self.inside_edge_list[self.start[u]..self.start[u + 1]].iter()
     }
 
+    // flow in units of the labels, which are FLOW_MAX only at the nodes that cannot reach the sink, and no
+    // augmenting path passes those, see push_real_flow for what it returns
     #[inline]
-    pub fn push_flow(&mut self, u: usize, i: usize, flow: Flow, labels: &Vec<Flow>) {
+    pub fn push_flow(&mut self, u: usize, i: usize, flow: Flow, labels: &[Flow]) -> (Flow, Flow) {
         let to = self.inside_edge_list[i].to;
+        debug_assert!(
+            labels[u] < FLOW_MAX && labels[to] < FLOW_MAX,
+            "flow pushed through {} -> {} without a label",
+            u,
+            to
+        );
+        let (sent, received) = (flow * labels[u], flow * labels[to]);
+        debug_assert!(
+            sent.is_finite() && received.is_finite(),
+            "flow {} pushed through {} -> {}",
+            flow,
+            u,
+            to
+        );
+        self.push_real_flow(i, sent, received)
+    }
+
+    // push_flow that also moves the excess from u to the head of edge i, for the algorithms that keep one at every
+    // node instead of at the ends of the paths
+    #[inline]
+    pub fn push_flow_with_excess(&mut self, u: usize, i: usize, flow: Flow, labels: &[Flow]) {
+        let to = self.inside_edge_list[i].to;
+        self.push_real_flow_with_excess(u, i, flow * labels[u], flow * labels[to]);
+    }
+
+    // push_real_flow from u, and sent out of the excess of u into received at the head
+    #[inline]
+    pub fn push_real_flow_with_excess(&mut self, u: usize, i: usize, sent: Flow, received: Flow) {
+        let to = self.inside_edge_list[i].to;
+        let (sent, received) = self.push_real_flow(i, sent, received);
+        self.add_excess(u, -sent);
+        self.add_excess(to, received);
+    }
+
+    // sent leaves the tail of inside edge i and received arrives at its head, both in original units, and the two
+    // are returned as moved: rounded by round_flow, for the excesses
+    #[inline]
+    pub fn push_real_flow(&mut self, i: usize, sent: Flow, received: Flow) -> (Flow, Flow) {
         let rev = self.inside_edge_list[i].rev;
-        self.inside_edge_list[i].flow += flow * labels[u];
-        self.inside_edge_list[rev].flow -= flow * labels[to];
+        let (sent, received) = (self.round_flow(sent), self.round_flow(received));
+        if !self.unit_flows.is_empty() {
+            // along the reverse edge, what arrives at its head leaves the forward edge
+            if self.unit_flows[i].is_some() {
+                self.push_units(i, sent);
+                return (sent, received);
+            }
+            if self.unit_flows[rev].is_some() {
+                self.push_units(rev, -received);
+                return (sent, received);
+            }
+        }
+        if self.compensated {
+            compensated_add(
+                &mut self.inside_edge_list[i].flow,
+                &mut self.flow_compensation[i],
+                sent,
+            );
+            compensated_add(
+                &mut self.inside_edge_list[rev].flow,
+                &mut self.flow_compensation[rev],
+                -received,
+            );
+        } else {
+            self.inside_edge_list[i].flow += sent;
+            self.inside_edge_list[rev].flow -= received;
+        }
+        let (flow, rev_flow) = (
+            self.inside_edge_list[i].flow,
+            self.inside_edge_list[rev].flow,
+        );
 
         if self.inside_edge_list[i].flow > self.inside_edge_list[i].capacity {
             self.inside_edge_list[i].flow = self.inside_edge_list[i].capacity;
@@ -123,88 +1364,382 @@ impl<'a> ScalingGraph {
             self.inside_edge_list[i].flow = self.inside_edge_list[i].capacity;
         }
 
-        if self.inside_edge_list[i].residual_capacity() <= EPS || self.inside_edge_list[rev].flow <= EPS {
+        // less than the unit of integral_capacities can't be pushed
+        let snap = if self.unit_flows.is_empty() {
+            EPS
+        } else {
+            FLOW_UNIT
+        };
+        if self.inside_edge_list[i].residual_capacity() <= snap
+            || self.inside_edge_list[rev].flow <= snap
+        {
             self.inside_edge_list[i].flow = self.inside_edge_list[i].capacity;
             self.inside_edge_list[rev].flow = 0.0;
         }
+
+        let correction = (self.inside_edge_list[i].flow - flow).abs()
+            + (self.inside_edge_list[rev].flow - rev_flow).abs();
+        if correction > 0.0 {
+            self.drift += correction;
+            self.flow_compensation[i] = 0.0;
+            self.flow_compensation[rev] = 0.0;
+        }
+        (sent, received)
+    }
+
+    // x as push_real_flow moves it: to the nearest multiple of FLOW_UNIT with integral_capacities, as is otherwise
+    #[inline]
+    pub fn round_flow(&self, x: Flow) -> Flow {
+        if self.unit_flows.is_empty() {
+            return x;
+        }
+        // the amounts past 2^1023 * FLOW_UNIT are whole units already
+        let units = (x / FLOW_UNIT).round();
+        if units.is_finite() {
+            units * FLOW_UNIT
+        } else {
+            x
+        }
     }
 
+    // amount, a multiple of FLOW_UNIT, more on forward inside edge i of integral_capacities
+    fn push_units(&mut self, i: usize, amount: Flow) {
+        let (flow, capacity) = self.unit_flows[i].unwrap();
+        let moved = flow.saturating_add((amount / FLOW_UNIT) as i64);
+        let clamped = moved.clamp(0, capacity);
+        if clamped != moved {
+            self.drift += clamped.abs_diff(moved) as Flow * FLOW_UNIT;
+        }
+        self.unit_flows[i] = Some((clamped, capacity));
+        let edge = &self.inside_edge_list[i];
+        let (rev, scaled_gain) = (edge.rev, self.scaled_gain(edge.dist));
+        self.inside_edge_list[i].flow = clamped as Flow * FLOW_UNIT;
+        // capacity * scaled_gain without flow, as rescale sets the reverse edge, and 0 once saturated
+        self.inside_edge_list[rev].flow = (capacity - clamped) as Flow * FLOW_UNIT * scaled_gain;
+    }
+
+    // the unit_flows of integral_capacities for the capacities of the forward inside edges, without flow
+    // the edges of the user pass validate, the demands of the super sink may be fractional
+    fn set_unit_flows(&mut self) {
+        self.unit_flows.clear();
+        if !self.integral_capacities {
+            return;
+        }
+        self.unit_flows = vec![None; self.inside_edge_list.len()];
+        for &forward in self.inside_edge_index.iter() {
+            let capacity = self.inside_edge_list[forward].capacity;
+            if capacity.fract() == 0.0 && capacity <= INTEGRAL_CAPACITY_MAX {
+                self.unit_flows[forward] = Some((0, (capacity / FLOW_UNIT) as i64));
+            }
+        }
+    }
+
+    // leaves at most one direction of each undirected edge with flow, after solving: what both carry is taken back
+    // out of both, and the ends keep what the two directions no longer lose as excess
+    pub(crate) fn cancel_opposite_flows(&mut self) {
+        for k in 0..self.undirected_edges.len() {
+            let (a, b) = (
+                self.inside_edge_index[self.undirected_edges[k]],
+                self.inside_edge_index[self.undirected_edges[k] + 1],
+            );
+            let common = self.inside_edge_list[a]
+                .flow
+                .min(self.inside_edge_list[b].flow);
+            if common > 0.0 {
+                self.take_back(a, common);
+                self.take_back(b, common);
+            }
+        }
+    }
+
+    // amount less sent along inside edge i, its tail keeps it and its head gives up what it received, which an in
+    // copy then passes back through its gain
+    // a negative push rather than one along the reverse edge, whose residual capacity stays 0 for the unbounded edges
+    // of the node gains (see rescale)
+    fn take_back(&mut self, i: usize, amount: Flow) {
+        let edge = &self.inside_edge_list[i];
+        let (from, to) = (self.inside_edge_list[edge.rev].to, edge.to);
+        let received = amount * self.scaled_gain(edge.dist);
+        self.push_real_flow_with_excess(from, i, -amount, -received);
+        let first_copy = self.num_user_nodes();
+        if to >= first_copy {
+            self.take_back(self.node_gain_index[to - first_copy], received);
+        }
+    }
+
+    // adds to the excess of u, compensated if set
+    #[inline]
+    pub fn add_excess(&mut self, u: usize, x: Flow) {
+        if self.compensated {
+            compensated_add(&mut self.excesses[u], &mut self.excess_compensation[u], x);
+        } else {
+            self.excesses[u] += x;
+        }
+    }
+
+    // the total amount push_real_flow clamped the flows of the last solve by, into [0, capacity] and to the
+    // capacity when less than EPS remains: rounding errors the augmentations leave on the edges
+    // with integral_capacities, the edges of integer capacity are only clamped into [0, capacity], the others when less
+    // than FLOW_UNIT remains
+    pub fn numerical_drift(&self) -> Flow {
+        self.drift
+    }
+
+    // the first build after adding edges lays out the inside edges, later ones only rescale them
     pub fn build(&mut self) {
-        let mut degree = vec![0; self.num_nodes];
-        let mut edge_index = vec![usize::MAX; self.num_edges];
-        let mut reverse_edge_index = vec![usize::MAX; self.num_edges];
+        if !self.is_built {
+            self.build_inside_edges();
+            self.is_scaled = false;
+        }
+        if self.is_scaled {
+            self.reset();
+        } else {
+            self.rescale();
+        }
+    }
 
-        let mut tmp_inside_edge_list = Vec::with_capacity(2 * self.num_edges);
-        for (i, e) in self.edge_list.iter().enumerate() {
-            edge_index[i] = degree[e.from];
-            degree[e.from] += 1;
-            reverse_edge_index[i] = degree[e.to];
-            degree[e.to] += 1;
+    // the base of the next build, the edges are kept
+    pub fn set_epsilon(&mut self, epsilon: Flow) {
+        self.epsilon = Some(epsilon);
+        self.is_scaled = false;
+    }
 
-            // gain scaling
-            let c = e.gain.log(self.base).floor();
-            let scaled_gain = self.base.powf(c);
-            let dist = -c as Dist; // TODO: check over flow
+    // back to the state of the last rescale without rounding the gains again: no flow, no excess, the potentials
+    // of build, nothing to do before the first build
+    pub fn reset(&mut self) {
+        if !self.is_scaled {
+            return;
+        }
+        for &forward in self
+            .inside_edge_index
+            .iter()
+            .chain(self.node_gain_index.iter())
+        {
+            self.inside_edge_list[forward].flow = 0.0;
+            let rev = self.inside_edge_list[forward].rev;
+            self.inside_edge_list[rev].flow = self.inside_edge_list[rev].capacity;
+        }
+        self.excesses.iter_mut().for_each(|excess| *excess = 0.0);
+        self.potentials.clone_from(&self.initial_potentials);
+        self.flow_compensation.iter_mut().for_each(|c| *c = 0.0);
+        self.excess_compensation.iter_mut().for_each(|c| *c = 0.0);
+        self.drift = 0.0;
+        self.set_unit_flows();
+    }
+
+    fn build_inside_edges(&mut self) {
+        // the in copies of the nodes with a gain come after the other nodes
+        self.entries = (0..self.num_nodes).collect();
+        for (k, &(u, _)) in self.node_gains.iter().enumerate() {
+            self.entries[u] = self.num_nodes + k;
+        }
+        self.num_copies = self.node_gains.len();
+        self.num_nodes += self.num_copies;
+
+        // the edges of the user, then the one from the in copy of each node with a gain
+        let arcs = self
+            .edge_list
+            .iter()
+            .map(|e| (e.from, self.entries[e.to]))
+            .chain(self.node_gains.iter().map(|&(u, _)| (self.entries[u], u)));
+        let layout = CsrGraph::build(
+            self.num_nodes,
+            arcs.map(|(from, to)| {
+                (
+                    from,
+                    to,
+                    InsideEdge {
+                        to,
+                        rev: usize::MAX,
+                        ..Default::default()
+                    },
+                    InsideEdge {
+                        to: from,
+                        rev: usize::MAX,
+                        ..Default::default()
+                    },
+                )
+            }),
+        );
+        self.start = layout.start;
+        self.inside_edge_list = layout.arcs;
+        let mut edge_index = layout.edge_index;
+        self.node_gain_index = edge_index.split_off(self.num_edges);
+        self.inside_edge_index = edge_index;
+        self.is_built = true;
+    }
+
+    // scaled distances and reverse capacities for the current base, without any flow
+    fn rescale(&mut self) {
+        if let Some(epsilon) = self.epsilon {
+            self.base = base_for(self.num_nodes, epsilon);
+        }
+
+        let max_dist = max_edge_dist(self.num_nodes, self.base);
+        let edges = self
+            .edge_list
+            .iter()
+            .map(|e| (e.capacity - e.lower, e.gain, e.cost))
+            .zip(self.inside_edge_index.iter());
+        let nodes = self
+            .node_gains
+            .iter()
+            .map(|&(_, gain)| (FLOW_MAX, gain, 0.0))
+            .zip(self.node_gain_index.iter());
+        // gain scaling, in f64 until the exponent is known to fit
+        // gains below base^-max_dist are dropped (capacity 0), the ones above base^max_dist are
+        // rounded down to it
+        let scaled: Vec<(Dist, bool)> = self
+            .edge_list
+            .iter()
+            .map(|e| e.gain)
+            .chain(self.node_gains.iter().map(|&(_, gain)| gain))
+            .map(|gain| {
+                let c = gain.log(self.base).floor();
+                let usable = -c <= max_dist as Flow;
+                (
+                    if usable {
+                        (-c).max(-max_dist as Flow) as Dist
+                    } else {
+                        max_dist
+                    },
+                    usable,
+                )
+            })
+            .collect();
+        // the distances to the sink add up at most num_nodes - 1 of the dists
+        let largest_dist = scaled
+            .iter()
+            .map(|&(dist, _)| dist.abs())
+            .max()
+            .unwrap_or(0);
+        let gain_range = largest_dist.min(POWERS_RANGE);
+        let label_range = (largest_dist as i64 * self.num_nodes.saturating_sub(1) as i64)
+            .min(POWERS_RANGE as i64) as Dist;
+        self.powers = Powers::new(self.base, label_range, gain_range);
+
+        for (((capacity, _, cost), &forward), &(dist, usable)) in
+            edges.chain(nodes).zip(scaled.iter())
+        {
+            let scaled_gain = self.scaled_gain(dist);
+            // the reverse capacity stays finite, for the unbounded edges of the nodes
+            let capacity = if usable {
+                capacity.min(FLOW_MAX / scaled_gain.max(1.0))
+            } else {
+                0.0
+            };
 
             // from -> to
-            tmp_inside_edge_list.push((
-                e.from,
-                InsideEdge {
-                    to: e.to,
-                    flow: 0 as Flow,
-                    capacity: e.capacity,
-                    dist,
-                    rev: usize::MAX,
-                },
-            ));
+            let edge = &mut self.inside_edge_list[forward];
+            edge.flow = 0 as Flow;
+            edge.capacity = capacity;
+            edge.dist = dist;
+            edge.cost = cost;
 
             // to -> from
-            tmp_inside_edge_list.push((
-                e.to,
-                InsideEdge {
-                    to: e.from,
-                    flow: e.capacity * scaled_gain,
-                    capacity: e.capacity * scaled_gain,
-                    dist: -dist,
-                    rev: usize::MAX,
-                },
-            ));
-        }
-
-        // make graph
-        self.start = vec![0; self.num_nodes + 1];
+            let rev = edge.rev;
+            let edge = &mut self.inside_edge_list[rev];
+            edge.flow = capacity * scaled_gain;
+            edge.capacity = capacity * scaled_gain;
+            edge.dist = -dist;
+            edge.cost = -cost / scaled_gain;
+        }
+
         self.excesses = vec![0 as Flow; self.num_nodes];
         self.potentials = vec![0; self.num_nodes];
-        self.inside_edge_list = vec![Default::default(); 2 * self.num_edges];
+        self.flow_compensation = vec![0.0; self.inside_edge_list.len()];
+        self.excess_compensation = vec![0.0; self.num_nodes];
+        self.drift = 0.0;
+        self.set_unit_flows();
 
-        for (u, _) in tmp_inside_edge_list.iter() {
-            self.start[u + 1] += 1;
-        }
-        for i in 1..=self.num_nodes {
-            self.start[i] += self.start[i - 1];
-        }
-
-        let mut counter = self.start.clone();
-        for (u, e) in tmp_inside_edge_list {
-            self.inside_edge_list[counter[u]] = e;
-            counter[u] += 1;
+        // a lossy network gets potentials for calculate_distance_to_sink right away, the others need
+        // calculate_distance_to_sink_with_negative_edge
+        self.is_lossy = true;
+        if self.has_gain_above_one {
+            match self.distance_from_all_nodes() {
+                Some(distance) => self.potentials = distance.iter().map(|&d| -d).collect(),
+                None => {
+                    #[cfg(feature = "log")]
+                    log::debug!("flow generating cycle among the {} nodes", self.num_nodes);
+                    self.is_lossy = false;
+                }
+            }
         }
+        self.has_unit_gains = self.edge_list.iter().all(|e| e.gain == 1.0)
+            && self.node_gains.iter().all(|&(_, gain)| gain == 1.0);
+        self.initial_potentials.clone_from(&self.potentials);
+        self.is_scaled = true;
+    }
 
-        for (i, e) in self.edge_list.iter().enumerate() {
-            edge_index[i] += self.start[e.from];
-            reverse_edge_index[i] += self.start[e.to];
-            self.inside_edge_list[edge_index[i]].rev = reverse_edge_index[i];
-            self.inside_edge_list[reverse_edge_index[i]].rev = edge_index[i];
+    // shortest distance to each node from any node over the residual edges (bellman-ford from a
+    // virtual node with an edge of length 0 to every node), None if a negative cycle exists
+    fn distance_from_all_nodes(&self) -> Option<Vec<Dist>> {
+        let mut distance = vec![0; self.num_nodes];
+        for _ in 0..=self.num_nodes {
+            let mut relaxed = false;
+            for u in 0..self.num_nodes {
+                for e in self.neighbors(u) {
+                    if e.residual_capacity() > 0.0 && distance[u] + e.dist < distance[e.to] {
+                        distance[e.to] = distance[u] + e.dist;
+                        relaxed = true;
+                    }
+                }
+            }
+            if !relaxed {
+                return Some(distance);
+            }
         }
+        None
     }
 }
 
 impl ScalingGraph {
-    pub fn calculate_distance_to_sink_with_negative_edge(&mut self, sink: usize) -> Option<Vec<Dist>> {
+    // label correcting towards the sink, with the smaller label first and large label last heuristics if
+    // queue_heuristics is set, they order the queue better than fifo on dense networks of negative edges, but can
+    // relax a node num_nodes times without a negative cycle, then the fifo order, in which that means one, decides
+    pub fn calculate_distance_to_sink_with_negative_edge(
+        &mut self,
+        sink: usize,
+    ) -> Option<Vec<Dist>> {
+        self.relaxations = 0;
+        let labels = match self.queue_heuristics {
+            true => self.label_correcting_to_sink(sink, true).or_else(|| {
+                #[cfg(feature = "log")]
+                log::debug!(
+                    "label correcting churns after {} relaxations, again in fifo order",
+                    self.relaxations
+                );
+                self.label_correcting_to_sink(sink, false)
+            }),
+            false => self.label_correcting_to_sink(sink, false),
+        };
+        let (distance, distance_to_sink) = labels?;
+
+        let farthest = distance
+            .iter()
+            .copied()
+            .filter(|&d| d != DIST_MAX)
+            .max()
+            .unwrap_or(0);
+        self.potentials = self
+            .potentials
+            .iter()
+            .enumerate()
+            .map(|(u, p)| p + distance[u].min(farthest))
+            .collect();
+        Some(distance_to_sink)
+    }
+
+    // (reduced distance, distance to the sink), None once a node is relaxed num_nodes times
+    fn label_correcting_to_sink(
+        &mut self,
+        sink: usize,
+        heuristics: bool,
+    ) -> Option<(Vec<Dist>, Vec<Dist>)> {
         let mut distance = vec![DIST_MAX; self.num_nodes];
         let mut distance_to_sink = vec![DIST_MAX; self.num_nodes];
         let mut in_queue = vec![false; self.num_nodes];
-        let mut visit_count = vec![0 as usize; self.num_nodes];
+        let mut visit_count = vec![0_usize; self.num_nodes];
 
         distance[sink] = 0;
         distance_to_sink[sink] = 0;
@@ -212,95 +1747,303 @@ impl ScalingGraph {
         let mut que = VecDeque::new();
         que.push_back(sink);
         in_queue[sink] = true;
+        // of the distances in the queue, for the large label last heuristic
+        let mut queued_sum: i64 = 0;
 
-        let mut farthest = 0;
-        while let Some(u) = que.pop_front() {
+        while let Some(mut u) = que.pop_front() {
+            if heuristics {
+                // a label above the average goes to the back, one at most the average is in the queue
+                while distance[u] as i64 * (que.len() as i64 + 1) > queued_sum {
+                    que.push_back(u);
+                    u = que.pop_front().unwrap();
+                }
+                queued_sum -= distance[u] as i64;
+            }
             in_queue[u] = false;
-            farthest = farthest.max(distance[u]);
             assert_ne!(distance[u], DIST_MAX);
 
             for i in self.start[u]..self.start[u + 1] {
                 let e = &self.inside_edge_list[i];
-                if e.flow > 0.0 {
+                // like calculate_distance_to_sink
+                if self.inside_edge_list[e.rev].residual_capacity() > 0.0 {
                     let dist = -e.dist - self.potentials[e.to] + self.potentials[u];
                     let new_dist = distance[u] + dist;
 
                     if new_dist < distance[e.to] {
+                        self.relaxations += 1;
+                        if in_queue[e.to] {
+                            queued_sum -= (distance[e.to] - new_dist) as i64;
+                        }
                         distance[e.to] = new_dist;
                         distance_to_sink[e.to] = distance_to_sink[u] - e.dist;
 
                         visit_count[e.to] += 1;
                         if visit_count[e.to] >= self.num_nodes {
-                            // negative cycle detected
+                            // negative cycle detected, or churning heuristics
+                            #[cfg(feature = "log")]
+                            if !heuristics {
+                                log::debug!(
+                                    "flow generating cycle: node {} relaxed {} times",
+                                    e.to,
+                                    visit_count[e.to]
+                                );
+                            }
                             return None;
                         }
 
                         if !in_queue[e.to] {
                             in_queue[e.to] = true;
-                            que.push_back(e.to);
+                            queued_sum += new_dist as i64;
+                            // the smaller label first heuristic
+                            match que.front() {
+                                Some(&front) if heuristics && new_dist < distance[front] => {
+                                    que.push_front(e.to)
+                                }
+                                _ => que.push_back(e.to),
+                            }
                         }
                     }
                 }
             }
         }
+        Some((distance, distance_to_sink))
+    }
 
-        self.potentials = self.potentials.iter().enumerate().map(|(u, p)| p + distance[u].min(farthest)).collect();
-        Some(distance_to_sink)
+    // a cycle with negative length, i.e. flow generating after rounding, in the residual network
+    // that reaches the sink, as the inside edges in the direction of flow
+    pub fn find_flow_generating_cycle_to_sink(&self, sink: usize) -> Option<Vec<usize>> {
+        let mut distance = vec![DIST_MAX; self.num_nodes];
+        // parent[v]: the residual edge v -> w that relaxed v last
+        let mut parent = vec![usize::MAX; self.num_nodes];
+        distance[sink] = 0;
+
+        // bellman-ford towards the sink, a relaxation in round num_nodes means a negative cycle
+        let mut relaxed = None;
+        for _ in 0..self.num_nodes {
+            relaxed = None;
+            for u in 0..self.num_nodes {
+                if distance[u] == DIST_MAX {
+                    continue;
+                }
+                for e in self.neighbors(u) {
+                    // edge(e.to -> u) is e.rev
+                    if e.flow > 0.0 && distance[u] - e.dist < distance[e.to] {
+                        distance[e.to] = distance[u] - e.dist;
+                        parent[e.to] = e.rev;
+                        relaxed = Some(e.to);
+                    }
+                }
+            }
+            relaxed?;
+        }
+
+        // num_nodes steps along the parents end up on the cycle
+        let mut v = relaxed?;
+        for _ in 0..self.num_nodes {
+            v = self.inside_edge_list[parent[v]].to;
+        }
+        let start = v;
+        let mut cycle = Vec::new();
+        loop {
+            cycle.push(parent[v]);
+            v = self.inside_edge_list[parent[v]].to;
+            if v == start {
+                break;
+            }
+        }
+        #[cfg(feature = "log")]
+        log::debug!(
+            "flow generating cycle of {} edges at node {}",
+            cycle.len(),
+            start
+        );
+        Some(cycle)
+    }
+
+    // a cycle with negative length in the residual network anywhere, as the nodes of the user in the direction of
+    // flow (see GeneralizedFlowError::FlowGeneratingCycle): label correcting in fifo order from every node, once a
+    // node is relaxed num_nodes times the predecessor chains close such a cycle, or will after a few relaxations more
+    pub fn find_flow_generating_cycle(&self) -> Option<Vec<usize>> {
+        let n = self.num_nodes;
+        let mut distance = vec![0; n];
+        // parent[v]: the residual edge into v that relaxed v last
+        let mut parent = vec![usize::MAX; n];
+        let mut visit_count = vec![0_usize; n];
+        let mut in_queue = vec![true; n];
+        let mut que: VecDeque<usize> = (0..n).collect();
+        let mut tripped = false;
+
+        while let Some(u) = que.pop_front() {
+            in_queue[u] = false;
+            for i in self.start[u]..self.start[u + 1] {
+                let e = &self.inside_edge_list[i];
+                if e.residual_capacity() <= 0.0 || distance[u] + e.dist >= distance[e.to] {
+                    continue;
+                }
+                distance[e.to] = distance[u] + e.dist;
+                parent[e.to] = i;
+                visit_count[e.to] += 1;
+                tripped |= visit_count[e.to] >= n;
+                if tripped {
+                    let from = |v: usize| {
+                        (parent[v] != usize::MAX)
+                            .then(|| self.inside_edge_list[self.inside_edge_list[parent[v]].rev].to)
+                    };
+                    if let Some(mut cycle) = cycle_along(e.to, n, from) {
+                        #[cfg(feature = "log")]
+                        log::debug!(
+                            "flow generating cycle of {} edges after {} relaxations of node {}",
+                            cycle.len(),
+                            visit_count[e.to],
+                            e.to
+                        );
+                        cycle.reverse();
+                        return Some(normalize_cycle(
+                            cycle.into_iter().map(|v| self.user_node(v)).collect(),
+                        ));
+                    }
+                }
+                if !in_queue[e.to] {
+                    in_queue[e.to] = true;
+                    que.push_back(e.to);
+                }
+            }
+        }
+        None
+    }
+
+    // the error for a network build found a flow generating cycle in
+    pub(crate) fn flow_generating_cycle_error(&self) -> GeneralizedFlowError {
+        GeneralizedFlowError::FlowGeneratingCycle(
+            self.find_flow_generating_cycle().unwrap_or_default(),
+        )
+    }
+
+    // buckets for the distances up to num_nodes (at most BUCKET_QUEUE_MAX_SPAN) past the smallest one: the search
+    // passes each distance up to its farthest node, where the heap takes a logarithm per edge
+    fn distance_queue(&mut self) -> DistanceQueue {
+        let mut buckets = std::mem::take(&mut self.buckets);
+        buckets.iter_mut().for_each(Vec::clear);
+        buckets.resize_with(self.num_nodes.min(BUCKET_QUEUE_MAX_SPAN) + 1, Vec::new);
+        let mut heap = std::mem::take(&mut self.heap);
+        heap.clear();
+        DistanceQueue {
+            buckets,
+            current: 0,
+            len: 0,
+            heap,
+            is_heap: !self.bucket_queue,
+        }
+    }
+
+    fn recycle(&mut self, queue: DistanceQueue) {
+        self.buckets = queue.buckets;
+        self.heap = queue.heap;
     }
 
     pub fn calculate_distance_to_sink(&mut self, sink: usize) -> Vec<Dist> {
-        let mut distance = vec![DIST_MAX; self.num_nodes];
-        let mut distance_to_sink = vec![DIST_MAX; self.num_nodes];
-        let mut visited = vec![false; self.num_nodes];
+        self.update_distance_to_sink(sink);
+        self.distance_to_sink.clone()
+    }
+
+    // calculate_distance_to_sink into distance_to_sink, without allocating once the vectors have grown to num_nodes
+    pub(crate) fn update_distance_to_sink(&mut self, sink: usize) {
+        #[cfg(feature = "rayon")]
+        if self.inside_edge_list.len() >= self.delta_stepping_min_edges {
+            return self.update_distance_to_sink_delta_stepping(sink);
+        }
+        let mut distance = std::mem::take(&mut self.distance);
+        let mut distance_to_sink = std::mem::take(&mut self.distance_to_sink);
+        let mut visited = std::mem::take(&mut self.visited);
+        refill(&mut distance, self.num_nodes, DIST_MAX);
+        refill(&mut distance_to_sink, self.num_nodes, DIST_MAX);
+        refill(&mut visited, self.num_nodes, false);
         distance[sink] = 0;
         distance_to_sink[sink] = 0;
 
-        let mut heap = BinaryHeap::new();
-        heap.push((Reverse(0), sink));
+        let mut queue = self.distance_queue();
+        queue.push(0, sink);
 
         let mut farthest = 0;
-        while let Some((d, u)) = heap.pop() {
+        while let Some((d, u)) = queue.pop() {
             if visited[u] {
                 continue;
             }
             visited[u] = true;
-            farthest = d.0;
+            farthest = d;
 
             for i in self.start[u]..self.start[u + 1] {
                 let e = &self.inside_edge_list[i];
                 // for e in self.graph[u].iter() {
-                // edge(e.to -> u) is not usable
-                if e.flow > 0.0 && !visited[e.to] {
+                // edge(e.to -> u) is e.rev, usable with the residual capacity the level graphs look at: the flow of e
+                // can stay positive when a push too small for the precision of the flow of e.rev leaves it saturated
+                if self.inside_edge_list[e.rev].residual_capacity() > 0.0 && !visited[e.to] {
                     // using dist of edge(e.to -> u)
                     let dist = -e.dist - self.potentials[e.to] + self.potentials[u];
                     assert!(dist >= 0);
 
-                    let new_dist = d.0 as Dist + dist;
+                    let new_dist = d + dist;
                     if new_dist < distance[e.to] {
                         distance[e.to] = new_dist;
                         distance_to_sink[e.to] = distance_to_sink[u] - e.dist;
-                        heap.push((Reverse(new_dist), e.to));
+                        queue.push(new_dist, e.to);
                     }
                 }
             }
         }
+        self.recycle(queue);
 
         // update potentials
-        self.potentials = self.potentials.iter().enumerate().map(|(u, p)| p + distance[u].min(farthest)).collect();
-        distance_to_sink
+        for (p, &d) in self.potentials.iter_mut().zip(distance.iter()) {
+            *p += d.min(farthest);
+        }
+        self.distance = distance;
+        self.distance_to_sink = distance_to_sink;
+        self.visited = visited;
     }
 
     // find shortest path from source to sink & update potentials
-    pub fn find_shortest_path(&mut self, source: usize, sink: usize) -> Option<Vec<(usize, usize)>> {
-        let mut prev = vec![(self.num_nodes, self.num_nodes); self.num_nodes];
+    pub fn find_shortest_path(
+        &mut self,
+        source: usize,
+        sink: usize,
+    ) -> Option<Vec<(usize, usize)>> {
+        self.find_shortest_path_from_any(&[source], sink)
+    }
 
-        let mut visited = vec![false; self.num_nodes];
-        let mut distance = vec![DIST_MAX; self.num_nodes];
-        distance[source] = 0;
+    // shortest path from any of the sources, which starts at the node without prev
+    // as if a super source had a 0 length edge to each of them, whose potential is the largest of theirs
+    pub fn find_shortest_path_from_any(
+        &mut self,
+        sources: &[usize],
+        sink: usize,
+    ) -> Option<Vec<(usize, usize)>> {
+        if self.shortest_path_from_any(sources, sink) {
+            Some(self.prev.clone())
+        } else {
+            None
+        }
+    }
 
-        let mut heap = BinaryHeap::new();
-        heap.push((Reverse(0), source));
-        while let Some((d, u)) = heap.pop() {
+    // find_shortest_path_from_any into prev, false without a path, without allocating once the vectors have
+    // grown to num_nodes
+    pub(crate) fn shortest_path_from_any(&mut self, sources: &[usize], sink: usize) -> bool {
+        let Some(super_potential) = sources.iter().map(|&s| self.potentials[s]).max() else {
+            return false;
+        };
+        let mut prev = std::mem::take(&mut self.prev);
+        let mut visited = std::mem::take(&mut self.visited);
+        let mut distance = std::mem::take(&mut self.distance);
+        refill(&mut prev, self.num_nodes, (self.num_nodes, self.num_nodes));
+        refill(&mut visited, self.num_nodes, false);
+        refill(&mut distance, self.num_nodes, DIST_MAX);
+
+        let mut queue = self.distance_queue();
+        for &s in sources {
+            distance[s] = super_potential - self.potentials[s];
+            queue.push(distance[s], s);
+        }
+        while let Some((d, u)) = queue.pop() {
             if visited[u] {
                 continue;
             }
@@ -321,16 +2064,16 @@ impl ScalingGraph {
 
                 let dist = e.dist + self.potentials[u] - self.potentials[e.to];
                 assert!(dist >= 0);
-                let dist = dist;
 
-                let new_dist = d.0 + dist;
+                let new_dist = d + dist;
                 if new_dist < distance[e.to] {
                     distance[e.to] = new_dist;
                     prev[e.to] = (u, st + i);
-                    heap.push((Reverse(new_dist), e.to));
+                    queue.push(new_dist, e.to);
                 }
             }
         }
+        self.recycle(queue);
 
         // update potentials
         for u in 0..self.num_nodes {
@@ -339,10 +2082,88 @@ impl ScalingGraph {
             }
         }
 
-        if !visited[sink] {
-            return None;
+        let found = visited[sink];
+        self.prev = prev;
+        self.visited = visited;
+        self.distance = distance;
+        found
+    }
+}
+
+// len copies of value, in the memory v already has
+pub(crate) fn refill<T: Clone>(v: &mut Vec<T>, len: usize, value: T) {
+    v.clear();
+    v.resize(len, value);
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use super::ScalingGraph;
+    use crate::graph::GeneralizedFlowError;
+    use crate::highest_gain_path_method::HighestGainPathMethod;
+    use log::{Level, Log, Metadata, Record};
+    use std::cell::RefCell;
+
+    // records are kept per thread, so that tests running in parallel don't see each other's
+    struct CapturingLogger;
+
+    thread_local! {
+        static RECORDS: RefCell<Vec<(Level, String)>> = const { RefCell::new(Vec::new()) };
+    }
+
+    impl Log for CapturingLogger {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            RECORDS.with(|r| {
+                r.borrow_mut()
+                    .push((record.level(), record.args().to_string()))
+            });
         }
 
-        Some(prev)
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger;
+
+    fn capture(f: impl FnOnce()) -> Vec<(Level, String)> {
+        // fails if another test installed the logger first
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Trace);
+        RECORDS.with(|r| r.borrow_mut().clear());
+        f();
+        RECORDS.with(|r| r.take())
+    }
+
+    #[test]
+    fn non_positive_gain_warning() {
+        let records = capture(|| {
+            let mut graph = ScalingGraph::new(2, 0.1);
+            assert_eq!(graph.add_directed_edge(0, 1, 1.0, 0.0), None);
+            assert_eq!(graph.add_directed_edge(0, 1, 1.0, 0.5), Some(0));
+        });
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].0, Level::Warn);
+        assert!(records[0].1.contains("gain needs to be greater than 0"));
+    }
+
+    #[test]
+    fn flow_generating_cycle() {
+        let records = capture(|| {
+            let mut solver = HighestGainPathMethod::new(3, 0.1);
+            solver.add_directed_edge(0, 1, 1.0, 1.0).unwrap();
+            solver.add_directed_edge(1, 2, 1.0, 2.0).unwrap();
+            solver.add_directed_edge(2, 1, 1.0, 2.0).unwrap();
+            assert_eq!(
+                solver.try_solve(0, 2),
+                Err(GeneralizedFlowError::FlowGeneratingCycle(vec![1, 2]))
+            );
+        });
+        assert!(records
+            .iter()
+            .any(|(level, message)| *level == Level::Debug
+                && message.contains("flow generating cycle")));
     }
 }
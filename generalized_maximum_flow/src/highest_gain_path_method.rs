@@ -1,7 +1,34 @@
-use crate::graph::{Dist, Flow, InsideEdge, ScalingGraph, DIST_MAX, EPS, FLOW_MAX};
+use crate::graph::{
+    count, refill, Dist, EdgeError, Flow, GeneralizedFlowError, InsideEdge, LimitedSolution,
+    ScalingGraph, SolveLimits, SolveStats, DIST_MAX, EPS, FLOW_MAX,
+};
+use crate::solver::GeneralizedMaxFlowSolver;
+use std::collections::VecDeque;
+use std::time::Instant;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HighestGainPathMethod {
     graph: ScalingGraph,
+    stats: SolveStats,
+    // after each dijkstra, a blocking flow over all the edges of zero reduced cost like RoundedPrimalDual, instead of
+    // the one path to the sink, unset by default
+    pub blocking_flow: bool,
+    // kept from one round to the next, so that the rounds allocate nothing
+    #[cfg_attr(feature = "serde", serde(skip))]
+    active: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    canonical_labels: Vec<Flow>,
+    // maximum flow(dinic) of blocking_flow
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sources: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    iter: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    level: Vec<isize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    que: VecDeque<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stack: Vec<(usize, Flow, usize)>,
 }
 
 #[allow(dead_code)]
@@ -10,165 +37,1036 @@ impl HighestGainPathMethod {
         assert!(num_nodes > 0);
         assert!(epsilon > 0.0);
 
+        Self::with_graph(ScalingGraph::new(num_nodes, epsilon))
+    }
+
+    // a graph built before, e.g. deserialized, the next solve keeps its rounded gains
+    pub fn with_graph(graph: ScalingGraph) -> Self {
         HighestGainPathMethod {
-            graph: ScalingGraph::new(num_nodes, epsilon),
+            graph,
+            stats: SolveStats::default(),
+            blocking_flow: false,
+            active: Vec::new(),
+            canonical_labels: Vec::new(),
+            sources: Vec::new(),
+            iter: Vec::new(),
+            level: Vec::new(),
+            que: VecDeque::new(),
+            stack: Vec::new(),
         }
     }
 
-    pub fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow, gain: Flow) {
-        self.graph.add_directed_edge(from, to, capacity, gain);
+    pub fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.graph.try_add_directed_edge(from, to, capacity, gain)
+    }
+
+    // edges i and i + 1, see ScalingGraph::try_add_undirected_edge
+    pub fn add_undirected_edge(
+        &mut self,
+        u: usize,
+        v: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.graph.try_add_undirected_edge(u, v, capacity, gain)
+    }
+
+    // (flow entering, flow leaving) the edge, see ScalingGraph::get_flow
+    pub fn get_flow(&self, edge_index: usize) -> (Flow, Flow) {
+        self.graph.get_flow(edge_index)
+    }
+
+    // see ScalingGraph::excess_at
+    pub fn excess_at(&self, u: usize) -> Flow {
+        self.graph.excess_at(u)
+    }
+
+    // drops the flow of the last solve, see ScalingGraph::reset, the next solve (with any source and sink)
+    // does the same and keeps the gains rounded as long as no edge is added
+    pub fn reset(&mut self) {
+        self.graph.reset();
+        self.stats = SolveStats::default();
+        self.canonical_labels.clear();
+    }
+
+    // counters of the last solve, each round augments along one highest gain path, or along all of them with
+    // blocking_flow
+    pub fn stats(&self) -> &SolveStats {
+        &self.stats
     }
 
+    // for diagnostics after a solve: the canonical labels of its last augmentation over the nodes of the built graph,
+    // a unit at the sink costs label units at a node, FLOW_MAX off the highest gain path (or off the paths of zero
+    // reduced cost with blocking_flow), empty if nothing was augmented
+    pub fn labels(&self) -> &[Flow] {
+        &self.canonical_labels
+    }
+
+    // the rounded gain from node u of the user to the sink along the last augmentation of the solve, None off it
+    pub fn effective_gain_to_sink(&self, u: usize) -> Option<Flow> {
+        let label = *self.canonical_labels.get(self.graph.entry(u))?;
+        (label != FLOW_MAX).then(|| 1.0 / label)
+    }
+
+    // 0 if try_solve fails
     pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
-        self.graph.build();
-        if self.graph.num_nodes == 0 || self.graph.num_edges == 0 {
-            return 0 as Flow;
+        self.try_solve(source, sink).unwrap_or(0.0)
+    }
+
+    pub fn try_solve(&mut self, source: usize, sink: usize) -> Result<Flow, GeneralizedFlowError> {
+        self.try_solve_with_supply(source, sink, FLOW_MAX)
+    }
+
+    // only supply units are available at the source, 0 if try_solve_with_supply fails
+    pub fn solve_with_supply(&mut self, source: usize, sink: usize, supply: Flow) -> Flow {
+        self.try_solve_with_supply(source, sink, supply)
+            .unwrap_or(0.0)
+    }
+
+    pub fn try_solve_with_supply(
+        &mut self,
+        source: usize,
+        sink: usize,
+        supply: Flow,
+    ) -> Result<Flow, GeneralizedFlowError> {
+        self.try_solve_multi_source(&[(source, supply)], sink)
+    }
+
+    // each (source, supply) offers supply units, 0 if try_solve_multi_source fails
+    pub fn solve_multi_source(&mut self, sources: &[(usize, Flow)], sink: usize) -> Flow {
+        self.try_solve_multi_source(sources, sink).unwrap_or(0.0)
+    }
+
+    pub fn try_solve_multi_source(
+        &mut self,
+        sources: &[(usize, Flow)],
+        sink: usize,
+    ) -> Result<Flow, GeneralizedFlowError> {
+        self.solve_multi_source_with_limits(sources, sink, SolveLimits::default())
+            .map(|solution| solution.value)
+    }
+
+    // stops before the round past the limits, with the flow delivered so far
+    pub fn solve_with_limits(
+        &mut self,
+        source: usize,
+        sink: usize,
+        limits: SolveLimits,
+    ) -> Result<LimitedSolution, GeneralizedFlowError> {
+        self.solve_multi_source_with_limits(&[(source, FLOW_MAX)], sink, limits)
+    }
+
+    fn solve_multi_source_with_limits(
+        &mut self,
+        sources: &[(usize, Flow)],
+        sink: usize,
+        limits: SolveLimits,
+    ) -> Result<LimitedSolution, GeneralizedFlowError> {
+        let started = Instant::now();
+        let nodes: Vec<usize> = sources.iter().map(|&(s, _)| s).collect();
+        self.graph.validate(&nodes, sink)?;
+        if self.graph.has_lower_bounds() {
+            return Err(GeneralizedFlowError::LowerBoundsUnsupported);
         }
+        if sources
+            .iter()
+            .any(|&(_, supply)| supply.is_nan() || supply < 0.0)
+        {
+            return Err(GeneralizedFlowError::InvalidSupply);
+        }
+        self.graph.build();
+        self.stats = SolveStats {
+            augmentations: self.blocking_flow.then_some(0),
+            ..Default::default()
+        };
+        self.canonical_labels.clear();
 
         if !self.graph.is_lossy {
-            match self.graph.calculate_distance_to_sink_with_negative_edge(sink) {
-                Some(_distance_to_sink) => {}
-                None => {
-                    eprintln!("Error. flow generating cycle detected");
-                    return 0.0;
-                }
-            }
+            return Err(self.graph.flow_generating_cycle_error());
+        }
+        // find_shortest_path measures from the source, the potentials of build towards the sink
+        for p in self.graph.potentials.iter_mut() {
+            *p = -*p;
         }
 
-        self.graph.excesses[source] = FLOW_MAX;
+        // supply enters a node with a gain at its in copy
+        let nodes: Vec<usize> = nodes.iter().map(|&s| self.graph.entry(s)).collect();
+        for (&source, &(_, supply)) in nodes.iter().zip(sources) {
+            self.graph.excesses[source] += self.graph.round_flow(supply);
+        }
 
-        while self.graph.excesses[source] > EPS {
-            if !self.argument_flow(source, sink) {
+        let mut truncated = false;
+        let mut active = std::mem::take(&mut self.active);
+        loop {
+            active.clear();
+            active.extend(
+                nodes
+                    .iter()
+                    .copied()
+                    .filter(|&s| self.graph.excesses[s] > EPS),
+            );
+            if active.is_empty() {
+                break;
+            }
+            if limits.reached(&self.stats, started) {
+                truncated = true;
+                break;
+            }
+            if !self.argument_flow(&active, sink) {
                 break;
             }
         }
+        self.active = active;
 
-        self.graph.excesses[sink]
+        self.graph.cancel_opposite_flows();
+        Ok(LimitedSolution {
+            value: self.graph.excesses[sink],
+            truncated,
+        })
     }
 
-    fn argument_flow(&mut self, source: usize, sink: usize) -> bool {
-        match self.graph.find_shortest_path(source, sink) {
-            None => false,
-            Some(prev) => {
-                // calculate delta and canonical labels
-                let mut delta = FLOW_MAX;
-                let mut canonical_labels = vec![FLOW_MAX; self.graph.num_nodes];
-                canonical_labels[sink] = 1.0;
+    // each (sink, demand) takes at most demand units, the result is what each sink received
+    // zeros if try_solve_multi_sink fails
+    pub fn solve_multi_sink(&mut self, source: usize, sinks: &[(usize, Flow)]) -> Vec<Flow> {
+        self.try_solve_multi_sink(source, sinks)
+            .unwrap_or_else(|_| vec![0.0; sinks.len()])
+    }
 
-                let mut dist_to_sink = 0;
-                let mut v = sink;
-                while v != source {
-                    // u -> v
-                    let (u, i) = prev[v];
-                    let edge = &self.graph.inside_edge_list[i];
+    pub fn try_solve_multi_sink(
+        &mut self,
+        source: usize,
+        sinks: &[(usize, Flow)],
+    ) -> Result<Vec<Flow>, GeneralizedFlowError> {
+        let super_sink = self.graph.add_super_sink(source, sinks)?;
+        let result = self
+            .try_solve(source, super_sink.node)
+            .map(|_| self.graph.received(&super_sink));
+        self.graph.remove_super_sink(super_sink);
+        result
+    }
 
-                    dist_to_sink += edge.dist;
+    fn argument_flow(&mut self, sources: &[usize], sink: usize) -> bool {
+        #[cfg(feature = "log")]
+        log::trace!("augmentation: excess at sink {}", self.graph.excesses[sink]);
+        self.stats.dijkstras += 1;
+        if !self.graph.shortest_path_from_any(sources, sink) {
+            self.stats.record_round(0.0);
+            return false;
+        }
+        if self.blocking_flow {
+            self.augment_blocking_flow(sources, sink);
+            return true;
+        }
+
+        // calculate delta and canonical labels, in the vectors of the last round
+        let prev = std::mem::take(&mut self.graph.prev);
+        let mut canonical_labels = std::mem::take(&mut self.canonical_labels);
+        canonical_labels.clear();
+        canonical_labels.resize(self.graph.num_nodes, FLOW_MAX);
+        let mut delta = FLOW_MAX;
+        canonical_labels[sink] = 1.0;
+
+        let mut dist_to_sink = 0;
+        let mut v = sink;
+        while prev[v].0 != self.graph.num_nodes {
+            // u -> v
+            let (u, i) = prev[v];
+            let edge = &self.graph.inside_edge_list[i];
+
+            dist_to_sink += edge.dist;
+
+            let label = self.graph.label(dist_to_sink);
+            canonical_labels[u] = label;
+
+            delta = delta.min(self.labeled_residual_capacity(u, edge, &canonical_labels));
+            v = u;
+        }
+
+        // the path starts at the source without prev
+        let source = v;
+        delta = delta.min(self.graph.excesses[source] / canonical_labels[source]);
+
+        // update flow
+        let mut v = sink;
+        while v != source {
+            // u -> v
+            let (u, i) = prev[v];
+            self.graph.push_flow(u, i, delta, &canonical_labels);
+            v = u;
+        }
+
+        // as the first and the last edge of the path moved it
+        let (sent, received) = (
+            self.graph.round_flow(canonical_labels[source] * delta),
+            self.graph.round_flow(delta),
+        );
+        self.graph.add_excess(source, -sent);
+        self.graph.add_excess(sink, received);
+        self.stats.record_round(delta);
+
+        self.graph.prev = prev;
+        self.canonical_labels = canonical_labels;
+        true
+    }
 
-                    let label = self.graph.base.powf(dist_to_sink as f64);
-                    canonical_labels[u] = label;
+    // the dijkstra left every edge between the nodes it settled with a nonnegative reduced cost, the highest gain
+    // paths are those of zero reduced cost from the sources it reached first, on which the labels of the potentials
+    // are canonical, the flow along them keeps the reduced costs nonnegative for the next dijkstra
+    fn augment_blocking_flow(&mut self, active: &[usize], sink: usize) {
+        let mut canonical_labels = std::mem::take(&mut self.canonical_labels);
+        canonical_labels.clear();
+        let potential_sink = self.graph.potentials[sink];
+        canonical_labels.extend((0..self.graph.num_nodes).map(|u| {
+            if self.graph.visited[u] {
+                self.graph.label(potential_sink - self.graph.potentials[u])
+            } else {
+                FLOW_MAX
+            }
+        }));
+        canonical_labels[sink] = 1.0;
+        self.canonical_labels = canonical_labels;
+        refill(&mut self.iter, self.graph.num_nodes, 0);
+        refill(&mut self.level, self.graph.num_nodes, -1);
 
-                    delta = delta.min(self.labeled_residual_capacity(u, edge, &canonical_labels));
-                    v = u;
+        // the edge of 0 from the super source of shortest_path_from_any has zero reduced cost into the sources of the
+        // highest potential
+        let top = active
+            .iter()
+            .filter(|&&s| self.graph.visited[s])
+            .map(|&s| self.graph.potentials[s])
+            .max();
+        let before = self.graph.excesses[sink];
+        let mut sources = std::mem::take(&mut self.sources);
+        loop {
+            sources.clear();
+            sources.extend(active.iter().copied().filter(|&s| {
+                Some(self.graph.potentials[s]) == top
+                    && self.graph.visited[s]
+                    && self.graph.excesses[s] > EPS
+            }));
+            if sources.is_empty() {
+                break;
+            }
+            self.bfs(&sources);
+            if self.level[sink] < 0 {
+                break;
+            }
+            self.iter
+                .copy_from_slice(&self.graph.start[..self.graph.num_nodes]);
+            for &u in sources.iter() {
+                while self.graph.excesses[u] > EPS {
+                    let flow = self.dfs(u, sink, self.graph.excesses[u] / self.canonical_labels[u]);
+                    if flow <= 0 as Flow {
+                        break;
+                    }
+                    self.graph.add_excess(u, -flow * self.canonical_labels[u]);
+                    self.graph.add_excess(sink, flow);
+                    count(&mut self.stats.augmentations);
                 }
+            }
+        }
+        self.sources = sources;
+        self.stats.record_round(self.graph.excesses[sink] - before);
+    }
 
-                delta = delta.min(self.graph.excesses[source] / canonical_labels[source]);
+    // the edges of zero reduced cost that shortest_path_from_any relaxes
+    #[inline]
+    fn is_admissible(&self, u: usize, edge: &InsideEdge) -> bool {
+        edge.residual_capacity() >= EPS
+            && self.canonical_labels[edge.to] != FLOW_MAX
+            && edge.dist + self.graph.potentials[u] - self.graph.potentials[edge.to] == 0
+    }
 
-                // update flow
-                let mut v = sink;
-                while v != source {
-                    // u -> v
-                    let (u, i) = prev[v];
-                    self.graph.push_flow(u, i, delta, &canonical_labels);
-                    v = u;
+    fn bfs(&mut self, sources: &[usize]) {
+        self.level.fill(-1);
+        let mut que = std::mem::take(&mut self.que);
+        for &source in sources {
+            self.level[source] = 0;
+            que.push_back(source);
+        }
+
+        while let Some(u) = que.pop_front() {
+            for i in self.graph.start[u]..self.graph.start[u + 1] {
+                let edge = &self.graph.inside_edge_list[i];
+                if self.level[edge.to] < 0 && self.is_admissible(u, edge) {
+                    self.level[edge.to] = self.level[u] + 1;
+                    que.push_back(edge.to);
                 }
+            }
+        }
+        self.que = que;
+    }
 
-                self.graph.excesses[source] -= canonical_labels[source] * delta;
-                self.graph.excesses[sink] += delta;
+    // an augmenting path in the level graph, see RoundedPrimalDual::dfs
+    fn dfs(&mut self, source: usize, sink: usize, flow: Flow) -> Flow {
+        let mut stack = std::mem::take(&mut self.stack);
+        stack.clear();
+        stack.push((source, flow, self.iter[source]));
+        let mut pushed = 0.0;
+        while let Some(&(u, flow, next)) = stack.last() {
+            if u == sink {
+                for &(v, _, _) in stack.iter().rev().skip(1) {
+                    self.graph
+                        .push_flow(v, self.iter[v], flow, &self.canonical_labels);
+                }
+                pushed = flow;
+                break;
+            }
 
-                true
+            let mut advance = None;
+            for i in next..self.graph.start[u + 1] {
+                self.iter[u] = i;
+                let edge = &self.graph.inside_edge_list[i];
+                if self.level[u] < self.level[edge.to] && self.is_admissible(u, edge) {
+                    advance = Some((
+                        edge.to,
+                        flow.min(self.labeled_residual_capacity(u, edge, &self.canonical_labels)),
+                    ));
+                    break;
+                }
+            }
+            match advance {
+                Some((v, d)) => {
+                    stack.last_mut().unwrap().2 = self.iter[u] + 1;
+                    stack.push((v, d, self.iter[v]));
+                }
+                None => {
+                    stack.pop();
+                }
             }
         }
+
+        self.stack = stack;
+        pushed
     }
 
-    fn calculate_canonical_labels(&mut self, distance_to_sink: &Vec<Dist>, sink: usize) -> Vec<Flow> {
+    fn calculate_canonical_labels(&mut self, distance_to_sink: &[Dist], sink: usize) -> Vec<Flow> {
         let mut canonical_labels: Vec<Flow> = distance_to_sink
             .iter()
-            .map(|&d| if d != DIST_MAX { self.graph.base.powf(d as Flow) } else { FLOW_MAX })
+            .map(|&d| {
+                if d != DIST_MAX {
+                    self.graph.label(d)
+                } else {
+                    FLOW_MAX
+                }
+            })
             .collect();
         canonical_labels[sink] = 1.0;
         canonical_labels
     }
 
     #[inline]
-    fn labeled_residual_capacity(&self, u: usize, edge: &InsideEdge, labels: &Vec<f64>) -> Flow {
+    fn labeled_residual_capacity(&self, u: usize, edge: &InsideEdge, labels: &[f64]) -> Flow {
         edge.residual_capacity() / labels[u]
     }
 }
 
+impl GeneralizedMaxFlowSolver for HighestGainPathMethod {
+    fn new(num_nodes: usize, epsilon: Flow) -> Self {
+        HighestGainPathMethod::new(num_nodes, epsilon)
+    }
+
+    fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.add_directed_edge(from, to, capacity, gain)
+    }
+
+    fn add_undirected_edge(
+        &mut self,
+        u: usize,
+        v: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.add_undirected_edge(u, v, capacity, gain)
+    }
+
+    fn try_solve(&mut self, source: usize, sink: usize) -> Result<Flow, GeneralizedFlowError> {
+        self.try_solve(source, sink)
+    }
+
+    fn graph(&self) -> &ScalingGraph {
+        &self.graph
+    }
+
+    fn graph_mut(&mut self) -> &mut ScalingGraph {
+        &mut self.graph
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::HighestGainPathMethod;
-    use super::{Flow, EPS};
-    use crate::test_utilities::{read_expected, read_graph_instance};
+    use super::{EdgeError, Flow, GeneralizedFlowError, EPS};
+    use crate::generators;
+    use crate::graph::{max_edge_dist, LimitedSolution, SolveLimits};
+    use crate::instances::read_graph_instance;
+    use crate::test_utilities::{
+        assert_approximate_conservation, assert_certified_case, read_expected, sample_instance,
+    };
+    use crate::verify::check;
     use rstest::*;
     use std::path::PathBuf;
+    use std::time::Duration;
 
     #[test]
     fn sample() {
         let epsilon = 0.01;
         let num_nodes = 8;
         let mut solver = HighestGainPathMethod::new(num_nodes, epsilon);
-        solver.add_directed_edge(0, 1, 12.0, 0.7);
-        solver.add_directed_edge(0, 2, 3.0, 0.9);
-        solver.add_directed_edge(0, 3, 4.0, 0.8);
+        solver.add_directed_edge(0, 1, 12.0, 0.7).unwrap();
+        solver.add_directed_edge(0, 2, 3.0, 0.9).unwrap();
+        solver.add_directed_edge(0, 3, 4.0, 0.8).unwrap();
 
-        solver.add_directed_edge(1, 4, 3.0, 0.5);
-        solver.add_directed_edge(1, 5, 5.0, 0.8);
+        solver.add_directed_edge(1, 4, 3.0, 0.5).unwrap();
+        solver.add_directed_edge(1, 5, 5.0, 0.8).unwrap();
 
-        solver.add_directed_edge(2, 1, 2.7, 1.0);
-        solver.add_directed_edge(2, 3, 20.0 / 9.0, 0.9);
-        solver.add_directed_edge(2, 5, 5.0, 0.7);
+        solver.add_directed_edge(2, 1, 2.7, 1.0).unwrap();
+        solver.add_directed_edge(2, 3, 20.0 / 9.0, 0.9).unwrap();
+        solver.add_directed_edge(2, 5, 5.0, 0.7).unwrap();
 
-        solver.add_directed_edge(3, 5, 1.0, 1.0);
-        solver.add_directed_edge(3, 6, 2.0, 0.7);
+        solver.add_directed_edge(3, 5, 1.0, 1.0).unwrap();
+        solver.add_directed_edge(3, 6, 2.0, 0.7).unwrap();
 
-        solver.add_directed_edge(4, 7, 2.0, 0.5);
+        solver.add_directed_edge(4, 7, 2.0, 0.5).unwrap();
 
-        solver.add_directed_edge(5, 4, 1.0, 0.5);
-        solver.add_directed_edge(5, 6, 6.0, 0.7);
-        solver.add_directed_edge(5, 7, 1.3, 1.0);
+        solver.add_directed_edge(5, 4, 1.0, 0.5).unwrap();
+        solver.add_directed_edge(5, 6, 6.0, 0.7).unwrap();
+        solver.add_directed_edge(5, 7, 1.3, 1.0).unwrap();
 
-        solver.add_directed_edge(6, 7, 7.0, 1.0);
+        solver.add_directed_edge(6, 7, 7.0, 1.0).unwrap();
 
         let actual = solver.solve(0, 7);
         let expected = 7.363;
         assert!(expected * (1.0 - epsilon) <= actual && actual <= expected);
     }
 
+    #[test]
+    fn stats() {
+        let instance = sample_instance();
+        let mut solver: HighestGainPathMethod = instance.build_solver(0.01);
+        let actual = solver.solve(instance.source, instance.sink);
+        let stats = solver.stats();
+        assert!(stats.rounds <= 2 * instance.num_nodes, "{:?}", stats);
+        assert_eq!(
+            (stats.dijkstras, stats.bellman_fords, stats.augmentations),
+            (stats.rounds, 0, None)
+        );
+        assert!((stats.flow_per_round.iter().sum::<Flow>() - actual).abs() < 1e-9);
+        assert_eq!(
+            (stats.flow_per_round.last(), stats.stalled_rounds),
+            (Some(&0.0), 1)
+        );
+    }
+
+    // the last path is 0 -> 1 -> 2, 3 is off it
+    #[test]
+    fn labels() {
+        let epsilon: Flow = 0.01;
+        let mut solver = HighestGainPathMethod::new(4, epsilon);
+        solver.add_directed_edge(0, 1, 1.0, 0.5).unwrap();
+        solver.add_directed_edge(1, 2, 10.0, 0.8).unwrap();
+        solver.add_directed_edge(2, 3, 1.0, 0.9).unwrap();
+        solver.solve(0, 2);
+        assert_eq!(solver.labels()[2], 1.0);
+        let gain = solver.effective_gain_to_sink(0).unwrap();
+        assert!(0.4 * (1.0 - epsilon) <= gain && gain <= 0.4, "{}", gain);
+        assert_eq!(solver.effective_gain_to_sink(3), None);
+        solver.reset();
+        assert_eq!(solver.effective_gain_to_sink(0), None);
+    }
+
+    // every highest gain path of a dijkstra at once: within 1 - epsilon as well, in no more dijkstras
     #[rstest]
-    fn aoj_grl_6_a(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
-        let mut expected_file_path = path.clone();
-        expected_file_path.set_extension("out");
+    fn blocking_flow(
+        #[files("test_cases/gain_random/*.in")] path: PathBuf,
+        #[values(0.1, 0.01)] epsilon: Flow,
+    ) {
+        let expected = read_expected(&path.with_extension("out"));
+        let instance = read_graph_instance(&path).unwrap();
+        let mut single: HighestGainPathMethod = instance.build_solver(epsilon);
+        single.solve(instance.source, instance.sink);
+        let mut solver: HighestGainPathMethod = instance.build_solver(epsilon);
+        solver.blocking_flow = true;
+        let actual = solver.solve(instance.source, instance.sink);
+        check(&solver.graph, instance.source, instance.sink, 1e-6).unwrap();
+        assert!(
+            expected * (1.0 - epsilon) <= actual && actual <= expected + EPS,
+            "{} {}",
+            actual,
+            expected
+        );
+
+        let (stats, single) = (solver.stats(), single.stats());
+        assert!(
+            stats.dijkstras <= single.dijkstras,
+            "{} {}",
+            stats.dijkstras,
+            single.dijkstras
+        );
+        assert!(stats.augmentations.unwrap() >= stats.rounds - 1);
+        let certificate = assert_certified_case(&path, epsilon);
+        assert!(
+            (1.0 - epsilon) * certificate.upper_bound <= actual + 1e-9 * expected.max(1.0),
+            "{} {:?}",
+            actual,
+            certificate
+        );
+    }
+
+    #[test]
+    fn blocking_flow_dijkstras() {
+        // many paths of the same rounded gain, which a single path per dijkstra takes one by one
+        let instance = generators::random_graph(100, 3200, 1);
+        let mut single: HighestGainPathMethod = instance.build_solver(0.01);
+        let expected = single.solve(instance.source, instance.sink);
+        let mut solver: HighestGainPathMethod = instance.build_solver(0.01);
+        solver.blocking_flow = true;
+        assert!((solver.solve(instance.source, instance.sink) - expected).abs() <= 1e-9 * expected);
+        assert!(
+            solver.stats().dijkstras + 20 < single.stats().dijkstras,
+            "{} {}",
+            solver.stats().dijkstras,
+            single.stats().dijkstras
+        );
+        assert!(solver.stats().augmentations.unwrap() > solver.stats().rounds);
+    }
 
+    #[test]
+    fn limits() {
+        let instance = sample_instance();
+        let mut solver: HighestGainPathMethod = instance.build_solver(0.01);
+        let full = solver.solve(instance.source, instance.sink);
+
+        // the first round alone, a feasible flow of less
+        let one_round = SolveLimits {
+            max_rounds: Some(1),
+            ..Default::default()
+        };
+        let solution = solver
+            .solve_with_limits(instance.source, instance.sink, one_round)
+            .unwrap();
+        assert!(
+            solution.truncated && 0.0 < solution.value && solution.value < full,
+            "{:?}",
+            solution
+        );
+        assert_eq!(solver.stats().rounds, 1);
+        assert_eq!(
+            check(&solver.graph, instance.source, instance.sink, 1e-9),
+            Ok(())
+        );
+
+        let no_time = SolveLimits {
+            time_budget: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        assert_eq!(
+            solver.solve_with_limits(instance.source, instance.sink, no_time),
+            Ok(LimitedSolution {
+                value: 0.0,
+                truncated: true
+            })
+        );
+
+        let generous = SolveLimits {
+            max_rounds: Some(1000),
+            time_budget: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        };
+        assert_eq!(
+            solver.solve_with_limits(instance.source, instance.sink, generous),
+            Ok(LimitedSolution {
+                value: full,
+                truncated: false
+            })
+        );
+    }
+
+    #[test]
+    fn reset() {
+        let instance = sample_instance();
+        let mut solver: HighestGainPathMethod = instance.build_solver(0.01);
+        let first = solver.solve(instance.source, instance.sink);
+        solver.reset();
+        assert!((0..instance.edges.len()).all(|i| solver.graph.get_flow(i) == (0.0, 0.0)));
+        assert!((0..instance.num_nodes).all(|u| solver.graph.excess(u) == 0.0));
+        assert_eq!(solver.stats().rounds, 0);
+        assert_eq!(solver.solve(instance.source, instance.sink), first);
+    }
+
+    #[test]
+    fn supply() {
         let epsilon: Flow = 0.01;
-        let actual = get_result(&path, epsilon) as Flow;
-        let expected = read_expected(&expected_file_path) as Flow;
+        let instance = sample_instance();
+        let solver = || {
+            let mut solver = HighestGainPathMethod::new(instance.num_nodes, epsilon);
+            for &(from, to, capacity, gain) in instance.edges.iter() {
+                solver.add_directed_edge(from, to, capacity, gain).unwrap();
+            }
+            solver
+        };
+        let used =
+            |solver: &HighestGainPathMethod| -> Flow { (0..3).map(|i| solver.get_flow(i).0).sum() };
 
-        if expected == 0.0 {
-            assert!(actual < EPS);
-        } else {
-            assert!(expected * (1.0 - epsilon) <= actual && actual <= expected);
+        let mut unconstrained = solver();
+        let value = unconstrained.solve(instance.source, instance.sink);
+        let usage = used(&unconstrained);
+
+        // 0 -> 2 -> 3 -> 5 -> 7 has the highest gain, 0.81, and takes all of 1 unit
+        let mut limited = solver();
+        let actual = limited.solve_with_supply(instance.source, instance.sink, 1.0);
+        assert!(
+            0.81 * (1.0 - epsilon) <= actual && actual <= 0.81 + EPS,
+            "{}",
+            actual
+        );
+
+        let mut limited = solver();
+        let actual = limited.solve_with_supply(instance.source, instance.sink, usage / 2.0);
+        assert!(
+            value / 2.0 < actual && actual < value,
+            "{} {}",
+            actual,
+            value
+        );
+        assert!(used(&limited) <= usage / 2.0 + 1e-9);
+
+        let mut ample = solver();
+        let actual = ample.solve_with_supply(instance.source, instance.sink, usage * 2.0);
+        assert!((actual - value).abs() < 1e-9, "{} {}", actual, value);
+
+        assert_eq!(
+            ample.try_solve_with_supply(instance.source, instance.sink, Flow::NAN),
+            Err(GeneralizedFlowError::InvalidSupply)
+        );
+        assert_eq!(
+            ample.solve_with_supply(instance.source, instance.sink, 0.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn multi_source() {
+        // LP optimum 7: 2 -> 4 and 1 -> 3 are saturated, source 0 sends all of its 4 units at gain 0.9
+        // and source 1 tops 2 -> 4 up with 4 units at gain 0.6 besides the 2 units through 3
+        let epsilon: Flow = 0.01;
+        let mut solver = HighestGainPathMethod::new(5, epsilon);
+        for (from, to, capacity, gain) in [
+            (0, 2, 5.0, 0.9),
+            (1, 2, 5.0, 0.6),
+            (2, 4, 6.0, 1.0),
+            (1, 3, 2.0, 0.5),
+            (3, 4, 10.0, 1.0),
+        ] {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
         }
+        let actual = solver.solve_multi_source(&[(0, 4.0), (1, 10.0)], 4);
+        assert!(
+            7.0 * (1.0 - epsilon) <= actual && actual <= 7.0 + EPS,
+            "{}",
+            actual
+        );
+
+        let used_0 = solver.get_flow(0).0;
+        let used_1 = solver.get_flow(1).0 + solver.get_flow(3).0;
+        assert!(
+            used_0 <= 4.0 + 1e-9 && used_1 <= 10.0 + 1e-9,
+            "{} {}",
+            used_0,
+            used_1
+        );
+        assert!(used_1 > used_0 + 1.0, "{} {}", used_0, used_1);
+
+        assert_eq!(
+            solver.try_solve_multi_source(&[(0, 1.0), (4, 1.0)], 4),
+            Err(GeneralizedFlowError::SourceEqualsSink)
+        );
+        assert_eq!(
+            solver.try_solve_multi_source(&[(0, 1.0), (1, -1.0)], 4),
+            Err(GeneralizedFlowError::InvalidSupply)
+        );
+        assert_eq!(solver.solve_multi_source(&[], 4), 0.0);
     }
 
-    fn get_result(file_path: &PathBuf, epsilon: Flow) -> Flow {
-        let instance = read_graph_instance(file_path);
+    #[test]
+    fn multi_sink() {
+        // 0 -> 1 (gain 0.9) brings 4.5 units to 1, whose 2.5 beyond the demand of 1 arrive at 2 as 2 units,
+        // so the other 4 units of the demand of 2 take 0 -> 2 (gain 0.5)
+        let epsilon: Flow = 0.01;
+        let mut solver = HighestGainPathMethod::new(3, epsilon);
+        for (from, to, capacity, gain) in [(0, 1, 5.0, 0.9), (1, 2, 10.0, 0.8), (0, 2, 10.0, 0.5)] {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
+        }
+        let received = solver.solve_multi_sink(0, &[(1, 2.0), (2, 6.0)]);
+        assert!(
+            (received[0] - 2.0).abs() < 1e-9 && (received[1] - 6.0).abs() < 1e-9,
+            "{:?}",
+            received
+        );
+        assert!(
+            solver.get_flow(1).0 > 1.0 && solver.get_flow(2).0 > 7.0,
+            "{:?} {:?}",
+            solver.get_flow(1),
+            solver.get_flow(2)
+        );
+
+        // 2 can only get 2 + 5 units
+        let received = solver.solve_multi_sink(0, &[(1, 2.0), (2, 100.0)]);
+        assert!((received[0] - 2.0).abs() < 1e-9, "{:?}", received);
+        assert!(
+            7.0 * (1.0 - epsilon) <= received[1] && received[1] <= 7.0 + EPS,
+            "{:?}",
+            received
+        );
+
+        // the super sink is gone again
+        assert_eq!(solver.graph.num_nodes, 3);
+        let actual = solver.solve(0, 2);
+        assert!(
+            8.6 * (1.0 - epsilon) <= actual && actual <= 8.6 + EPS,
+            "{}",
+            actual
+        );
+
+        assert_eq!(
+            solver.solve_multi_sink(0, &[(1, 0.0), (2, 0.0)]),
+            vec![0.0, 0.0]
+        );
+        assert_eq!(
+            solver.try_solve_multi_sink(0, &[(1, 1.0), (0, 1.0)]),
+            Err(GeneralizedFlowError::SourceEqualsSink)
+        );
+        assert_eq!(
+            solver.try_solve_multi_sink(0, &[(1, Flow::NAN)]),
+            Err(GeneralizedFlowError::InvalidDemand)
+        );
+        assert_eq!(
+            solver.try_solve_multi_sink(0, &[(3, 1.0)]),
+            Err(GeneralizedFlowError::InvalidTerminal(3))
+        );
+    }
+
+    #[test]
+    fn flow_readback() {
+        let epsilon: Flow = 0.01;
+        let instance = sample_instance();
         let mut solver = HighestGainPathMethod::new(instance.num_nodes, epsilon);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
+        }
+        let value = solver.solve(instance.source, instance.sink);
+
+        let flows: Vec<(Flow, Flow)> = (0..instance.edges.len())
+            .map(|i| solver.get_flow(i))
+            .collect();
+        for (&(_, _, _, gain), &(flow_in, flow_out)) in instance.edges.iter().zip(flows.iter()) {
+            assert!((flow_in * gain - flow_out).abs() < EPS);
+        }
+        assert_approximate_conservation(&instance, &flows, value, epsilon);
+    }
+
+    #[test]
+    fn flow_generating_cycle() {
+        // 1 -> 2 -> 1 doubles the flow twice, and is reachable from the source and reaches the sink
+        let mut solver = HighestGainPathMethod::new(4, 0.01);
+        solver.add_directed_edge(0, 1, 1.0, 1.0).unwrap();
+        solver.add_directed_edge(1, 2, 1.0, 2.0).unwrap();
+        solver.add_directed_edge(2, 1, 1.0, 2.0).unwrap();
+        solver.add_directed_edge(2, 3, 1.0, 1.0).unwrap();
+        assert_eq!(
+            solver.try_solve(0, 3),
+            Err(GeneralizedFlowError::FlowGeneratingCycle(vec![1, 2]))
+        );
+        assert_eq!(solver.solve(0, 3), 0.0);
+    }
+
+    #[test]
+    fn gain_above_one_without_cycle() {
+        // 1.25 then 0.5 delivers 2 of the 4 units reaching node 1, 0.9 more comes directly
+        let epsilon: Flow = 0.01;
+        let mut solver = HighestGainPathMethod::new(4, epsilon);
+        solver.add_directed_edge(0, 1, 4.0, 1.25).unwrap();
+        solver.add_directed_edge(1, 2, 4.0, 0.5).unwrap();
+        solver.add_directed_edge(0, 2, 1.0, 0.9).unwrap();
+        solver.add_directed_edge(2, 3, 10.0, 2.0).unwrap();
+        let expected = 5.8;
+        let actual = solver.try_solve(0, 3).unwrap();
+        assert!(solver.graph.is_lossy);
+        assert!(
+            expected * (1.0 - epsilon) <= actual && actual <= expected + EPS,
+            "{}",
+            actual
+        );
+    }
+
+    #[test]
+    fn arbitrage_cycle() {
+        // 1 -> 2 -> 1 gains a factor 1.5
+        let mut solver = HighestGainPathMethod::new(3, 0.01);
+        solver.add_directed_edge(0, 1, 1.0, 0.5).unwrap();
+        solver.add_directed_edge(1, 2, 3.0, 2.0).unwrap();
+        solver.add_directed_edge(2, 1, 4.0, 0.75).unwrap();
+        solver.add_directed_edge(1, 2, 1.0, 1.0).unwrap();
+        assert_eq!(
+            solver.try_solve(0, 2),
+            Err(GeneralizedFlowError::FlowGeneratingCycle(vec![1, 2]))
+        );
+        assert!(!solver.graph.is_lossy);
+    }
+
+    #[test]
+    fn currency_triangle() {
+        // usd (0) -> eur (1) -> gbp (2) -> usd gains 0.9 * 0.9 * 1.3 = 1.053, every other cycle loses
+        let mut solver = HighestGainPathMethod::new(4, 0.01);
+        for (from, to, rate) in [
+            (0, 1, 0.9),
+            (1, 2, 0.9),
+            (2, 0, 1.3),
+            (1, 0, 1.0),
+            (2, 1, 1.0),
+            (0, 2, 0.75),
+        ] {
+            solver.add_directed_edge(from, to, 100.0, rate).unwrap();
+        }
+        solver.add_directed_edge(2, 3, 100.0, 1.0).unwrap();
+        assert_eq!(
+            solver.try_solve(0, 3),
+            Err(GeneralizedFlowError::FlowGeneratingCycle(vec![0, 1, 2]))
+        );
+        assert_eq!(
+            solver.graph.find_flow_generating_cycle(),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(
+            solver.try_solve(0, 3).unwrap_err().to_string(),
+            "flow generating cycle detected: [0, 1, 2]"
+        );
+
+        // with a fee of 10% at eur the triangle loses, until eur pays a bonus of 5% instead, which its copy carries
+        solver.graph.set_node_gain(1, 0.9).unwrap();
+        assert!(solver.try_solve(0, 3).is_ok());
+        assert_eq!(solver.graph.find_flow_generating_cycle(), None);
+        solver.graph.set_node_gain(1, 1.05).unwrap();
+        assert_eq!(
+            solver.try_solve(0, 3),
+            Err(GeneralizedFlowError::FlowGeneratingCycle(vec![0, 1, 2]))
+        );
+    }
+
+    #[test]
+    fn node_count_hint_too_small() {
+        // every gain just below 1 is rounded down by almost a factor base, 19 times along the path,
+        // which only stays within 1 - epsilon if the base is computed for all 20 nodes
+        let epsilon: Flow = 0.01;
+        let mut solver = HighestGainPathMethod::new(10, epsilon);
+        for u in 0..19 {
+            solver.add_directed_edge(u, u + 1, 1.0, 1.0 - 1e-9).unwrap();
+        }
+        let expected = (1.0 - 1e-9 as Flow).powi(19);
+        let actual = solver.solve(0, 19);
+        assert!(
+            expected * (1.0 - epsilon) <= actual && actual <= expected,
+            "{}",
+            actual
+        );
+    }
+
+    #[test]
+    fn extreme_gains() {
+        // the two paths through 1 and 2 deliver next to nothing, 0 -> 3 delivers 0.5
+        let epsilon: Flow = 0.01;
+        for mut solver in [
+            HighestGainPathMethod::new(4, epsilon),
+            HighestGainPathMethod::new(4, 1e-6),
+        ] {
+            solver.add_directed_edge(0, 1, 1.0, 1e-200).unwrap();
+            solver.add_directed_edge(1, 3, 1.0, 1.0).unwrap();
+            solver.add_directed_edge(0, 2, 1.0, 1e-300).unwrap();
+            solver.add_directed_edge(2, 3, 1.0, 1.0).unwrap();
+            solver.add_directed_edge(0, 3, 1.0, 0.5).unwrap();
+            let actual = solver.solve(0, 3);
+            assert!(
+                0.5 * (1.0 - epsilon) <= actual && actual <= 0.5 + 1e-100,
+                "{}",
+                actual
+            );
 
-        for (from, to, capacity, gain) in instance.edges {
-            solver.add_directed_edge(from, to, capacity, gain);
+            let max_dist = max_edge_dist(solver.graph.num_nodes, solver.graph.base);
+            assert!(solver
+                .graph
+                .inside_edge_list
+                .iter()
+                .all(|e| e.dist.abs() <= max_dist));
         }
+    }
+
+    #[test]
+    fn invalid_instances() {
+        let mut solver = HighestGainPathMethod::new(2, 0.01);
+        assert_eq!(
+            solver.try_solve(0, 1),
+            Err(GeneralizedFlowError::EmptyGraph)
+        );
+
+        solver.add_directed_edge(0, 1, 1.0, 0.5).unwrap();
+        assert_eq!(
+            solver.try_solve(0, 2),
+            Err(GeneralizedFlowError::InvalidTerminal(2))
+        );
+        assert_eq!(
+            solver.try_solve(3, 1),
+            Err(GeneralizedFlowError::InvalidTerminal(3))
+        );
+        assert_eq!(
+            solver.try_solve(1, 1),
+            Err(GeneralizedFlowError::SourceEqualsSink)
+        );
+        assert!((solver.try_solve(0, 1).unwrap() - 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn invalid_edges() {
+        let mut solver = HighestGainPathMethod::new(2, 0.01);
+        assert_eq!(
+            solver.add_directed_edge(0, 1, Flow::NAN, 0.5),
+            Err(EdgeError::NonFiniteCapacity)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, Flow::INFINITY, 0.5),
+            Err(EdgeError::NonFiniteCapacity)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, 1.0, Flow::NAN),
+            Err(EdgeError::NonFiniteGain)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, 1.0, Flow::NEG_INFINITY),
+            Err(EdgeError::NonFiniteGain)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, -1.0, 0.5),
+            Err(EdgeError::NonPositiveCapacity)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, 1.0, 0.0),
+            Err(EdgeError::NonPositiveGain)
+        );
 
-        solver.solve(instance.source, instance.sink)
+        // the rejected edges leave no trace in the answer
+        assert_eq!(solver.add_directed_edge(0, 1, 2.0, 0.5), Ok(0));
+        let flow = solver.try_solve(0, 1).unwrap();
+        assert!((flow - 1.0).abs() < 0.01);
     }
 }
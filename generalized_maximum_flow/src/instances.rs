@@ -0,0 +1,306 @@
+use crate::graph::Flow;
+use crate::highest_gain_path_method::HighestGainPathMethod;
+use crate::rounded_primal_dual::RoundedPrimalDual;
+use crate::solver::GeneralizedMaxFlowSolver;
+pub use network_flows_instances::ParseError;
+use network_flows_instances::{read_gain_random, write_gain_random, Edge, Instance};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+// the format of test_cases/gain_random: a header `num_nodes num_edges source sink`, then one edge
+// `from to capacity gain` per line, node ids are 0-indexed, edges are kept in input order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphInstance {
+    pub num_nodes: usize,
+    pub edges: Vec<(usize, usize, Flow, Flow)>,
+    pub source: usize,
+    pub sink: usize,
+}
+
+#[deprecated(note = "renamed to GraphInstance")]
+pub type GraphInstancve = GraphInstance;
+
+impl GraphInstance {
+    // edges (from, to, capacity, gain) in the order of the iterator, checked by build_solver only
+    pub fn from_edges(
+        num_nodes: usize,
+        source: usize,
+        sink: usize,
+        edges: impl IntoIterator<Item = (usize, usize, Flow, Flow)>,
+    ) -> Self {
+        GraphInstance {
+            num_nodes,
+            edges: edges.into_iter().collect(),
+            source,
+            sink,
+        }
+    }
+
+    // from_edges with num_nodes one above the largest id of the edges, source and sink
+    pub fn from_edges_inferred(
+        source: usize,
+        sink: usize,
+        edges: impl IntoIterator<Item = (usize, usize, Flow, Flow)>,
+    ) -> Self {
+        let edges: Vec<_> = edges.into_iter().collect();
+        let num_nodes = edges
+            .iter()
+            .map(|&(from, to, _, _)| from.max(to))
+            .chain([source, sink])
+            .max()
+            .unwrap()
+            + 1;
+        GraphInstance {
+            num_nodes,
+            edges,
+            source,
+            sink,
+        }
+    }
+
+    // every edge of the instance added to a new S, panics if one is rejected
+    pub fn build_solver<S: GeneralizedMaxFlowSolver>(&self, epsilon: Flow) -> S {
+        let mut solver = S::new(self.num_nodes, epsilon);
+        for &(from, to, capacity, gain) in self.edges.iter() {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
+        }
+        solver
+    }
+
+    pub fn solver_rounded(&self, epsilon: Flow) -> RoundedPrimalDual {
+        self.build_solver(epsilon)
+    }
+
+    pub fn solver_path(&self, epsilon: Flow) -> HighestGainPathMethod {
+        self.build_solver(epsilon)
+    }
+}
+
+impl Extend<(usize, usize, Flow, Flow)> for GraphInstance {
+    fn extend<I: IntoIterator<Item = (usize, usize, Flow, Flow)>>(&mut self, edges: I) {
+        self.edges.extend(edges);
+    }
+}
+
+// gain 1 for the edges of a file without gains, e.g. the maximum flow test cases
+impl From<Instance<Flow>> for GraphInstance {
+    fn from(instance: Instance<Flow>) -> Self {
+        GraphInstance {
+            num_nodes: instance.num_nodes,
+            edges: instance.gains().collect(),
+            source: instance.source,
+            sink: instance.sink,
+        }
+    }
+}
+
+impl From<Instance<i64>> for GraphInstance {
+    fn from(instance: Instance<i64>) -> Self {
+        instance.map_capacity(|c| c as Flow).into()
+    }
+}
+
+impl From<&GraphInstance> for Instance<Flow> {
+    fn from(instance: &GraphInstance) -> Self {
+        let edges = instance
+            .edges
+            .iter()
+            .map(|&(from, to, capacity, gain)| Edge::new(from, to, capacity).with_gain(gain))
+            .collect();
+        Instance {
+            num_nodes: instance.num_nodes,
+            edges,
+            source: instance.source,
+            sink: instance.sink,
+        }
+    }
+}
+
+// blank lines and comments, lines starting with `#` or a `c` of its own, are ignored, and so are CRLF endings
+// the edges are checked like ScalingGraph::try_add_directed_edge does, see network_flows_instances::read_gain_random
+pub fn read_instance<R: BufRead>(r: R) -> Result<GraphInstance, ParseError> {
+    Ok(read_gain_random::<Flow, _>(r)?.into())
+}
+
+pub fn read_graph_instance(path: impl AsRef<Path>) -> Result<GraphInstance, ParseError> {
+    read_instance(BufReader::new(File::open(path)?))
+}
+
+// the format read_instance reads, the capacities and gains parse back to the same values
+pub fn write_instance<W: Write>(w: W, instance: &GraphInstance) -> std::io::Result<()> {
+    write_gain_random(w, &instance.into())
+}
+
+pub fn write_graph_instance(
+    path: impl AsRef<Path>,
+    instance: &GraphInstance,
+) -> std::io::Result<()> {
+    write_instance(BufWriter::new(File::create(path)?), instance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        read_graph_instance, read_instance, write_graph_instance, write_instance, GraphInstance,
+        ParseError,
+    };
+    use crate::test_utilities::{assert_certified_case, read_expected, sample_instance};
+    use rstest::*;
+    use std::path::PathBuf;
+
+    fn error_line(data: &str) -> usize {
+        match read_instance(data.as_bytes()) {
+            Err(ParseError::Syntax { line, .. }) => line,
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("parsed a malformed instance"),
+        }
+    }
+
+    #[test]
+    fn comments_and_whitespace() {
+        let data = "# generated\r\nc 3 nodes\r\n\r\n3 2 0 2  \r\n0 1 5 0.5\t\r\n  c\r\n1 2 1.5 0.9\r\n\r\n";
+        let instance = read_instance(data.as_bytes()).unwrap();
+        assert_eq!(
+            instance,
+            GraphInstance {
+                num_nodes: 3,
+                edges: vec![(0, 1, 5.0, 0.5), (1, 2, 1.5, 0.9)],
+                source: 0,
+                sink: 2
+            }
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        // header
+        assert_eq!(error_line(""), 0);
+        assert_eq!(error_line("# only a comment\n\n"), 0);
+        assert_eq!(error_line("3 2 0\n"), 1);
+        assert_eq!(error_line("3 2 0 2 7\n"), 1);
+        assert_eq!(error_line("three 2 0 2\n"), 1);
+        assert_eq!(error_line("3 2 0 3\n"), 1);
+        assert_eq!(error_line("3 3 0 2\n0 1 5 0.5\n1 2 1 0.9\n"), 1);
+        // edges
+        assert_eq!(error_line("3 2 0 2\n0 1 5 0.5\n1 2 1\n"), 3);
+        assert_eq!(error_line("3 2 0 2\n0 1 5 0.5 1\n1 2 1 0.9\n"), 2);
+        assert_eq!(error_line("3 2 0 2\n\n0 1 five 0.5\n1 2 1 0.9\n"), 3);
+        assert_eq!(error_line("3 2 0 2\n0 -1 5 0.5\n1 2 1 0.9\n"), 2);
+        assert_eq!(error_line("3 2 0 2\n0 3 5 0.5\n1 2 1 0.9\n"), 2);
+        assert_eq!(error_line("3 2 0 2\n0 1 0 0.5\n1 2 1 0.9\n"), 2);
+        assert_eq!(error_line("3 2 0 2\n0 1 inf 0.5\n1 2 1 0.9\n"), 2);
+        assert_eq!(error_line("3 2 0 2\n0 1 5 NaN\n1 2 1 0.9\n"), 2);
+        assert_eq!(error_line("3 2 0 2\n0 1 5 -0.5\n1 2 1 0.9\n"), 2);
+        assert_eq!(error_line("3 1 0 2\n0 1 5 0.5\n1 2 1 0.9\n"), 1);
+
+        let message = read_instance("3 2 0 2\n0 1 5 0.5\n1 2 1 0\n".as_bytes())
+            .unwrap_err()
+            .to_string();
+        assert_eq!(message, "line 3: gain needs to be greater than 0");
+        assert!(matches!(
+            read_graph_instance("test_cases/does_not_exist.in"),
+            Err(ParseError::Io(_))
+        ));
+    }
+
+    #[rstest]
+    fn round_trip(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        let instance = read_graph_instance(&path).unwrap();
+        let copy = std::env::temp_dir().join(format!(
+            "generalized_instances_{}_{}",
+            std::process::id(),
+            path.file_name().unwrap().to_string_lossy()
+        ));
+        write_graph_instance(&copy, &instance).unwrap();
+        assert_eq!(read_graph_instance(&copy).unwrap(), instance);
+        std::fs::remove_file(copy).unwrap();
+        assert_certified_case(&path, 0.01);
+    }
+
+    #[test]
+    fn round_trip_exact_floats() {
+        let instance = sample_instance();
+        let mut data = Vec::new();
+        write_instance(&mut data, &instance).unwrap();
+        assert_eq!(read_instance(data.as_slice()).unwrap(), instance);
+    }
+
+    #[test]
+    fn from_edges() {
+        let instance = sample_instance();
+        let edges = instance.edges.iter().copied();
+        assert_eq!(
+            GraphInstance::from_edges(
+                instance.num_nodes,
+                instance.source,
+                instance.sink,
+                edges.clone()
+            ),
+            instance
+        );
+        assert_eq!(
+            GraphInstance::from_edges_inferred(instance.source, instance.sink, edges.clone()),
+            instance
+        );
+        let mut extended = GraphInstance::from_edges(
+            instance.num_nodes,
+            instance.source,
+            instance.sink,
+            edges.clone().take(3),
+        );
+        extended.extend(edges.skip(3));
+        assert_eq!(extended, instance);
+        assert_eq!(
+            GraphInstance::from_edges_inferred(0, 4, [(0, 1, 1.0, 0.5)]).num_nodes,
+            5
+        );
+
+        // built from edges, written and read back, solves the same
+        let mut data = Vec::new();
+        write_instance(&mut data, &extended).unwrap();
+        let read = read_instance(data.as_slice()).unwrap();
+        assert_eq!(read, instance);
+        assert_eq!(
+            read.solver_rounded(0.01).solve(read.source, read.sink),
+            instance
+                .solver_rounded(0.01)
+                .solve(instance.source, instance.sink)
+        );
+    }
+
+    #[rstest]
+    fn solvers(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        let instance = read_graph_instance(&path).unwrap();
+        let expected = read_expected(&path.with_extension("out"));
+        let built = GraphInstance::from_edges(
+            instance.num_nodes,
+            instance.source,
+            instance.sink,
+            instance.edges.iter().copied(),
+        );
+        let mut data = Vec::new();
+        write_instance(&mut data, &built).unwrap();
+        let read = read_instance(data.as_slice()).unwrap();
+        assert_eq!(read, instance);
+        for actual in [
+            read.solver_rounded(0.01).solve(read.source, read.sink),
+            read.solver_path(0.01).solve(read.source, read.sink),
+        ] {
+            assert!(
+                expected * 0.99 <= actual + 1e-9 * expected.max(1.0)
+                    && actual <= expected + 1e-9 * expected.max(1.0),
+                "{} {}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_name() {
+        let instance: super::GraphInstancve = sample_instance();
+        assert_eq!(instance, sample_instance());
+    }
+}
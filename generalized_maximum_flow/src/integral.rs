@@ -0,0 +1,492 @@
+// ScalingGraph::round_to_integral: whole units into every edge, from the fractional flow a solver left
+// the cycles of the support of the flow whose gains multiply to at most 1 are canceled first, which only strands
+// flow at the node each starts at, then the nodes are rounded in topological order of what is left: a node knows
+// what it receives before it sends, and sends the floor or the ceiling of the flow of each edge out of it, as far as
+// what it receives covers them
+// the nodes on or behind a flow generating cycle, which have no such order, take the floors and then give back
+// units until none sends more than it receives
+// conservation is that of verify::check with the rounded gains, which the original gains only add to
+use crate::graph::{Flow, GeneralizedFlowError, ScalingGraph, DECOMPOSITION_EPS};
+
+// the flow of ScalingGraph::round_to_integral
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegralFlow {
+    // whole units entering each edge, by edge index
+    pub flows: Vec<Flow>,
+    // what the sink receives and keeps
+    pub delivered: Flow,
+    // what the fractional flow delivered minus delivered, negative where the ceilings deliver more
+    pub loss: Flow,
+}
+
+struct Rounding<'a> {
+    graph: &'a ScalingGraph,
+    sink: usize,
+    // the rounded gains of the edges and the nodes
+    gain: Vec<Flow>,
+    node_gain: Vec<Flow>,
+    out_edges: Vec<Vec<usize>>,
+    // the other direction of an undirected edge
+    partner: Vec<Option<usize>>,
+    fractional: Vec<Flow>,
+    integral: Vec<Flow>,
+    // the units the edges into a node carry, times their gains
+    received: Vec<Flow>,
+    // what the source sent of its supply, past its gain, and 0 elsewhere
+    released: Vec<Flow>,
+}
+
+impl ScalingGraph {
+    // after solving from source to sink, without lower bounds
+    pub fn round_to_integral(
+        &self,
+        source: usize,
+        sink: usize,
+    ) -> Result<IntegralFlow, GeneralizedFlowError> {
+        self.validate(&[source], sink)?;
+        if self.has_lower_bounds() {
+            return Err(GeneralizedFlowError::LowerBoundsUnsupported);
+        }
+        Ok(Rounding::new(self, source, sink).run())
+    }
+}
+
+impl<'a> Rounding<'a> {
+    fn new(graph: &'a ScalingGraph, source: usize, sink: usize) -> Self {
+        let num_nodes = graph.num_user_nodes();
+        let mut node_gain = vec![1.0; num_nodes];
+        for u in graph.nodes_with_gain() {
+            node_gain[u] = graph.rounded_node_gain(u);
+        }
+        let mut out_edges = vec![Vec::new(); num_nodes];
+        for (i, e) in graph.edges().enumerate() {
+            out_edges[e.from].push(i);
+        }
+        let mut partner = vec![None; graph.num_edges];
+        for i in graph.undirected_edges() {
+            partner[i] = Some(i + 1);
+            partner[i + 1] = Some(i);
+        }
+        let gain: Vec<Flow> = (0..graph.num_edges)
+            .map(|i| graph.rounded_gain(i))
+            .collect();
+        let fractional: Vec<Flow> = (0..graph.num_edges).map(|i| graph.get_flow(i).0).collect();
+
+        let mut released = vec![0.0; num_nodes];
+        released[source] = out_edges[source]
+            .iter()
+            .map(|&i| fractional[i])
+            .sum::<Flow>();
+        for (i, _) in graph.edges().enumerate().filter(|(_, e)| e.to == source) {
+            released[source] -= node_gain[source] * fractional[i] * gain[i];
+        }
+
+        Rounding {
+            graph,
+            sink,
+            gain,
+            node_gain,
+            out_edges,
+            partner,
+            fractional,
+            integral: vec![0.0; graph.num_edges],
+            received: vec![0.0; num_nodes],
+            released,
+        }
+    }
+
+    fn run(mut self) -> IntegralFlow {
+        let before = self.delivered(&self.fractional);
+        self.cancel_cycles();
+        let order = self.topological_order();
+        let mut ordered = vec![false; self.node_gain.len()];
+        for &v in order.iter() {
+            ordered[v] = true;
+            self.round(v);
+        }
+        self.give_back_behind_cycles(&ordered);
+
+        let delivered = self.delivered(&self.integral);
+        IntegralFlow {
+            flows: self.integral,
+            delivered,
+            loss: before - delivered,
+        }
+    }
+
+    fn is_zero(&self, flow: Flow, i: usize) -> bool {
+        flow <= DECOMPOSITION_EPS * self.graph.get_directed_edge(i).capacity
+    }
+
+    fn delivered(&self, flows: &[Flow]) -> Flow {
+        let received: Flow = self
+            .graph
+            .edges()
+            .enumerate()
+            .filter(|(_, e)| e.to == self.sink)
+            .map(|(i, _)| flows[i] * self.gain[i])
+            .sum();
+        let sent: Flow = self.out_edges[self.sink].iter().map(|&i| flows[i]).sum();
+        self.node_gain[self.sink] * received - sent
+    }
+
+    // depth first along the support, a cycle closes at a node still on the stack: without flow generation it loses
+    // its smallest flow and the search resumes at the tail of the first edge that lost all of it, otherwise its
+    // last edge is passed over for good
+    fn cancel_cycles(&mut self) {
+        let num_nodes = self.node_gain.len();
+        let mut position = vec![usize::MAX; num_nodes];
+        let mut done = vec![false; num_nodes];
+        let mut next = vec![0; num_nodes];
+        let mut passed = vec![false; self.graph.num_edges];
+        // (node, edge into it)
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+        for root in 0..num_nodes {
+            if done[root] {
+                continue;
+            }
+            position[root] = 0;
+            stack.push((root, usize::MAX));
+            while let Some(&(u, _)) = stack.last() {
+                let Some(&i) = self.out_edges[u].get(next[u]) else {
+                    done[u] = true;
+                    position[u] = usize::MAX;
+                    stack.pop();
+                    continue;
+                };
+                next[u] += 1;
+                let to = self.graph.get_directed_edge(i).to;
+                if passed[i] || done[to] || self.is_zero(self.fractional[i], i) {
+                    continue;
+                }
+                if position[to] == usize::MAX {
+                    position[to] = stack.len();
+                    stack.push((to, i));
+                    continue;
+                }
+
+                // the edges of the cycle from to back to to
+                let start = position[to];
+                let edges: Vec<usize> = stack[start + 1..]
+                    .iter()
+                    .map(|&(_, e)| e)
+                    .chain([i])
+                    .collect();
+                let mut multiplier = vec![1.0; edges.len() + 1];
+                for (k, &e) in edges.iter().enumerate() {
+                    multiplier[k + 1] = multiplier[k]
+                        * self.gain[e]
+                        * self.node_gain[self.graph.get_directed_edge(e).to];
+                }
+                if multiplier[edges.len()] > 1.0 {
+                    passed[i] = true;
+                    continue;
+                }
+                let (smallest, canceled) = edges
+                    .iter()
+                    .enumerate()
+                    .map(|(k, &e)| (k, self.fractional[e] / multiplier[k]))
+                    .fold((0, Flow::MAX), |a, b| if b.1 < a.1 { b } else { a });
+                for (k, &e) in edges.iter().enumerate() {
+                    self.fractional[e] -= canceled * multiplier[k];
+                    if k == smallest || self.is_zero(self.fractional[e], e) {
+                        self.fractional[e] = 0.0;
+                    }
+                }
+                let first = edges
+                    .iter()
+                    .position(|&e| self.fractional[e] == 0.0)
+                    .unwrap();
+                // back to the tail of edges[first], which looks at its edges again
+                for (v, _) in stack.drain(start + first + 1..) {
+                    position[v] = usize::MAX;
+                    next[v] = 0;
+                }
+                let tail = stack.last().unwrap().0;
+                next[tail] = 0;
+            }
+        }
+    }
+
+    // Kahn's order of the nodes over the support, without the nodes on or behind a cycle left
+    fn topological_order(&self) -> Vec<usize> {
+        let num_nodes = self.node_gain.len();
+        let mut in_degree = vec![0; num_nodes];
+        for (i, e) in self.graph.edges().enumerate() {
+            if !self.is_zero(self.fractional[i], i) {
+                in_degree[e.to] += 1;
+            }
+        }
+        let mut order: Vec<usize> = (0..num_nodes).filter(|&v| in_degree[v] == 0).collect();
+        let mut k = 0;
+        while k < order.len() {
+            let u = order[k];
+            k += 1;
+            for &i in self.out_edges[u].iter() {
+                let to = self.graph.get_directed_edge(i).to;
+                if !self.is_zero(self.fractional[i], i) {
+                    in_degree[to] -= 1;
+                    if in_degree[to] == 0 {
+                        order.push(to);
+                    }
+                }
+            }
+        }
+        order
+    }
+
+    // whole units of the flow of edge i, within its capacity
+    fn floor(&self, i: usize) -> Flow {
+        let flow = self.fractional[i];
+        (flow + tolerance(flow))
+            .floor()
+            .min(self.graph.get_directed_edge(i).capacity.floor())
+            .max(0.0)
+    }
+
+    fn available(&self, v: usize) -> Flow {
+        self.released[v] + self.node_gain[v] * self.received[v]
+    }
+
+    fn sent(&self, v: usize) -> Flow {
+        self.out_edges[v].iter().map(|&i| self.integral[i]).sum()
+    }
+
+    fn set(&mut self, i: usize, units: Flow) {
+        let e = self.graph.get_directed_edge(i);
+        self.received[e.to] += (units - self.integral[i]) * self.gain[i];
+        self.integral[i] = units;
+    }
+
+    // the floors, then the ceilings of the largest fractional parts first, as far as what v receives covers them
+    fn round(&mut self, v: usize) {
+        for k in 0..self.out_edges[v].len() {
+            let i = self.out_edges[v][k];
+            self.set(i, self.floor(i));
+        }
+        if self.give_back(v) || v == self.sink {
+            return;
+        }
+        let mut ceilings: Vec<usize> = self.out_edges[v]
+            .iter()
+            .copied()
+            .filter(|&i| self.integral[i] < self.fractional[i])
+            .collect();
+        ceilings.sort_by(|&a, &b| {
+            (self.fractional[b] - self.integral[b])
+                .total_cmp(&(self.fractional[a] - self.integral[a]))
+        });
+        let available = self.available(v);
+        let mut sent = self.sent(v);
+        for i in ceilings {
+            let others = self.partner[i].map_or(0.0, |p| self.integral[p].max(self.floor(p)));
+            let capacity = self.graph.get_directed_edge(i).capacity;
+            if sent + 1.0 > available + tolerance(available)
+                || self.integral[i] + others + 1.0 > capacity + tolerance(capacity)
+            {
+                continue;
+            }
+            self.set(i, self.integral[i] + 1.0);
+            sent += 1.0;
+        }
+    }
+
+    // units back on the edges out of v until it sends no more than it has, first on the ones rounded up and then
+    // on the ones of the lowest gains, true if v had to
+    fn give_back(&mut self, v: usize) -> bool {
+        let available = self.available(v);
+        let mut excess = self.sent(v) - available - tolerance(available);
+        if excess <= 0.0 {
+            return false;
+        }
+        let mut edges = self.out_edges[v].clone();
+        edges.sort_by(|&a, &b| {
+            (self.integral[a] <= self.fractional[a])
+                .cmp(&(self.integral[b] <= self.fractional[b]))
+                .then(self.gain[a].total_cmp(&self.gain[b]))
+        });
+        for i in edges {
+            if excess <= 0.0 {
+                break;
+            }
+            let units = self.integral[i].min(excess.ceil());
+            self.set(i, self.integral[i] - units);
+            excess -= units;
+        }
+        true
+    }
+
+    // the floors at the nodes topological_order left out, then units back until every one of them has enough, which
+    // only ever takes from the others of them
+    fn give_back_behind_cycles(&mut self, ordered: &[bool]) {
+        let mut pending: Vec<usize> = (0..ordered.len()).filter(|&v| !ordered[v]).collect();
+        for &v in pending.iter() {
+            for k in 0..self.out_edges[v].len() {
+                let i = self.out_edges[v][k];
+                self.set(i, self.floor(i));
+            }
+        }
+        while let Some(v) = pending.pop() {
+            if self.give_back(v) {
+                pending.extend(
+                    self.out_edges[v]
+                        .iter()
+                        .map(|&i| self.graph.get_directed_edge(i).to)
+                        .filter(|&to| !ordered[to]),
+                );
+            }
+        }
+    }
+}
+
+// the flows the solvers leave are a little off the integers they meet
+fn tolerance(flow: Flow) -> Flow {
+    DECOMPOSITION_EPS * flow.abs().max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntegralFlow, Rounding};
+    use crate::graph::{Flow, GeneralizedFlowError, ScalingGraph};
+    use crate::instances::read_graph_instance;
+    use crate::rounded_primal_dual::RoundedPrimalDual;
+    use crate::test_utilities::sample_instance;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    // whole units within the capacities, and no node but the source sends more than it receives
+    fn assert_feasible(graph: &ScalingGraph, source: usize, rounded: &IntegralFlow) {
+        let num_nodes = graph.num_user_nodes();
+        let mut received = vec![0.0; num_nodes];
+        let mut sent = vec![0.0; num_nodes];
+        for (i, &units) in rounded.flows.iter().enumerate() {
+            let edge = graph.get_directed_edge(i);
+            assert!(
+                units >= 0.0 && units == units.floor() && units <= edge.capacity,
+                "edge {} carries {}",
+                i,
+                units
+            );
+            sent[edge.from] += units;
+            received[edge.to] += units * graph.rounded_gain(i);
+        }
+        for i in graph.undirected_edges() {
+            assert!(rounded.flows[i] + rounded.flows[i + 1] <= graph.get_directed_edge(i).capacity);
+        }
+        for v in (0..num_nodes).filter(|&v| v != source) {
+            assert!(
+                sent[v] <= graph.rounded_node_gain(v) * received[v] + 1e-9,
+                "node {} receives {} but sends {}",
+                v,
+                received[v],
+                sent[v]
+            );
+        }
+    }
+
+    // at most a unit lost for each node the flow passes, the gains are at most 1
+    #[rstest]
+    fn gain_random(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        let instance = read_graph_instance(&path).unwrap();
+        let mut solver: RoundedPrimalDual = instance.build_solver(0.01);
+        let value = solver.solve(instance.source, instance.sink);
+        let rounded = solver
+            .round_to_integral(instance.source, instance.sink)
+            .unwrap();
+        assert_feasible(&solver.graph, instance.source, &rounded);
+        assert!(
+            (rounded.delivered + rounded.loss - value).abs() <= 1e-6 * value.max(1.0),
+            "{} {:?}",
+            value,
+            rounded
+        );
+        let nodes = (0..instance.num_nodes)
+            .filter(|&v| {
+                (0..instance.edges.len())
+                    .any(|i| instance.edges[i].0 == v && solver.get_flow(i).0 > 0.0)
+            })
+            .count();
+        assert!(
+            rounded.loss <= nodes as Flow,
+            "{} {} {:?}",
+            value,
+            nodes,
+            rounded
+        );
+    }
+
+    // whole units deliver at most 5.5 of the 7.363 here, 5 along 6 -> 7 and half a unit along 4 -> 7
+    #[test]
+    fn sample() {
+        let instance = sample_instance();
+        let mut solver: RoundedPrimalDual = instance.build_solver(0.01);
+        solver.solve(instance.source, instance.sink);
+        let rounded = solver
+            .round_to_integral(instance.source, instance.sink)
+            .unwrap();
+        assert_feasible(&solver.graph, instance.source, &rounded);
+        assert!(rounded.delivered >= 5.0, "{:?}", rounded);
+    }
+
+    // half a unit around 1 -> 2 -> 1, which would leave 1 and 2 to their floors, the cancelation orders them
+    #[test]
+    fn cycle() {
+        let mut solver = RoundedPrimalDual::new(4, 0.01);
+        for (from, to, capacity) in [
+            (0, 1, 2.0),
+            (0, 2, 2.0),
+            (1, 2, 1.0),
+            (2, 1, 1.0),
+            (1, 3, 2.0),
+            (2, 3, 2.0),
+        ] {
+            solver.add_directed_edge(from, to, capacity, 1.0).unwrap();
+        }
+        solver.solve(0, 3);
+        let mut rounding = Rounding::new(&solver.graph, 0, 3);
+        rounding.fractional = vec![1.5, 1.5, 0.5, 0.5, 1.5, 1.5];
+        rounding.released[0] = 3.0;
+        let rounded = rounding.run();
+        assert_eq!(
+            rounded,
+            IntegralFlow {
+                flows: vec![2.0, 1.0, 0.0, 0.0, 2.0, 1.0],
+                delivered: 3.0,
+                loss: 0.0
+            }
+        );
+    }
+
+    // 1 -> 2 -> 1 gains a factor 1.5 and feeds the sink, its nodes have no order and give back what they lack, in
+    // whole units it can't deliver anything
+    #[test]
+    fn flow_generating_cycle() {
+        let mut solver = RoundedPrimalDual::new(4, 0.01);
+        solver.add_directed_edge(1, 2, 3.0, 2.0).unwrap();
+        solver.add_directed_edge(2, 1, 4.0, 0.75).unwrap();
+        solver.add_directed_edge(1, 3, 5.0, 1.0).unwrap();
+        solver.add_directed_edge(0, 3, 1.0, 0.5).unwrap();
+        solver.solve(0, 3);
+        let rounded = solver.round_to_integral(0, 3).unwrap();
+        assert_feasible(&solver.graph, 0, &rounded);
+        assert_eq!(rounded.flows, vec![2.0, 3.0, 0.0, 1.0]);
+        assert!((rounded.delivered - 0.5).abs() < 0.01, "{:?}", rounded);
+    }
+
+    #[test]
+    fn invalid_input() {
+        let mut solver = RoundedPrimalDual::new(3, 0.01);
+        solver.add_directed_edge(0, 1, 1.0, 0.5).unwrap();
+        solver
+            .add_directed_edge_with_lower_bound(1, 2, 0.1, 1.0, 0.5)
+            .unwrap();
+        assert_eq!(
+            solver.round_to_integral(0, 0),
+            Err(GeneralizedFlowError::SourceEqualsSink)
+        );
+        assert_eq!(
+            solver.round_to_integral(0, 2),
+            Err(GeneralizedFlowError::LowerBoundsUnsupported)
+        );
+    }
+}
@@ -1,4 +1,28 @@
+// reproducibility: the same build of the crate on the same input gives bit-identical results, run after run and
+// whichever thread runs it: the solvers use no hash maps and no randomness, visit nodes and edges in the order of
+// their indices, break the ties of their queues by the order of the pushes or the node index, and the delta stepping
+// of the rayon feature finds the distances of the dijkstra whatever the threads do
+// only SolveLimits::time_budget depends on the clock, and powf, ln and log on the math library of the platform
+#[cfg(feature = "rayon")]
+mod delta_stepping;
+#[cfg(feature = "exact")]
+pub mod exact;
+pub mod gain_scaling_push_relabel;
+pub mod generators;
 pub mod graph;
 pub mod highest_gain_path_method;
+pub mod instances;
+pub mod integral;
+pub mod min_cost_generalized_flow;
+pub mod packing_fptas;
 pub mod rounded_primal_dual;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod solver;
 pub mod test_utilities;
+pub mod verify;
+
+pub use gain_scaling_push_relabel::GainScalingPushRelabel;
+pub use highest_gain_path_method::HighestGainPathMethod;
+pub use rounded_primal_dual::RoundedPrimalDual;
+pub use solver::GeneralizedMaxFlowSolver;
@@ -0,0 +1,591 @@
+use crate::graph::{Dist, EdgeError, Flow, GeneralizedFlowError, ScalingGraph, DIST_MAX, EPS};
+use std::collections::VecDeque;
+
+// relative change below which a price counts as the same, the tight cycles of the residual network
+// would otherwise keep lowering their prices by rounding errors
+const PRICE_TOLERANCE: Flow = 1e-12;
+
+// result of solve_min_cost
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinCostSolution {
+    // at the sink, the target unless no more reaches it
+    pub delivered: Flow,
+    // sum of cost times the flow entering each edge
+    pub cost: Flow,
+}
+
+// what the price of the sink was set by, as inside edges in the direction of flow: a path from the
+// source, or a flow generating cycle (cycle nonempty) and the path from its first node to the sink
+struct Augmentation {
+    cycle: Vec<usize>,
+    path: Vec<usize>,
+}
+
+// successive shortest paths on the gains rounded like RoundedPrimalDual: the price of a node is the cheapest
+// cost of a unit there, bought at the source for free, or made by a flow generating cycle of the residual
+// network at cost / (gain - 1) per unit, and each augmentation goes along what sets the price of the sink,
+// until the target arrives or nothing reaches the sink
+// the price follows (price + cost) / gain along an edge, so the edges of an augmentation stay tight in both
+// directions, no residual cycle ever pays, and each intermediate flow is the cheapest for what it delivers
+// (with the rounded gains, the original ones deliver up to a factor 1 + epsilon more)
+// the prices fall along the edges of gain above 1, e.g. the reverse edges, so that potentials do not make
+// them dijkstra weights, and a label correcting queue finds them
+pub struct MinCostGeneralizedFlow {
+    pub graph: ScalingGraph,
+    prices: Vec<Flow>,
+    prev: Vec<usize>,
+    // the flow generating cycle that set the price of a node, starting there, empty for the others
+    cycle_at: Vec<Vec<usize>>,
+    augmentations: usize,
+}
+
+#[allow(dead_code)]
+impl MinCostGeneralizedFlow {
+    pub fn new(num_nodes: usize, epsilon: Flow) -> Self {
+        assert!(epsilon > 0.0);
+        Self::with_graph(ScalingGraph::new(num_nodes, epsilon))
+    }
+
+    pub fn new_with_base(base: Flow) -> Self {
+        assert!(base > 1.0);
+        Self::with_graph(ScalingGraph::new_with_base(base))
+    }
+
+    fn with_graph(graph: ScalingGraph) -> Self {
+        MinCostGeneralizedFlow {
+            graph,
+            prices: Vec::new(),
+            prev: Vec::new(),
+            cycle_at: Vec::new(),
+            augmentations: 0,
+        }
+    }
+
+    // cost per unit entering the edge
+    pub fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+        cost: Flow,
+    ) -> Result<usize, EdgeError> {
+        self.graph
+            .try_add_directed_edge_with_cost(from, to, capacity, gain, cost)
+    }
+
+    // (flow entering, flow leaving) the edge, see ScalingGraph::get_flow
+    pub fn get_flow(&self, edge_index: usize) -> (Flow, Flow) {
+        self.graph.get_flow(edge_index)
+    }
+
+    // augmentations of the last solve
+    pub fn augmentations(&self) -> usize {
+        self.augmentations
+    }
+
+    // nothing delivered at no cost if try_solve_min_cost fails
+    pub fn solve_min_cost(&mut self, source: usize, sink: usize, target: Flow) -> MinCostSolution {
+        self.try_solve_min_cost(source, sink, target)
+            .unwrap_or(MinCostSolution {
+                delivered: 0.0,
+                cost: 0.0,
+            })
+    }
+
+    pub fn try_solve_min_cost(
+        &mut self,
+        source: usize,
+        sink: usize,
+        target: Flow,
+    ) -> Result<MinCostSolution, GeneralizedFlowError> {
+        self.graph.validate(&[source], sink)?;
+        if self.graph.has_lower_bounds() {
+            return Err(GeneralizedFlowError::LowerBoundsUnsupported);
+        }
+        if target.is_nan() || target < 0.0 {
+            return Err(GeneralizedFlowError::InvalidDemand);
+        }
+        self.graph.build();
+        if !self.graph.is_lossy {
+            return Err(self.graph.flow_generating_cycle_error());
+        }
+        let source = self.graph.entry(source);
+
+        self.augmentations = 0;
+        let mut delivered = 0.0;
+        while target - delivered > EPS * target {
+            let Some(augmentation) = self.find_cheapest_augmentation(source, sink) else {
+                break;
+            };
+            delivered += self.augment(&augmentation, target - delivered);
+            self.augmentations += 1;
+        }
+        self.graph.excesses[sink] = delivered;
+
+        let cost = (0..self.graph.num_edges)
+            .map(|i| self.graph.get_directed_edge(i).cost * self.get_flow(i).0)
+            .sum();
+        Ok(MinCostSolution { delivered, cost })
+    }
+
+    #[inline]
+    fn gain(&self, i: usize) -> Flow {
+        self.graph.scaled_gain(self.graph.inside_edge_list[i].dist)
+    }
+
+    #[inline]
+    fn tail(&self, i: usize) -> usize {
+        self.graph.inside_edge_list[self.graph.inside_edge_list[i].rev].to
+    }
+
+    // flow entering each edge per unit entering the first one, and after the last one
+    fn multipliers(&self, edges: &[usize]) -> Vec<Flow> {
+        let mut multipliers = vec![1.0];
+        for &i in edges.iter() {
+            multipliers.push(multipliers.last().unwrap() * self.gain(i));
+        }
+        multipliers
+    }
+
+    // as much as the residual capacities allow, up to remaining at the sink, returns what arrived there
+    fn augment(&mut self, augmentation: &Augmentation, remaining: Flow) -> Flow {
+        let cycle = self.multipliers(&augmentation.cycle);
+        let path = self.multipliers(&augmentation.path);
+        // the path carries what the cycle makes per unit sent around it
+        let scale = if augmentation.cycle.is_empty() {
+            1.0
+        } else {
+            cycle.last().unwrap() - 1.0
+        };
+
+        // the path can run over edges of the cycle, those take both shares
+        let mut usage: Vec<(usize, Flow)> = augmentation
+            .cycle
+            .iter()
+            .zip(cycle.iter())
+            .map(|(&i, &m)| (i, m))
+            .collect();
+        usage.extend(
+            augmentation
+                .path
+                .iter()
+                .zip(path.iter())
+                .map(|(&i, &m)| (i, scale * m)),
+        );
+        usage.sort_by_key(|&(i, _)| i);
+        let mut amount = remaining / (scale * path.last().unwrap());
+        for group in usage.chunk_by(|a, b| a.0 == b.0) {
+            let share: Flow = group.iter().map(|&(_, m)| m).sum();
+            amount =
+                amount.min(self.graph.inside_edge_list[group[0].0].residual_capacity() / share);
+        }
+
+        for (k, &i) in augmentation.cycle.iter().enumerate() {
+            self.graph
+                .push_real_flow(i, amount * cycle[k], amount * cycle[k + 1]);
+        }
+        // as the last edge of the path moved it, if the cycle doesn't end at the sink
+        let mut received = self.graph.round_flow(amount * scale * path.last().unwrap());
+        for (k, &i) in augmentation.path.iter().enumerate() {
+            received = self
+                .graph
+                .push_real_flow(i, amount * scale * path[k], amount * scale * path[k + 1])
+                .1;
+        }
+        received
+    }
+
+    // the prices from the source, then from the flow generating cycles no price reached, until none is left
+    // None if the sink is out of reach, or the prices do not settle
+    fn find_cheapest_augmentation(&mut self, source: usize, sink: usize) -> Option<Augmentation> {
+        let n = self.graph.num_nodes;
+        self.prices = vec![Flow::INFINITY; n];
+        self.prev = vec![usize::MAX; n];
+        self.cycle_at = vec![Vec::new(); n];
+        self.prices[source] = 0.0;
+        let mut start = source;
+        loop {
+            self.settle_prices(start, source)?;
+            let unpriced: Vec<bool> = self.prices.iter().map(|&p| p == Flow::INFINITY).collect();
+            let Some(cycle) = self.find_flow_generating_cycle(&unpriced) else {
+                break;
+            };
+            start = self.tail(cycle[0]);
+            self.prices[start] = self.cycle_price(&cycle)?;
+            self.cycle_at[start] = cycle;
+        }
+
+        if self.prices[sink] == Flow::INFINITY {
+            return None;
+        }
+        // back from the sink to the source or the node of a cycle, the prev edges around a cycle may close
+        // a tight cycle of gain 1 instead
+        let mut path = Vec::new();
+        let mut v = sink;
+        while v != source && self.cycle_at[v].is_empty() {
+            path.push(self.prev[v]);
+            v = self.tail(self.prev[v]);
+            if path.len() > n {
+                return None;
+            }
+        }
+        path.reverse();
+        Some(Augmentation {
+            cycle: self.cycle_at[v].clone(),
+            path,
+        })
+    }
+
+    // label correcting from start, a node relabeled more than n times is behind a flow generating cycle of the
+    // prev edges, whose price is the limit the prices fall towards
+    fn settle_prices(&mut self, start: usize, source: usize) -> Option<()> {
+        let n = self.graph.num_nodes;
+        let mut in_queue = vec![false; n];
+        let mut visit_count = vec![0_usize; n];
+        let mut cycles = 0;
+
+        let mut que = VecDeque::from([start]);
+        in_queue[start] = true;
+        while let Some(u) = que.pop_front() {
+            in_queue[u] = false;
+            for i in self.graph.start[u]..self.graph.start[u + 1] {
+                let e = &self.graph.inside_edge_list[i];
+                if e.residual_capacity() <= 0.0 || e.to == source {
+                    continue;
+                }
+                let price = (self.prices[u] + e.cost) / self.gain(i);
+                let known = self.prices[e.to];
+                if known != Flow::INFINITY && known - price <= PRICE_TOLERANCE * known.abs() {
+                    continue;
+                }
+                self.prices[e.to] = price;
+                self.prev[e.to] = i;
+                self.cycle_at[e.to].clear();
+
+                let mut v = e.to;
+                visit_count[v] += 1;
+                if visit_count[v] > n {
+                    // n steps back along the prev edges end up on the cycle
+                    for _ in 0..n {
+                        v = self.tail(*self.prev.get(v).filter(|&&i| i != usize::MAX)?);
+                    }
+                    let mut cycle = vec![self.prev[v]];
+                    while self.tail(*cycle.last().unwrap()) != v {
+                        cycle.push(self.prev[self.tail(*cycle.last().unwrap())]);
+                        if cycle.len() > n {
+                            return None;
+                        }
+                    }
+                    cycle.reverse();
+                    let price = self.cycle_price(&cycle)?;
+                    if price < self.prices[v] {
+                        self.prices[v] = price;
+                        self.cycle_at[v] = cycle;
+                    }
+                    visit_count = vec![0; n];
+                    cycles += 1;
+                    if cycles > n {
+                        #[cfg(feature = "log")]
+                        log::debug!("prices do not settle at node {}", v);
+                        return None;
+                    }
+                }
+                if !in_queue[v] {
+                    in_queue[v] = true;
+                    que.push_back(v);
+                }
+            }
+        }
+        Some(())
+    }
+
+    // cost per unit a cycle makes at its first node, None unless it generates flow
+    fn cycle_price(&self, cycle: &[usize]) -> Option<Flow> {
+        // by the dists, a cycle of rounded gain 1 can multiply out above it
+        if cycle
+            .iter()
+            .map(|&i| self.graph.inside_edge_list[i].dist)
+            .sum::<Dist>()
+            >= 0
+        {
+            return None;
+        }
+        let multipliers = self.multipliers(cycle);
+        let gain = *multipliers.last().unwrap();
+        let cost: Flow = cycle
+            .iter()
+            .zip(multipliers.iter())
+            .map(|(&i, m)| self.graph.inside_edge_list[i].cost * m)
+            .sum();
+        Some(cost / (gain - 1.0))
+    }
+
+    // a cycle of negative dist over residual edges between the given nodes, by bellman-ford from all of them,
+    // as inside edges in the direction of flow
+    fn find_flow_generating_cycle(&self, among: &[bool]) -> Option<Vec<usize>> {
+        let n = self.graph.num_nodes;
+        let mut distance: Vec<Dist> = vec![0; n];
+        let mut prev = vec![usize::MAX; n];
+        let mut relaxed = None;
+        for _ in 0..n {
+            relaxed = None;
+            for u in (0..n).filter(|&u| among[u]) {
+                for i in self.graph.start[u]..self.graph.start[u + 1] {
+                    let e = &self.graph.inside_edge_list[i];
+                    if among[e.to]
+                        && e.residual_capacity() > 0.0
+                        && distance[u] + e.dist < distance[e.to]
+                    {
+                        distance[e.to] = (distance[u] + e.dist).max(-DIST_MAX);
+                        prev[e.to] = i;
+                        relaxed = Some(e.to);
+                    }
+                }
+            }
+            relaxed?;
+        }
+
+        let mut v = relaxed?;
+        for _ in 0..n {
+            v = self.tail(prev[v]);
+        }
+        let mut cycle = vec![prev[v]];
+        while self.tail(*cycle.last().unwrap()) != v {
+            cycle.push(prev[self.tail(*cycle.last().unwrap())]);
+        }
+        cycle.reverse();
+        Some(cycle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinCostGeneralizedFlow;
+    use crate::graph::{EdgeError, Flow, GeneralizedFlowError, FLOW_MAX};
+    use crate::instances::{read_graph_instance, GraphInstance};
+    use crate::rounded_primal_dual::RoundedPrimalDual;
+    use crate::test_utilities::assert_certified_case;
+    use crate::verify::check;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    // every fifth edge is free, the others cost 1 to 4 per unit
+    fn cost_of(i: usize) -> Flow {
+        (i * 7 % 5) as Flow
+    }
+
+    fn solver_with_costs(instance: &GraphInstance, epsilon: Flow) -> MinCostGeneralizedFlow {
+        let mut solver = MinCostGeneralizedFlow::new(instance.num_nodes, epsilon);
+        for (i, &(from, to, capacity, gain)) in instance.edges.iter().enumerate() {
+            solver
+                .add_directed_edge(from, to, capacity, gain, cost_of(i))
+                .unwrap();
+        }
+        solver
+    }
+
+    #[test]
+    fn cheaper_lossy_path_first() {
+        // 0 -> 1 -> 3 costs 2 per unit delivered, 0 -> 2 -> 3 costs 3
+        // base 2 keeps the gains exact
+        let mut solver = MinCostGeneralizedFlow::new_with_base(2.0);
+        for (from, to, capacity, gain, cost) in [
+            (0, 1, 10.0, 1.0, 1.0),
+            (1, 3, 10.0, 0.5, 0.0),
+            (0, 2, 4.0, 1.0, 3.0),
+            (2, 3, 10.0, 1.0, 0.0),
+        ] {
+            solver
+                .add_directed_edge(from, to, capacity, gain, cost)
+                .unwrap();
+        }
+        let solution = solver.solve_min_cost(0, 3, 4.0);
+        assert!(
+            (solution.delivered - 4.0).abs() < 1e-9 && (solution.cost - 8.0).abs() < 1e-9,
+            "{:?}",
+            solution
+        );
+        let solution = solver.solve_min_cost(0, 3, 6.0);
+        assert!(
+            (solution.delivered - 6.0).abs() < 1e-9 && (solution.cost - 13.0).abs() < 1e-9,
+            "{:?}",
+            solution
+        );
+        assert_eq!(solver.augmentations(), 2);
+        // no more than 5 + 4 reaches the sink
+        let solution = solver.solve_min_cost(0, 3, 100.0);
+        assert!(
+            (solution.delivered - 9.0).abs() < 1e-9 && (solution.cost - 22.0).abs() < 1e-9,
+            "{:?}",
+            solution
+        );
+    }
+
+    #[test]
+    fn reroutes_over_reverse_edges() {
+        // the cheapest unit goes 0 -> 1 -> 2 -> 3, the second one has to undo 1 -> 2 to use both 1 -> 3 and 0 -> 2
+        let mut solver = MinCostGeneralizedFlow::new_with_base(2.0);
+        for (from, to, capacity, gain, cost) in [
+            (0, 1, 1.0, 1.0, 1.0),
+            (1, 2, 1.0, 1.0, 0.0),
+            (2, 3, 1.0, 1.0, 1.0),
+            (1, 3, 1.0, 1.0, 5.0),
+            (0, 2, 1.0, 1.0, 5.0),
+        ] {
+            solver
+                .add_directed_edge(from, to, capacity, gain, cost)
+                .unwrap();
+        }
+        let solution = solver.solve_min_cost(0, 3, 1.0);
+        assert!((solution.cost - 2.0).abs() < 1e-9, "{:?}", solution);
+        let solution = solver.solve_min_cost(0, 3, 2.0);
+        assert!(
+            (solution.delivered - 2.0).abs() < 1e-9 && (solution.cost - 12.0).abs() < 1e-9,
+            "{:?}",
+            solution
+        );
+        assert_eq!(solver.get_flow(1), (0.0, 0.0));
+    }
+
+    #[test]
+    fn invalid() {
+        let mut solver = MinCostGeneralizedFlow::new(4, 0.01);
+        assert_eq!(
+            solver.try_solve_min_cost(0, 1, 1.0),
+            Err(GeneralizedFlowError::EmptyGraph)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, 1.0, 1.0, -1.0),
+            Err(EdgeError::NegativeCost)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, 1.0, 1.0, Flow::NAN),
+            Err(EdgeError::NonFiniteCost)
+        );
+
+        // 1 -> 2 -> 1 doubles the flow on its way to 3
+        solver.add_directed_edge(0, 1, 1.0, 1.0, 0.0).unwrap();
+        solver.add_directed_edge(1, 2, 1.0, 2.0, 0.0).unwrap();
+        solver.add_directed_edge(2, 1, 1.0, 1.0, 0.0).unwrap();
+        solver.add_directed_edge(2, 3, 10.0, 1.0, 0.0).unwrap();
+        assert_eq!(
+            solver.try_solve_min_cost(0, 3, -1.0),
+            Err(GeneralizedFlowError::InvalidDemand)
+        );
+        assert_eq!(
+            solver.try_solve_min_cost(0, 3, Flow::NAN),
+            Err(GeneralizedFlowError::InvalidDemand)
+        );
+        assert_eq!(
+            solver.try_solve_min_cost(0, 3, 1.0),
+            Err(GeneralizedFlowError::FlowGeneratingCycle(vec![1, 2]))
+        );
+        let solution = solver.solve_min_cost(0, 3, 1.0);
+        assert_eq!((solution.delivered, solution.cost), (0.0, 0.0));
+    }
+
+    #[rstest]
+    fn aoj_grl_6_a(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        let epsilon: Flow = 0.01;
+        let instance = read_graph_instance(&path).unwrap();
+
+        // without a limit, as much arrives as RoundedPrimalDual sends on the same rounded gains
+        let mut solver = solver_with_costs(&instance, epsilon);
+        let mut rounded_primal_dual = RoundedPrimalDual::new(instance.num_nodes, epsilon);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            rounded_primal_dual
+                .add_directed_edge(from, to, capacity, gain)
+                .unwrap();
+        }
+        let maximum = rounded_primal_dual.solve(instance.source, instance.sink);
+        let all = solver
+            .try_solve_min_cost(instance.source, instance.sink, FLOW_MAX)
+            .unwrap();
+        assert!(
+            (all.delivered - maximum).abs() <= 1e-9 * maximum.max(1.0),
+            "{} {}",
+            all.delivered,
+            maximum
+        );
+        check(&solver.graph, instance.source, instance.sink, 1e-6).unwrap();
+
+        // half of it costs at most half as much, the cheapest units go first
+        let half = solver
+            .try_solve_min_cost(instance.source, instance.sink, maximum / 2.0)
+            .unwrap();
+        assert!(
+            (half.delivered - maximum / 2.0).abs() <= 1e-9 * maximum.max(1.0),
+            "{} {}",
+            half.delivered,
+            maximum
+        );
+        assert!(
+            half.cost <= all.cost / 2.0 + 1e-9 * all.cost.max(1.0),
+            "{} {}",
+            half.cost,
+            all.cost
+        );
+        check(&solver.graph, instance.source, instance.sink, 1e-6).unwrap();
+        assert_certified_case(&path, epsilon);
+    }
+
+    // the linear program on the rounded gains has the same optimum
+    #[cfg(feature = "exact")]
+    #[rstest]
+    fn linear_program(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        use crate::exact::min_cost_generalized_flow_lp;
+        use num_rational::BigRational;
+        use num_traits::ToPrimitive;
+
+        assert_certified_case(&path, 0.01);
+        let instance = read_graph_instance(&path).unwrap();
+        if instance.edges.len() > 20 {
+            return;
+        }
+        let rational = |x: Flow| BigRational::from_float(x).unwrap();
+
+        let mut solver = solver_with_costs(&instance, 0.01);
+        let maximum = solver
+            .solve_min_cost(instance.source, instance.sink, FLOW_MAX)
+            .delivered;
+        let edges: Vec<_> = instance
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, &(from, to, capacity, _))| {
+                (
+                    from,
+                    to,
+                    rational(capacity),
+                    rational(solver.graph.rounded_gain(i)),
+                    rational(cost_of(i)),
+                )
+            })
+            .collect();
+
+        for target in [maximum / 3.0, maximum] {
+            let actual = solver.solve_min_cost(instance.source, instance.sink, target);
+            let (delivered, cost) = min_cost_generalized_flow_lp(
+                instance.num_nodes,
+                &edges,
+                instance.source,
+                instance.sink,
+                &rational(target),
+            );
+            let (delivered, cost) = (delivered.to_f64().unwrap(), cost.to_f64().unwrap());
+            assert!(
+                (actual.delivered - delivered).abs() <= 1e-9 * delivered.max(1.0),
+                "{} {}",
+                actual.delivered,
+                delivered
+            );
+            assert!(
+                (actual.cost - cost).abs() <= 1e-6 * cost.max(1.0),
+                "{} {}",
+                actual.cost,
+                cost
+            );
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use crate::graph::{
+    check_edge, cycle_along, normalize_cycle, Edge, EdgeError, Flow, GeneralizedFlowError,
+};
+
+// the lengths are divided by this, and the offset raised, once one of them grows above it
+const RESCALE: Flow = 1e100;
+
+// Garg-Koenemann packing with the phases of Fleischer, on the paths of the lossy network: sending a unit
+// from the source along a path uses its gain prefix of each edge, and delivers its gain at the sink
+// edge lengths grow multiplicatively with their use, and each augmentation takes a path whose length per
+// unit delivered is within 1 + epsilon / 3 of the shortest, which gives the (1 - epsilon) guarantee
+// flow generating cycles (and so the gains above 1 that close one) are not supported
+pub struct PackingFptas {
+    pub num_nodes: usize,
+    pub num_edges: usize,
+    epsilon: Flow,
+    edge_list: Vec<Edge>,
+    iterations: usize,
+}
+
+#[allow(dead_code)]
+impl PackingFptas {
+    pub fn new(epsilon: Flow) -> Self {
+        assert!(epsilon > 0.0 && epsilon < 1.0);
+        PackingFptas {
+            num_nodes: 0,
+            num_edges: 0,
+            epsilon,
+            edge_list: Vec::new(),
+            iterations: 0,
+        }
+    }
+
+    pub fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError> {
+        check_edge(capacity, gain)?;
+        self.edge_list.push(Edge {
+            from,
+            to,
+            flow: 0.0,
+            capacity,
+            gain,
+            cost: 0.0,
+            lower: 0.0,
+        });
+        self.num_nodes = self.num_nodes.max(from.max(to) + 1);
+        self.num_edges += 1;
+        Ok(self.num_edges - 1)
+    }
+
+    // (flow entering, flow leaving) the edge
+    pub fn get_flow(&self, edge_index: usize) -> (Flow, Flow) {
+        let edge = &self.edge_list[edge_index];
+        (edge.flow, edge.flow * edge.gain)
+    }
+
+    // augmentations of the last solve
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    // 0 if try_solve fails
+    pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.try_solve(source, sink).unwrap_or(0.0)
+    }
+
+    pub fn try_solve(&mut self, source: usize, sink: usize) -> Result<Flow, GeneralizedFlowError> {
+        if self.num_edges == 0 {
+            return Err(GeneralizedFlowError::EmptyGraph);
+        }
+        for u in [source, sink] {
+            if u >= self.num_nodes {
+                return Err(GeneralizedFlowError::InvalidTerminal(u));
+            }
+        }
+        if source == sink {
+            return Err(GeneralizedFlowError::SourceEqualsSink);
+        }
+        for e in self.edge_list.iter_mut() {
+            e.flow = 0.0;
+        }
+        self.iterations = 0;
+
+        let epsilon = self.epsilon / 3.0;
+        let m = self.num_edges as Flow;
+        // delta = (1 + epsilon) / ((1 + epsilon) m)^(1 / epsilon), far below f64 for small epsilon
+        let ln_delta = (1.0 + epsilon).ln() - ((1.0 + epsilon) * m).ln() / epsilon;
+
+        // the length of edge i is lengths[i] * e^offset, delta / capacity at first
+        let mut lengths: Vec<Flow> = self.edge_list.iter().map(|e| 1.0 / e.capacity).collect();
+        let mut offset = ln_delta;
+        // a lower bound on the length per unit delivered of every path, in the units of lengths
+        let ln_gain_bound: Flow = self.edge_list.iter().map(|e| e.gain.ln().max(0.0)).sum();
+        let mut lambda = (lengths.iter().copied().fold(Flow::MAX, Flow::min)
+            * (-ln_gain_bound).exp())
+        .max(Flow::MIN_POSITIVE);
+
+        let mut delivered = 0.0;
+        loop {
+            // the dual is feasible once no path is shorter than 1 per unit delivered
+            let capped = lambda.ln() + (1.0 + epsilon).ln() + offset >= 0.0;
+            let threshold = if capped {
+                (-offset).exp()
+            } else {
+                lambda * (1.0 + epsilon)
+            };
+
+            let Some(path) = self.find_short_path(source, sink, &lengths, threshold)? else {
+                if capped {
+                    break;
+                }
+                lambda *= 1.0 + epsilon;
+                continue;
+            };
+
+            // flow entering each edge of the path per unit leaving the source
+            let mut multiplier = 1.0;
+            let mut multipliers = Vec::with_capacity(path.len());
+            for &i in path.iter() {
+                multipliers.push(multiplier);
+                multiplier *= self.edge_list[i].gain;
+            }
+            let sent = path
+                .iter()
+                .zip(multipliers.iter())
+                .map(|(&i, m)| self.edge_list[i].capacity / m)
+                .fold(Flow::MAX, Flow::min);
+
+            for (&i, m) in path.iter().zip(multipliers.iter()) {
+                let e = &mut self.edge_list[i];
+                e.flow += sent * m;
+                lengths[i] *= 1.0 + epsilon * sent * m / e.capacity;
+            }
+            delivered += sent * multiplier;
+            self.iterations += 1;
+
+            if lengths.iter().any(|&l| l > RESCALE) {
+                for l in lengths.iter_mut() {
+                    *l /= RESCALE;
+                }
+                lambda = (lambda / RESCALE).max(Flow::MIN_POSITIVE);
+                offset += RESCALE.ln();
+            }
+        }
+
+        // each edge carries at most log_{1 + epsilon}((1 + epsilon) / delta) times its capacity,
+        // the congestion only catches the rounding errors
+        let scale = ((1.0 + epsilon).ln() - ln_delta) / (1.0 + epsilon).ln();
+        let congestion = self
+            .edge_list
+            .iter()
+            .map(|e| e.flow / scale / e.capacity)
+            .fold(1.0, Flow::max);
+        for e in self.edge_list.iter_mut() {
+            e.flow /= scale * congestion;
+        }
+        Ok(delivered / (scale * congestion))
+    }
+
+    // a path whose length per unit delivered is below threshold: bellman-ford towards the sink on the value
+    // of a unit at each node, its gain to the sink minus its length divided by threshold
+    fn find_short_path(
+        &self,
+        source: usize,
+        sink: usize,
+        lengths: &[Flow],
+        threshold: Flow,
+    ) -> Result<Option<Vec<usize>>, GeneralizedFlowError> {
+        let mut value = vec![Flow::NEG_INFINITY; self.num_nodes];
+        let mut next = vec![usize::MAX; self.num_nodes];
+        value[sink] = 1.0;
+
+        for round in 0..=self.num_nodes {
+            let mut relaxed = None;
+            for (i, e) in self.edge_list.iter().enumerate() {
+                // a path through a node without positive value has none either, and lossy cycles would
+                // keep raising negative values towards their fixed point
+                if e.from == sink || value[e.to] <= 0.0 {
+                    continue;
+                }
+                let candidate = e.gain * value[e.to] - lengths[i] / threshold;
+                if candidate > value[e.from] {
+                    value[e.from] = candidate;
+                    next[e.from] = i;
+                    relaxed = Some(e.from);
+                }
+            }
+            let Some(u) = relaxed else {
+                break;
+            };
+            if round == self.num_nodes {
+                // the nexts from a node relaxed in the last round run into the cycle
+                let cycle = cycle_along(u, self.num_nodes, |v| {
+                    (next[v] != usize::MAX).then(|| self.edge_list[next[v]].to)
+                });
+                return Err(GeneralizedFlowError::FlowGeneratingCycle(
+                    cycle.map(normalize_cycle).unwrap_or_default(),
+                ));
+            }
+        }
+
+        if value[source] <= 0.0 {
+            return Ok(None);
+        }
+        let mut path = Vec::new();
+        let mut u = source;
+        while u != sink {
+            path.push(next[u]);
+            u = self.edge_list[next[u]].to;
+        }
+        Ok(Some(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PackingFptas;
+    use crate::graph::{Flow, GeneralizedFlowError, EPS};
+    use crate::instances::read_graph_instance;
+    use crate::rounded_primal_dual::RoundedPrimalDual;
+    use crate::test_utilities::{
+        assert_approximate_conservation, assert_certified_case, read_expected, sample_instance,
+    };
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn sample() {
+        let epsilon = 0.05;
+        let instance = sample_instance();
+        let mut solver = PackingFptas::new(epsilon);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
+        }
+        let actual = solver.solve(instance.source, instance.sink);
+        assert!(
+            7.363 * (1.0 - epsilon) <= actual && actual <= 7.363 + 1e-9,
+            "{}",
+            actual
+        );
+        assert!(solver.iterations() > 0);
+
+        // the paths conserve the flow exactly, and the scaling keeps every edge within its capacity
+        let flows: Vec<(Flow, Flow)> = (0..instance.edges.len())
+            .map(|i| solver.get_flow(i))
+            .collect();
+        assert_approximate_conservation(&instance, &flows, actual, 0.0);
+    }
+
+    #[test]
+    fn invalid_instances() {
+        let mut solver = PackingFptas::new(0.1);
+        assert_eq!(
+            solver.try_solve(0, 1),
+            Err(GeneralizedFlowError::EmptyGraph)
+        );
+        // 1 -> 2 -> 1 doubles the flow on its way to 3
+        solver.add_directed_edge(0, 1, 1.0, 1.0).unwrap();
+        solver.add_directed_edge(1, 2, 1.0, 2.0).unwrap();
+        solver.add_directed_edge(2, 1, 1.0, 1.0).unwrap();
+        solver.add_directed_edge(2, 3, 10.0, 1.0).unwrap();
+        assert_eq!(
+            solver.try_solve(0, 0),
+            Err(GeneralizedFlowError::SourceEqualsSink)
+        );
+        assert_eq!(
+            solver.try_solve(0, 4),
+            Err(GeneralizedFlowError::InvalidTerminal(4))
+        );
+        assert_eq!(
+            solver.try_solve(0, 3),
+            Err(GeneralizedFlowError::FlowGeneratingCycle(vec![1, 2]))
+        );
+    }
+
+    #[rstest]
+    fn aoj_grl_6_a(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        let mut expected_file_path = path.clone();
+        expected_file_path.set_extension("out");
+
+        let epsilon: Flow = 0.05;
+        let instance = read_graph_instance(&path).unwrap();
+        let mut solver = PackingFptas::new(epsilon);
+        let mut rounded_primal_dual = RoundedPrimalDual::new(instance.num_nodes, epsilon);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
+            rounded_primal_dual
+                .add_directed_edge(from, to, capacity, gain)
+                .unwrap();
+        }
+        let actual = solver.try_solve(instance.source, instance.sink).unwrap();
+        let expected = read_expected(&expected_file_path) as Flow;
+
+        if expected == 0.0 {
+            assert!(actual < EPS);
+        } else {
+            assert!(
+                expected * (1.0 - epsilon) <= actual && actual <= expected + 1e-6,
+                "{} {}",
+                actual,
+                expected
+            );
+        }
+        // both approximate the same optimum from below
+        let other = rounded_primal_dual.solve(instance.source, instance.sink);
+        assert!(
+            (actual - other).abs() <= epsilon * expected + 1e-6,
+            "{} {}",
+            actual,
+            other
+        );
+        assert_certified_case(&path, epsilon);
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// bump when the layout of the serialized structs changes
+pub const FORMAT_VERSION: u32 = 9;
+
+// a built ScalingGraph keeps its rounded gains, RoundedPrimalDual::with_graph and HighestGainPathMethod::with_graph
+// solve on it without rounding them again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionMismatch {
+    pub expected: u32,
+    pub found: u32,
+}
+
+impl fmt::Display for VersionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unsupported format version {} (expected {})",
+            self.found, self.expected
+        )
+    }
+}
+
+impl std::error::Error for VersionMismatch {}
+
+impl<T> Versioned<T> {
+    pub fn new(data: T) -> Self {
+        Versioned {
+            version: FORMAT_VERSION,
+            data,
+        }
+    }
+
+    pub fn into_inner(self) -> Result<T, VersionMismatch> {
+        if self.version != FORMAT_VERSION {
+            return Err(VersionMismatch {
+                expected: FORMAT_VERSION,
+                found: self.version,
+            });
+        }
+        Ok(self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VersionMismatch, Versioned, FORMAT_VERSION};
+    use crate::graph::ScalingGraph;
+    use crate::highest_gain_path_method::HighestGainPathMethod;
+    use crate::instances::read_graph_instance;
+    use crate::rounded_primal_dual::RoundedPrimalDual;
+    use crate::test_utilities::{assert_certified_case, sample_instance};
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn graph_round_trip(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        let instance = read_graph_instance(&path).unwrap();
+        let mut graph = ScalingGraph::new(instance.num_nodes, 0.01);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            graph.add_directed_edge(from, to, capacity, gain).unwrap();
+        }
+        graph.build();
+
+        let json = serde_json::to_string(&Versioned::new(&graph)).unwrap();
+        let restored: ScalingGraph = serde_json::from_str::<Versioned<ScalingGraph>>(&json)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        assert_eq!(
+            (restored.base(), restored.is_lossy()),
+            (graph.base(), graph.is_lossy())
+        );
+
+        let mut original = RoundedPrimalDual::with_graph(graph);
+        let mut solver = RoundedPrimalDual::with_graph(restored);
+        assert_eq!(
+            solver.solve(instance.source, instance.sink),
+            original.solve(instance.source, instance.sink)
+        );
+        for i in 0..instance.edges.len() {
+            assert_eq!(solver.get_flow(i), original.get_flow(i));
+        }
+        assert_certified_case(&path, 0.01);
+    }
+
+    #[test]
+    fn solver_round_trip() {
+        let instance = sample_instance();
+        let mut solver: HighestGainPathMethod = instance.build_solver(0.01);
+        let value = solver.solve(instance.source, instance.sink);
+
+        let json = serde_json::to_string(&Versioned::new(&solver)).unwrap();
+        let mut restored: HighestGainPathMethod =
+            serde_json::from_str::<Versioned<HighestGainPathMethod>>(&json)
+                .unwrap()
+                .into_inner()
+                .unwrap();
+        assert_eq!(restored.stats(), solver.stats());
+        for i in 0..instance.edges.len() {
+            assert_eq!(restored.get_flow(i), solver.get_flow(i));
+        }
+        assert_eq!(restored.solve(instance.source, instance.sink), value);
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let json = format!(
+            "{{\"version\":{},\"data\":{}}}",
+            FORMAT_VERSION + 1,
+            serde_json::to_string(&ScalingGraph::new(1, 0.1)).unwrap()
+        );
+        let versioned: Versioned<ScalingGraph> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            versioned.into_inner().err(),
+            Some(VersionMismatch {
+                expected: FORMAT_VERSION,
+                found: FORMAT_VERSION + 1
+            })
+        );
+    }
+}
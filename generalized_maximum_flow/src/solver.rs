@@ -0,0 +1,431 @@
+use crate::graph::{EdgeError, Flow, GeneralizedFlowError, ScalingGraph};
+
+// the solvers that round the gains of a ScalingGraph for epsilon, the value they return is at least 1 - epsilon
+// times the maximum
+pub trait GeneralizedMaxFlowSolver {
+    // num_nodes only gives the base until the edges are known
+    fn new(num_nodes: usize, epsilon: Flow) -> Self
+    where
+        Self: Sized;
+    fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError>;
+    // see ScalingGraph::try_add_undirected_edge
+    fn add_undirected_edge(
+        &mut self,
+        u: usize,
+        v: usize,
+        capacity: Flow,
+        gain: Flow,
+    ) -> Result<usize, EdgeError>;
+    fn try_solve(&mut self, source: usize, sink: usize) -> Result<Flow, GeneralizedFlowError>;
+    // the flow of the last solve, see ScalingGraph::get_flow
+    fn graph(&self) -> &ScalingGraph;
+    // for the settings of the graph before solving, e.g. ScalingGraph::integral_capacities
+    fn graph_mut(&mut self) -> &mut ScalingGraph;
+
+    // 0 if try_solve fails
+    fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.try_solve(source, sink).unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GeneralizedMaxFlowSolver;
+    use crate::gain_scaling_push_relabel::GainScalingPushRelabel;
+    use crate::generators;
+    use crate::graph::{EdgeError, Flow, GeneralizedFlowError, EPS, FLOW_MAX, FLOW_UNIT};
+    use crate::highest_gain_path_method::HighestGainPathMethod;
+    use crate::instances::read_graph_instance;
+    use crate::rounded_primal_dual::RoundedPrimalDual;
+    use crate::test_utilities::{assert_certified, read_expected, sample_instance};
+    use crate::verify::check;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    // within 1 - epsilon of the optimum, a flow verify::check accepts, and hardly any clamping on the way
+    fn aoj_grl_6_a<S: GeneralizedMaxFlowSolver>(path: &PathBuf, epsilon: Flow) {
+        let mut expected_file_path = path.clone();
+        expected_file_path.set_extension("out");
+        let expected = read_expected(&expected_file_path);
+
+        let instance = read_graph_instance(path).unwrap();
+        let mut solver: S = instance.build_solver(epsilon);
+        let actual = solver.solve(instance.source, instance.sink);
+        check(solver.graph(), instance.source, instance.sink, 1e-6).unwrap();
+        assert!(
+            solver.graph().numerical_drift() <= 1e-9 * actual.max(1.0),
+            "{}",
+            solver.graph().numerical_drift()
+        );
+
+        if expected == 0.0 {
+            assert!(actual < EPS);
+        } else {
+            assert!(
+                expected * (1.0 - epsilon) <= actual && actual <= expected,
+                "{} {}",
+                actual,
+                expected
+            );
+        }
+        let certificate = assert_certified(&instance, epsilon, expected);
+        assert!(
+            (1.0 - epsilon) * certificate.upper_bound <= actual + 1e-9 * expected.max(1.0),
+            "{} {:?}",
+            actual,
+            certificate
+        );
+    }
+
+    // the capacities of gain_random are integers, so the flows of ScalingGraph::integral_capacities need no clamping,
+    // the flows and the excesses are whole units, and the values stay within 1 - epsilon of the optimum
+    // a fractional capacity is rejected
+    fn integral_capacities<S: GeneralizedMaxFlowSolver>(path: &PathBuf, epsilon: Flow) {
+        let expected = read_expected(&path.with_extension("out"));
+        let instance = read_graph_instance(path).unwrap();
+        let mut solver: S = instance.build_solver(epsilon);
+        solver.graph_mut().integral_capacities = true;
+        let actual = solver.solve(instance.source, instance.sink);
+        assert_eq!(solver.graph().numerical_drift(), 0.0);
+        let graph = solver.graph();
+        assert!((0..graph.num_edges()).all(|i| graph.get_flow(i).0 % FLOW_UNIT == 0.0));
+        assert!(graph
+            .excesses
+            .iter()
+            .all(|&excess| excess == FLOW_MAX || excess % FLOW_UNIT == 0.0));
+        check(solver.graph(), instance.source, instance.sink, 1e-6).unwrap();
+        assert!(
+            expected * (1.0 - epsilon) <= actual && actual <= expected + 1e-9,
+            "{} {}",
+            actual,
+            expected
+        );
+
+        let mut solver: S = instance.build_solver(epsilon);
+        solver.graph_mut().integral_capacities = true;
+        let edge = solver.add_directed_edge(instance.source, instance.sink, 0.5, 0.5);
+        assert_eq!(
+            solver.try_solve(instance.source, instance.sink),
+            Err(GeneralizedFlowError::NonIntegralCapacity(edge.unwrap()))
+        );
+    }
+
+    fn sample<S: GeneralizedMaxFlowSolver>() {
+        let epsilon: Flow = 0.01;
+        let instance = sample_instance();
+        let mut solver: S = instance.build_solver(epsilon);
+        let actual = solver.solve(instance.source, instance.sink);
+        let expected = 7.363;
+        assert!(
+            expected * (1.0 - epsilon) <= actual && actual <= expected,
+            "{}",
+            actual
+        );
+        // GainScalingPushRelabel strands excess inside, so no assert_approximate_conservation here
+        assert_eq!(
+            check(solver.graph(), instance.source, instance.sink, 1e-9),
+            Ok(())
+        );
+    }
+
+    fn invalid_input<S: GeneralizedMaxFlowSolver>() {
+        let mut solver = S::new(3, 0.01);
+        assert_eq!(
+            solver.try_solve(0, 2),
+            Err(GeneralizedFlowError::EmptyGraph)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, 0.0, 1.0),
+            Err(EdgeError::NonPositiveCapacity)
+        );
+        assert_eq!(
+            solver.add_directed_edge(0, 1, 1.0, Flow::NAN),
+            Err(EdgeError::NonFiniteGain)
+        );
+        assert_eq!(
+            solver.add_directed_edge(1, 1, 1.0, 0.5),
+            Err(EdgeError::SelfLoop)
+        );
+        assert_eq!(
+            solver.add_directed_edge(1, 1, 1.0, 2.0),
+            Err(EdgeError::FlowGeneratingSelfLoop)
+        );
+        assert_eq!(
+            solver.add_undirected_edge(1, 1, 1.0, 1.0),
+            Err(EdgeError::SelfLoop)
+        );
+        assert_eq!(solver.add_directed_edge(0, 1, 2.0, 0.5), Ok(0));
+        assert_eq!(solver.add_directed_edge(1, 2, 2.0, 0.5), Ok(1));
+        assert_eq!(
+            solver.try_solve(1, 1),
+            Err(GeneralizedFlowError::SourceEqualsSink)
+        );
+        assert_eq!(
+            solver.try_solve(0, 3),
+            Err(GeneralizedFlowError::InvalidTerminal(3))
+        );
+        assert_eq!(solver.solve(0, 3), 0.0);
+        assert!((solver.solve(0, 2) - 0.5).abs() < 0.01);
+    }
+
+    // the gains are rounded once for the queries, which leave nothing behind for the next one
+    fn reuse<S: GeneralizedMaxFlowSolver>() {
+        let instance = sample_instance();
+        let mut solver: S = instance.build_solver(0.01);
+        for (source, sink) in [(0, 7), (0, 5), (1, 7), (2, 6), (3, 7)] {
+            let mut fresh: S = instance.build_solver(0.01);
+            assert_eq!(
+                solver.try_solve(source, sink),
+                fresh.try_solve(source, sink)
+            );
+            for i in 0..instance.edges.len() {
+                assert_eq!(solver.graph().get_flow(i), fresh.graph().get_flow(i));
+            }
+        }
+    }
+
+    // two edges 0 -> 1 of gains 0.9 and 0.5 into 1 -> 2 of capacity 1.2: the first one fills, the second brings
+    // the remaining 0.3 with at least 0.6 (GainScalingPushRelabel may strand more at 1), each read back with its own
+    // gain
+    fn parallel_edges<S: GeneralizedMaxFlowSolver>() {
+        let epsilon: Flow = 0.01;
+        let mut solver = S::new(3, epsilon);
+        assert_eq!(solver.add_directed_edge(0, 1, 1.0, 0.5), Ok(0));
+        assert_eq!(solver.add_directed_edge(0, 1, 1.0, 0.9), Ok(1));
+        assert_eq!(solver.add_directed_edge(1, 2, 1.2, 1.0), Ok(2));
+        let actual = solver.solve(0, 2);
+        assert!(
+            1.2 * (1.0 - epsilon) <= actual && actual <= 1.2 + EPS,
+            "{}",
+            actual
+        );
+        let (lossy, better) = (solver.graph().get_flow(0), solver.graph().get_flow(1));
+        assert_eq!((lossy.1, better.1), (lossy.0 * 0.5, better.0 * 0.9));
+        assert!(
+            0.55 <= lossy.0 && lossy.0 <= 1.0 && 0.95 <= better.0 && better.0 <= 1.0,
+            "{:?} {:?}",
+            lossy,
+            better
+        );
+        check(solver.graph(), 0, 2, 1e-9).unwrap();
+    }
+
+    // bit-identical values and flows, run after run
+    fn repeatable<S: GeneralizedMaxFlowSolver>(path: &PathBuf) {
+        let instance = read_graph_instance(path).unwrap();
+        let run = || {
+            let mut solver: S = instance.build_solver(0.01);
+            let value = solver.solve(instance.source, instance.sink);
+            let flows: Vec<(u64, u64)> = (0..instance.edges.len())
+                .map(|i| solver.graph().get_flow(i))
+                .map(|(sent, received)| (sent.to_bits(), received.to_bits()))
+                .collect();
+            (value.to_bits(), flows)
+        };
+        let first = run();
+        for _ in 1..5 {
+            assert_eq!(run(), first);
+        }
+    }
+
+    // within 1 - epsilon of the optimum of random series parallel networks
+    fn series_parallel<S: GeneralizedMaxFlowSolver>(epsilon: Flow) {
+        for seed in 0..20 {
+            let (instance, optimum) = generators::series_parallel(5 + 5 * seed as usize, seed);
+            let mut solver: S = instance.build_solver(epsilon);
+            let actual = solver.solve(instance.source, instance.sink);
+            assert!(
+                optimum * (1.0 - epsilon) <= actual && actual <= optimum * (1.0 + 1e-9),
+                "{} {} {}",
+                seed,
+                actual,
+                optimum
+            );
+            check(solver.graph(), instance.source, instance.sink, 1e-6).unwrap();
+            let certificate = assert_certified(&instance, epsilon, optimum);
+            assert!(
+                (1.0 - epsilon) * certificate.upper_bound <= actual + 1e-9 * optimum.max(1.0),
+                "{} {} {:?}",
+                seed,
+                actual,
+                certificate
+            );
+        }
+    }
+
+    // every third edge undirected, against its explicit model: the units entering it at either end go through a
+    // splitter edge of its capacity and gain, then on to either end
+    fn undirected<S: GeneralizedMaxFlowSolver>(epsilon: Flow) {
+        for seed in 0..10 {
+            let instance = generators::random_graph_with_gains(30, 120, 50..=100, seed);
+            let mut solver = S::new(instance.num_nodes, epsilon);
+            let mut splitters = S::new(instance.num_nodes, epsilon);
+            let mut next = instance.num_nodes;
+            for (k, &(from, to, capacity, gain)) in instance.edges.iter().enumerate() {
+                if k % 3 != 0 {
+                    solver.add_directed_edge(from, to, capacity, gain).unwrap();
+                    splitters
+                        .add_directed_edge(from, to, capacity, gain)
+                        .unwrap();
+                    continue;
+                }
+                let i = solver.graph().num_edges();
+                assert_eq!(solver.add_undirected_edge(from, to, capacity, gain), Ok(i));
+                let (enter, leave) = (next, next + 1);
+                next += 2;
+                for u in [from, to] {
+                    splitters
+                        .add_directed_edge(u, enter, capacity, 1.0)
+                        .unwrap();
+                    splitters
+                        .add_directed_edge(leave, u, capacity, 1.0)
+                        .unwrap();
+                }
+                splitters
+                    .add_directed_edge(enter, leave, capacity, gain)
+                    .unwrap();
+            }
+            let actual = solver.solve(instance.source, instance.sink);
+            let expected = splitters.solve(instance.source, instance.sink);
+            // both within 1 - epsilon of the same optimum
+            assert!(
+                (actual - expected).abs()
+                    <= epsilon / (1.0 - epsilon) * actual.max(expected) + 1e-9,
+                "{} {} {}",
+                seed,
+                actual,
+                expected
+            );
+            check(solver.graph(), instance.source, instance.sink, 1e-6).unwrap();
+        }
+        assert_eq!(
+            S::new(2, epsilon).add_undirected_edge(0, 1, 1.0, 1.5),
+            Err(EdgeError::UndirectedGainAboveOne)
+        );
+    }
+
+    // every solver within 1 - epsilon of the optimum of the linear program over the rationals, solved once per instance
+    // as the simplex on big rationals is slow
+    #[cfg(feature = "exact")]
+    fn linear_program(instance: &crate::instances::GraphInstance) {
+        use crate::exact::generalized_max_flow_lp;
+        use crate::instances::GraphInstance;
+        use num_rational::BigRational;
+        use num_traits::ToPrimitive;
+
+        fn within<S: GeneralizedMaxFlowSolver>(instance: &GraphInstance, optimum: Flow) {
+            for epsilon in [0.1, 0.01] {
+                let mut solver: S = instance.build_solver(epsilon);
+                let actual = solver.solve(instance.source, instance.sink);
+                assert!(
+                    optimum * (1.0 - epsilon) - 1e-9 <= actual
+                        && actual <= optimum * (1.0 + 1e-9) + 1e-9,
+                    "{} {} {}",
+                    epsilon,
+                    actual,
+                    optimum
+                );
+            }
+        }
+
+        let rational = |x: Flow| BigRational::from_float(x).unwrap();
+        let edges: Vec<_> = instance
+            .edges
+            .iter()
+            .map(|&(from, to, capacity, gain)| (from, to, rational(capacity), rational(gain)))
+            .collect();
+        let optimum =
+            generalized_max_flow_lp(instance.num_nodes, &edges, instance.source, instance.sink)
+                .to_f64()
+                .unwrap();
+        within::<RoundedPrimalDual>(instance, optimum);
+        within::<HighestGainPathMethod>(instance, optimum);
+        within::<GainScalingPushRelabel>(instance, optimum);
+        for epsilon in [0.1, 0.01] {
+            assert_certified(instance, epsilon, optimum);
+        }
+    }
+
+    #[cfg(feature = "exact")]
+    #[rstest]
+    fn linear_program_gain_random(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+        linear_program(&read_graph_instance(&path).unwrap());
+    }
+
+    #[cfg(feature = "exact")]
+    #[test]
+    fn linear_program_random() {
+        for seed in 0..10 {
+            linear_program(&generators::random_graph(8, 24, seed));
+        }
+    }
+
+    macro_rules! suite {
+        ($name:ident, $solver:ty) => {
+            mod $name {
+                use super::*;
+
+                #[rstest]
+                fn aoj_grl_6_a(
+                    #[files("test_cases/gain_random/*.in")] path: PathBuf,
+                    #[values(0.1, 0.01)] epsilon: Flow,
+                ) {
+                    super::aoj_grl_6_a::<$solver>(&path, epsilon);
+                }
+
+                #[rstest]
+                fn integral_capacities(
+                    #[files("test_cases/gain_random/*.in")] path: PathBuf,
+                    #[values(0.1, 0.01)] epsilon: Flow,
+                ) {
+                    super::integral_capacities::<$solver>(&path, epsilon);
+                }
+
+                #[rstest]
+                fn series_parallel(#[values(0.1, 0.01)] epsilon: Flow) {
+                    super::series_parallel::<$solver>(epsilon);
+                }
+
+                #[rstest]
+                fn repeatable(#[files("test_cases/gain_random/*.in")] path: PathBuf) {
+                    super::repeatable::<$solver>(&path);
+                }
+
+                #[test]
+                fn sample() {
+                    super::sample::<$solver>();
+                }
+
+                #[rstest]
+                fn undirected(#[values(0.1, 0.01)] epsilon: Flow) {
+                    super::undirected::<$solver>(epsilon);
+                }
+
+                #[test]
+                fn parallel_edges() {
+                    super::parallel_edges::<$solver>();
+                }
+
+                #[test]
+                fn reuse() {
+                    super::reuse::<$solver>();
+                }
+
+                #[test]
+                fn invalid_input() {
+                    super::invalid_input::<$solver>();
+                }
+            }
+        };
+    }
+
+    suite!(rounded_primal_dual, RoundedPrimalDual);
+    suite!(highest_gain_path_method, HighestGainPathMethod);
+    suite!(gain_scaling_push_relabel, GainScalingPushRelabel);
+}
@@ -1,47 +1,132 @@
 use crate::graph::Flow;
-use std::fs::read_to_string;
-use std::path::{Path, PathBuf};
+use crate::instances::{read_graph_instance, GraphInstance};
+use crate::rounded_primal_dual::{CertifiedSolution, RoundedPrimalDual};
+use std::path::Path;
 
-pub struct GraphInstancve {
-    pub num_nodes: usize,
-    pub edges: Vec<(usize, usize, Flow, Flow)>,
-    pub source: usize,
-    pub sink: usize,
+pub fn read_expected(file_path: &Path) -> Flow {
+    network_flows_instances::read_expected(file_path).unwrap()
 }
 
-pub fn read_expected(file_path: &Path) -> Flow {
-    let data = read_to_string(file_path).unwrap();
-    data.trim().parse().unwrap()
+// the 8-node example of the sample tests, its maximum flow is 7.363
+pub fn sample_instance() -> GraphInstance {
+    GraphInstance {
+        num_nodes: 8,
+        edges: vec![
+            (0, 1, 12.0, 0.7),
+            (0, 2, 3.0, 0.9),
+            (0, 3, 4.0, 0.8),
+            (1, 4, 3.0, 0.5),
+            (1, 5, 5.0, 0.8),
+            (2, 1, 2.7, 1.0),
+            (2, 3, 20.0 / 9.0, 0.9),
+            (2, 5, 5.0, 0.7),
+            (3, 5, 1.0, 1.0),
+            (3, 6, 2.0, 0.7),
+            (4, 7, 2.0, 0.5),
+            (5, 4, 1.0, 0.5),
+            (5, 6, 6.0, 0.7),
+            (5, 7, 1.3, 1.0),
+            (6, 7, 7.0, 1.0),
+        ],
+        source: 0,
+        sink: 7,
+    }
 }
 
-pub fn read_graph_instance(file_path: &PathBuf) -> GraphInstancve {
-    let data = read_to_string(file_path).unwrap();
-    let data: Vec<&str> = data.trim().split('\n').collect();
+// a chain of edges of gain 1.5 from the source to the sink, and an edge of gain 1 from each node straight to the
+// sink: label correcting towards the sink in fifo order (see ScalingGraph::queue_heuristics) improves the chain
+// one node per pass over the queue
+pub fn shortcut_chain(num_nodes: usize) -> GraphInstance {
+    let sink = num_nodes - 1;
+    let chain = (0..sink).map(|u| (u, u + 1, 1.0, 1.5));
+    let shortcuts = (0..sink).map(|u| (u, sink, 1.0, 1.0));
+    GraphInstance {
+        num_nodes,
+        edges: chain.chain(shortcuts).collect(),
+        source: 0,
+        sink,
+    }
+}
 
-    let (num_nodes, source, sink) = {
-        let header: Vec<&str> = data[0].trim().split(' ').collect();
-        let num_nodes = header[0].parse().unwrap();
-        let source = header[2].parse().unwrap();
-        let sink = header[3].parse().unwrap();
-        (num_nodes, source, sink)
-    };
+// every node other than source and sink receives at least what it sends, and at most
+// (1 + epsilon) times that, the sink receives between value and (1 + epsilon) times value
+pub fn assert_approximate_conservation(
+    instance: &GraphInstance,
+    flows: &[(Flow, Flow)],
+    value: Flow,
+    epsilon: Flow,
+) {
+    let tolerance = 1e-9;
+    let mut inflow = vec![0.0; instance.num_nodes];
+    let mut outflow = vec![0.0; instance.num_nodes];
+    for (&(from, to, capacity, _), &(flow_in, flow_out)) in instance.edges.iter().zip(flows) {
+        assert!(-tolerance <= flow_in && flow_in <= capacity + tolerance);
+        outflow[from] += flow_in;
+        inflow[to] += flow_out;
+    }
 
-    let mut edges = Vec::new();
-    for line in data[1..].iter() {
-        let line: Vec<&str> = line.trim().split(' ').collect();
-        let (from, to, capacity, gain) = (
-            line[0].parse().unwrap(),
-            line[1].parse().unwrap(),
-            line[2].parse().unwrap(),
-            line[3].parse().unwrap(),
+    for u in 0..instance.num_nodes {
+        if u == instance.source {
+            continue;
+        }
+        let sent = if u == instance.sink {
+            value
+        } else {
+            outflow[u]
+        };
+        assert!(
+            sent - tolerance <= inflow[u],
+            "node {}: {} in, {} out",
+            u,
+            inflow[u],
+            sent
+        );
+        assert!(
+            inflow[u] <= sent * (1.0 + epsilon) + tolerance,
+            "node {}: {} in, {} out",
+            u,
+            inflow[u],
+            sent
         );
-        edges.push((from, to, capacity, gain));
     }
+}
 
-    GraphInstancve {
-        num_nodes,
-        edges,
-        source,
-        sink,
-    }
+// the certificate of RoundedPrimalDual::solve_certified: its upper bound is at least the optimum, and the value
+// within 1 - epsilon of it
+pub fn assert_certified(
+    instance: &GraphInstance,
+    epsilon: Flow,
+    optimum: Flow,
+) -> CertifiedSolution {
+    let mut solver: RoundedPrimalDual = instance.build_solver(epsilon);
+    let certificate = solver
+        .solve_certified(instance.source, instance.sink)
+        .unwrap();
+    let tolerance = 1e-9 * optimum.max(1.0);
+    assert!(
+        certificate.value <= certificate.rounded_upper_bound + tolerance,
+        "{:?}",
+        certificate
+    );
+    assert!(
+        optimum <= certificate.upper_bound + tolerance,
+        "{} {:?}",
+        optimum,
+        certificate
+    );
+    assert!(
+        (1.0 - epsilon) * certificate.upper_bound <= certificate.value + tolerance,
+        "{:?}",
+        certificate
+    );
+    certificate
+}
+
+// assert_certified on an instance file with its answer next to it
+pub fn assert_certified_case(path: &Path, epsilon: Flow) -> CertifiedSolution {
+    assert_certified(
+        &read_graph_instance(path).unwrap(),
+        epsilon,
+        read_expected(&path.with_extension("out")),
+    )
 }
@@ -0,0 +1,203 @@
+use crate::graph::{Flow, ScalingGraph};
+
+// the first violated condition found by check
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    // flow into node (multiplied by the rounded gains) differs from the flow out of it plus its excess
+    Conservation {
+        node: usize,
+        inflow: Flow,
+        outflow: Flow,
+    },
+    // flow on the edge is negative, below its lower bound or above its capacity
+    Capacity {
+        edge: usize,
+        flow: Flow,
+        capacity: Flow,
+    },
+    // the two directions of the undirected edge starting at edge both carry flow, or more than their capacity
+    UndirectedEdge {
+        edge: usize,
+        flows: (Flow, Flow),
+        capacity: Flow,
+    },
+    // the excess of the sink differs from the net flow into it
+    SinkExcess {
+        excess: Flow,
+        inflow: Flow,
+    },
+    // excess left at a node other than the source and the sink, see check_drained
+    Stranded {
+        node: usize,
+        excess: Flow,
+    },
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::Conservation {
+                node,
+                inflow,
+                outflow,
+            } => write!(f, "node {} receives {} but sends {}", node, inflow, outflow),
+            Violation::Capacity {
+                edge,
+                flow,
+                capacity,
+            } => write!(
+                f,
+                "edge {} carries {} with capacity {}",
+                edge, flow, capacity
+            ),
+            Violation::UndirectedEdge {
+                edge,
+                flows,
+                capacity,
+            } => write!(
+                f,
+                "undirected edge {} carries {} and {} back with capacity {}",
+                edge, flows.0, flows.1, capacity
+            ),
+            Violation::SinkExcess { excess, inflow } => {
+                write!(f, "sink has excess {} but receives {}", excess, inflow)
+            }
+            Violation::Stranded { node, excess } => {
+                write!(f, "node {} keeps {} that reaches no sink", node, excess)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Violation {}
+
+// checks the flow a solver left in graph, conservation uses the gains rounded the way the solver saw them
+pub fn check(
+    graph: &ScalingGraph,
+    source: usize,
+    sink: usize,
+    tolerance: Flow,
+) -> Result<(), Violation> {
+    let mut inflow = vec![0.0; graph.num_nodes];
+    let mut outflow = vec![0.0; graph.num_nodes];
+    for i in 0..graph.num_edges {
+        let edge = graph.get_directed_edge(i);
+        let (flow, _) = graph.get_flow(i);
+        if flow < edge.lower - tolerance || flow > edge.capacity + tolerance {
+            return Err(Violation::Capacity {
+                edge: i,
+                flow,
+                capacity: edge.capacity,
+            });
+        }
+        outflow[edge.from] += flow;
+        inflow[graph.entry(edge.to)] += flow * graph.rounded_gain(i);
+    }
+    for i in graph.undirected_edges() {
+        let flows = (graph.get_flow(i).0, graph.get_flow(i + 1).0);
+        let capacity = graph.get_directed_edge(i).capacity;
+        if flows.0.min(flows.1) > tolerance || flows.0 + flows.1 > capacity + tolerance {
+            return Err(Violation::UndirectedEdge {
+                edge: i,
+                flows,
+                capacity,
+            });
+        }
+    }
+    // a node with a gain passes the flow on from its in copy, which counts as the node in a violation
+    for u in graph.nodes_with_gain() {
+        let (flow, _) = graph.get_node_flow(u).unwrap();
+        outflow[graph.entry(u)] += flow;
+        inflow[u] += flow * graph.rounded_node_gain(u);
+    }
+
+    for v in 0..graph.num_nodes {
+        let u = graph.user_node(v);
+        if u == source || v == sink {
+            continue;
+        }
+        // flow generating cycles may leave excess at nodes that don't reach the sink
+        if (inflow[v] - outflow[v] - graph.excesses[v]).abs() > tolerance {
+            return Err(Violation::Conservation {
+                node: u,
+                inflow: inflow[v],
+                outflow: outflow[v],
+            });
+        }
+    }
+
+    let excess = graph.excesses[sink];
+    if (excess - (inflow[sink] - outflow[sink])).abs() > tolerance {
+        return Err(Violation::SinkExcess {
+            excess,
+            inflow: inflow[sink] - outflow[sink],
+        });
+    }
+    Ok(())
+}
+
+// check, and no excess left anywhere but at the source and the sink: what left the source is what the edges lost and
+// what the sink received
+pub fn check_drained(
+    graph: &ScalingGraph,
+    source: usize,
+    sink: usize,
+    tolerance: Flow,
+) -> Result<(), Violation> {
+    check(graph, source, sink, tolerance)?;
+    for u in (0..graph.num_nodes()).filter(|&u| u != source && u != sink) {
+        let excess = graph.excess_at(u);
+        if excess.abs() > tolerance {
+            return Err(Violation::Stranded { node: u, excess });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Violation};
+    use crate::rounded_primal_dual::RoundedPrimalDual;
+    use crate::test_utilities::sample_instance;
+
+    #[test]
+    fn sample() {
+        let instance = sample_instance();
+        let mut solver = RoundedPrimalDual::new(instance.num_nodes, 0.01);
+        for &(from, to, capacity, gain) in instance.edges.iter() {
+            solver.add_directed_edge(from, to, capacity, gain).unwrap();
+        }
+        solver.solve(instance.source, instance.sink);
+        assert_eq!(
+            check(&solver.graph, instance.source, instance.sink, 1e-9),
+            Ok(())
+        );
+
+        // the first inside edge of the source is the forward edge 0 -> 1
+        let first = solver.graph.start[instance.source];
+        assert_eq!(solver.graph.inside_edge_list[first].to, 1);
+        let flow = solver.graph.inside_edge_list[first].flow;
+        solver.graph.inside_edge_list[first].flow = flow - 1.0;
+        assert!(matches!(
+            check(&solver.graph, instance.source, instance.sink, 1e-9),
+            Err(Violation::Conservation { node: 1, .. })
+        ));
+
+        solver.graph.inside_edge_list[first].flow = 13.0;
+        assert_eq!(
+            check(&solver.graph, instance.source, instance.sink, 1e-9),
+            Err(Violation::Capacity {
+                edge: 0,
+                flow: 13.0,
+                capacity: 12.0
+            })
+        );
+
+        solver.graph.inside_edge_list[first].flow = flow;
+        solver.graph.excesses[instance.sink] += 1.0;
+        assert!(matches!(
+            check(&solver.graph, instance.source, instance.sink, 1e-9),
+            Err(Violation::SinkExcess { .. })
+        ));
+    }
+}
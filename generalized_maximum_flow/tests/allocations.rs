@@ -0,0 +1,73 @@
+// the rounds of RoundedPrimalDual and HighestGainPathMethod reuse their vectors: the allocations of a solve, counted
+// on the thread of the test, stay far below its rounds
+use generalized_maximum_flow::generators::random_graph;
+use generalized_maximum_flow::instances::GraphInstance;
+use generalized_maximum_flow::{
+    GeneralizedMaxFlowSolver, HighestGainPathMethod, RoundedPrimalDual,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+// (allocations, rounds) of a solve on a built graph
+fn count<S: GeneralizedMaxFlowSolver>(
+    instance: &GraphInstance,
+    rounds: impl Fn(&S) -> usize,
+) -> (usize, usize) {
+    let mut solver: S = instance.build_solver(0.01);
+    solver.solve(instance.source, instance.sink);
+    let allocated = allocations(|| {
+        solver.solve(instance.source, instance.sink);
+    });
+    (allocated, rounds(&solver))
+}
+
+#[test]
+fn rounds_do_not_allocate() {
+    let instance = random_graph(100, 3200, 1);
+    let (allocated, rounds) = count::<RoundedPrimalDual>(&instance, |solver| solver.stats().rounds);
+    assert!(
+        rounds >= 100 && allocated < 50,
+        "{} allocations in {} rounds",
+        allocated,
+        rounds
+    );
+    let (allocated, rounds) =
+        count::<HighestGainPathMethod>(&instance, |solver| solver.stats().rounds);
+    assert!(
+        rounds >= 100 && allocated < 50,
+        "{} allocations in {} rounds",
+        allocated,
+        rounds
+    );
+}
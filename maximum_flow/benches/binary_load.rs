@@ -0,0 +1,81 @@
+// compares loading a large generated instance from DIMACS text and from the binary format
+// cargo bench --bench binary_load [--features mmap]
+// the number of edges can be set with BINARY_LOAD_EDGES (default 2_000_000)
+use maximum_flow::instances::GraphInstance;
+use maximum_flow::io::{read_binary, read_dimacs_max, write_binary, write_dimacs_max};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::time::Instant;
+
+fn generate(num_nodes: usize, num_edges: usize) -> GraphInstance {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let edges = (0..num_edges)
+        .map(|_| {
+            let from = (next() % num_nodes as u64) as usize;
+            let to = (next() % num_nodes as u64) as usize;
+            (from, to, (next() % 1_000_000) as i64 + 1)
+        })
+        .collect();
+    GraphInstance {
+        num_nodes,
+        edges,
+        source: 0,
+        sink: num_nodes - 1,
+    }
+}
+
+fn main() {
+    let num_edges: usize = std::env::var("BINARY_LOAD_EDGES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(2_000_000);
+    let instance = generate(num_edges / 10 + 2, num_edges);
+
+    let directory = std::env::temp_dir();
+    let text_path = directory.join("maximum_flow_binary_load.max");
+    let binary_path = directory.join("maximum_flow_binary_load.bin");
+    write_dimacs_max(BufWriter::new(File::create(&text_path).unwrap()), &instance).unwrap();
+    write_binary(
+        BufWriter::new(File::create(&binary_path).unwrap()),
+        &instance,
+    )
+    .unwrap();
+
+    let timer = Instant::now();
+    let text = read_dimacs_max(BufReader::new(File::open(&text_path).unwrap())).unwrap();
+    println!(
+        "dimacs text: {:>10.1} ms",
+        timer.elapsed().as_secs_f64() * 1000.0
+    );
+
+    let timer = Instant::now();
+    let binary = read_binary(BufReader::new(File::open(&binary_path).unwrap())).unwrap();
+    println!(
+        "binary:      {:>10.1} ms",
+        timer.elapsed().as_secs_f64() * 1000.0
+    );
+    assert_eq!(text.edges, binary.edges);
+
+    #[cfg(feature = "mmap")]
+    {
+        use maximum_flow::dinic::Dinic;
+        use maximum_flow::io::MappedInstance;
+
+        let timer = Instant::now();
+        let mapped = MappedInstance::open(&binary_path).unwrap();
+        let _solver = Dinic::from_graph(mapped.build_graph().unwrap());
+        println!(
+            "mmap:        {:>10.1} ms",
+            timer.elapsed().as_secs_f64() * 1000.0
+        );
+    }
+
+    std::fs::remove_file(&text_path).unwrap();
+    std::fs::remove_file(&binary_path).unwrap();
+}
@@ -0,0 +1,86 @@
+// the memory of a built Graph with its edge list and of one of Graph::build_lean, held after the build and at the peak
+// while building, counted by the allocator
+// cargo bench --bench lean_memory
+// the number of edges can be set with LEAN_MEMORY_EDGES (default 50_000_000, about 16 GB at the peak of build)
+use maximum_flow::graph::Graph;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+// the same edges every time
+fn edges(num_nodes: usize, num_edges: usize) -> impl Iterator<Item = (usize, usize, i64)> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    (0..num_edges).map(move |_| {
+        let from = (next() % num_nodes as u64) as usize;
+        let to = (next() % num_nodes as u64) as usize;
+        (from, to, (next() % 1_000_000) as i64 + 1)
+    })
+}
+
+// (held, peak) in bytes above what was allocated before
+fn measure(build: impl FnOnce() -> Graph) -> (usize, usize) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let graph = build();
+    let held = ALLOCATED.load(Ordering::Relaxed) - before;
+    let peak = PEAK.load(Ordering::Relaxed) - before;
+    drop(graph);
+    (held, peak)
+}
+
+fn main() {
+    let num_edges: usize = std::env::var("LEAN_MEMORY_EDGES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50_000_000);
+    let num_nodes = num_edges / 10 + 2;
+
+    let full = measure(|| {
+        let mut graph = Graph::new();
+        for (from, to, capacity) in edges(num_nodes, num_edges) {
+            graph.add_directed_edge(from, to, capacity);
+        }
+        graph.build();
+        graph
+    });
+    let lean = measure(|| Graph::build_lean(|| edges(num_nodes, num_edges)));
+
+    let mb = |bytes: usize| bytes as f64 / 1e6;
+    println!("{} edges", num_edges);
+    for (name, (held, peak)) in [("build", full), ("build_lean", lean)] {
+        println!(
+            "{:<10}  held {:>10.1} MB ({:>5.1} B/edge)  peak {:>10.1} MB ({:>5.1} B/edge)",
+            name,
+            mb(held),
+            held as f64 / num_edges as f64,
+            mb(peak),
+            peak as f64 / num_edges as f64
+        );
+    }
+}
@@ -0,0 +1,62 @@
+// Graph::build of a large random graph on thread pools of 1 (the sequential build) up to all the cores
+// cargo bench --features rayon --bench parallel_build
+// the number of edges can be set with PARALLEL_BUILD_EDGES (default 50_000_000, about 7 GB while building)
+use maximum_flow::graph::Graph;
+use std::time::Instant;
+
+fn generate(num_nodes: usize, num_edges: usize) -> Graph {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut graph = Graph::new();
+    for _ in 0..num_edges {
+        let from = (next() % num_nodes as u64) as usize;
+        let to = (next() % num_nodes as u64) as usize;
+        graph.add_directed_edge(from, to, (next() % 1_000_000) as i64 + 1);
+    }
+    graph
+}
+
+// 1, 2, 4, ... threads and all of them
+fn thread_counts() -> Vec<usize> {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut counts: Vec<usize> = std::iter::successors(Some(1), |&t| Some(t * 2))
+        .take_while(|&t| t < cores)
+        .collect();
+    counts.push(cores);
+    counts
+}
+
+fn main() {
+    let num_edges: usize = std::env::var("PARALLEL_BUILD_EDGES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50_000_000);
+    let mut graph = generate(num_edges / 10 + 2, num_edges);
+
+    let mut layout = None;
+    for threads in thread_counts() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let timer = Instant::now();
+        pool.install(|| graph.build());
+        println!(
+            "{:>3} threads: {:>10.1} ms",
+            threads,
+            timer.elapsed().as_secs_f64() * 1000.0
+        );
+        // the same layout whatever the threads
+        let start = std::mem::take(&mut graph.start);
+        let edge_index = std::mem::take(&mut graph.edge_index_to_inside_edge_index);
+        match &layout {
+            None => layout = Some((start, edge_index)),
+            Some(first) => assert!(*first == (start, edge_index)),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+// compares the five solvers on generated instances
+// cargo bench --bench solvers [-- GROUP_FILTER]
+use criterion::measurement::WallTime;
+use criterion::{
+    criterion_group, criterion_main, BatchSize, BenchmarkGroup, BenchmarkId, Criterion,
+};
+use maximum_flow::capacity_scaling::CapacityScaling;
+use maximum_flow::dinic::Dinic;
+use maximum_flow::ford_fulkerson::FordFulkerson;
+use maximum_flow::generators::{genrmf, random_bipartite, random_graph, washington, Rng};
+use maximum_flow::graph::FLOW_MAX;
+use maximum_flow::instances::GraphInstance;
+use maximum_flow::push_relabel_fifo::PushRelabelFIFO;
+use maximum_flow::push_relabel_highest_label::PushRelabelHighestLabel;
+use maximum_flow::solver::MaxFlowSolver;
+
+fn bench_solver<S: MaxFlowSolver>(
+    group: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    parameter: &str,
+    instance: &GraphInstance,
+) {
+    group.bench_with_input(
+        BenchmarkId::new(name, parameter),
+        instance,
+        |b, instance| {
+            // a fresh solver per iteration, so every run starts from zero flow
+            b.iter_batched(
+                || instance.build_solver::<S>(),
+                |mut solver| solver.solve(instance.source, instance.sink),
+                BatchSize::LargeInput,
+            )
+        },
+    );
+}
+
+fn bench_group(
+    c: &mut Criterion,
+    name: &str,
+    instances: &[(String, GraphInstance)],
+    with_ford_fulkerson: bool,
+) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    for (parameter, instance) in instances.iter() {
+        bench_solver::<Dinic>(&mut group, "dinic", parameter, instance);
+        if with_ford_fulkerson {
+            bench_solver::<FordFulkerson>(&mut group, "ford_fulkerson", parameter, instance);
+        }
+        bench_solver::<CapacityScaling>(&mut group, "capacity_scaling", parameter, instance);
+        bench_solver::<PushRelabelFIFO>(&mut group, "push_relabel_fifo", parameter, instance);
+        bench_solver::<PushRelabelHighestLabel>(
+            &mut group,
+            "push_relabel_highest_label",
+            parameter,
+            instance,
+        );
+    }
+    group.finish();
+}
+
+fn sparse_random(c: &mut Criterion) {
+    let instances: Vec<_> = [1_000, 5_000]
+        .iter()
+        .map(|&n| (format!("n={}", n), random_graph(n, 4 * n, 1_000, 1)))
+        .collect();
+    bench_group(c, "sparse_random", &instances, true);
+}
+
+fn dense_random(c: &mut Criterion) {
+    let instances: Vec<_> = [100, 300]
+        .iter()
+        .map(|&n| (format!("n={}", n), random_graph(n, n * n / 4, 1_000, 2)))
+        .collect();
+    bench_group(c, "dense_random", &instances, true);
+}
+
+fn genrmf_mesh(c: &mut Criterion) {
+    let instances: Vec<_> = [(4, 8), (8, 16)]
+        .iter()
+        .map(|&(a, b)| (format!("a={},b={}", a, b), genrmf(a, b, 1, 1_000, 3)))
+        .collect();
+    bench_group(c, "genrmf", &instances, true);
+}
+
+fn unit_bipartite(c: &mut Criterion) {
+    let instances: Vec<_> = [200, 1_000]
+        .iter()
+        .map(|&n| {
+            (
+                format!("l=r={}", n),
+                random_bipartite(n, n, 10.0 / n as f64, 4),
+            )
+        })
+        .collect();
+    bench_group(c, "unit_bipartite", &instances, true);
+}
+
+fn long_path(c: &mut Criterion) {
+    let instances: Vec<_> = [1_000, 10_000]
+        .iter()
+        .map(|&length| {
+            let instance = washington(3, &[length, 4, 1_000], 5).unwrap();
+            (format!("length={}", length), instance)
+        })
+        .collect();
+    bench_group(c, "long_path", &instances, true);
+}
+
+// capacities in [FLOW_MAX / 4, FLOW_MAX / 2]; the source has a single outgoing edge, so the
+// total excess of the preflow algorithms stays below FLOW_MAX / 2 and nothing overflows
+// FordFulkerson is left out: its running time grows with the flow value and it doesn't finish
+fn near_i64_max(c: &mut Criterion) {
+    let instances: Vec<_> = [1_000, 5_000]
+        .iter()
+        .map(|&n| {
+            let mut rng = Rng::new(6);
+            let mut instance = random_graph(n, 4 * n, 1, 6);
+            instance
+                .edges
+                .retain(|&(from, _, _)| from != instance.source);
+            for edge in instance.edges.iter_mut() {
+                edge.2 = FLOW_MAX / 4 + rng.gen_range(0, (FLOW_MAX / 4) as u64) as i64;
+            }
+            instance.edges.push((instance.source, 1, FLOW_MAX / 2));
+            (format!("n={}", n), instance)
+        })
+        .collect();
+    bench_group(c, "near_i64_max", &instances, false);
+}
+
+criterion_group!(
+    benches,
+    sparse_random,
+    dense_random,
+    genrmf_mesh,
+    unit_bipartite,
+    long_path,
+    near_i64_max
+);
+criterion_main!(benches);
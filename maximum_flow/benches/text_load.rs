@@ -0,0 +1,69 @@
+// compares reading a large generated AOJ instance through read_to_string + parse_aoj
+// with the streaming reader
+// cargo bench --bench text_load
+// the number of edges can be set with TEXT_LOAD_EDGES (default 10_000_000)
+// peak memory is read from /proc/self/status, so it is only reported on Linux
+use maximum_flow::io::{parse_aoj, read_aoj};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+fn generate(path: &Path, num_nodes: usize, num_edges: usize) {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut w = BufWriter::new(File::create(path).unwrap());
+    writeln!(w, "{} {}", num_nodes, num_edges).unwrap();
+    for _ in 0..num_edges {
+        let from = next() % num_nodes as u64;
+        let to = next() % num_nodes as u64;
+        writeln!(w, "{} {} {}", from, to, next() % 1_000_000 + 1).unwrap();
+    }
+}
+
+fn peak_memory_mb() -> Option<f64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: f64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024.0)
+}
+
+fn report(name: &str, timer: Instant) {
+    let elapsed = timer.elapsed().as_secs_f64() * 1000.0;
+    match peak_memory_mb() {
+        Some(mb) => println!("{}: {:>10.1} ms, peak {:>8.1} MB", name, elapsed, mb),
+        None => println!("{}: {:>10.1} ms", name, elapsed),
+    }
+}
+
+fn main() {
+    let num_edges: usize = std::env::var("TEXT_LOAD_EDGES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10_000_000);
+    let path = std::env::temp_dir().join("maximum_flow_text_load.in");
+    generate(&path, num_edges / 10 + 2, num_edges);
+    println!(
+        "file:      {:>10.1} MB",
+        std::fs::metadata(&path).unwrap().len() as f64 / (1024.0 * 1024.0)
+    );
+
+    // the peak only grows, so the streaming reader runs first
+    let timer = Instant::now();
+    let streamed = read_aoj(BufReader::new(File::open(&path).unwrap())).unwrap();
+    report("streaming", timer);
+    let num_streamed = streamed.edges.len();
+    drop(streamed);
+
+    let timer = Instant::now();
+    let slurped = parse_aoj(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    report("slurp    ", timer);
+    assert_eq!(slurped.edges.len(), num_streamed);
+
+    std::fs::remove_file(&path).unwrap();
+}
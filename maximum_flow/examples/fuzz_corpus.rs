@@ -0,0 +1,49 @@
+// writes seed inputs for the solver_operations fuzz target, one per instance and solver
+// cargo run --example fuzz_corpus -- fuzz/corpus/solver_operations test_cases/AOJ_GRL_6_A/*.in
+use maximum_flow::fuzzing::encode_instance;
+use maximum_flow::instances::{read_instance_aoj, read_instance_dimacs, read_instance_libreoj};
+use std::path::Path;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((out, paths)) = args.split_first() else {
+        eprintln!("usage: fuzz_corpus OUT_DIR INSTANCE...");
+        return ExitCode::FAILURE;
+    };
+    if let Err(e) = std::fs::create_dir_all(out) {
+        eprintln!("error: {}: {}", out, e);
+        return ExitCode::FAILURE;
+    }
+
+    let mut written = 0;
+    for path in paths.iter().map(Path::new) {
+        let instance = match path.extension().and_then(|e| e.to_str()) {
+            Some("max") => read_instance_dimacs(path),
+            _ if path.to_string_lossy().contains("LibreOJ") => read_instance_libreoj(path),
+            _ => read_instance_aoj(path),
+        };
+        let instance = match instance {
+            Ok(instance) => instance,
+            Err(e) => {
+                eprintln!("error: {}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
+        };
+        let stem = path.file_stem().unwrap().to_string_lossy();
+        for algorithm in 0..5 {
+            // instances with more than 256 nodes or large capacities don't fit the encoding
+            let Some(data) = encode_instance(algorithm, &instance) else {
+                break;
+            };
+            let name = Path::new(out).join(format!("{}_{}", stem, algorithm));
+            if let Err(e) = std::fs::write(&name, data) {
+                eprintln!("error: {}: {}", name.display(), e);
+                return ExitCode::FAILURE;
+            }
+            written += 1;
+        }
+    }
+    eprintln!("wrote {} seeds", written);
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeSet;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: bundle dinic|ff|scaling|pr-fifo|pr-hl
+
+Prints the CsrGraph of network_flows_core, graph.rs, control.rs and the selected solver as one self-contained source
+file.";
+
+const CSR_GRAPH: &str = include_str!("../../../network_flows_core/src/lib.rs");
+const GRAPH: &str = include_str!("../graph.rs");
+const CONTROL: &str = include_str!("../control.rs");
+
+fn solver_source(algo: &str) -> Option<&'static str> {
+    match algo {
+        "dinic" => Some(include_str!("../dinic.rs")),
+        "ff" => Some(include_str!("../ford_fulkerson.rs")),
+        "scaling" => Some(include_str!("../capacity_scaling.rs")),
+        "pr-fifo" => Some(include_str!("../push_relabel_fifo.rs")),
+        "pr-hl" => Some(include_str!("../push_relabel_highest_label.rs")),
+        _ => None,
+    }
+}
+
+// drops tests, crate-internal imports (network_flows_core is bundled too), crate attributes, feature-gated
+// attributes, the MaxFlowSolver impl and `pub`
+// the remaining imports are collected into `uses` so that they can be written once at the top
+fn flatten(source: &str, uses: &mut BTreeSet<String>) -> String {
+    let mut body = String::new();
+    let mut in_trait_impl = false;
+    for line in source.lines() {
+        if line.starts_with("#[cfg(test)]") {
+            break;
+        }
+        if line.starts_with("impl MaxFlowSolver for") {
+            in_trait_impl = true;
+        }
+        if in_trait_impl {
+            in_trait_impl = line != "}";
+            continue;
+        }
+
+        if line.starts_with("#![") || line.starts_with("extern crate ") {
+            continue;
+        }
+        let line = line
+            .replace("network_flows_core::", "crate::")
+            .replace("alloc::", "std::")
+            .replace("core::", "std::");
+        if let Some(path) = line.strip_prefix("use ") {
+            // Vec and vec! are in the std prelude
+            if !path.starts_with("crate::") && !path.starts_with("std::vec") {
+                uses.insert(line);
+            }
+            continue;
+        }
+        if line.trim_start().starts_with("#[cfg_attr(feature") {
+            continue;
+        }
+
+        let line = line.replace("pub(crate) ", "").replace("pub ", "");
+        if !(line.is_empty() && (body.is_empty() || body.ends_with("\n\n"))) {
+            body.push_str(&line);
+            body.push('\n');
+        }
+    }
+    body.trim_end().to_string()
+}
+
+fn bundle(algo: &str) -> Option<String> {
+    let solver = solver_source(algo)?;
+    let mut uses = BTreeSet::new();
+    let csr_graph = flatten(CSR_GRAPH, &mut uses);
+    let graph = flatten(GRAPH, &mut uses);
+    let control = flatten(CONTROL, &mut uses);
+    let solver = flatten(solver, &mut uses);
+
+    let mut output = format!(
+        "// maximum_flow {} ({})\n// generated by `cargo run --bin bundle -- {}`\n\n",
+        env!("CARGO_PKG_VERSION"),
+        algo,
+        algo
+    );
+    for line in uses.iter() {
+        output.push_str(line);
+        output.push('\n');
+    }
+    output.push('\n');
+    output.push_str(&csr_graph);
+    output.push_str("\n\n");
+    output.push_str(&graph);
+    output.push_str("\n\n");
+    output.push_str(&control);
+    output.push_str("\n\n");
+    output.push_str(&solver);
+    output.push('\n');
+    Some(output)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [algo] = args.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(1);
+    };
+    match bundle(algo) {
+        Some(output) => {
+            print!("{}", output);
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("error: unknown algorithm `{}`\n{}", algo, USAGE);
+            ExitCode::from(1)
+        }
+    }
+}
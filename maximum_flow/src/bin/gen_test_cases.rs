@@ -0,0 +1,212 @@
+use maximum_flow::dinic::Dinic;
+use maximum_flow::generators::{
+    ff_worst_case, genrmf, push_relabel_bad_case, random_bipartite, random_dag, random_graph,
+    washington, Rng,
+};
+use maximum_flow::instances::GraphInstance;
+use maximum_flow::io::{write_aoj, write_libreoj};
+use maximum_flow::push_relabel_highest_label::PushRelabelHighestLabel;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: gen_test_cases [--out DIR] [--prefix NAME] [--count N] [--seed S] [--format aoj|libreoj] [--budget EDGES] FAMILY RANGE...
+
+Each RANGE is `LO..HI` (inclusive) or a single value, one per family parameter:
+  random N M MAX_CAPACITY
+  bipartite L R PERCENT
+  dag N M MAX_CAPACITY
+  genrmf A B C1 C2
+  washington FUNCTION_ID ARGS...
+  ff-worst-case K
+  pr-bad-case K
+
+Writes DIR/NAME_XX.in and DIR/NAME_XX.out, the answer being checked by Dinic and PushRelabelHighestLabel.
+Stops early when the total number of edges would exceed the budget.
+`--out test_cases/AOJ_GRL_6_A --prefix 90_random` adds the cases to the solvers' AOJ test suite.";
+
+const EXIT_USAGE: u8 = 1;
+const EXIT_IO: u8 = 2;
+const EXIT_DISAGREE: u8 = 3;
+
+struct Options {
+    out: PathBuf,
+    prefix: String,
+    count: usize,
+    seed: u64,
+    format: String,
+    budget: usize,
+    family: String,
+    ranges: Vec<(u64, u64)>,
+}
+
+fn parse_range(arg: &str) -> Result<(u64, u64), String> {
+    let invalid = || format!("invalid range `{}`", arg);
+    let (low, high) = match arg.split_once("..") {
+        Some((low, high)) => (low, high),
+        None => (arg, arg),
+    };
+    let low: u64 = low.parse().map_err(|_| invalid())?;
+    let high: u64 = high.parse().map_err(|_| invalid())?;
+    if low > high {
+        return Err(invalid());
+    }
+    Ok((low, high))
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        out: PathBuf::from("."),
+        prefix: "gen".to_string(),
+        count: 10,
+        seed: 0,
+        format: "aoj".to_string(),
+        budget: usize::MAX,
+        family: String::new(),
+        ranges: Vec::new(),
+    };
+
+    let mut family = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or(format!("{} needs a value", name))
+        };
+        let number = |name: &str, v: String| {
+            v.parse::<u64>()
+                .map_err(|_| format!("invalid {} `{}`", name, v))
+        };
+        match arg.as_str() {
+            "--out" => options.out = PathBuf::from(value("--out")?),
+            "--prefix" => options.prefix = value("--prefix")?,
+            "--count" => options.count = number("--count", value("--count")?)? as usize,
+            "--seed" => options.seed = number("--seed", value("--seed")?)?,
+            "--format" => options.format = value("--format")?,
+            "--budget" => options.budget = number("--budget", value("--budget")?)? as usize,
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ if family.is_none() => family = Some(arg.clone()),
+            _ => options.ranges.push(parse_range(arg)?),
+        }
+    }
+    options.family = family.ok_or("missing generator family")?;
+    if options.format != "aoj" && options.format != "libreoj" {
+        return Err(format!("unknown format `{}`", options.format));
+    }
+    Ok(options)
+}
+
+// None if the family is unknown or the parameters don't fit it
+fn generate(family: &str, params: &[u64], seed: u64) -> Option<GraphInstance> {
+    let p: Vec<usize> = params.iter().map(|&p| p as usize).collect();
+    match (family, p.as_slice()) {
+        ("random", &[n, m, c]) if n >= 2 && c >= 1 => Some(random_graph(n, m, c as i64, seed)),
+        ("bipartite", &[l, r, percent]) if percent <= 100 => {
+            Some(random_bipartite(l, r, percent as f64 / 100.0, seed))
+        }
+        ("dag", &[n, m, c]) if n >= 2 && c >= 1 => Some(random_dag(n, m, c as i64, seed)),
+        ("genrmf", &[a, b, c1, c2])
+            if a >= 1 && b >= 1 && a * a * b >= 2 && 1 <= c1 && c1 <= c2 =>
+        {
+            Some(genrmf(a, b, c1 as i64, c2 as i64, seed))
+        }
+        ("washington", [function_id, args @ ..]) => washington(*function_id as u32, args, seed),
+        ("ff-worst-case", &[k]) if k <= 60 => Some(ff_worst_case(k as u32)),
+        ("pr-bad-case", &[k]) if k >= 1 => Some(push_relabel_bad_case(k)),
+        _ => None,
+    }
+}
+
+// the AOJ format fixes source = 0 and sink = num_nodes - 1
+fn move_terminals(instance: &mut GraphInstance) {
+    let last = instance.num_nodes - 1;
+    let mut relabel: Vec<usize> = (0..instance.num_nodes).collect();
+    relabel.swap(0, instance.source);
+    let sink = relabel.iter().position(|&u| u == instance.sink).unwrap();
+    relabel.swap(sink, last);
+    // relabel[new] = old
+    let mut new_id = vec![0; instance.num_nodes];
+    for (new, &old) in relabel.iter().enumerate() {
+        new_id[old] = new;
+    }
+    for edge in instance.edges.iter_mut() {
+        edge.0 = new_id[edge.0];
+        edge.1 = new_id[edge.1];
+    }
+    instance.source = 0;
+    instance.sink = last;
+}
+
+fn write_case(
+    options: &Options,
+    name: &str,
+    instance: &GraphInstance,
+    flow: i64,
+) -> std::io::Result<()> {
+    let path = |extension: &str| Path::new(&options.out).join(format!("{}.{}", name, extension));
+    let w = BufWriter::new(File::create(path("in"))?);
+    match options.format.as_str() {
+        "aoj" => write_aoj(w, instance)?,
+        _ => write_libreoj(w, instance)?,
+    }
+    std::fs::write(path("out"), format!("{}\n", flow))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n{}", message, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    if let Err(e) = std::fs::create_dir_all(&options.out) {
+        eprintln!("error: {}: {}", options.out.display(), e);
+        return ExitCode::from(EXIT_IO);
+    }
+
+    let mut rng = Rng::new(options.seed);
+    let mut remaining_budget = options.budget;
+    for index in 0..options.count {
+        let params: Vec<u64> = options
+            .ranges
+            .iter()
+            .map(|&(low, high)| rng.gen_range(low, high + 1))
+            .collect();
+        let Some(mut instance) = generate(&options.family, &params, rng.next_u64()) else {
+            eprintln!(
+                "error: unknown family `{}` or invalid parameters {:?}\n{}",
+                options.family, params, USAGE
+            );
+            return ExitCode::from(EXIT_USAGE);
+        };
+        if instance.edges.len() > remaining_budget {
+            eprintln!("edge budget reached after {} cases", index);
+            break;
+        }
+        remaining_budget -= instance.edges.len();
+        if options.format == "aoj" {
+            move_terminals(&mut instance);
+        }
+
+        let flow = instance.solve::<Dinic>();
+        let other = instance.solve::<PushRelabelHighestLabel>();
+        let name = format!("{}_{:02}", options.prefix, index);
+        if flow != other {
+            eprintln!(
+                "error: {}: Dinic found {} but PushRelabelHighestLabel found {}",
+                name, flow, other
+            );
+            return ExitCode::from(EXIT_DISAGREE);
+        }
+        if let Err(e) = write_case(&options, &name, &instance, flow) {
+            eprintln!("error: {}: {}", name, e);
+            return ExitCode::from(EXIT_IO);
+        }
+    }
+
+    ExitCode::SUCCESS
+}
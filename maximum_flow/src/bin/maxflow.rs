@@ -0,0 +1,172 @@
+use maximum_flow::capacity_scaling::CapacityScaling;
+use maximum_flow::dinic::Dinic;
+use maximum_flow::ford_fulkerson::FordFulkerson;
+use maximum_flow::instances::GraphInstance;
+use maximum_flow::io::{parse_aoj, parse_libreoj, read_dimacs_max, ParseError};
+use maximum_flow::push_relabel_fifo::PushRelabelFIFO;
+use maximum_flow::push_relabel_highest_label::PushRelabelHighestLabel;
+use maximum_flow::solver::MaxFlowSolver;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Instant;
+
+const USAGE: &str = "usage: maxflow [--format dimacs|aoj|libreoj] [--algo dinic|ff|scaling|pr-fifo|pr-hl] [--cut] [--flows] [--time] FILE
+
+Node ids in the output are 0-indexed.";
+
+const EXIT_USAGE: u8 = 1;
+const EXIT_PARSE: u8 = 2;
+const EXIT_SOLVER: u8 = 3;
+
+struct Options {
+    path: String,
+    format: Option<String>,
+    algo: String,
+    cut: bool,
+    flows: bool,
+    time: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: String::new(),
+        format: None,
+        algo: "dinic".to_string(),
+        cut: false,
+        flows: false,
+        time: false,
+    };
+
+    let mut path = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                options.format = Some(iter.next().ok_or("--format needs a value")?.clone())
+            }
+            "--algo" => options.algo = iter.next().ok_or("--algo needs a value")?.clone(),
+            "--cut" => options.cut = true,
+            "--flows" => options.flows = true,
+            "--time" => options.time = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+    options.path = path.ok_or("missing input file")?;
+    Ok(options)
+}
+
+fn detect_format(path: &Path, data: &str) -> Option<&'static str> {
+    if path.extension().is_some_and(|e| e == "max") {
+        return Some("dimacs");
+    }
+    let first = data.lines().find(|l| !l.trim().is_empty())?;
+    match first.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["c", ..] | ["p", ..] => Some("dimacs"),
+        [_, _] => Some("aoj"),
+        [_, _, _, _] => Some("libreoj"),
+        _ => None,
+    }
+}
+
+fn read_instance(options: &Options) -> Result<GraphInstance, String> {
+    let path = Path::new(&options.path);
+    let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", options.path, e))?;
+    let format = match &options.format {
+        Some(format) => format.as_str(),
+        None => detect_format(path, &data).ok_or("cannot detect the input format, use --format")?,
+    };
+    let result: Result<GraphInstance, ParseError> = match format {
+        "dimacs" => read_dimacs_max(data.as_bytes()),
+        "aoj" => parse_aoj(&data),
+        "libreoj" => parse_libreoj(&data),
+        _ => return Err(format!("unknown format `{}`", format)),
+    };
+    result.map_err(|e| format!("{}: {}", options.path, e))
+}
+
+fn make_solver(algo: &str) -> Option<Box<dyn MaxFlowSolver>> {
+    match algo {
+        "dinic" => Some(Box::new(Dinic::new())),
+        "ff" => Some(Box::new(FordFulkerson::new())),
+        "scaling" => Some(Box::new(CapacityScaling::new())),
+        "pr-fifo" => Some(Box::new(PushRelabelFIFO::new())),
+        "pr-hl" => Some(Box::new(PushRelabelHighestLabel::new())),
+        _ => None,
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n{}", message, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let mut solver = match make_solver(&options.algo) {
+        Some(solver) => solver,
+        None => {
+            eprintln!("error: unknown algorithm `{}`\n{}", options.algo, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let instance = match read_instance(&options) {
+        Ok(instance) => instance,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            return ExitCode::from(EXIT_PARSE);
+        }
+    };
+    if instance.source >= instance.num_nodes || instance.sink >= instance.num_nodes {
+        eprintln!("error: source or sink is out of range");
+        return ExitCode::from(EXIT_SOLVER);
+    }
+
+    let timer = Instant::now();
+    let edge_indices: Vec<Option<usize>> = instance
+        .edges
+        .iter()
+        .map(|&(from, to, capacity)| solver.add_directed_edge(from, to, capacity))
+        .collect();
+    let flow = match catch_unwind(AssertUnwindSafe(|| {
+        solver.solve(instance.source, instance.sink)
+    })) {
+        Ok(flow) => flow,
+        Err(_) => {
+            eprintln!("error: the solver failed on this instance");
+            return ExitCode::from(EXIT_SOLVER);
+        }
+    };
+    let elapsed = timer.elapsed();
+
+    println!("flow {}", flow);
+    let graph = solver.graph();
+    if options.cut {
+        let side = graph.min_cut(instance.sink);
+        for (i, index) in edge_indices.iter().enumerate() {
+            if let Some(index) = *index {
+                let edge = graph.get_directed_edge(index);
+                if side[edge.from] && !side[edge.to] {
+                    println!("cut {} {} {} {}", i, edge.from, edge.to, edge.capacity);
+                }
+            }
+        }
+    }
+    if options.flows {
+        for (i, index) in edge_indices.iter().enumerate() {
+            let (from, to, capacity) = instance.edges[i];
+            let flow = index.map_or(0, |index| graph.get_directed_edge(index).flow);
+            println!("edge {} {} {} {} {}", i, from, to, flow, capacity);
+        }
+    }
+    if options.time {
+        println!("time {:.3} ms", elapsed.as_secs_f64() * 1000.0);
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,160 @@
+use crate::graph::Flow;
+use crate::min_cost_flow::{Cost, MinCostFlow};
+use alloc::vec::Vec;
+
+// O(min(n_left, n_right) * m log n) with m the number of edges, and O(nm) for the first potentials
+// a matching of maximum total weight among the edges (left, right, weight), which need not be perfect: the successive
+// shortest paths from the left to the right side cost the negated weights and stop at the first that costs at least 0,
+// as with a bypass arc from the source to the sink of cost 0, so the edges of weight at most 0 are never matched
+// returns the weight and the matched pairs (left, right) sorted by left
+pub fn max_weight_matching(
+    n_left: usize,
+    n_right: usize,
+    weighted_edges: &[(usize, usize, Cost)],
+) -> (Cost, Vec<(usize, usize)>) {
+    let source = n_left + n_right;
+    let sink = source + 1;
+    let mut solver = MinCostFlow::new();
+    let mut pairs = Vec::new();
+    for &(left, right, weight) in weighted_edges.iter() {
+        assert!(
+            left < n_left && right < n_right,
+            "edge ({}, {}) out of range",
+            left,
+            right
+        );
+        if weight > 0 {
+            solver.add_directed_edge(left, n_left + right, 1, -weight);
+            pairs.push((left, right));
+        }
+    }
+    for left in 0..n_left {
+        solver.add_directed_edge(source, left, 1, 0);
+    }
+    for right in 0..n_right {
+        solver.add_directed_edge(n_left + right, sink, 1, 0);
+    }
+
+    let (_, cost) = solver.solve_with_limit(source, sink, n_left.min(n_right) as Flow, 0);
+    let mut matching: Vec<(usize, usize)> = pairs
+        .into_iter()
+        .enumerate()
+        .filter(|&(i, _)| solver.get_directed_edge(i).flow > 0)
+        .map(|(_, pair)| pair)
+        .collect();
+    matching.sort_unstable();
+    (-cost, matching)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bipartite_matching::max_weight_matching;
+    use crate::generators::Rng;
+    use crate::min_cost_flow::Cost;
+    use proptest::prelude::*;
+
+    type Instance = (usize, usize, Vec<(usize, usize, Cost)>);
+
+    // O(n_left * 2^n_right * m) over the sets of matched right nodes
+    fn brute_force((n_left, n_right, edges): &Instance) -> Cost {
+        let mut best = vec![0 as Cost; 1 << n_right];
+        for left in 0..*n_left {
+            let mut next = best.clone();
+            for (used, &weight) in best.iter().enumerate() {
+                for &(l, r, w) in edges.iter() {
+                    if l == left && used & 1 << r == 0 {
+                        let m = used | 1 << r;
+                        next[m] = next[m].max(weight + w);
+                    }
+                }
+            }
+            best = next;
+        }
+        best.into_iter().max().unwrap()
+    }
+
+    // the weight of the returned pairs, each by its heaviest edge, after checking that they form a matching
+    fn matching_weight((n_left, n_right, edges): &Instance, matching: &[(usize, usize)]) -> Cost {
+        let (mut left_used, mut right_used) = (vec![false; *n_left], vec![false; *n_right]);
+        matching
+            .iter()
+            .map(|&(left, right)| {
+                assert!(!left_used[left] && !right_used[right], "{:?}", matching);
+                left_used[left] = true;
+                right_used[right] = true;
+                edges
+                    .iter()
+                    .filter(|&&(l, r, _)| (l, r) == (left, right))
+                    .map(|e| e.2)
+                    .max()
+                    .unwrap()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn known_matching() {
+        // matching 0 - 0 alone weighs 5, 0 - 1 and 1 - 0 weigh 4 + 3, and 2 - 1 of weight -1 is never matched
+        let instance = (
+            3,
+            2,
+            vec![(0, 0, 5), (0, 1, 4), (1, 0, 3), (2, 1, -1), (1, 1, 0)],
+        );
+        assert_eq!(
+            max_weight_matching(3, 2, &instance.2),
+            (7, vec![(0, 1), (1, 0)])
+        );
+        assert_eq!(max_weight_matching(3, 2, &[(2, 1, -1)]), (0, vec![]));
+        assert_eq!(max_weight_matching(0, 0, &[]), (0, vec![]));
+        // the unprofitable pair is skipped although a perfect matching exists
+        assert_eq!(
+            max_weight_matching(2, 2, &[(0, 0, 5), (1, 1, -3)]),
+            (5, vec![(0, 0)])
+        );
+    }
+
+    #[test]
+    fn brute_force_up_to_8x8() {
+        let mut rng = Rng::new(7);
+        for _ in 0..300 {
+            let n_left = rng.gen_range(0, 9) as usize;
+            let n_right = rng.gen_range(0, 9) as usize;
+            let edges: Vec<(usize, usize, Cost)> = if n_left == 0 || n_right == 0 {
+                Vec::new()
+            } else {
+                (0..rng.gen_range(0, 30))
+                    .map(|_| {
+                        (
+                            rng.gen_range(0, n_left as u64) as usize,
+                            rng.gen_range(0, n_right as u64) as usize,
+                            rng.gen_range(0, 30) as Cost - 8,
+                        )
+                    })
+                    .collect()
+            };
+            let instance = (n_left, n_right, edges);
+            let (weight, matching) = max_weight_matching(n_left, n_right, &instance.2);
+            assert_eq!(weight, brute_force(&instance), "{:?}", instance);
+            assert_eq!(matching_weight(&instance, &matching), weight);
+        }
+    }
+
+    fn instance() -> impl Strategy<Value = Instance> {
+        (1..10_usize, 1..10_usize).prop_flat_map(|(n_left, n_right)| {
+            (
+                Just(n_left),
+                Just(n_right),
+                prop::collection::vec((0..n_left, 0..n_right, -50..=100 as Cost), 0..40),
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn reported_weight_is_the_matching_weight(instance in instance()) {
+            let (weight, matching) = max_weight_matching(instance.0, instance.1, &instance.2);
+            prop_assert!(weight >= 0);
+            prop_assert_eq!(matching_weight(&instance, &matching), weight);
+        }
+    }
+}
@@ -1,11 +1,18 @@
-use crate::graph::{Flow, Graph};
-use std::collections::VecDeque;
+use crate::control::{Cancelled, SolveControl};
+use crate::graph::{count, Flow, Graph, SolveStats, FLOW_MAX};
+use crate::solver::MaxFlowSolver;
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
 
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CapacityScaling {
     graph: Graph,
     current_edge: Vec<usize>,
     level: Vec<isize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stats: SolveStats,
 }
 
 impl CapacityScaling {
@@ -17,8 +24,31 @@ impl CapacityScaling {
         self.graph.add_directed_edge(from, to, capacity)
     }
 
+    pub fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
     pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.solve_with_control(source, sink, &SolveControl::new())
+            .unwrap_or_else(|cancelled| cancelled.flow_so_far)
+    }
+
+    pub fn solve_with_control(
+        &mut self,
+        source: usize,
+        sink: usize,
+        control: &SolveControl,
+    ) -> Result<Flow, Cancelled> {
         self.graph.build();
+        self.stats = SolveStats::augmenting_path();
+        // a source or sink without incident edges is not part of the graph
+        if source == sink || source >= self.graph.num_nodes || sink >= self.graph.num_nodes {
+            return Ok(0);
+        }
+        // no cut separates them
+        if self.graph.has_unbounded_path(source, sink) {
+            return Ok(FLOW_MAX);
+        }
 
         let mut max_capacity = 0;
         for u in 0..self.graph.num_nodes {
@@ -27,17 +57,24 @@ impl CapacityScaling {
             }
         }
 
-        let c = (max_capacity as f64).log2().floor();
-        let mut delta = 2_f64.powf(c) as i64;
+        // largest power of two not exceeding max_capacity
+        let mut delta = if max_capacity > 0 {
+            1 << max_capacity.ilog2()
+        } else {
+            0
+        };
 
         let mut flow = 0;
         while delta >= 1 {
             loop {
+                control.checkpoint(flow, &self.stats)?;
                 self.bfs(source, delta);
                 if self.level[sink] < 0 {
                     break;
                 }
 
+                #[cfg(feature = "tracing")]
+                let _span = tracing::trace_span!("blocking_flow").entered();
                 self.current_edge = (0..self.graph.num_nodes)
                     .map(|u| self.graph.start[u])
                     .collect();
@@ -46,16 +83,35 @@ impl CapacityScaling {
                     if f == 0 {
                         break;
                     }
+                    count(&mut self.stats.augmenting_paths);
                     flow += f;
                 }
             }
             delta /= 2;
         }
 
-        flow
+        Ok(flow)
+    }
+
+    // stops at the first phase boundary after the deadline, keeping the feasible flow found so far
+    #[cfg(feature = "std")]
+    pub fn solve_with_deadline(
+        &mut self,
+        source: usize,
+        sink: usize,
+        deadline: core::time::Duration,
+    ) -> crate::control::BoundedResult {
+        let mut control = SolveControl::new();
+        if let Some(deadline) = std::time::Instant::now().checked_add(deadline) {
+            control = control.deadline(deadline);
+        }
+        self.solve_with_control(source, sink, &control).into()
     }
 
     fn bfs(&mut self, source: usize, delta: i64) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("bfs").entered();
+        count(&mut self.stats.phases);
         self.level = vec![-1; self.graph.num_nodes];
         let mut que = VecDeque::new();
         self.level[source] = 0;
@@ -83,6 +139,7 @@ impl CapacityScaling {
             let residual_capacity = edge.residual_capacity();
 
             if residual_capacity >= delta && self.level[u] + 1 == self.level[to] {
+                count(&mut self.stats.advances);
                 let d = self.dfs(to, sink, flow.min(residual_capacity), delta);
                 if d > 0 {
                     self.graph.push_flow(u, i, d);
@@ -91,16 +148,49 @@ impl CapacityScaling {
             }
         }
         self.current_edge[u] = self.graph.start[u + 1];
+        count(&mut self.stats.retreats);
 
         0
     }
 }
 
+impl MaxFlowSolver for CapacityScaling {
+    fn new() -> Self {
+        CapacityScaling::new()
+    }
+
+    fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow) -> Option<usize> {
+        self.add_directed_edge(from, to, capacity)
+    }
+
+    fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.solve(source, sink)
+    }
+
+    fn solve_with_control(
+        &mut self,
+        source: usize,
+        sink: usize,
+        control: &SolveControl,
+    ) -> Result<Flow, Cancelled> {
+        self.solve_with_control(source, sink, control)
+    }
+
+    fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    fn stats(&self) -> &SolveStats {
+        self.stats()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::capacity_scaling::{CapacityScaling, Flow};
     use crate::test_utility::{
-        read_expected, read_instance_aoj, read_instance_libreoj, GraphInstance,
+        read_expected, read_instance_aoj, read_instance_dimacs, read_instance_libreoj,
+        GraphInstance,
     };
     use rstest::*;
     use std::path::PathBuf;
@@ -125,6 +215,31 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[rstest]
+    fn dimacs(#[files("test_cases/**/*.max")] path: PathBuf) {
+        let mut expected_file_path = path.clone();
+        expected_file_path.set_extension("out");
+
+        let actual = execute(read_instance_dimacs(&path));
+        let expected = read_expected(&expected_file_path);
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    fn stats(#[files("test_cases/AOJ_GRL_6_A/*.in")] path: PathBuf) {
+        let instance = read_instance_aoj(&path);
+        let mut solver: CapacityScaling = instance.build_solver();
+        let flow = solver.solve(instance.source, instance.sink);
+        let stats = solver.stats();
+        let max_capacity = instance.edges.iter().map(|e| e.2).max().unwrap_or(0).max(1);
+        let num_scales = max_capacity.ilog2() as usize + 1;
+        assert!(stats.phases.unwrap() <= num_scales * instance.num_nodes);
+        assert!(stats.augmenting_paths.unwrap() as Flow <= flow);
+        assert!(stats.augmenting_paths.unwrap() <= stats.advances.unwrap());
+        assert_eq!(stats.relabels, None);
+        assert_eq!(stats.visited_nodes, None);
+    }
+
     fn execute(instance: GraphInstance) -> Flow {
         let mut solver = CapacityScaling::new();
         for (from, to, capacity) in instance.edges {
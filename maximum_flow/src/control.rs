@@ -0,0 +1,244 @@
+use crate::graph::{Flow, SolveStats};
+use alloc::boxed::Box;
+use core::cell::RefCell;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+// reported at every phase boundary
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'s> {
+    // flow sent to the sink so far (the excess of the sink for push-relabel)
+    pub flow: Flow,
+    pub stats: &'s SolveStats,
+}
+
+// returned by solve_with_control when the cancellation flag was set
+// the solver's graph holds a feasible flow of value flow_so_far (a preflow for push-relabel)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled {
+    pub flow_so_far: Flow,
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cancelled after sending {} units of flow",
+            self.flow_so_far
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Cancelled {}
+
+// result of solve_with_deadline
+// a lower bound comes with a feasible flow of that value in the solver's graph
+// (push-relabel would only have a preflow, whose excess at the sink is still a lower bound)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundedResult {
+    Optimal(Flow),
+    LowerBound(Flow),
+}
+
+impl BoundedResult {
+    pub fn value(&self) -> Flow {
+        match *self {
+            BoundedResult::Optimal(flow) | BoundedResult::LowerBound(flow) => flow,
+        }
+    }
+
+    pub fn is_optimal(&self) -> bool {
+        matches!(self, BoundedResult::Optimal(_))
+    }
+}
+
+impl From<Result<Flow, Cancelled>> for BoundedResult {
+    fn from(result: Result<Flow, Cancelled>) -> Self {
+        match result {
+            Ok(flow) => BoundedResult::Optimal(flow),
+            Err(cancelled) => BoundedResult::LowerBound(cancelled.flow_so_far),
+        }
+    }
+}
+
+type ProgressCallback<'a> = Box<dyn FnMut(Progress) + 'a>;
+
+// checked at phase boundaries: each BFS of Dinic and CapacityScaling, each augmentation of
+// FordFulkerson, each pass over the FIFO queue and each bucket sweep of highest-label push-relabel
+#[derive(Default)]
+pub struct SolveControl<'a> {
+    cancel: Option<&'a AtomicBool>,
+    #[cfg(feature = "std")]
+    deadline: Option<std::time::Instant>,
+    progress: RefCell<Option<ProgressCallback<'a>>>,
+}
+
+impl<'a> SolveControl<'a> {
+    pub fn new() -> Self {
+        SolveControl::default()
+    }
+
+    // the solve stops at the next phase boundary once flag is true, it may be set from another thread
+    pub fn cancel_on(mut self, flag: &'a AtomicBool) -> Self {
+        self.cancel = Some(flag);
+        self
+    }
+
+    // the solve stops at the first phase boundary after deadline
+    #[cfg(feature = "std")]
+    pub fn deadline(mut self, deadline: std::time::Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn on_progress(self, f: impl FnMut(Progress) + 'a) -> Self {
+        *self.progress.borrow_mut() = Some(Box::new(f));
+        self
+    }
+
+    pub(crate) fn checkpoint(&self, flow: Flow, stats: &SolveStats) -> Result<(), Cancelled> {
+        #[cfg(feature = "log")]
+        log::trace!("phase boundary: flow {}, {:?}", flow, stats);
+        if let Some(f) = self.progress.borrow_mut().as_mut() {
+            f(Progress { flow, stats });
+        }
+        let cancelled = self.cancel.is_some_and(|flag| flag.load(Ordering::Relaxed));
+        #[cfg(feature = "std")]
+        let cancelled = cancelled
+            || self
+                .deadline
+                .is_some_and(|d| std::time::Instant::now() >= d);
+        if cancelled {
+            return Err(Cancelled { flow_so_far: flow });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::capacity_scaling::CapacityScaling;
+    use crate::control::{BoundedResult, Cancelled, SolveControl};
+    use crate::dinic::Dinic;
+    use crate::ford_fulkerson::FordFulkerson;
+    use crate::generators::random_graph;
+    use crate::instances::GraphInstance;
+    use crate::push_relabel_fifo::PushRelabelFIFO;
+    use crate::push_relabel_highest_label::PushRelabelHighestLabel;
+    use crate::solver::MaxFlowSolver;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn instance() -> GraphInstance {
+        random_graph(200, 2_000, 1_000, 1)
+    }
+
+    // the callback hands over to another thread at the second checkpoint and waits for it to cancel
+    fn cancel_from_thread<S: MaxFlowSolver>(
+        instance: &GraphInstance,
+        true_flow: bool,
+    ) -> Result<i64, Cancelled> {
+        let flag = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::channel();
+        std::thread::scope(|scope| {
+            let flag = &flag;
+            scope.spawn(move || {
+                receiver.recv().unwrap();
+                flag.store(true, Ordering::Relaxed);
+            });
+
+            let mut checkpoints = 0;
+            let control = SolveControl::new().cancel_on(flag).on_progress(|_| {
+                checkpoints += 1;
+                if checkpoints == 2 {
+                    sender.send(()).unwrap();
+                    while !flag.load(Ordering::Relaxed) {
+                        std::thread::yield_now();
+                    }
+                }
+            });
+            let mut solver: S = instance.build_solver();
+            let result = solver.solve_with_control(instance.source, instance.sink, &control);
+            if let Err(cancelled) = result {
+                if true_flow {
+                    let graph = solver.graph();
+                    let value = graph.validate_flow(instance.source, instance.sink);
+                    assert_eq!(value, Ok(cancelled.flow_so_far));
+                }
+            }
+            result
+        })
+    }
+
+    #[test]
+    fn cancel() {
+        let instance = instance();
+        let optimum = instance.solve::<Dinic>();
+        for result in [
+            cancel_from_thread::<Dinic>(&instance, true),
+            cancel_from_thread::<FordFulkerson>(&instance, true),
+            cancel_from_thread::<CapacityScaling>(&instance, true),
+            cancel_from_thread::<PushRelabelFIFO>(&instance, false),
+            cancel_from_thread::<PushRelabelHighestLabel>(&instance, false),
+        ] {
+            let Err(Cancelled { flow_so_far }) = result else {
+                panic!("not cancelled");
+            };
+            assert!(0 <= flow_so_far && flow_so_far <= optimum);
+        }
+    }
+
+    #[test]
+    fn progress() {
+        let instance = instance();
+        let mut flows = Vec::new();
+        let control = SolveControl::new().on_progress(|p| flows.push(p.flow));
+        let mut solver: Dinic = instance.build_solver();
+        let flow = solver.solve_with_control(instance.source, instance.sink, &control);
+        drop(control);
+
+        assert_eq!(flow, Ok(instance.solve::<Dinic>()));
+        // once per BFS
+        assert_eq!(flows.len(), solver.stats().phases.unwrap());
+        assert!(flows.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn not_cancelled() {
+        let instance = instance();
+        let flag = AtomicBool::new(false);
+        let control = SolveControl::new().cancel_on(&flag);
+        let mut solver: PushRelabelHighestLabel = instance.build_solver();
+        let flow = solver.solve_with_control(instance.source, instance.sink, &control);
+        assert_eq!(flow, Ok(instance.solve::<Dinic>()));
+    }
+
+    #[test]
+    fn deadline() {
+        let instance = random_graph(2_000, 20_000, 1_000, 2);
+        let optimum = instance.solve::<Dinic>();
+
+        let mut dinic: Dinic = instance.build_solver();
+        let mut scaling: CapacityScaling = instance.build_solver();
+        for (result, graph) in [
+            (
+                dinic.solve_with_deadline(instance.source, instance.sink, Duration::from_micros(1)),
+                dinic.graph(),
+            ),
+            (
+                scaling.solve_with_deadline(instance.source, instance.sink, Duration::ZERO),
+                scaling.graph(),
+            ),
+        ] {
+            assert!(result.value() <= optimum);
+            let value = graph.validate_flow(instance.source, instance.sink);
+            assert_eq!(value, Ok(result.value()));
+        }
+
+        let mut dinic: Dinic = instance.build_solver();
+        let result = dinic.solve_with_deadline(instance.source, instance.sink, Duration::MAX);
+        assert_eq!(result, BoundedResult::Optimal(optimum));
+    }
+}
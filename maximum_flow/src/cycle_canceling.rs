@@ -0,0 +1,493 @@
+use crate::dinic::Dinic;
+use crate::graph::{Flow, Graph};
+use crate::min_cost_flow::{build_cost_graph, Cost, CostArc, CostEdge};
+use crate::solver::MaxFlowSolver;
+use alloc::vec;
+use alloc::vec::Vec;
+use network_flows_core::CsrGraph;
+
+// the cycle canceled next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CycleSelection {
+    // any cycle of negative cost that Bellman-Ford finds, the number of cycles is only bounded by the cost
+    #[default]
+    NegativeCycle,
+    // a cycle of minimum mean cost (Goldberg and Tarjan), O(nm^2 log n) cycles at most
+    MinimumMean,
+}
+
+// minimum cost flows by canceling cycles of negative cost in the residual network
+// any flow is a start: solve takes the maximum flow of Dinic, improve_cost the flow of another solver
+// canceling keeps the excess of every node, so the value of the flow stays, and with integer costs every cycle
+// lowers the cost by at least 1, which bounds the number of cycles
+#[derive(Default)]
+pub struct CycleCanceling {
+    num_nodes: usize,
+    edge_list: Vec<CostEdge>,
+    graph: CsrGraph<CostArc>,
+    selection: CycleSelection,
+    canceled_cycles: usize,
+}
+
+impl CycleCanceling {
+    pub fn new() -> Self {
+        CycleCanceling::default()
+    }
+
+    pub fn selection(mut self, selection: CycleSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    pub fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        cost: Cost,
+    ) -> Option<usize> {
+        if capacity <= 0 {
+            return None;
+        }
+        self.edge_list.push(CostEdge {
+            from,
+            to,
+            flow: 0,
+            capacity,
+            cost,
+        });
+        self.num_nodes = self.num_nodes.max(from.max(to) + 1);
+        Some(self.edge_list.len() - 1)
+    }
+
+    // the edges of a solved graph with their flows, costs[i] is the cost of edge i
+    pub fn from_flow(graph: &Graph, costs: &[Cost]) -> Self {
+        assert_eq!(costs.len(), graph.num_edges, "one cost per edge");
+        let edge_list: Vec<CostEdge> = (0..graph.num_edges)
+            .map(|i| {
+                let e = graph.get_directed_edge(i);
+                CostEdge {
+                    from: e.from,
+                    to: e.to,
+                    flow: e.flow,
+                    capacity: e.capacity,
+                    cost: costs[i],
+                }
+            })
+            .collect();
+        let mut solver = CycleCanceling {
+            num_nodes: graph.num_nodes,
+            edge_list,
+            ..Default::default()
+        };
+        solver.build();
+        solver
+    }
+
+    pub fn get_directed_edge(&self, edge_index: usize) -> CostEdge {
+        let mut edge = self.edge_list[edge_index].clone();
+        if edge_index < self.graph.edge_index.len() {
+            edge.flow = self.graph.forward(edge_index).flow;
+        }
+        edge
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.edge_list.len()
+    }
+
+    // the negative cycles canceled since the flow was set
+    pub fn canceled_cycles(&self) -> usize {
+        self.canceled_cycles
+    }
+
+    // the cost of the current flow
+    pub fn cost(&self) -> Cost {
+        (0..self.num_edges())
+            .map(|i| {
+                let e = self.get_directed_edge(i);
+                e.flow * e.cost
+            })
+            .sum()
+    }
+
+    // a maximum flow of minimum cost, (flow, cost)
+    pub fn solve(&mut self, source: usize, sink: usize) -> (Flow, Cost) {
+        let mut dinic = Dinic::new();
+        for e in self.edge_list.iter() {
+            dinic.add_directed_edge(e.from, e.to, e.capacity);
+        }
+        let flow = dinic.solve(source, sink);
+        for (i, e) in self.edge_list.iter_mut().enumerate() {
+            e.flow = dinic.graph().get_directed_edge(i).flow;
+        }
+        self.build();
+        (flow, self.improve())
+    }
+
+    // O(nm) per canceled cycle
+    // cancels negative cycles until there are none, and returns the cost of the flow, which is then the minimum of
+    // those with the same excesses
+    pub fn improve(&mut self) -> Cost {
+        loop {
+            let cycle = match self.selection {
+                CycleSelection::NegativeCycle => self.negative_cycle(),
+                CycleSelection::MinimumMean => self.min_mean_negative_cycle(),
+            };
+            let Some(cycle) = cycle else {
+                break;
+            };
+            let delta = cycle
+                .iter()
+                .map(|&i| self.graph.arcs[i].residual_capacity())
+                .min()
+                .unwrap();
+            for &i in cycle.iter() {
+                let rev = self.graph.arcs[i].rev;
+                self.graph.arcs[i].flow += delta;
+                self.graph.arcs[rev].flow -= delta;
+            }
+            self.canceled_cycles += 1;
+        }
+        self.cost()
+    }
+
+    fn build(&mut self) {
+        self.graph = build_cost_graph(self.num_nodes, &self.edge_list);
+        self.canceled_cycles = 0;
+    }
+
+    // the node each arc leaves
+    fn tails(&self) -> Vec<usize> {
+        (0..self.num_nodes)
+            .flat_map(|u| core::iter::repeat_n(u, self.graph.start[u + 1] - self.graph.start[u]))
+            .collect()
+    }
+
+    // O(nm)
+    // Bellman-Ford from all nodes at once: a node still improving in round n has a negative cycle behind it among
+    // the arcs it was reached by, the arcs of that cycle in order
+    fn negative_cycle(&self) -> Option<Vec<usize>> {
+        let mut distance = vec![0 as Cost; self.num_nodes];
+        let mut parent = vec![usize::MAX; self.num_nodes];
+        let tail = self.tails();
+
+        let mut improved = None;
+        for _ in 0..self.num_nodes {
+            improved = None;
+            for (i, arc) in self.graph.arcs.iter().enumerate() {
+                let u = tail[i];
+                if arc.residual_capacity() > 0 && distance[u] + arc.cost < distance[arc.to] {
+                    distance[arc.to] = distance[u] + arc.cost;
+                    parent[arc.to] = i;
+                    improved = Some(arc.to);
+                }
+            }
+            improved?;
+        }
+
+        // n steps back along the parents end on the cycle
+        let mut v = improved?;
+        for _ in 0..self.num_nodes {
+            v = tail[parent[v]];
+        }
+        let mut cycle = Vec::new();
+        let mut u = v;
+        loop {
+            cycle.push(parent[u]);
+            u = tail[parent[u]];
+            if u == v {
+                break;
+            }
+        }
+        cycle.reverse();
+        Some(cycle)
+    }
+
+    // O(nm) the cycle of minimum mean cost among the arcs with residual capacity, if that is negative
+    fn min_mean_negative_cycle(&self) -> Option<Vec<usize>> {
+        let tail = self.tails();
+        let arcs: Vec<usize> = (0..self.graph.arcs.len())
+            .filter(|&i| self.graph.arcs[i].residual_capacity() > 0)
+            .collect();
+        let edges: Vec<(usize, usize, Cost)> = arcs
+            .iter()
+            .map(|&i| (tail[i], self.graph.arcs[i].to, self.graph.arcs[i].cost))
+            .collect();
+        let (cycle, mean) = min_mean_cycle(self.num_nodes, &edges)?;
+        (mean < 0.).then(|| cycle.iter().map(|&i| arcs[i]).collect())
+    }
+}
+
+// O(nm) time and O(n^2) memory
+// the cycle of minimum mean cost among the edges (from, to, cost), its edges in order and its mean cost, by Karp's
+// algorithm: with d_k(v) the minimum cost of the walks of k edges that end in v, the minimum mean is the minimum over
+// v of the maximum over k < n of (d_n(v) - d_k(v)) / (n - k), and the walk of n edges behind the v of the minimum
+// closes a cycle of that mean first
+pub fn min_mean_cycle(
+    num_nodes: usize,
+    edges: &[(usize, usize, Cost)],
+) -> Option<(Vec<usize>, f64)> {
+    let n = num_nodes;
+    // distance[k * n + v] = d_k(v), parent[k * n + v] the last edge of the walk
+    let mut distance = vec![Cost::MAX; (n + 1) * n];
+    let mut parent = vec![usize::MAX; (n + 1) * n];
+    distance[..n].fill(0);
+    for k in 1..=n {
+        for (i, &(from, to, cost)) in edges.iter().enumerate() {
+            let d = distance[(k - 1) * n + from];
+            if d != Cost::MAX && d + cost < distance[k * n + to] {
+                distance[k * n + to] = d + cost;
+                parent[k * n + to] = i;
+            }
+        }
+    }
+
+    // the means as fractions (cost, edges), compared exactly
+    let less = |(a, b): (Cost, usize), (c, d): (Cost, usize)| {
+        (a as i128) * (d as i128) < (c as i128) * (b as i128)
+    };
+    let mut best: Option<(usize, (Cost, usize))> = None;
+    for v in 0..n {
+        let d_n = distance[n * n + v];
+        if d_n == Cost::MAX {
+            continue;
+        }
+        let worst = (0..n)
+            .filter(|&k| distance[k * n + v] != Cost::MAX)
+            .map(|k| (d_n - distance[k * n + v], n - k))
+            .reduce(|worst, mean| if less(worst, mean) { mean } else { worst })
+            .unwrap();
+        if best.is_none_or(|(_, mean)| less(worst, mean)) {
+            best = Some((v, worst));
+        }
+    }
+
+    // back along the walk until a node repeats, walk[j] is the edge into the node at step n - j
+    let (mut v, _) = best?;
+    let mut seen = vec![usize::MAX; n];
+    let mut walk = Vec::new();
+    let mut k = n;
+    seen[v] = k;
+    loop {
+        let i = parent[k * n + v];
+        walk.push(i);
+        v = edges[i].0;
+        k -= 1;
+        if seen[v] != usize::MAX {
+            break;
+        }
+        seen[v] = k;
+    }
+    let mut cycle = walk[n - seen[v]..].to_vec();
+    cycle.reverse();
+    let cost: Cost = cycle.iter().map(|&i| edges[i].2).sum();
+    let mean = cost as f64 / cycle.len() as f64;
+    Some((cycle, mean))
+}
+
+// the flow of graph, e.g. the maximum flow of Dinic, at minimum cost for the given costs of its edges
+pub fn improve_cost(graph: &Graph, costs: &[Cost]) -> CycleCanceling {
+    let mut solver = CycleCanceling::from_flow(graph, costs);
+    solver.improve();
+    solver
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cycle_canceling::{improve_cost, min_mean_cycle, CycleCanceling, CycleSelection};
+    use crate::dinic::Dinic;
+    use crate::generators::Rng;
+    use crate::graph::Flow;
+    use crate::min_cost_flow::{Cost, MinCostFlow};
+    use crate::solver::MaxFlowSolver;
+
+    type Edges = Vec<(usize, usize, Flow, Cost)>;
+
+    // the capacities and flows of the edges stay feasible and every node but source and sink keeps its balance
+    fn assert_feasible(
+        solver: &CycleCanceling,
+        num_nodes: usize,
+        source: usize,
+        sink: usize,
+    ) -> Flow {
+        let mut balance = vec![0 as Flow; num_nodes];
+        for i in 0..solver.num_edges() {
+            let e = solver.get_directed_edge(i);
+            assert!(0 <= e.flow && e.flow <= e.capacity, "{:?}", e);
+            balance[e.from] -= e.flow;
+            balance[e.to] += e.flow;
+        }
+        for (u, &b) in balance.iter().enumerate() {
+            if u != source && u != sink {
+                assert_eq!(b, 0, "node {}", u);
+            }
+        }
+        balance[sink]
+    }
+
+    fn random_edges(rng: &mut Rng, n: usize, acyclic: bool) -> Edges {
+        (0..rng.gen_range(0, 25))
+            .map(|_| {
+                let capacity = rng.gen_capacity(10);
+                if acyclic {
+                    // from < to, so that negative costs make no cycle
+                    let from = rng.gen_range(0, n as u64 - 1) as usize;
+                    let to = rng.gen_range(from as u64 + 1, n as u64) as usize;
+                    (from, to, capacity, rng.gen_range(0, 20) as Cost - 8)
+                } else {
+                    let from = rng.gen_range(0, n as u64) as usize;
+                    let to = rng.gen_range(0, n as u64) as usize;
+                    (from, to, capacity, rng.gen_range(0, 10) as Cost)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_optimum_as_successive_shortest_paths() {
+        let mut rng = Rng::new(5);
+        for round in 0..300 {
+            let n = rng.gen_range(2, 9) as usize;
+            let edges = random_edges(&mut rng, n, round % 2 == 0);
+            let mut ssp = MinCostFlow::new();
+            let mut canceling = CycleCanceling::new();
+            let mut min_mean = CycleCanceling::new().selection(CycleSelection::MinimumMean);
+            for &(from, to, capacity, cost) in edges.iter() {
+                ssp.add_directed_edge(from, to, capacity, cost);
+                canceling.add_directed_edge(from, to, capacity, cost);
+                min_mean.add_directed_edge(from, to, capacity, cost);
+            }
+            let expected = ssp.solve(0, n - 1);
+            for solver in [&mut canceling, &mut min_mean] {
+                assert_eq!(solver.solve(0, n - 1), expected, "{:?}", edges);
+                assert_eq!(assert_feasible(solver, n, 0, n - 1), expected.0);
+                assert_eq!(solver.cost(), expected.1);
+            }
+
+            let mut dinic = Dinic::new();
+            for &(from, to, capacity, _) in edges.iter() {
+                dinic.add_directed_edge(from, to, capacity);
+            }
+            dinic.solve(0, n - 1);
+            let costs: Vec<Cost> = edges.iter().map(|e| e.3).collect();
+            assert_eq!(improve_cost(dinic.graph(), &costs).cost(), expected.1);
+        }
+    }
+
+    #[test]
+    fn improves_a_flow_of_dinic() {
+        // 2 units from 1 to 3, Dinic takes the edge of cost 10 on the shortest path, not 1 -> 2 -> 3 of cost 2
+        let edges: Edges = vec![(0, 1, 2, 0), (1, 3, 2, 10), (1, 2, 2, 1), (2, 3, 2, 1)];
+        let mut dinic = Dinic::new();
+        for &(from, to, capacity, _) in edges.iter() {
+            dinic.add_directed_edge(from, to, capacity);
+        }
+        assert_eq!(dinic.solve(0, 3), 2);
+        assert_eq!(dinic.graph().get_directed_edge(1).flow, 2);
+        let costs: Vec<Cost> = edges.iter().map(|e| e.3).collect();
+        let solver = improve_cost(dinic.graph(), &costs);
+        assert_eq!(assert_feasible(&solver, 4, 0, 3), 2);
+        assert_eq!(solver.cost(), 4);
+        assert_eq!(solver.get_directed_edge(1).flow, 0);
+        assert_eq!(solver.canceled_cycles(), 1);
+    }
+
+    #[test]
+    fn negative_cycle_without_source_and_sink() {
+        // a circulation: the cycle 0 -> 1 -> 2 -> 0 of cost -3 is saturated, 0 -> 1 -> 0 of cost 1 isn't
+        let mut solver = CycleCanceling::new();
+        for (from, to, capacity, cost) in [(0, 1, 4, -2), (1, 2, 3, 0), (2, 0, 5, -1), (1, 0, 2, 3)]
+        {
+            solver.add_directed_edge(from, to, capacity, cost);
+        }
+        assert_eq!(solver.solve(0, 0), (0, -9));
+        assert_eq!(solver.canceled_cycles(), 1);
+        assert_eq!(assert_feasible(&solver, 3, 0, 0), 0);
+    }
+
+    #[test]
+    fn min_mean_over_the_least_total_cost() {
+        // 0 -> 2 -> 1 -> 3 -> 0 of cost -3 and mean -3/4 carries the flow, but Bellman-Ford finds 0 -> 2 -> 3 -> 0
+        // of cost -2 and then 2 -> 1 -> 3 -> 2 of cost -1, both through the edge of capacity 1 between 2 and 3
+        let c = 1000;
+        let edges: Edges = vec![
+            (2, 1, c, -4),
+            (0, 2, c, -2),
+            (1, 3, c, 2),
+            (3, 0, c, 1),
+            (2, 3, 1, -1),
+        ];
+        let mut canceled_cycles = Vec::new();
+        for selection in [CycleSelection::NegativeCycle, CycleSelection::MinimumMean] {
+            let mut solver = CycleCanceling::new().selection(selection);
+            for &(from, to, capacity, cost) in edges.iter() {
+                solver.add_directed_edge(from, to, capacity, cost);
+            }
+            assert_eq!(solver.solve(0, 0), (0, -3 * c));
+            assert_feasible(&solver, 4, 0, 0);
+            canceled_cycles.push(solver.canceled_cycles());
+        }
+        assert_eq!(canceled_cycles, [2 * c as usize, 1]);
+    }
+
+    // Bellman-Ford from every node at once
+    fn has_negative_cycle(num_nodes: usize, edges: &[(usize, usize, i128)]) -> bool {
+        let mut distance = vec![0; num_nodes];
+        for _ in 0..=num_nodes {
+            let mut updated = false;
+            for &(from, to, cost) in edges.iter() {
+                if distance[from] + cost < distance[to] {
+                    distance[to] = distance[from] + cost;
+                    updated = true;
+                }
+            }
+            if !updated {
+                return false;
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn min_mean_cycle_random() {
+        let mut rng = Rng::new(6);
+        for _ in 0..500 {
+            let n = rng.gen_range(1, 7) as usize;
+            let edges: Vec<(usize, usize, Cost)> = (0..rng.gen_range(0, 12))
+                .map(|_| {
+                    let from = rng.gen_range(0, n as u64) as usize;
+                    let to = rng.gen_range(0, n as u64) as usize;
+                    (from, to, rng.gen_range(0, 11) as Cost - 5)
+                })
+                .collect();
+            let Some((cycle, mean)) = min_mean_cycle(n, &edges) else {
+                // acyclic
+                let edges: Vec<_> = edges.iter().map(|&(from, to, _)| (from, to, -1)).collect();
+                assert!(!has_negative_cycle(n, &edges));
+                continue;
+            };
+            for (j, &i) in cycle.iter().enumerate() {
+                assert_eq!(
+                    edges[i].1,
+                    edges[cycle[(j + 1) % cycle.len()]].0,
+                    "{:?}",
+                    edges
+                );
+            }
+            let cost: Cost = cycle.iter().map(|&i| edges[i].2).sum();
+            assert_eq!(mean, cost as f64 / cycle.len() as f64);
+            // no cycle of a lower mean, that would be negative for the costs cost * |cycle| - cost(cycle)
+            let shifted: Vec<_> = edges
+                .iter()
+                .map(|&(from, to, c)| (from, to, (c * cycle.len() as Cost - cost) as i128))
+                .collect();
+            assert!(!has_negative_cycle(n, &shifted), "{:?} {:?}", edges, cycle);
+        }
+        assert_eq!(min_mean_cycle(0, &[]), None);
+        assert_eq!(
+            min_mean_cycle(2, &[(0, 1, 3), (1, 0, -1), (1, 1, 0)]),
+            Some((vec![2], 0.))
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::graph::Flow;
+use crate::instances::GraphInstance;
+
+// greedily shrinks an instance while predicate (e.g. "Dinic and PushRelabelFIFO disagree") holds
+// removes edges, merges nodes and reduces capacities until none of them keeps the predicate true
+// the instance is returned unchanged if the predicate doesn't hold for it in the first place
+pub fn shrink_instance(
+    instance: GraphInstance,
+    mut predicate: impl FnMut(&GraphInstance) -> bool,
+) -> GraphInstance {
+    let mut current = instance;
+    if !predicate(&current) {
+        return current;
+    }
+
+    loop {
+        let mut changed = remove_edges(&mut current, &mut predicate);
+        changed |= merge_nodes(&mut current, &mut predicate);
+        changed |= reduce_capacities(&mut current, &mut predicate);
+        if !changed {
+            break;
+        }
+    }
+
+    let compacted = compact(&current);
+    if predicate(&compacted) {
+        current = compacted;
+    }
+    current
+}
+
+// removes chunks of edges, halving the chunk size down to single edges
+fn remove_edges(
+    current: &mut GraphInstance,
+    predicate: &mut impl FnMut(&GraphInstance) -> bool,
+) -> bool {
+    let mut changed = false;
+    let mut chunk = current.edges.len().div_ceil(2);
+    while chunk > 0 {
+        let mut i = 0;
+        while i < current.edges.len() {
+            let mut candidate = current.clone();
+            let end = (i + chunk).min(candidate.edges.len());
+            candidate.edges.drain(i..end);
+            if predicate(&candidate) {
+                *current = candidate;
+                changed = true;
+            } else {
+                i += chunk;
+            }
+        }
+        chunk /= 2;
+    }
+    changed
+}
+
+// merges v into u, keeping source and sink apart
+fn merge_nodes(
+    current: &mut GraphInstance,
+    predicate: &mut impl FnMut(&GraphInstance) -> bool,
+) -> bool {
+    let mut changed = false;
+    let mut v = 1;
+    while v < current.num_nodes {
+        let mut merged = false;
+        for u in 0..v {
+            let terminals = [current.source, current.sink];
+            if terminals.contains(&u) && terminals.contains(&v) {
+                continue;
+            }
+            let candidate = merge(current, u, v);
+            if predicate(&candidate) {
+                *current = candidate;
+                changed = true;
+                merged = true;
+                break;
+            }
+        }
+        // the nodes above v moved down by one
+        if !merged {
+            v += 1;
+        }
+    }
+    changed
+}
+
+fn merge(instance: &GraphInstance, u: usize, v: usize) -> GraphInstance {
+    debug_assert!(u < v);
+    let relabel = |w: usize| match w {
+        _ if w == v => u,
+        _ if w > v => w - 1,
+        _ => w,
+    };
+    GraphInstance {
+        num_nodes: instance.num_nodes - 1,
+        edges: instance
+            .edges
+            .iter()
+            .map(|&(from, to, capacity)| (relabel(from), relabel(to), capacity))
+            .collect(),
+        source: relabel(instance.source),
+        sink: relabel(instance.sink),
+    }
+}
+
+// tries 1, half and one less for every capacity
+fn reduce_capacities(
+    current: &mut GraphInstance,
+    predicate: &mut impl FnMut(&GraphInstance) -> bool,
+) -> bool {
+    let mut changed = false;
+    for i in 0..current.edges.len() {
+        loop {
+            let capacity = current.edges[i].2;
+            let smaller = [1, capacity / 2, capacity - 1]
+                .into_iter()
+                .filter(|&c: &Flow| 0 < c && c < capacity)
+                .find(|&c| {
+                    let mut candidate = current.clone();
+                    candidate.edges[i].2 = c;
+                    predicate(&candidate)
+                });
+            match smaller {
+                Some(c) => {
+                    current.edges[i].2 = c;
+                    changed = true;
+                }
+                None => break,
+            }
+        }
+    }
+    changed
+}
+
+// drops the nodes without incident edges, except source and sink
+fn compact(instance: &GraphInstance) -> GraphInstance {
+    let mut new_id = vec![usize::MAX; instance.num_nodes];
+    let mut num_nodes = 0;
+    let mut visit = |u: usize| {
+        if new_id[u] == usize::MAX {
+            new_id[u] = num_nodes;
+            num_nodes += 1;
+        }
+        new_id[u]
+    };
+    let source = visit(instance.source);
+    let sink = visit(instance.sink);
+    let edges = instance
+        .edges
+        .iter()
+        .map(|&(from, to, capacity)| (visit(from), visit(to), capacity))
+        .collect();
+    GraphInstance {
+        num_nodes,
+        edges,
+        source,
+        sink,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::debugging::shrink_instance;
+    use crate::dinic::Dinic;
+    use crate::generators::random_graph;
+    use crate::instances::GraphInstance;
+    use crate::push_relabel_fifo::PushRelabelFIFO;
+
+    // a "solver" that loses one unit of flow on every edge with capacity 7 or more
+    fn corrupted(instance: &GraphInstance) -> i64 {
+        let mut instance = instance.clone();
+        for edge in instance.edges.iter_mut() {
+            if edge.2 >= 7 {
+                edge.2 -= 1;
+            }
+        }
+        instance.solve::<PushRelabelFIFO>()
+    }
+
+    #[test]
+    fn corrupted_comparison() {
+        let instance = random_graph(30, 200, 100, 1);
+        let disagree = |instance: &GraphInstance| instance.solve::<Dinic>() != corrupted(instance);
+        assert!(disagree(&instance));
+
+        let shrunk = shrink_instance(instance, disagree);
+        assert!(disagree(&shrunk));
+        // a single edge from source to sink with capacity 7
+        assert_eq!(shrunk.num_nodes, 2);
+        assert_eq!(shrunk.edges, vec![(shrunk.source, shrunk.sink, 7)]);
+    }
+
+    #[test]
+    fn predicate_does_not_hold() {
+        let instance = random_graph(10, 30, 5, 2);
+        let shrunk = shrink_instance(instance.clone(), |_| false);
+        assert_eq!(shrunk, instance);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::graph::{Flow, Graph};
+use crate::lean::NoEdgeList;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Default)]
+pub struct DotOptions {
+    pub hide_zero_flow: bool,
+    // only nodes in this subset (and edges between them) are drawn
+    pub nodes: Option<Vec<usize>>,
+    // cut[u] = true if u is on the source side (e.g. Graph::min_cut)
+    pub cut: Option<Vec<bool>>,
+}
+
+impl Graph {
+    // edges are labeled `flow/capacity`, saturated edges are bold and cut edges are red
+    // panics on a lean graph, see Graph::try_to_dot
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        let mut visible = vec![options.nodes.is_none(); self.num_nodes];
+        for &u in options.nodes.iter().flatten() {
+            if u < self.num_nodes {
+                visible[u] = true;
+            }
+        }
+
+        let mut dot = String::from("digraph {\n");
+        for u in (0..self.num_nodes).filter(|&u| visible[u]) {
+            match &options.cut {
+                Some(cut) if cut[u] => {
+                    writeln!(dot, "    {} [style=filled, fillcolor=lightblue];", u).unwrap()
+                }
+                Some(_) => writeln!(dot, "    {} [style=filled, fillcolor=lightpink];", u).unwrap(),
+                None => writeln!(dot, "    {};", u).unwrap(),
+            }
+        }
+
+        for i in 0..self.num_edges {
+            let edge = self.get_directed_edge(i);
+            if !visible[edge.from] || !visible[edge.to] {
+                continue;
+            }
+            if options.hide_zero_flow && edge.flow == 0 as Flow {
+                continue;
+            }
+
+            let mut attributes = vec![format!("label=\"{}/{}\"", edge.flow, edge.capacity)];
+            if edge.flow == edge.capacity {
+                attributes.push("style=bold".to_string());
+            }
+            if let Some(cut) = &options.cut {
+                if cut[edge.from] && !cut[edge.to] {
+                    attributes.push("color=red".to_string());
+                }
+            }
+            writeln!(
+                dot,
+                "    {} -> {} [{}];",
+                edge.from,
+                edge.to,
+                attributes.join(", ")
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    // to_dot, or an error on a lean graph
+    pub fn try_to_dot(&self, options: &DotOptions) -> Result<String, NoEdgeList> {
+        self.no_edge_list()?;
+        Ok(self.to_dot(options))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::dot::DotOptions;
+    use crate::solver::MaxFlowSolver;
+
+    fn solved_sample() -> Dinic {
+        let mut solver = Dinic::new();
+        solver.add_directed_edge(0, 1, 2);
+        solver.add_directed_edge(0, 2, 1);
+        solver.add_directed_edge(1, 2, 1);
+        solver.add_directed_edge(1, 3, 1);
+        solver.add_directed_edge(2, 3, 2);
+        solver.add_directed_edge(2, 0, 4);
+        assert_eq!(solver.solve(0, 3), 3);
+        solver
+    }
+
+    #[test]
+    fn default_options() {
+        let solver = solved_sample();
+        let expected = "digraph {
+    0;
+    1;
+    2;
+    3;
+    0 -> 1 [label=\"2/2\", style=bold];
+    0 -> 2 [label=\"1/1\", style=bold];
+    1 -> 2 [label=\"1/1\", style=bold];
+    1 -> 3 [label=\"1/1\", style=bold];
+    2 -> 3 [label=\"2/2\", style=bold];
+    2 -> 0 [label=\"0/4\"];
+}
+";
+        assert_eq!(solver.graph().to_dot(&DotOptions::default()), expected);
+    }
+
+    #[test]
+    fn cut() {
+        let solver = solved_sample();
+        let options = DotOptions {
+            cut: Some(solver.graph().min_cut(3)),
+            ..Default::default()
+        };
+        let expected = "digraph {
+    0 [style=filled, fillcolor=lightblue];
+    1 [style=filled, fillcolor=lightblue];
+    2 [style=filled, fillcolor=lightblue];
+    3 [style=filled, fillcolor=lightpink];
+    0 -> 1 [label=\"2/2\", style=bold];
+    0 -> 2 [label=\"1/1\", style=bold];
+    1 -> 2 [label=\"1/1\", style=bold];
+    1 -> 3 [label=\"1/1\", style=bold, color=red];
+    2 -> 3 [label=\"2/2\", style=bold, color=red];
+    2 -> 0 [label=\"0/4\"];
+}
+";
+        assert_eq!(solver.graph().to_dot(&options), expected);
+    }
+
+    #[test]
+    fn hide_zero_flow_and_subset() {
+        let solver = solved_sample();
+        let options = DotOptions {
+            hide_zero_flow: true,
+            nodes: Some(vec![0, 2]),
+            ..Default::default()
+        };
+        let expected = "digraph {
+    0;
+    2;
+    0 -> 2 [label=\"1/1\", style=bold];
+}
+";
+        assert_eq!(solver.graph().to_dot(&options), expected);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::control::{Cancelled, SolveControl};
+use crate::graph::{count, Flow, Graph, SolveStats, FLOW_MAX};
+use crate::solver::MaxFlowSolver;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// the side of the search that reached a node
+const UNVISITED: u8 = 0;
+const FORWARD: u8 = 1;
+const BACKWARD: u8 = 2;
+
+// augments along the paths of a breadth first search, O(nm^2)
+// bidirectional searches from both ends at once, a level of the smaller frontier at a time, and stops where they meet:
+// the path is then at most one arc longer than a shortest one, and on graphs that widen with the distance the two
+// halves expand far fewer nodes than one search all the way
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EdmondsKarp {
+    graph: Graph,
+    bidirectional: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stats: SolveStats,
+}
+
+impl EdmondsKarp {
+    pub fn new() -> Self {
+        EdmondsKarp::default()
+    }
+
+    pub fn bidirectional(mut self, bidirectional: bool) -> Self {
+        self.bidirectional = bidirectional;
+        self
+    }
+
+    pub fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow) -> Option<usize> {
+        self.graph.add_directed_edge(from, to, capacity)
+    }
+
+    pub fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.solve_with_control(source, sink, &SolveControl::new())
+            .unwrap_or_else(|cancelled| cancelled.flow_so_far)
+    }
+
+    pub fn solve_with_control(
+        &mut self,
+        source: usize,
+        sink: usize,
+        control: &SolveControl,
+    ) -> Result<Flow, Cancelled> {
+        self.graph.build();
+        self.stats = SolveStats {
+            augmenting_paths: Some(0),
+            visited_nodes: Some(0),
+            ..Default::default()
+        };
+        // a source or sink without incident edges is not part of the graph
+        if source == sink || source >= self.graph.num_nodes || sink >= self.graph.num_nodes {
+            return Ok(0);
+        }
+        // no cut separates them
+        if self.graph.has_unbounded_path(source, sink) {
+            return Ok(FLOW_MAX);
+        }
+
+        let mut flow = 0;
+        loop {
+            control.checkpoint(flow, &self.stats)?;
+            #[cfg(feature = "tracing")]
+            let _span = tracing::trace_span!("augment").entered();
+            let path = if self.bidirectional {
+                self.bidirectional_bfs(source, sink)
+            } else {
+                self.bfs(source, sink)
+            };
+            if path.is_empty() {
+                return Ok(flow);
+            }
+
+            let delta = path
+                .iter()
+                .map(|&(_, i)| self.graph.inside_edge_list[i].residual_capacity())
+                .min()
+                .unwrap();
+            for &(u, i) in path.iter() {
+                self.graph.push_flow(u, i, delta);
+            }
+            count(&mut self.stats.augmenting_paths);
+            flow += delta;
+        }
+    }
+
+    // O(m) the arcs (tail, arc) of a shortest augmenting path, empty if there is none
+    fn bfs(&mut self, source: usize, sink: usize) -> Vec<(usize, usize)> {
+        let mut parent = vec![usize::MAX; self.graph.num_nodes];
+        let mut visited = vec![false; self.graph.num_nodes];
+        let mut queue = vec![source];
+        visited[source] = true;
+        let mut head = 0;
+        while head < queue.len() && !visited[sink] {
+            let u = queue[head];
+            head += 1;
+            count(&mut self.stats.visited_nodes);
+            for i in self.graph.start[u]..self.graph.start[u + 1] {
+                let arc = &self.graph.inside_edge_list[i];
+                if !visited[arc.to] && arc.residual_capacity() > 0 {
+                    visited[arc.to] = true;
+                    parent[arc.to] = i;
+                    queue.push(arc.to);
+                }
+            }
+        }
+        if !visited[sink] {
+            return Vec::new();
+        }
+        self.path_to(source, sink, &parent)
+    }
+
+    // O(m) the same from both ends: parent[v] is the arc into v of the forward search, or out of v of the backward
+    // search, which follows the arcs u -> v with residual capacity through their reverse arcs v -> u in the list of v
+    fn bidirectional_bfs(&mut self, source: usize, sink: usize) -> Vec<(usize, usize)> {
+        let arcs = &self.graph.inside_edge_list;
+        let mut parent = vec![usize::MAX; self.graph.num_nodes];
+        let mut side = vec![UNVISITED; self.graph.num_nodes];
+        side[source] = FORWARD;
+        side[sink] = BACKWARD;
+        let (mut front, mut back) = (vec![source], vec![sink]);
+
+        // (tail, arc) from the forward to the backward search
+        let mut meeting = None;
+        'search: while !front.is_empty() && !back.is_empty() {
+            let mut next = Vec::new();
+            if front.len() <= back.len() {
+                for &u in front.iter() {
+                    count(&mut self.stats.visited_nodes);
+                    let (start, end) = (self.graph.start[u], self.graph.start[u + 1]);
+                    for (i, arc) in (start..end).zip(&arcs[start..end]) {
+                        let v = arc.to;
+                        if arc.residual_capacity() == 0 || side[v] == FORWARD {
+                            continue;
+                        }
+                        if side[v] == BACKWARD {
+                            meeting = Some((u, i));
+                            break 'search;
+                        }
+                        side[v] = FORWARD;
+                        parent[v] = i;
+                        next.push(v);
+                    }
+                }
+                front = next;
+            } else {
+                for &v in back.iter() {
+                    count(&mut self.stats.visited_nodes);
+                    for i in self.graph.start[v]..self.graph.start[v + 1] {
+                        // u -> v
+                        let (u, rev) = (arcs[i].to, arcs[i].rev);
+                        if arcs[rev].residual_capacity() == 0 || side[u] == BACKWARD {
+                            continue;
+                        }
+                        if side[u] == FORWARD {
+                            meeting = Some((u, rev));
+                            break 'search;
+                        }
+                        side[u] = BACKWARD;
+                        parent[u] = rev;
+                        next.push(u);
+                    }
+                }
+                back = next;
+            }
+        }
+
+        let Some((u, i)) = meeting else {
+            return Vec::new();
+        };
+        let mut path = self.path_to(source, u, &parent);
+        path.push((u, i));
+        let mut v = arcs[i].to;
+        while v != sink {
+            let i = parent[v];
+            path.push((v, i));
+            v = arcs[i].to;
+        }
+        path
+    }
+
+    // the arcs (tail, arc) from source to v along the parents of a forward search
+    fn path_to(&self, source: usize, mut v: usize, parent: &[usize]) -> Vec<(usize, usize)> {
+        let mut path = Vec::new();
+        while v != source {
+            let i = parent[v];
+            let u = self.graph.inside_edge_list[self.graph.inside_edge_list[i].rev].to;
+            path.push((u, i));
+            v = u;
+        }
+        path.reverse();
+        path
+    }
+}
+
+impl MaxFlowSolver for EdmondsKarp {
+    fn new() -> Self {
+        EdmondsKarp::new()
+    }
+
+    fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow) -> Option<usize> {
+        self.add_directed_edge(from, to, capacity)
+    }
+
+    fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.solve(source, sink)
+    }
+
+    fn solve_with_control(
+        &mut self,
+        source: usize,
+        sink: usize,
+        control: &SolveControl,
+    ) -> Result<Flow, Cancelled> {
+        self.solve_with_control(source, sink, control)
+    }
+
+    fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    fn stats(&self) -> &SolveStats {
+        self.stats()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::edmonds_karp::EdmondsKarp;
+    use crate::generators::{random_graph, Rng};
+    use crate::graph::Flow;
+    use crate::solver::MaxFlowSolver;
+    use crate::test_utility::{
+        read_expected, read_instance_aoj, read_instance_dimacs, read_instance_libreoj,
+        GraphInstance,
+    };
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn aoj_grl_6_a(#[files("test_cases/AOJ_GRL_6_A/*.in")] path: PathBuf) {
+        let mut expected_file_path = path.clone();
+        expected_file_path.set_extension("out");
+
+        let instance = read_instance_aoj(&path);
+        let expected = read_expected(&expected_file_path);
+        assert_eq!(execute(&instance, false), expected);
+        assert_eq!(execute(&instance, true), expected);
+    }
+
+    #[rstest]
+    fn libreoj_101(#[files("test_cases/LibreOJ_101/*.in")] path: PathBuf) {
+        let mut expected_file_path = path.clone();
+        expected_file_path.set_extension("out");
+
+        let instance = read_instance_libreoj(&path);
+        let expected = read_expected(&expected_file_path);
+        assert_eq!(execute(&instance, false), expected);
+        assert_eq!(execute(&instance, true), expected);
+    }
+
+    #[rstest]
+    fn dimacs(#[files("test_cases/**/*.max")] path: PathBuf) {
+        let mut expected_file_path = path.clone();
+        expected_file_path.set_extension("out");
+
+        let instance = read_instance_dimacs(&path);
+        let expected = read_expected(&expected_file_path);
+        assert_eq!(execute(&instance, false), expected);
+        assert_eq!(execute(&instance, true), expected);
+    }
+
+    #[test]
+    fn random_graphs() {
+        let mut rng = Rng::new(10);
+        for seed in 0..300 {
+            let n = rng.gen_range(2, 15) as usize;
+            let instance = random_graph(n, rng.gen_range(0, 40) as usize, 20, seed);
+            let expected = instance.solve::<Dinic>();
+            for bidirectional in [false, true] {
+                let (flow, solver) = solve(&instance, bidirectional);
+                assert_eq!(flow, expected);
+                let graph = solver.graph();
+                assert_eq!(
+                    graph.validate_flow(instance.source, instance.sink),
+                    Ok(flow)
+                );
+            }
+        }
+    }
+
+    // a grid of width x width with unit capacities both ways, source and sink in the middle rows, a quarter of the
+    // width from the left and the right side
+    fn grid(width: usize) -> GraphInstance {
+        let id = |row: usize, column: usize| row * width + column;
+        let mut edges = Vec::new();
+        for row in 0..width {
+            for column in 0..width {
+                if column + 1 < width {
+                    edges.push((id(row, column), id(row, column + 1), 1));
+                    edges.push((id(row, column + 1), id(row, column), 1));
+                }
+                if row + 1 < width {
+                    edges.push((id(row, column), id(row + 1, column), 1));
+                    edges.push((id(row + 1, column), id(row, column), 1));
+                }
+            }
+        }
+        GraphInstance {
+            num_nodes: width * width,
+            edges,
+            source: id(width / 2, width / 4),
+            sink: id(width / 2, width - 1 - width / 4),
+        }
+    }
+
+    #[test]
+    fn fewer_expansions_on_a_grid() {
+        let instance = grid(80);
+        let mut visited_nodes = Vec::new();
+        for bidirectional in [false, true] {
+            let (flow, solver) = solve(&instance, bidirectional);
+            assert_eq!(flow, 4);
+            assert_eq!(solver.stats().augmenting_paths, Some(4));
+            visited_nodes.push(solver.stats().visited_nodes.unwrap());
+        }
+        assert!(
+            visited_nodes[1] * 3 < visited_nodes[0] * 2,
+            "{:?}",
+            visited_nodes
+        );
+    }
+
+    fn solve(instance: &GraphInstance, bidirectional: bool) -> (Flow, EdmondsKarp) {
+        let mut solver = EdmondsKarp::new().bidirectional(bidirectional);
+        for &(from, to, capacity) in instance.edges.iter() {
+            solver.add_directed_edge(from, to, capacity);
+        }
+        (solver.solve(instance.source, instance.sink), solver)
+    }
+
+    fn execute(instance: &GraphInstance, bidirectional: bool) -> Flow {
+        solve(instance, bidirectional).0
+    }
+}
@@ -0,0 +1,772 @@
+use crate::dinic::Dinic;
+use crate::graph::{Flow, Graph, FLOW_MAX};
+use crate::min_cost_flow::{Cost, MinCostFlow};
+use crate::solver::MaxFlowSolver;
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt;
+
+// flow entering the edge at time t leaves it at time t + transit_time, at most capacity per time step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitEdge {
+    pub from: usize,
+    pub to: usize,
+    pub capacity: Flow,
+    pub transit_time: usize,
+}
+
+// result of a flow over time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlowOverTime {
+    // what reaches the sink within the horizon
+    pub value: Flow,
+    // (edge, t, flow): flow enters the edge at time step t, sorted by t and then edge
+    pub schedule: Vec<(usize, usize, Flow)>,
+}
+
+// the discrete time model: time steps 0..horizon, a copy u_t of every node u for each of them, and a copy
+// u_t -> v_{t + transit_time} of every edge whose head is still within the horizon
+// flow can wait at the source and the sink, and at the other nodes too with waiting(true)
+#[derive(Debug, Clone, Default)]
+pub struct TimeExpandedBuilder {
+    num_nodes: usize,
+    horizon: usize,
+    edges: Vec<TransitEdge>,
+    waiting: bool,
+}
+
+impl TimeExpandedBuilder {
+    pub fn new(num_nodes: usize, horizon: usize) -> Self {
+        TimeExpandedBuilder {
+            num_nodes,
+            horizon,
+            ..Default::default()
+        }
+    }
+
+    // holdover arcs u_t -> u_{t + 1} of unbounded capacity at every node, which don't change the maximum value
+    pub fn waiting(mut self, waiting: bool) -> Self {
+        self.waiting = waiting;
+        self
+    }
+
+    // None for an edge without capacity, like Graph::add_directed_edge
+    pub fn add_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity_per_step: Flow,
+        transit_time: usize,
+    ) -> Option<usize> {
+        if capacity_per_step <= 0 {
+            return None;
+        }
+        self.num_nodes = self.num_nodes.max(from.max(to) + 1);
+        self.edges.push(TransitEdge {
+            from,
+            to,
+            capacity: capacity_per_step,
+            transit_time,
+        });
+        Some(self.edges.len() - 1)
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    pub fn horizon(&self) -> usize {
+        self.horizon
+    }
+
+    pub fn edges(&self) -> &[TransitEdge] {
+        &self.edges
+    }
+
+    // O(m log n) plus the size of the expanded graph
+    // only the copies u_t with a path from the source that arrives by t, and one on to the sink that arrives before
+    // the horizon, are part of the graph: those are the times from the earliest arrival at u to the horizon minus
+    // the shortest transit time to the sink
+    pub fn build(&self, source: usize, sink: usize) -> TimeExpandedNetwork {
+        if source == sink || source >= self.num_nodes || sink >= self.num_nodes {
+            return TimeExpandedNetwork::empty();
+        }
+        self.expand(
+            source,
+            sink,
+            self.horizon,
+            &self.distances(source, sink),
+            FLOW_MAX,
+        )
+    }
+
+    // build and solve
+    pub fn solve(&self, source: usize, sink: usize) -> FlowOverTime {
+        self.build(source, sink).solve()
+    }
+
+    // the shortest transit times don't depend on the horizon, so a search over horizons computes them once
+    fn distances(&self, source: usize, sink: usize) -> Distances {
+        Distances {
+            earliest: self.shortest_transit_times(source, false),
+            to_sink: self.shortest_transit_times(sink, true),
+        }
+    }
+
+    // the expansion for any horizon, supply bounds what leaves the source in total
+    fn expand(
+        &self,
+        source: usize,
+        sink: usize,
+        horizon: usize,
+        distances: &Distances,
+        supply: Flow,
+    ) -> TimeExpandedNetwork {
+        let mut network = TimeExpandedNetwork::empty();
+        let earliest = &distances.earliest;
+        // first copy and number of copies of each node
+        let mut first = vec![0; self.num_nodes];
+        let mut copies = vec![0; self.num_nodes];
+        let mut num_copies = 0;
+        for u in 0..self.num_nodes {
+            let Some(latest) = horizon.checked_sub(distances.to_sink[u].saturating_add(1)) else {
+                continue;
+            };
+            if earliest[u] <= latest {
+                first[u] = num_copies;
+                copies[u] = latest - earliest[u] + 1;
+                num_copies += copies[u];
+            }
+        }
+        if copies[sink] == 0 {
+            return network;
+        }
+        let id = |u: usize, t: usize| first[u] + t - earliest[u];
+
+        for (i, e) in self.edges.iter().enumerate() {
+            if copies[e.from] == 0 || copies[e.to] == 0 {
+                continue;
+            }
+            // the head is reached by earliest[e.from] + transit_time, but may have to leave for the sink earlier
+            let end = (earliest[e.from] + copies[e.from])
+                .min((earliest[e.to] + copies[e.to]).saturating_sub(e.transit_time));
+            for t in earliest[e.from]..end {
+                network.solver.add_directed_edge(
+                    id(e.from, t),
+                    id(e.to, t + e.transit_time),
+                    e.capacity,
+                );
+                network.copies.push((i, t));
+            }
+        }
+        for u in 0..self.num_nodes {
+            if self.waiting || u == source || u == sink {
+                for t in earliest[u]..(earliest[u] + copies[u]).saturating_sub(1) {
+                    network
+                        .solver
+                        .add_directed_edge(id(u, t), id(u, t + 1), FLOW_MAX);
+                }
+            }
+        }
+        network.num_nodes = num_copies;
+        network.source = id(source, 0);
+        network.sink = id(sink, horizon - 1);
+        if supply < FLOW_MAX {
+            // a node of its own in front of the source
+            network
+                .solver
+                .add_directed_edge(num_copies, network.source, supply);
+            network.source = num_copies;
+            network.num_nodes += 1;
+        }
+        network
+    }
+
+    // Dijkstra on the transit times, from the source or, reversed, to the sink, usize::MAX for an unreachable node
+    fn shortest_transit_times(&self, root: usize, reversed: bool) -> Vec<usize> {
+        let mut adjacency = vec![Vec::new(); self.num_nodes];
+        for e in self.edges.iter() {
+            let (from, to) = if reversed {
+                (e.to, e.from)
+            } else {
+                (e.from, e.to)
+            };
+            adjacency[from].push((to, e.transit_time));
+        }
+        let mut distance = vec![usize::MAX; self.num_nodes];
+        let mut heap = BinaryHeap::new();
+        distance[root] = 0;
+        heap.push(Reverse((0, root)));
+        while let Some(Reverse((d, u))) = heap.pop() {
+            if d > distance[u] {
+                continue;
+            }
+            for &(v, transit_time) in adjacency[u].iter() {
+                let d = d.saturating_add(transit_time);
+                if d < distance[v] {
+                    distance[v] = d;
+                    heap.push(Reverse((d, v)));
+                }
+            }
+        }
+        distance
+    }
+}
+
+// the shortest transit times from the source and to the sink, usize::MAX for no path
+struct Distances {
+    earliest: Vec<usize>,
+    to_sink: Vec<usize>,
+}
+
+// the expanded graph of TimeExpandedBuilder::build
+pub struct TimeExpandedNetwork {
+    solver: Dinic,
+    // (edge, t) of each edge of the expanded graph that is a copy, the waiting arcs come after them
+    copies: Vec<(usize, usize)>,
+    num_nodes: usize,
+    // usize::MAX if the sink can't be reached within the horizon
+    source: usize,
+    sink: usize,
+}
+
+impl TimeExpandedNetwork {
+    fn empty() -> Self {
+        TimeExpandedNetwork {
+            solver: Dinic::new(),
+            copies: Vec::new(),
+            num_nodes: 0,
+            source: usize::MAX,
+            sink: usize::MAX,
+        }
+    }
+
+    pub fn graph(&self) -> &Graph {
+        self.solver.graph()
+    }
+
+    // the node copies left after pruning
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.graph().num_edges
+    }
+
+    // O(Dinic on the expanded graph)
+    pub fn solve(&mut self) -> FlowOverTime {
+        if self.source == usize::MAX {
+            return FlowOverTime::default();
+        }
+        let value = self.solver.solve(self.source, self.sink);
+        let graph = self.solver.graph();
+        let mut schedule: Vec<(usize, usize, Flow)> = self
+            .copies
+            .iter()
+            .enumerate()
+            .map(|(i, &(edge, t))| (edge, t, graph.get_directed_edge(i).flow))
+            .filter(|&(_, _, flow)| flow > 0)
+            .collect();
+        schedule.sort_by_key(|&(edge, t, _)| (t, edge));
+        FlowOverTime { value, schedule }
+    }
+}
+
+// returned by quickest_flow for a positive demand when no path leads from the source to the sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unreachable {
+    pub demand: Flow,
+}
+
+impl fmt::Display for Unreachable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no time horizon sends {} units, the sink is unreachable from the source",
+            self.demand
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Unreachable {}
+
+// the smallest horizon within which demand units reach the sink, and a flow over time that sends exactly those
+// the horizon of network is ignored
+// O(log(horizon)) expansions: doubling from the shortest transit time to the sink on, then binary search, with the
+// shortest transit times of the pruning computed once; the flows of one horizon don't carry over to the next, whose
+// expanded graph is another one
+// with all transit times 0 every time step is a copy of the static network, so the horizon is demand over the static
+// maximum flow rounded up, and the schedule sends along its paths at each step until the demand is met
+pub fn quickest_flow(
+    network: &TimeExpandedBuilder,
+    source: usize,
+    sink: usize,
+    demand: Flow,
+) -> Result<(usize, FlowOverTime), Unreachable> {
+    if demand <= 0 {
+        return Ok((0, FlowOverTime::default()));
+    }
+    let unreachable = Unreachable { demand };
+    if source == sink || source >= network.num_nodes || sink >= network.num_nodes {
+        return Err(unreachable);
+    }
+    let distances = network.distances(source, sink);
+    let shortest = distances.earliest[sink];
+    if shortest == usize::MAX {
+        return Err(unreachable);
+    }
+    if network.edges.iter().all(|e| e.transit_time == 0) {
+        return Ok(repeated_static_flow(network, source, sink, demand));
+    }
+
+    let solve = |horizon: usize| {
+        network
+            .expand(source, sink, horizon, &distances, demand)
+            .solve()
+    };
+    // every step after the first shortest + 1 sends at least one more unit along a shortest path
+    let mut low = shortest + 1;
+    let mut high = low;
+    let mut best = solve(high);
+    while best.value < demand {
+        low = high + 1;
+        high *= 2;
+        best = solve(high);
+    }
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let result = solve(middle);
+        if result.value < demand {
+            low = middle + 1;
+        } else {
+            high = middle;
+            best = result;
+        }
+    }
+    Ok((high, best))
+}
+
+fn repeated_static_flow(
+    network: &TimeExpandedBuilder,
+    source: usize,
+    sink: usize,
+    demand: Flow,
+) -> (usize, FlowOverTime) {
+    let mut solver = Dinic::new();
+    for e in network.edges.iter() {
+        solver.add_directed_edge(e.from, e.to, e.capacity);
+    }
+    let value = solver.solve(source, sink);
+    let horizon = demand / value + (demand % value != 0) as Flow;
+    let paths = solver.graph().decompose_flow(source, sink);
+
+    let mut schedule = Vec::new();
+    let mut sent = 0;
+    let mut flow = vec![0; network.edges.len()];
+    for t in 0..horizon as usize {
+        for (edges, path_flow) in paths.iter() {
+            let delta = (*path_flow).min(demand - sent);
+            for &edge in edges.iter() {
+                flow[edge] += delta;
+            }
+            sent += delta;
+        }
+        for (edge, flow) in flow.iter_mut().enumerate() {
+            if *flow > 0 {
+                schedule.push((edge, t, *flow));
+                *flow = 0;
+            }
+        }
+    }
+    (
+        horizon as usize,
+        FlowOverTime {
+            value: demand,
+            schedule,
+        },
+    )
+}
+
+// a path of a temporally repeated flow: flow units leave the source along it at every time step from 0 to
+// departures - 1, and enter each of its edges (edge, offset) offset steps after they left
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepeatedPath {
+    pub edges: Vec<(usize, usize)>,
+    pub flow: Flow,
+    pub departures: usize,
+}
+
+// the result of max_flow_over_time, its paths are all that is kept of the schedule
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemporallyRepeatedFlow {
+    pub value: Flow,
+    pub paths: Vec<RepeatedPath>,
+}
+
+impl TemporallyRepeatedFlow {
+    // O(the edges of the paths times their departures), the size of the schedule
+    pub fn schedule(&self) -> FlowOverTime {
+        let mut schedule = Vec::new();
+        for path in self.paths.iter() {
+            for t in 0..path.departures {
+                for &(edge, offset) in path.edges.iter() {
+                    schedule.push((edge, t + offset, path.flow));
+                }
+            }
+        }
+        schedule.sort_unstable_by_key(|&(edge, t, _)| (t, edge));
+        let mut merged: Vec<(usize, usize, Flow)> = Vec::with_capacity(schedule.len());
+        for (edge, t, flow) in schedule {
+            match merged.last_mut() {
+                Some(last) if (last.0, last.1) == (edge, t) => last.2 += flow,
+                _ => merged.push((edge, t, flow)),
+            }
+        }
+        FlowOverTime {
+            value: self.value,
+            schedule: merged,
+        }
+    }
+}
+
+// the maximum flow over time of Ford and Fulkerson, without the time expansion: a static flow x that maximizes
+// horizon * |x| minus the sum of transit_time * x_e, sent again along each path of its decomposition at every step
+// that still arrives before the horizon
+// the static flow is a minimum cost flow with the transit times as costs, of the shortest paths that take less than
+// the horizon; its cost doesn't depend on the horizon, so a horizon of 10^6 is no harder than one of 10
+// the horizon of network is ignored, and so is waiting, which a temporally repeated flow doesn't need
+pub fn max_flow_over_time(
+    network: &TimeExpandedBuilder,
+    source: usize,
+    sink: usize,
+    horizon: usize,
+) -> TemporallyRepeatedFlow {
+    let mut solver = MinCostFlow::new();
+    for e in network.edges.iter() {
+        solver.add_directed_edge(e.from, e.to, e.capacity, e.transit_time as Cost);
+    }
+    let horizon_cost = Cost::try_from(horizon).unwrap_or(Cost::MAX);
+    solver.solve_with_limit(source, sink, Flow::MAX, horizon_cost);
+
+    let mut result = TemporallyRepeatedFlow::default();
+    for (edges, flow) in solver.decompose_flow(source, sink) {
+        let mut transit_time = 0;
+        let edges: Vec<(usize, usize)> = edges
+            .into_iter()
+            .map(|edge| {
+                let offset = transit_time;
+                transit_time += network.edges[edge].transit_time;
+                (edge, offset)
+            })
+            .collect();
+        let departures = horizon.saturating_sub(transit_time);
+        if departures > 0 {
+            result.value += flow * departures as Flow;
+            result.paths.push(RepeatedPath {
+                edges,
+                flow,
+                departures,
+            });
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::flows_over_time::{
+        max_flow_over_time, quickest_flow, FlowOverTime, RepeatedPath, TimeExpandedBuilder,
+        Unreachable,
+    };
+    use crate::generators::Rng;
+    use crate::graph::{Flow, FLOW_MAX};
+
+    // s = 0, a = 1, t = 2: 1 unit per step along s -> a -> t (2 steps) and 1 along s -> t (3 steps)
+    fn example(horizon: usize) -> TimeExpandedBuilder {
+        let mut builder = TimeExpandedBuilder::new(3, horizon);
+        builder.add_edge(0, 1, 2, 1);
+        builder.add_edge(1, 2, 1, 1);
+        builder.add_edge(0, 2, 1, 3);
+        builder
+    }
+
+    // flow enters and leaves the edges within the horizon and within capacity, and every node but source and sink
+    // sends on what it receives, at once without waiting arcs
+    fn check_schedule(
+        builder: &TimeExpandedBuilder,
+        source: usize,
+        sink: usize,
+        result: &FlowOverTime,
+    ) {
+        let horizon = builder.horizon();
+        let mut balance = vec![vec![0 as Flow; horizon]; builder.num_nodes()];
+        for &(edge, t, flow) in result.schedule.iter() {
+            let e = builder.edges()[edge];
+            assert!(0 < flow && flow <= e.capacity);
+            assert!(t + e.transit_time < horizon);
+            balance[e.from][t] -= flow;
+            balance[e.to][t + e.transit_time] += flow;
+        }
+        for (u, balance) in balance.iter().enumerate() {
+            let mut stored = 0;
+            for (t, &b) in balance.iter().enumerate() {
+                stored += b;
+                if u != source && u != sink {
+                    assert!(
+                        stored >= 0,
+                        "node {} sends flow it hasn't received at {}",
+                        u,
+                        t
+                    );
+                    if !builder.waiting {
+                        assert_eq!(stored, 0, "node {} holds flow at {}", u, t);
+                    }
+                }
+            }
+            if u == sink {
+                assert_eq!(stored, result.value);
+            } else if u != source {
+                assert_eq!(stored, 0);
+            }
+        }
+    }
+
+    // every copy of every node and edge, with waiting arcs everywhere
+    fn full_expansion(
+        builder: &TimeExpandedBuilder,
+        source: usize,
+        sink: usize,
+        horizon: usize,
+    ) -> Flow {
+        if horizon == 0 {
+            return 0;
+        }
+        let id = |u: usize, t: usize| u * horizon + t;
+        let mut solver = Dinic::new();
+        for e in builder.edges() {
+            for t in 0..horizon.saturating_sub(e.transit_time) {
+                solver.add_directed_edge(id(e.from, t), id(e.to, t + e.transit_time), e.capacity);
+            }
+        }
+        for u in 0..builder.num_nodes() {
+            for t in 1..horizon {
+                solver.add_directed_edge(id(u, t - 1), id(u, t), FLOW_MAX);
+            }
+        }
+        solver.solve(id(source, 0), id(sink, horizon - 1))
+    }
+
+    #[test]
+    fn known_value() {
+        // s -> a -> t carries 1 unit for the 3 departures 0, 1, 2 and s -> t for 0 and 1 of a horizon of 5
+        for (horizon, value) in [(0, 0), (2, 0), (3, 1), (4, 3), (5, 5), (10, 15)] {
+            for waiting in [false, true] {
+                let builder = example(horizon).waiting(waiting);
+                let result = builder.solve(0, 2);
+                assert_eq!(result.value, value, "horizon {}", horizon);
+                check_schedule(&builder, 0, 2, &result);
+            }
+        }
+        let result = example(4).solve(0, 2);
+        assert_eq!(
+            result.schedule,
+            vec![(0, 0, 1), (2, 0, 1), (0, 1, 1), (1, 1, 1), (1, 2, 1)]
+        );
+    }
+
+    #[test]
+    fn pruned() {
+        // a dead end 3 and a node 4 that is too far from the source to be of use
+        let mut builder = example(5);
+        builder.add_edge(1, 3, 5, 1);
+        builder.add_edge(0, 4, 5, 4);
+        builder.add_edge(4, 2, 5, 1);
+        let mut network = builder.build(0, 2);
+        // s at 0..=2, a at 1..=3, t at 2..=4
+        assert_eq!(network.num_nodes(), 9);
+        assert_eq!(network.solve().value, 5);
+        // towards 3, t and 4 are the dead ends
+        assert_eq!(builder.build(0, 3).num_nodes(), 9);
+        assert_eq!(builder.build(2, 0).solve(), FlowOverTime::default());
+    }
+
+    #[test]
+    fn zero_transit_times() {
+        // every time step is a copy of the static graph
+        let mut builder = TimeExpandedBuilder::new(4, 3);
+        for (from, to, capacity) in [(0, 1, 3), (0, 2, 2), (1, 2, 1), (1, 3, 2), (2, 3, 3)] {
+            builder.add_edge(from, to, capacity, 0);
+        }
+        assert_eq!(builder.solve(0, 3).value, 3 * 5);
+        assert_eq!(builder.add_edge(0, 3, 0, 0), None);
+    }
+
+    fn random_network(rng: &mut Rng, n: usize, horizon: usize) -> TimeExpandedBuilder {
+        let mut builder = TimeExpandedBuilder::new(n, horizon);
+        for _ in 0..rng.gen_range(0, 15) {
+            let from = rng.gen_range(0, n as u64) as usize;
+            let to = rng.gen_range(0, n as u64) as usize;
+            builder.add_edge(from, to, rng.gen_capacity(5), rng.gen_range(0, 4) as usize);
+        }
+        builder
+    }
+
+    #[test]
+    fn matches_full_expansion() {
+        let mut rng = Rng::new(7);
+        for _ in 0..100 {
+            let n = rng.gen_range(2, 7) as usize;
+            let horizon = rng.gen_range(1, 9) as usize;
+            let builder = random_network(&mut rng, n, horizon);
+            let expected = full_expansion(&builder, 0, n - 1, horizon);
+            for waiting in [false, true] {
+                let builder = builder.clone().waiting(waiting);
+                let result = builder.solve(0, n - 1);
+                assert_eq!(result.value, expected, "{:?}", builder);
+                check_schedule(&builder, 0, n - 1, &result);
+            }
+        }
+    }
+
+    // the smallest horizon whose full expansion sends the demand
+    fn brute_force_quickest(builder: &TimeExpandedBuilder, demand: Flow) -> usize {
+        let sink = builder.num_nodes() - 1;
+        (0..)
+            .find(|&horizon| full_expansion(builder, 0, sink, horizon) >= demand)
+            .unwrap()
+    }
+
+    // the schedule of quickest_flow fits into its horizon and sends exactly the demand
+    fn check_quickest(builder: &TimeExpandedBuilder, demand: Flow) -> usize {
+        let sink = builder.num_nodes() - 1;
+        let (horizon, result) = quickest_flow(builder, 0, sink, demand).unwrap();
+        assert_eq!(result.value, demand);
+        let mut expanded = builder.clone();
+        expanded.horizon = horizon;
+        check_schedule(&expanded, 0, sink, &result);
+        horizon
+    }
+
+    #[test]
+    fn quickest() {
+        // the horizons 3, 4 and 5 send 1, 3 and 5 units, and every step after 2 more
+        let builder = example(0);
+        for (demand, horizon) in [
+            (0, 0),
+            (1, 3),
+            (2, 4),
+            (3, 4),
+            (4, 5),
+            (5, 5),
+            (6, 6),
+            (15, 10),
+            (16, 11),
+        ] {
+            let (t, result) = quickest_flow(&builder, 0, 2, demand).unwrap();
+            assert_eq!((t, result.value), (horizon, demand), "demand {}", demand);
+            assert_eq!(check_quickest(&builder, demand), horizon);
+        }
+        assert_eq!(
+            quickest_flow(&builder, 2, 0, 3),
+            Err(Unreachable { demand: 3 })
+        );
+        assert_eq!(quickest_flow(&builder, 2, 0, 0).unwrap().0, 0);
+        assert_eq!(
+            quickest_flow(&builder, 0, 0, 3),
+            Err(Unreachable { demand: 3 })
+        );
+    }
+
+    #[test]
+    fn quickest_zero_transit_times() {
+        // a static maximum flow of 5 per step
+        let mut builder = TimeExpandedBuilder::new(4, 0);
+        for (from, to, capacity) in [(0, 1, 3), (0, 2, 2), (1, 2, 1), (1, 3, 2), (2, 3, 3)] {
+            builder.add_edge(from, to, capacity, 0);
+        }
+        for (demand, horizon) in [(1, 1), (5, 1), (6, 2), (16, 4), (1000, 200)] {
+            assert_eq!(check_quickest(&builder, demand), horizon);
+            if horizon < 10 {
+                assert_eq!(brute_force_quickest(&builder, demand), horizon);
+            }
+        }
+    }
+
+    #[test]
+    fn quickest_matches_brute_force() {
+        let mut rng = Rng::new(11);
+        for _ in 0..100 {
+            let n = rng.gen_range(2, 6) as usize;
+            let mut builder = TimeExpandedBuilder::new(n, 0);
+            // a path from the source to the sink, so that any demand is met eventually
+            builder.add_edge(0, n - 1, 1, rng.gen_range(0, 4) as usize);
+            for _ in 0..rng.gen_range(0, 10) {
+                let from = rng.gen_range(0, n as u64) as usize;
+                let to = rng.gen_range(0, n as u64) as usize;
+                builder.add_edge(from, to, rng.gen_capacity(4), rng.gen_range(0, 4) as usize);
+            }
+            let demand = rng.gen_range(1, 30) as Flow;
+            for waiting in [false, true] {
+                let builder = builder.clone().waiting(waiting);
+                assert_eq!(
+                    check_quickest(&builder, demand),
+                    brute_force_quickest(&builder, demand),
+                    "{:?} {}",
+                    builder,
+                    demand
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn temporally_repeated() {
+        let builder = example(0);
+        for horizon in [0, 2, 3, 4, 5, 10] {
+            let repeated = max_flow_over_time(&builder, 0, 2, horizon);
+            let mut expanded = builder.clone();
+            expanded.horizon = horizon;
+            let result = repeated.schedule();
+            assert_eq!(result.value, expanded.solve(0, 2).value);
+            check_schedule(&expanded, 0, 2, &result);
+        }
+        // s -> a -> t leaves at 0, 1 and 2 and s -> t at 0 and 1
+        let mut paths = max_flow_over_time(&builder, 0, 2, 5).paths;
+        paths.sort_by_key(|p| p.edges.clone());
+        let path = |edges: Vec<(usize, usize)>, departures: usize| RepeatedPath {
+            edges,
+            flow: 1,
+            departures,
+        };
+        assert_eq!(
+            paths,
+            vec![path(vec![(0, 0), (1, 1)], 3), path(vec![(2, 0)], 2)]
+        );
+
+        // the expansion would have 3 * 10^6 nodes
+        let horizon = 1_000_000;
+        assert_eq!(
+            max_flow_over_time(&builder, 0, 2, horizon).value,
+            2 * horizon as Flow - 5
+        );
+        assert_eq!(max_flow_over_time(&builder, 2, 0, horizon).value, 0);
+    }
+
+    #[test]
+    fn temporally_repeated_matches_time_expansion() {
+        let mut rng = Rng::new(13);
+        for _ in 0..200 {
+            let n = rng.gen_range(2, 7) as usize;
+            let horizon = rng.gen_range(0, 12) as usize;
+            let builder = random_network(&mut rng, n, horizon);
+            let result = max_flow_over_time(&builder, 0, n - 1, horizon).schedule();
+            assert_eq!(result.value, builder.solve(0, n - 1).value, "{:?}", builder);
+            check_schedule(&builder, 0, n - 1, &result);
+        }
+    }
+}
@@ -1,8 +1,15 @@
-use crate::graph::{Flow, Graph};
+use crate::control::{Cancelled, SolveControl};
+use crate::graph::{count, Flow, Graph, SolveStats, FLOW_MAX};
+use crate::solver::MaxFlowSolver;
+use alloc::vec;
+use alloc::vec::Vec;
 
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FordFulkerson {
     graph: Graph,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stats: SolveStats,
 }
 
 impl FordFulkerson {
@@ -14,19 +21,47 @@ impl FordFulkerson {
         self.graph.add_directed_edge(from, to, capacity)
     }
 
+    pub fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
     pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.solve_with_control(source, sink, &SolveControl::new())
+            .unwrap_or_else(|cancelled| cancelled.flow_so_far)
+    }
+
+    pub fn solve_with_control(
+        &mut self,
+        source: usize,
+        sink: usize,
+        control: &SolveControl,
+    ) -> Result<Flow, Cancelled> {
         self.graph.build();
-        if source == sink || self.graph.num_nodes == 0 || self.graph.num_edges == 0 {
-            return 0;
+        self.stats = SolveStats {
+            augmenting_paths: Some(0),
+            visited_nodes: Some(0),
+            ..Default::default()
+        };
+        // a source or sink without incident edges is not part of the graph
+        if source == sink || source >= self.graph.num_nodes || sink >= self.graph.num_nodes {
+            return Ok(0);
+        }
+        // no cut separates them
+        if self.graph.has_unbounded_path(source, sink) {
+            return Ok(FLOW_MAX);
         }
 
         let mut flow = 0;
         loop {
+            control.checkpoint(flow, &self.stats)?;
+            #[cfg(feature = "tracing")]
+            let _span = tracing::trace_span!("augment").entered();
             let mut used = vec![false; self.graph.num_nodes];
             let delta = self.dfs(source, sink, Flow::MAX, &mut used);
             if delta == 0 {
-                return flow;
+                return Ok(flow);
             }
+            count(&mut self.stats.augmenting_paths);
             flow += delta;
         }
     }
@@ -36,6 +71,7 @@ impl FordFulkerson {
             return flow;
         }
         visited[u] = true;
+        count(&mut self.stats.visited_nodes);
 
         for i in self.graph.start[u]..self.graph.start[u + 1] {
             let to = self.graph.inside_edge_list[i].to;
@@ -54,11 +90,43 @@ impl FordFulkerson {
     }
 }
 
+impl MaxFlowSolver for FordFulkerson {
+    fn new() -> Self {
+        FordFulkerson::new()
+    }
+
+    fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow) -> Option<usize> {
+        self.add_directed_edge(from, to, capacity)
+    }
+
+    fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.solve(source, sink)
+    }
+
+    fn solve_with_control(
+        &mut self,
+        source: usize,
+        sink: usize,
+        control: &SolveControl,
+    ) -> Result<Flow, Cancelled> {
+        self.solve_with_control(source, sink, control)
+    }
+
+    fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    fn stats(&self) -> &SolveStats {
+        self.stats()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::ford_fulkerson::{Flow, FordFulkerson};
     use crate::test_utility::{
-        read_expected, read_instance_aoj, read_instance_libreoj, GraphInstance,
+        read_expected, read_instance_aoj, read_instance_dimacs, read_instance_libreoj,
+        GraphInstance,
     };
     use rstest::*;
     use std::path::PathBuf;
@@ -83,6 +151,29 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[rstest]
+    fn dimacs(#[files("test_cases/**/*.max")] path: PathBuf) {
+        let mut expected_file_path = path.clone();
+        expected_file_path.set_extension("out");
+
+        let actual = execute(read_instance_dimacs(&path));
+        let expected = read_expected(&expected_file_path);
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    fn stats(#[files("test_cases/AOJ_GRL_6_A/*.in")] path: PathBuf) {
+        let instance = read_instance_aoj(&path);
+        let mut solver: FordFulkerson = instance.build_solver();
+        let flow = solver.solve(instance.source, instance.sink);
+        let stats = solver.stats();
+        let augmenting_paths = stats.augmenting_paths.unwrap();
+        assert!(augmenting_paths as Flow <= flow);
+        assert!(stats.visited_nodes.unwrap() <= (augmenting_paths + 1) * instance.num_nodes);
+        assert_eq!(stats.phases, None);
+        assert_eq!(stats.relabels, None);
+    }
+
     fn execute(instance: GraphInstance) -> Flow {
         let mut solver = FordFulkerson::new();
         for (from, to, capacity) in instance.edges {
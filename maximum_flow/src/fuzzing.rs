@@ -0,0 +1,186 @@
+// harness functions of the fuzz targets in fuzz/, kept here so the normal test suite runs them too
+use crate::capacity_scaling::CapacityScaling;
+use crate::dinic::Dinic;
+use crate::ford_fulkerson::FordFulkerson;
+use crate::graph::Flow;
+use crate::instances::GraphInstance;
+use crate::io::{
+    read_aoj, read_binary, read_dimacs_max, read_edge_list, read_libreoj, EdgeListOptions,
+};
+use crate::push_relabel_fifo::PushRelabelFIFO;
+use crate::push_relabel_highest_label::PushRelabelHighestLabel;
+use crate::solver::MaxFlowSolver;
+
+// operation budget of one input, so that every input finishes quickly
+pub const MAX_EDGES: usize = 1024;
+pub const MAX_SOLVES: usize = 8;
+
+const ADD_EDGE: u8 = 0;
+const SOLVE: u8 = 1;
+
+// every parser must return an error on malformed input, never panic
+pub fn parse_instances(data: &[u8]) {
+    let _ = read_aoj(data);
+    let _ = read_libreoj(data);
+    let _ = read_dimacs_max(data);
+    let _ = read_binary(data);
+    let _ = read_edge_list(data, &EdgeListOptions::default());
+    let _ = read_edge_list(data, &EdgeListOptions::csv());
+}
+
+// byte 0 selects the solver, followed by a sequence of operations
+//   even byte, from (u8), to (u8), capacity (i16 le): add_directed_edge
+//   odd byte, source (u8), sink (u8): solve
+// a truncated operation ends the sequence
+pub fn solver_operations(data: &[u8]) {
+    let Some((&algorithm, operations)) = data.split_first() else {
+        return;
+    };
+    match algorithm % 5 {
+        0 => run::<Dinic>(operations, true),
+        1 => run::<FordFulkerson>(operations, true),
+        2 => run::<CapacityScaling>(operations, true),
+        3 => run::<PushRelabelFIFO>(operations, false),
+        _ => run::<PushRelabelHighestLabel>(operations, false),
+    }
+}
+
+// every result is checked against Dinic, and against validate_flow if the solver leaves a flow
+fn run<S: MaxFlowSolver>(mut operations: &[u8], true_flow: bool) {
+    let mut solver = S::new();
+    let mut edges = Vec::new();
+    let mut num_solves = 0;
+    while let Some((&operation, rest)) = operations.split_first() {
+        if operation % 2 == ADD_EDGE {
+            let &[from, to, c0, c1, ..] = rest else {
+                return;
+            };
+            operations = &rest[4..];
+            if edges.len() == MAX_EDGES {
+                continue;
+            }
+            let (from, to) = (from as usize, to as usize);
+            let capacity = i16::from_le_bytes([c0, c1]) as Flow;
+            let index = solver.add_directed_edge(from, to, capacity);
+            assert_eq!(index.is_some(), capacity > 0);
+            if index.is_some() {
+                edges.push((from, to, capacity));
+            }
+        } else {
+            debug_assert_eq!(operation % 2, SOLVE);
+            let &[source, sink, ..] = rest else {
+                return;
+            };
+            operations = &rest[2..];
+            if num_solves == MAX_SOLVES {
+                continue;
+            }
+            num_solves += 1;
+            let (source, sink) = (source as usize, sink as usize);
+            let flow = solver.solve(source, sink);
+
+            let mut reference = Dinic::new();
+            for &(from, to, capacity) in edges.iter() {
+                reference.add_directed_edge(from, to, capacity);
+            }
+            assert_eq!(flow, reference.solve(source, sink));
+            if true_flow && source != sink {
+                assert_eq!(solver.graph().validate_flow(source, sink), Ok(flow));
+            }
+        }
+    }
+}
+
+// None if a node id or a capacity doesn't fit the encoding
+pub fn encode_instance(algorithm: u8, instance: &GraphInstance) -> Option<Vec<u8>> {
+    let node = |u: usize| u8::try_from(u).ok();
+    let mut data = vec![algorithm];
+    for &(from, to, capacity) in instance.edges.iter() {
+        data.extend([ADD_EDGE, node(from)?, node(to)?]);
+        data.extend(i16::try_from(capacity).ok()?.to_le_bytes());
+    }
+    data.extend([SOLVE, node(instance.source)?, node(instance.sink)?]);
+    Some(data)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fuzzing::{encode_instance, parse_instances, solver_operations};
+    use crate::instances::GraphInstance;
+    use crate::io::write_binary;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn test_cases(
+        #[files("test_cases/AOJ_GRL_6_A/*.in")]
+        #[files("test_cases/DIMACS/*.max")]
+        path: PathBuf,
+    ) {
+        let data = std::fs::read(&path).unwrap();
+        parse_instances(&data);
+        parse_instances(&data[..data.len() / 2]);
+    }
+
+    #[test]
+    fn malformed_inputs() {
+        let mut huge_header = Vec::new();
+        let instance = GraphInstance {
+            num_nodes: 2,
+            edges: vec![(0, 1, 1)],
+            source: 0,
+            sink: 1,
+        };
+        write_binary(&mut huge_header, &instance).unwrap();
+        huge_header[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        for data in [
+            &b""[..],
+            b"\xff\xfe\n\x00",
+            b"18446744073709551615 0 1\n",
+            b"from,to,capacity\n0,18446744073709551615,1\n",
+            b"p max 3 1\nn 1 s\nn 3 t\na 1 4 5\n",
+            &huge_header,
+        ] {
+            parse_instances(data);
+        }
+    }
+
+    #[test]
+    fn operations() {
+        for data in [
+            &b""[..],
+            &[0],
+            // truncated edge
+            &[2, 0, 0, 1],
+            // zero and negative capacities, self loop, solve with source == sink
+            &[
+                1, 0, 0, 1, 0, 0, 0, 2, 3, 0, 0xff, 0, 4, 4, 7, 0, 1, 4, 4, 1, 0, 3,
+            ],
+            // solve, add an edge, solve again
+            &[3, 0, 0, 1, 5, 0, 1, 0, 1, 0, 1, 2, 9, 0, 1, 0, 2],
+            &[
+                4, 0, 0, 1, 0xff, 0x7f, 0, 1, 2, 0xff, 0x7f, 1, 0, 2, 1, 9, 0,
+            ],
+        ] {
+            solver_operations(data);
+        }
+    }
+
+    #[test]
+    fn encoded_sample() {
+        let instance = GraphInstance {
+            num_nodes: 4,
+            edges: vec![(0, 1, 2), (0, 2, 1), (1, 2, 1), (1, 3, 1), (2, 3, 2)],
+            source: 0,
+            sink: 3,
+        };
+        for algorithm in 0..5 {
+            solver_operations(&encode_instance(algorithm, &instance).unwrap());
+        }
+
+        let mut instance = instance;
+        instance.edges.push((0, 300, 1));
+        assert_eq!(encode_instance(0, &instance), None);
+    }
+}
@@ -0,0 +1,441 @@
+use crate::graph::Flow;
+use crate::instances::GraphInstance;
+
+// SplitMix64, small and good enough for generating test instances (not for cryptography)
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [low, high)
+    pub fn gen_range(&mut self, low: u64, high: u64) -> u64 {
+        assert!(low < high);
+        // rejection sampling to avoid modulo bias
+        let range = high - low;
+        let zone = u64::MAX - u64::MAX % range;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return low + x % range;
+            }
+        }
+    }
+
+    // uniform in [0, 1)
+    pub fn gen_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn gen_bool(&mut self, p: f64) -> bool {
+        self.gen_f64() < p
+    }
+
+    pub fn gen_capacity(&mut self, max_capacity: Flow) -> Flow {
+        assert!(max_capacity >= 1);
+        self.gen_range(1, max_capacity as u64 + 1) as Flow
+    }
+}
+
+// n nodes, m edges between distinct random endpoints with capacities in 1..=max_capacity
+// source = 0, sink = n - 1
+pub fn random_graph(n: usize, m: usize, max_capacity: Flow, seed: u64) -> GraphInstance {
+    assert!(n >= 2);
+    let mut rng = Rng::new(seed);
+    let edges = (0..m)
+        .map(|_| {
+            let from = rng.gen_range(0, n as u64) as usize;
+            // shift to skip self loops
+            let mut to = rng.gen_range(0, n as u64 - 1) as usize;
+            if to >= from {
+                to += 1;
+            }
+            (from, to, rng.gen_capacity(max_capacity))
+        })
+        .collect();
+
+    GraphInstance {
+        num_nodes: n,
+        edges,
+        source: 0,
+        sink: n - 1,
+    }
+}
+
+// bipartite matching instance with unit capacities, each left-right edge exists with probability p
+// source = 0, left = 1..=l, right = l + 1..=l + r, sink = l + r + 1
+pub fn random_bipartite(l: usize, r: usize, p: f64, seed: u64) -> GraphInstance {
+    let mut rng = Rng::new(seed);
+    let source = 0;
+    let sink = l + r + 1;
+
+    let mut edges = Vec::new();
+    for u in 1..=l {
+        edges.push((source, u, 1));
+    }
+    for u in 1..=l {
+        for v in l + 1..=l + r {
+            if rng.gen_bool(p) {
+                edges.push((u, v, 1));
+            }
+        }
+    }
+    for v in l + 1..=l + r {
+        edges.push((v, sink, 1));
+    }
+
+    GraphInstance {
+        num_nodes: l + r + 2,
+        edges,
+        source,
+        sink,
+    }
+}
+
+// node ids are a topological order: every edge goes from a smaller id to a larger one
+// source = 0, sink = n - 1
+pub fn random_dag(n: usize, m: usize, max_capacity: Flow, seed: u64) -> GraphInstance {
+    assert!(n >= 2);
+    let mut rng = Rng::new(seed);
+    let edges = (0..m)
+        .map(|_| {
+            let a = rng.gen_range(0, n as u64) as usize;
+            let mut b = rng.gen_range(0, n as u64 - 1) as usize;
+            if b >= a {
+                b += 1;
+            }
+            (a.min(b), a.max(b), rng.gen_capacity(max_capacity))
+        })
+        .collect();
+
+    GraphInstance {
+        num_nodes: n,
+        edges,
+        source: 0,
+        sink: n - 1,
+    }
+}
+
+// genrmf (Goldfarb and Grigoriadis): b frames, each an a x a grid
+// grid neighbors are joined in both directions with capacity c2 * a * a, and the nodes of frame k
+// are joined to a random permutation of frame k + 1 with capacities in c1..=c2
+// node (frame, row, col) = frame * a * a + row * a + col, source = 0, sink = a * a * b - 1
+// with c1 == c2 == c and b >= 2 the maximum flow is a * a * c
+pub fn genrmf(a: usize, b: usize, c1: Flow, c2: Flow, seed: u64) -> GraphInstance {
+    assert!(a >= 1 && b >= 1 && a * a * b >= 2);
+    assert!(1 <= c1 && c1 <= c2);
+    let mut rng = Rng::new(seed);
+    let frame_size = a * a;
+    let id = |frame: usize, row: usize, col: usize| frame * frame_size + row * a + col;
+
+    let mut edges = Vec::new();
+    for frame in 0..b {
+        for row in 0..a {
+            for col in 0..a {
+                let u = id(frame, row, col);
+                if col + 1 < a {
+                    edges.push((u, id(frame, row, col + 1), c2 * frame_size as Flow));
+                    edges.push((id(frame, row, col + 1), u, c2 * frame_size as Flow));
+                }
+                if row + 1 < a {
+                    edges.push((u, id(frame, row + 1, col), c2 * frame_size as Flow));
+                    edges.push((id(frame, row + 1, col), u, c2 * frame_size as Flow));
+                }
+            }
+        }
+        if frame + 1 < b {
+            // Fisher-Yates
+            let mut permutation: Vec<usize> = (0..frame_size).collect();
+            for i in (1..frame_size).rev() {
+                permutation.swap(i, rng.gen_range(0, i as u64 + 1) as usize);
+            }
+            for (i, &j) in permutation.iter().enumerate() {
+                let capacity = rng.gen_range(c1 as u64, c2 as u64 + 1) as Flow;
+                edges.push((
+                    frame * frame_size + i,
+                    (frame + 1) * frame_size + j,
+                    capacity,
+                ));
+            }
+        }
+    }
+
+    GraphInstance {
+        num_nodes: frame_size * b,
+        edges,
+        source: 0,
+        sink: frame_size * b - 1,
+    }
+}
+
+// families with the shapes of R. Anderson's Washington generator (the ids are this crate's own)
+//   1 mesh [rows, cols, max_capacity]: node (row, col) feeds rows row - 1, row, row + 1 (cyclic)
+//     of column col + 1
+//   2 random level [rows, cols, max_capacity]: every node feeds 3 random nodes of the next column
+//   3 line [length, lines, max_capacity]: `lines` disjoint paths of `length` edges
+//   4 cycle [n, max_capacity]: a ring of n nodes with an edge in each direction,
+//     source = 0, sink = n / 2
+// for 1 and 2, node (row, col) = 1 + col * rows + row, source = 0 feeds column 0,
+// the last column feeds sink = rows * cols + 1, and these edges never limit the flow
+// inner capacities are in 1..=max_capacity, returns None for an unknown id or wrong arguments
+pub fn washington(function_id: u32, args: &[usize], seed: u64) -> Option<GraphInstance> {
+    let mut rng = Rng::new(seed);
+    match (function_id, args) {
+        (1 | 2, &[rows, cols, max_capacity]) if rows >= 1 && cols >= 1 && max_capacity >= 1 => {
+            let max_capacity = max_capacity as Flow;
+            let node = |row: usize, col: usize| 1 + col * rows + row;
+            let sink = rows * cols + 1;
+            let unlimited = 3 * rows as Flow * max_capacity;
+
+            let mut edges = Vec::new();
+            for row in 0..rows {
+                edges.push((0, node(row, 0), unlimited));
+            }
+            for col in 0..cols - 1 {
+                for row in 0..rows {
+                    for k in 0..3 {
+                        let next = match function_id {
+                            1 => (row + rows + k - 1) % rows,
+                            _ => rng.gen_range(0, rows as u64) as usize,
+                        };
+                        let capacity = rng.gen_capacity(max_capacity);
+                        edges.push((node(row, col), node(next, col + 1), capacity));
+                    }
+                }
+            }
+            for row in 0..rows {
+                edges.push((node(row, cols - 1), sink, unlimited));
+            }
+
+            Some(GraphInstance {
+                num_nodes: rows * cols + 2,
+                edges,
+                source: 0,
+                sink,
+            })
+        }
+        (3, &[length, lines, max_capacity]) if length >= 1 && lines >= 1 && max_capacity >= 1 => {
+            let sink = lines * (length - 1) + 1;
+            let mut edges = Vec::new();
+            for line in 0..lines {
+                let mut path = vec![0];
+                path.extend((0..length - 1).map(|i| 1 + line * (length - 1) + i));
+                path.push(sink);
+                for w in path.windows(2) {
+                    edges.push((w[0], w[1], rng.gen_capacity(max_capacity as Flow)));
+                }
+            }
+
+            Some(GraphInstance {
+                num_nodes: sink + 1,
+                edges,
+                source: 0,
+                sink,
+            })
+        }
+        (4, &[n, max_capacity]) if n >= 3 && max_capacity >= 1 => {
+            let mut edges = Vec::new();
+            for u in 0..n {
+                edges.push((u, (u + 1) % n, rng.gen_capacity(max_capacity as Flow)));
+                edges.push(((u + 1) % n, u, rng.gen_capacity(max_capacity as Flow)));
+            }
+
+            Some(GraphInstance {
+                num_nodes: n,
+                edges,
+                source: 0,
+                sink: n / 2,
+            })
+        }
+        _ => None,
+    }
+}
+
+// the zigzag: s -> a, s -> b, a -> t, b -> t with capacity 2^k and a unit edge a -> b
+// source = 0, a = 1, b = 2, sink = 3, maximum flow 2^(k + 1)
+// augmenting along paths that alternate over the unit edge (s a b t, s b a t, ...) needs 2^(k + 1)
+// iterations; FordFulkerson here marks visited nodes and finishes in two, but solvers with a
+// different path rule can be checked against it
+pub fn ff_worst_case(k: u32) -> GraphInstance {
+    assert!(k <= 60);
+    let c: Flow = 1 << k;
+    GraphInstance {
+        num_nodes: 4,
+        edges: vec![(1, 2, 1), (0, 1, c), (0, 2, c), (1, 3, c), (2, 3, c)],
+        source: 0,
+        sink: 3,
+    }
+}
+
+// a path s -> v1 -> ... -> vk with capacity k where only v1 reaches the sink (capacity 1)
+// source = 0, v_i = i, sink = k + 1, maximum flow 1
+// without global relabeling, the k - 1 units of excess walk down the path and back up to the
+// source one relabel at a time, Theta(k^2) relabels in total
+pub fn push_relabel_bad_case(k: usize) -> GraphInstance {
+    assert!(k >= 1);
+    let mut edges = vec![(0, 1, k as Flow), (1, k + 1, 1)];
+    for v in 1..k {
+        edges.push((v, v + 1, k as Flow));
+    }
+    GraphInstance {
+        num_nodes: k + 2,
+        edges,
+        source: 0,
+        sink: k + 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::capacity_scaling::CapacityScaling;
+    use crate::dinic::Dinic;
+    use crate::ford_fulkerson::FordFulkerson;
+    use crate::generators::{
+        ff_worst_case, genrmf, push_relabel_bad_case, random_bipartite, random_dag, random_graph,
+        washington, Rng,
+    };
+    use crate::graph::Flow;
+    use crate::instances::GraphInstance;
+    use crate::push_relabel_fifo::PushRelabelFIFO;
+    use crate::push_relabel_highest_label::PushRelabelHighestLabel;
+
+    fn solve_all(instance: &GraphInstance) -> Flow {
+        let flow = instance.solve::<Dinic>();
+        assert_eq!(instance.solve::<FordFulkerson>(), flow);
+        assert_eq!(instance.solve::<CapacityScaling>(), flow);
+        assert_eq!(instance.solve::<PushRelabelFIFO>(), flow);
+        assert_eq!(instance.solve::<PushRelabelHighestLabel>(), flow);
+        flow
+    }
+
+    #[test]
+    fn deterministic() {
+        assert_eq!(random_graph(20, 50, 100, 7), random_graph(20, 50, 100, 7));
+        assert_ne!(random_graph(20, 50, 100, 7), random_graph(20, 50, 100, 8));
+        assert_eq!(
+            random_bipartite(8, 9, 0.3, 7),
+            random_bipartite(8, 9, 0.3, 7)
+        );
+        assert_eq!(random_dag(20, 50, 100, 7), random_dag(20, 50, 100, 7));
+
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+    }
+
+    #[test]
+    fn shapes() {
+        let instance = random_graph(10, 200, 5, 1);
+        assert_eq!(instance.edges.len(), 200);
+        assert!(instance
+            .edges
+            .iter()
+            .all(|&(u, v, c)| u != v && v < 10 && (1..=5).contains(&c)));
+
+        let instance = random_dag(10, 200, 5, 1);
+        assert!(instance.edges.iter().all(|&(u, v, _)| u < v && v < 10));
+
+        let instance = random_bipartite(4, 6, 1.0, 1);
+        assert_eq!(instance.num_nodes, 12);
+        assert_eq!(instance.edges.len(), 4 + 4 * 6 + 6);
+        assert_eq!(solve_all(&instance), 4);
+        assert_eq!(solve_all(&random_bipartite(4, 6, 0.0, 1)), 0);
+    }
+
+    #[test]
+    fn solvers_agree() {
+        let mut rng = Rng::new(2023);
+        for seed in 0..100 {
+            let n = rng.gen_range(2, 30) as usize;
+            let m = rng.gen_range(0, 4 * n as u64) as usize;
+            solve_all(&random_graph(n, m, 100, seed));
+            solve_all(&random_dag(n, m, 100, seed));
+
+            let l = rng.gen_range(0, 12) as usize;
+            let r = rng.gen_range(0, 12) as usize;
+            let flow = solve_all(&random_bipartite(l, r, rng.gen_f64(), seed));
+            assert!(flow <= l.min(r) as Flow);
+        }
+    }
+
+    #[test]
+    fn genrmf_formula() {
+        for (a, b, c) in [(1, 2, 3), (2, 2, 7), (3, 4, 5), (4, 3, 1)] {
+            let instance = genrmf(a, b, c, c, 1);
+            assert_eq!(instance.num_nodes, a * a * b);
+            assert_eq!(solve_all(&instance), (a * a) as Flow * c);
+        }
+        for seed in 0..10 {
+            let flow = solve_all(&genrmf(3, 3, 1, 100, seed));
+            assert!((9..=900).contains(&flow));
+        }
+    }
+
+    #[test]
+    fn washington_formulas() {
+        for seed in 0..10 {
+            // mesh and random level: bounded by the edges leaving any column
+            for function_id in [1, 2] {
+                let instance = washington(function_id, &[4, 5, 20], seed).unwrap();
+                let flow = solve_all(&instance);
+                let column_cut = |col: usize| {
+                    let first = 1 + col * 4;
+                    instance
+                        .edges
+                        .iter()
+                        .filter(|&&(u, v, _)| (first..first + 4).contains(&u) && v > u)
+                        .filter(|&&(_, v, _)| v != instance.sink)
+                        .map(|&(_, _, c)| c)
+                        .sum::<Flow>()
+                };
+                assert!(flow <= (0..4).map(column_cut).min().unwrap());
+            }
+
+            // line: sum of the bottlenecks
+            let instance = washington(3, &[5, 3, 20], seed).unwrap();
+            let expected: Flow = instance
+                .edges
+                .chunks(5)
+                .map(|line| line.iter().map(|&(_, _, c)| c).min().unwrap())
+                .sum();
+            assert_eq!(solve_all(&instance), expected);
+
+            // cycle: bottleneck of each direction around the ring
+            let n = 7;
+            let instance = washington(4, &[n, 20], seed).unwrap();
+            let clockwise = (0..n / 2).map(|u| instance.edges[2 * u].2).min().unwrap();
+            let counterclockwise = (n / 2..n)
+                .map(|u| instance.edges[2 * u + 1].2)
+                .min()
+                .unwrap();
+            assert_eq!(solve_all(&instance), clockwise + counterclockwise);
+        }
+
+        assert_eq!(washington(3, &[1, 4, 1], 0).map(|i| solve_all(&i)), Some(4));
+        assert!(washington(5, &[4, 5, 20], 0).is_none());
+        assert!(washington(1, &[4, 5], 0).is_none());
+        assert!(washington(4, &[2, 5], 0).is_none());
+    }
+
+    #[test]
+    fn bad_cases() {
+        for k in [0, 1, 10, 40] {
+            assert_eq!(solve_all(&ff_worst_case(k)), 2 << k);
+        }
+        for k in [1, 2, 10, 100] {
+            assert_eq!(solve_all(&push_relabel_bad_case(k)), 1);
+        }
+    }
+}
@@ -1,10 +1,15 @@
-use std::collections::VecDeque;
-use std::fmt::Debug;
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use network_flows_core::{CsrGraph, EdgePayload};
 
 pub type Flow = i64;
+// the capacity of an unbounded edge, see Graph::add_directed_edge_unbounded
 pub const FLOW_MAX: Flow = Flow::MAX;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Edge {
     pub from: usize,
     pub to: usize,
@@ -12,7 +17,34 @@ pub struct Edge {
     pub capacity: Flow,
 }
 
-#[derive(Debug)]
+impl Edge {
+    // the arcs from -> to and to -> from of the residual network, without their revs, with unbounded_capacity for
+    // the capacity of an unbounded edge
+    pub(crate) fn arcs(&self, unbounded_capacity: Flow) -> (InsideEdge, InsideEdge) {
+        let capacity = if self.capacity == FLOW_MAX {
+            unbounded_capacity
+        } else {
+            self.capacity
+        };
+        (
+            InsideEdge {
+                to: self.to,
+                flow: 0 as Flow,
+                capacity,
+                rev: usize::MAX,
+            },
+            InsideEdge {
+                to: self.from,
+                flow: capacity,
+                capacity,
+                rev: usize::MAX,
+            },
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InsideEdge {
     pub to: usize,
     pub flow: Flow,
@@ -28,8 +60,111 @@ impl InsideEdge {
     }
 }
 
+impl EdgePayload for InsideEdge {
+    fn to(&self) -> usize {
+        self.to
+    }
+
+    fn rev(&self) -> usize {
+        self.rev
+    }
+
+    fn set_rev(&mut self, rev: usize) {
+        self.rev = rev;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlowViolation {
+    Capacity {
+        edge: usize,
+        flow: Flow,
+        capacity: Flow,
+    },
+    Conservation {
+        node: usize,
+        excess: Flow,
+    },
+    // a graph of Graph::build_lean, whose flows can't be read back
+    NoEdgeList,
+}
+
+impl core::fmt::Display for FlowViolation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FlowViolation::Capacity {
+                edge,
+                flow,
+                capacity,
+            } => write!(
+                f,
+                "edge {} has flow {} outside 0..={}",
+                edge, flow, capacity
+            ),
+            FlowViolation::Conservation { node, excess } => {
+                write!(f, "node {} has excess {}", node, excess)
+            }
+            FlowViolation::NoEdgeList => {
+                write!(f, "the graph was built by build_lean without its edges")
+            }
+        }
+    }
+}
+
+// counters of the last solve, None for the ones the algorithm doesn't have
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolveStats {
+    // Dinic, CapacityScaling
+    pub phases: Option<usize>,
+    pub advances: Option<usize>,
+    pub retreats: Option<usize>,
+    // Dinic, CapacityScaling, FordFulkerson, EdmondsKarp
+    pub augmenting_paths: Option<usize>,
+    // FordFulkerson, EdmondsKarp, and the BFS of Dinic
+    pub visited_nodes: Option<usize>,
+    // push-relabel
+    pub saturating_pushes: Option<usize>,
+    pub non_saturating_pushes: Option<usize>,
+    pub relabels: Option<usize>,
+    pub global_relabels: Option<usize>,
+    pub gaps: Option<usize>,
+}
+
+impl SolveStats {
+    pub(crate) fn augmenting_path() -> Self {
+        SolveStats {
+            phases: Some(0),
+            advances: Some(0),
+            retreats: Some(0),
+            augmenting_paths: Some(0),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn push_relabel() -> Self {
+        SolveStats {
+            saturating_pushes: Some(0),
+            non_saturating_pushes: Some(0),
+            relabels: Some(0),
+            global_relabels: Some(0),
+            gaps: Some(0),
+            ..Default::default()
+        }
+    }
+}
+
+// adds one to a counter of SolveStats
+#[inline]
+pub(crate) fn count(counter: &mut Option<usize>) {
+    if let Some(c) = counter {
+        *c += 1;
+    }
+}
+
 // CSR format
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Graph {
     pub num_nodes: usize,
     pub num_edges: usize,
@@ -37,9 +172,17 @@ pub struct Graph {
 
     pub start: Vec<usize>,
     pub inside_edge_list: Vec<InsideEdge>,
+    pub edge_index_to_inside_edge_index: Vec<usize>,
 
     pub excesses: Vec<Flow>,
     pub distance: Vec<usize>,
+
+    // built by Graph::build_lean, without edge_list: the edges can't be read back, and build only clears the flow
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lean: bool,
+    // the indices of the unbounded edges of a lean graph, in order
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lean_unbounded_edges: Vec<usize>,
 }
 
 #[allow(dead_code)]
@@ -49,6 +192,10 @@ impl<'a> Graph {
     }
 
     pub fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow) -> Option<usize> {
+        assert!(
+            !self.lean,
+            "a graph built by build_lean takes no more edges"
+        );
         if capacity <= 0 as Flow {
             return None;
         }
@@ -64,80 +211,112 @@ impl<'a> Graph {
         Some(self.num_edges - 1)
     }
 
-    pub fn get_directed_edge(&self, edge_index: usize) -> &Edge {
-        &self.edge_list[edge_index]
+    // an edge of capacity FLOW_MAX, which no flow saturates: the solvers return FLOW_MAX if such edges alone lead
+    // from the source to the sink, and otherwise see it as an edge of more capacity than all the others together,
+    // so that it is never a bottleneck and no minimum cut contains it
+    // the finite capacities should add up to less than FLOW_MAX, or that capacity saturates
+    pub fn add_directed_edge_unbounded(&mut self, from: usize, to: usize) -> Option<usize> {
+        self.add_directed_edge(from, to, FLOW_MAX)
     }
 
-    pub fn build(&mut self) {
-        let mut degree = vec![0; self.num_nodes];
-        let mut edge_index = vec![usize::MAX; self.num_edges];
-        let mut reverse_edge_index = vec![usize::MAX; self.num_edges];
-
-        let mut tmp_inside_edge_list = Vec::with_capacity(2 * self.num_edges);
-        for (i, e) in self.edge_list.iter().enumerate() {
-            edge_index[i] = degree[e.from];
-            degree[e.from] += 1;
-            reverse_edge_index[i] = degree[e.to];
-            degree[e.to] += 1;
-
-            // from -> to
-            tmp_inside_edge_list.push((
-                e.from,
-                InsideEdge {
-                    to: e.to,
-                    flow: 0 as Flow,
-                    capacity: e.capacity,
-                    rev: usize::MAX,
-                },
-            ));
-
-            // to -> from
-            tmp_inside_edge_list.push((
-                e.to,
-                InsideEdge {
-                    to: e.from,
-                    flow: e.capacity,
-                    capacity: e.capacity,
-                    rev: usize::MAX,
-                },
-            ));
+    #[inline]
+    pub fn is_unbounded(&self, edge_index: usize) -> bool {
+        if self.lean {
+            return self.lean_unbounded_edges.binary_search(&edge_index).is_ok();
         }
+        self.edge_list[edge_index].capacity == FLOW_MAX
+    }
 
-        // make graph
-        self.excesses = vec![0 as Flow; self.num_nodes];
-        self.distance = vec![0; self.num_nodes];
-        self.start = vec![0; self.num_nodes + 1];
-        self.inside_edge_list = (0..2 * self.num_edges)
-            .map(|_| InsideEdge {
-                to: 0,
-                flow: 0 as Flow,
-                capacity: 0 as Flow,
-                rev: 0,
-            })
-            .collect();
-
-        for (u, _) in tmp_inside_edge_list.iter() {
-            self.start[u + 1] += 1;
+    // (from, to, capacity) of an edge, read from the arcs on a lean graph
+    pub(crate) fn edge_ends(&self, edge_index: usize) -> (usize, usize, Flow) {
+        if !self.lean {
+            let e = &self.edge_list[edge_index];
+            return (e.from, e.to, e.capacity);
         }
-        for i in 1..=self.num_nodes {
-            self.start[i] += self.start[i - 1];
+        let forward = &self.inside_edge_list[self.edge_index_to_inside_edge_index[edge_index]];
+        let capacity = if self.is_unbounded(edge_index) {
+            FLOW_MAX
+        } else {
+            forward.capacity
+        };
+        (self.inside_edge_list[forward.rev].to, forward.to, capacity)
+    }
+
+    // the flow is read from the residual network once the graph is built
+    // panics on a lean graph, see Graph::try_get_directed_edge
+    pub fn get_directed_edge(&self, edge_index: usize) -> Edge {
+        assert!(
+            !self.lean,
+            "a graph built by build_lean keeps no edges to read back"
+        );
+        let mut edge = self.edge_list[edge_index].clone();
+        if let Some(&i) = self.edge_index_to_inside_edge_index.get(edge_index) {
+            edge.flow = self.inside_edge_list[i].flow;
         }
+        edge
+    }
 
-        let mut counter = self.start.clone();
-        for (u, e) in tmp_inside_edge_list {
-            self.inside_edge_list[counter[u]] = e;
-            counter[u] += 1;
+    pub fn build(&mut self) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("build").entered();
+        if self.lean {
+            return self.clear_flow();
+        }
+        #[cfg(feature = "rayon")]
+        if self.num_edges >= crate::parallel_build::PARALLEL_BUILD_MIN_EDGES
+            && rayon::current_num_threads() > 1
+        {
+            let layout = crate::parallel_build::build(
+                self.num_nodes,
+                &self.edge_list,
+                self.unbounded_capacity(),
+            );
+            return self.set_layout(layout);
         }
+        let unbounded_capacity = self.unbounded_capacity();
+        let layout = CsrGraph::build(
+            self.num_nodes,
+            self.edge_list.iter().map(|e| {
+                let (forward, reverse) = e.arcs(unbounded_capacity);
+                (e.from, e.to, forward, reverse)
+            }),
+        );
+        self.set_layout(layout);
+    }
+
+    // one more than the finite capacities together, which bound any finite flow
+    fn unbounded_capacity(&self) -> Flow {
+        self.edge_list
+            .iter()
+            .filter(|e| e.capacity != FLOW_MAX)
+            .fold(0 as Flow, |total, e| total.saturating_add(e.capacity))
+            .saturating_add(1)
+    }
 
-        for (i, e) in self.edge_list.iter().enumerate() {
-            edge_index[i] += self.start[e.from];
-            reverse_edge_index[i] += self.start[e.to];
-            self.inside_edge_list[edge_index[i]].rev = reverse_edge_index[i];
-            self.inside_edge_list[reverse_edge_index[i]].rev = edge_index[i];
+    // the arcs of a lean graph as build would make them again
+    fn clear_flow(&mut self) {
+        for &i in self.edge_index_to_inside_edge_index.iter() {
+            let (capacity, rev) = (
+                self.inside_edge_list[i].capacity,
+                self.inside_edge_list[i].rev,
+            );
+            self.inside_edge_list[i].flow = 0 as Flow;
+            self.inside_edge_list[rev].flow = capacity;
         }
+        self.excesses = vec![0 as Flow; self.num_nodes];
+        self.distance = vec![0; self.num_nodes];
+    }
+
+    fn set_layout(&mut self, layout: CsrGraph<InsideEdge>) {
+        // make graph
+        self.excesses = vec![0 as Flow; self.num_nodes];
+        self.distance = vec![0; self.num_nodes];
+        self.start = layout.start;
+        self.inside_edge_list = layout.arcs;
+        self.edge_index_to_inside_edge_index = layout.edge_index;
     }
 
-    pub fn neighbors(&'a self, u: usize) -> std::slice::Iter<'a, InsideEdge> {
+    pub fn neighbors(&'a self, u: usize) -> core::slice::Iter<'a, InsideEdge> {
         self.inside_edge_list[self.start[u]..self.start[u + 1]].iter()
     }
 
@@ -167,7 +346,7 @@ impl<'a> Graph {
 
     // O(n + m)
     // calculate distance from u to sink in residual network
-    pub fn calculate_distance_to_sink(&mut self, sink: usize) -> Vec<usize> {
+    pub fn calculate_distance_to_sink(&self, sink: usize) -> Vec<usize> {
         let mut que = VecDeque::new();
         que.push_back(sink);
         let mut distance = vec![self.num_nodes; self.num_nodes];
@@ -185,8 +364,304 @@ impl<'a> Graph {
         distance
     }
 
+    // O(n + m)
+    // nodes that cannot reach sink in residual network (source side of a minimum cut)
+    // valid for both maximum flows and maximum preflows
+    pub fn min_cut(&self, sink: usize) -> Vec<bool> {
+        // sink has no incident edges
+        if sink >= self.num_nodes {
+            return vec![true; self.num_nodes];
+        }
+        self.calculate_distance_to_sink(sink)
+            .iter()
+            .map(|&d| d == self.num_nodes)
+            .collect()
+    }
+
+    // O(n + m)
+    // whether unbounded edges alone lead from source to sink, so that the flow is unbounded
+    pub fn has_unbounded_path(&self, source: usize, sink: usize) -> bool {
+        let mut unbounded = (0..self.num_edges)
+            .filter(|&i| self.is_unbounded(i))
+            .map(|i| self.edge_ends(i))
+            .peekable();
+        if source >= self.num_nodes || sink >= self.num_nodes || unbounded.peek().is_none() {
+            return false;
+        }
+        let mut adjacency = vec![Vec::new(); self.num_nodes];
+        for (from, to, _) in unbounded {
+            adjacency[from].push(to);
+        }
+        let mut visited = vec![false; self.num_nodes];
+        visited[source] = true;
+        let mut stack = vec![source];
+        while let Some(u) = stack.pop() {
+            for &v in adjacency[u].iter() {
+                if !visited[v] {
+                    visited[v] = true;
+                    stack.push(v);
+                }
+            }
+        }
+        visited[sink]
+    }
+
+    // O(m)
+    // the capacity of the edges from the nodes of source_side to the others, None if one of them is unbounded
+    pub fn cut_capacity(&self, source_side: &[bool]) -> Option<Flow> {
+        (0..self.num_edges)
+            .map(|i| self.edge_ends(i))
+            .filter(|&(from, to, _)| source_side[from] && !source_side[to])
+            .try_fold(0 as Flow, |total, (_, _, capacity)| {
+                (capacity != FLOW_MAX).then(|| total.saturating_add(capacity))
+            })
+    }
+
+    // O(nm)
+    // decompose the flow into source-sink paths (edge indices, flow)
+    // cycles are canceled, and flow ending at nodes with excess (preflow) is dropped
+    // panics on a lean graph, see Graph::try_decompose_flow
+    pub fn decompose_flow(&self, source: usize, sink: usize) -> Vec<(Vec<usize>, Flow)> {
+        let edges: Vec<(usize, usize, Flow)> = (0..self.num_edges)
+            .map(|i| {
+                let e = self.get_directed_edge(i);
+                (e.from, e.to, e.flow)
+            })
+            .collect();
+        decompose_flow(self.num_nodes, &edges, source, sink)
+    }
+
+    // O(n + m)
+    // checks the capacity constraints and flow conservation at all nodes but source and sink
+    // returns the flow value (net inflow into sink)
+    // the push-relabel solvers leave a maximum preflow, which fails the conservation check
+    pub fn validate_flow(&self, source: usize, sink: usize) -> Result<Flow, FlowViolation> {
+        if self.lean {
+            return Err(FlowViolation::NoEdgeList);
+        }
+        let mut balance = vec![0 as Flow; self.num_nodes];
+        for i in 0..self.num_edges {
+            let edge = self.get_directed_edge(i);
+            if edge.flow < 0 || edge.flow > edge.capacity {
+                return Err(FlowViolation::Capacity {
+                    edge: i,
+                    flow: edge.flow,
+                    capacity: edge.capacity,
+                });
+            }
+            balance[edge.from] -= edge.flow;
+            balance[edge.to] += edge.flow;
+        }
+
+        for (u, &excess) in balance.iter().enumerate() {
+            if u != source && u != sink && excess != 0 {
+                return Err(FlowViolation::Conservation { node: u, excess });
+            }
+        }
+        Ok(balance.get(sink).copied().unwrap_or(0))
+    }
+
     #[inline]
     pub fn is_admissible_edge(&self, from: usize, to: usize) -> bool {
         self.distance[from] == self.distance[to] + 1
     }
 }
+
+// the paths of Graph::decompose_flow for the flows of any edge list (from, to, flow), e.g. of min_cost_flow
+pub(crate) fn decompose_flow(
+    num_nodes: usize,
+    edges: &[(usize, usize, Flow)],
+    source: usize,
+    sink: usize,
+) -> Vec<(Vec<usize>, Flow)> {
+    let mut remaining: Vec<Flow> = edges.iter().map(|e| e.2).collect();
+    let mut out_edges = vec![Vec::new(); num_nodes];
+    for (i, &(from, _, flow)) in edges.iter().enumerate() {
+        if flow > 0 {
+            out_edges[from].push(i);
+        }
+    }
+    let mut current_edge = vec![0; num_nodes];
+    let mut on_path = vec![false; num_nodes];
+
+    let mut paths = Vec::new();
+    if source == sink || source >= num_nodes {
+        return paths;
+    }
+    loop {
+        let mut nodes = vec![source];
+        let mut path: Vec<usize> = Vec::new();
+        on_path[source] = true;
+
+        let mut u = source;
+        while u != sink {
+            while current_edge[u] < out_edges[u].len()
+                && remaining[out_edges[u][current_edge[u]]] == 0
+            {
+                current_edge[u] += 1;
+            }
+            if current_edge[u] == out_edges[u].len() {
+                break;
+            }
+
+            let i = out_edges[u][current_edge[u]];
+            let v = edges[i].1;
+            path.push(i);
+            if on_path[v] {
+                // cancel the cycle
+                let k = nodes.iter().position(|&w| w == v).unwrap();
+                let delta = path[k..].iter().map(|&i| remaining[i]).min().unwrap();
+                for &i in path[k..].iter() {
+                    remaining[i] -= delta;
+                }
+                for &w in nodes[k + 1..].iter() {
+                    on_path[w] = false;
+                }
+                nodes.truncate(k + 1);
+                path.truncate(k);
+            } else {
+                on_path[v] = true;
+                nodes.push(v);
+            }
+            u = *nodes.last().unwrap();
+        }
+
+        for &w in nodes.iter() {
+            on_path[w] = false;
+        }
+        if path.is_empty() {
+            break;
+        }
+
+        let delta = path.iter().map(|&i| remaining[i]).min().unwrap();
+        for &i in path.iter() {
+            remaining[i] -= delta;
+        }
+        if u == sink {
+            paths.push((path, delta));
+        }
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod test {
+    use crate::capacity_scaling::CapacityScaling;
+    use crate::dinic::Dinic;
+    use crate::edmonds_karp::EdmondsKarp;
+    use crate::ford_fulkerson::FordFulkerson;
+    use crate::graph::{Flow, FlowViolation, Graph, FLOW_MAX};
+    use crate::push_relabel_fifo::PushRelabelFIFO;
+    use crate::push_relabel_highest_label::PushRelabelHighestLabel;
+    use crate::solver::MaxFlowSolver;
+    use crate::test_utility::read_instance_aoj;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    fn check_decomposition<S: MaxFlowSolver>(path: &PathBuf) {
+        let instance = read_instance_aoj(path);
+        let mut solver: S = instance.build_solver();
+        let flow = solver.solve(instance.source, instance.sink);
+
+        let graph = solver.graph();
+        let paths = graph.decompose_flow(instance.source, instance.sink);
+        assert_eq!(paths.iter().map(|(_, f)| f).sum::<i64>(), flow);
+        for (edges, f) in paths.iter() {
+            assert!(*f > 0);
+            assert_eq!(graph.get_directed_edge(edges[0]).from, instance.source);
+            assert_eq!(
+                graph.get_directed_edge(*edges.last().unwrap()).to,
+                instance.sink
+            );
+            for w in edges.windows(2) {
+                assert_eq!(
+                    graph.get_directed_edge(w[0]).to,
+                    graph.get_directed_edge(w[1]).from
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn validate_flow() {
+        let mut solver = Dinic::new();
+        solver.add_directed_edge(0, 1, 2);
+        solver.add_directed_edge(1, 2, 1);
+        solver.add_directed_edge(0, 2, 4);
+        assert_eq!(solver.graph().validate_flow(0, 2), Ok(0));
+        assert_eq!(solver.solve(0, 2), 5);
+        assert_eq!(solver.graph().validate_flow(0, 2), Ok(5));
+
+        let mut graph = Graph::new();
+        graph.add_directed_edge(0, 1, 2);
+        graph.add_directed_edge(1, 2, 1);
+        graph.build();
+        graph.push_flow(0, graph.edge_index_to_inside_edge_index[0], 2);
+        assert_eq!(
+            graph.validate_flow(0, 2),
+            Err(FlowViolation::Conservation { node: 1, excess: 2 })
+        );
+    }
+
+    #[rstest]
+    fn decompose_flow(#[files("test_cases/AOJ_GRL_6_A/*.in")] path: PathBuf) {
+        check_decomposition::<Dinic>(&path);
+        check_decomposition::<PushRelabelFIFO>(&path);
+    }
+
+    // the flow from 0 to 3 and the capacity of the min cut, FLOW_MAX for an unbounded edge
+    fn solve_unbounded<S: MaxFlowSolver>(edges: &[(usize, usize, Flow)]) -> (Flow, Option<Flow>) {
+        let mut solver = S::new();
+        for &(from, to, capacity) in edges.iter() {
+            if capacity == FLOW_MAX {
+                solver.add_directed_edge_unbounded(from, to);
+            } else {
+                solver.add_directed_edge(from, to, capacity);
+            }
+        }
+        let flow = solver.solve(0, 3);
+        let graph = solver.graph();
+        (flow, graph.cut_capacity(&graph.min_cut(3)))
+    }
+
+    fn check_unbounded<S: MaxFlowSolver>() {
+        // the cut {0, 1} of 1 -> 2, 0 -> 2 and 1 -> 3 is the only finite one
+        let edges = [
+            (0, 1, FLOW_MAX),
+            (1, 2, 3),
+            (0, 2, 4),
+            (2, 3, FLOW_MAX),
+            (1, 3, 2),
+        ];
+        assert_eq!(solve_unbounded::<S>(&edges), (9, Some(9)));
+        // every cut contains 0 -> 1 or 1 -> 3
+        let edges = [(0, 1, FLOW_MAX), (1, 3, FLOW_MAX), (0, 2, 5), (2, 3, 5)];
+        assert_eq!(solve_unbounded::<S>(&edges).0, FLOW_MAX);
+        assert_eq!(solve_unbounded::<S>(&edges[..2]).0, FLOW_MAX);
+    }
+
+    #[test]
+    fn unbounded_edges() {
+        check_unbounded::<Dinic>();
+        check_unbounded::<FordFulkerson>();
+        check_unbounded::<EdmondsKarp>();
+        check_unbounded::<CapacityScaling>();
+        check_unbounded::<PushRelabelFIFO>();
+        check_unbounded::<PushRelabelHighestLabel>();
+
+        let mut solver = Dinic::new();
+        solver.add_directed_edge(0, 1, 4);
+        solver.add_directed_edge_unbounded(1, 2);
+        solver.add_directed_edge(2, 3, 7);
+        assert_eq!(solver.solve(0, 3), 4);
+        let graph = solver.graph();
+        assert!(graph.is_unbounded(1) && !graph.is_unbounded(0));
+        assert_eq!(graph.get_directed_edge(1).flow, 4);
+        assert_eq!(graph.get_directed_edge(1).capacity, FLOW_MAX);
+        assert_eq!(graph.validate_flow(0, 3), Ok(4));
+        assert_eq!(graph.cut_capacity(&[true, false, false, false]), Some(4));
+        assert_eq!(graph.cut_capacity(&[true, true, false, false]), None);
+        assert!(!graph.has_unbounded_path(0, 3) && graph.has_unbounded_path(1, 2));
+    }
+}
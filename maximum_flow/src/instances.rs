@@ -0,0 +1,148 @@
+use crate::graph::{Flow, Graph};
+use crate::io::{read_aoj, read_dimacs_max, read_libreoj, ParseError};
+use crate::solver::MaxFlowSolver;
+use network_flows_instances::{Edge, Instance};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+// node ids are 0-indexed, edges are kept in input order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphInstance {
+    pub num_nodes: usize,
+    pub edges: Vec<(usize, usize, Flow)>,
+    pub source: usize,
+    pub sink: usize,
+}
+
+impl GraphInstance {
+    // edges with non-positive capacity are rejected by the solver, so edge indices may shift
+    pub fn build_solver<S: MaxFlowSolver>(&self) -> S {
+        let mut solver = S::new();
+        for &(from, to, capacity) in self.edges.iter() {
+            solver.add_directed_edge(from, to, capacity);
+        }
+        solver
+    }
+
+    pub fn build_graph(&self) -> Graph {
+        let mut graph = Graph::new();
+        for &(from, to, capacity) in self.edges.iter() {
+            graph.add_directed_edge(from, to, capacity);
+        }
+        graph
+    }
+
+    pub fn solve<S: MaxFlowSolver>(&self) -> Flow {
+        self.build_solver::<S>().solve(self.source, self.sink)
+    }
+}
+
+// the gains and costs of the edges are dropped
+impl From<Instance<Flow>> for GraphInstance {
+    fn from(instance: Instance<Flow>) -> Self {
+        GraphInstance {
+            num_nodes: instance.num_nodes,
+            edges: instance.capacities().collect(),
+            source: instance.source,
+            sink: instance.sink,
+        }
+    }
+}
+
+impl From<&GraphInstance> for Instance<Flow> {
+    fn from(instance: &GraphInstance) -> Self {
+        Instance {
+            num_nodes: instance.num_nodes,
+            edges: instance
+                .edges
+                .iter()
+                .map(|&(from, to, capacity)| Edge::new(from, to, capacity))
+                .collect(),
+            source: instance.source,
+            sink: instance.sink,
+        }
+    }
+}
+
+pub fn read_instance_aoj(path: impl AsRef<Path>) -> Result<GraphInstance, ParseError> {
+    read_aoj(BufReader::new(File::open(path)?))
+}
+
+pub fn read_instance_libreoj(path: impl AsRef<Path>) -> Result<GraphInstance, ParseError> {
+    read_libreoj(BufReader::new(File::open(path)?))
+}
+
+pub fn read_instance_dimacs(path: impl AsRef<Path>) -> Result<GraphInstance, ParseError> {
+    read_dimacs_max(BufReader::new(File::open(path)?))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::instances::{read_instance_aoj, read_instance_libreoj, GraphInstance};
+    use crate::io::ParseError;
+    use crate::push_relabel_fifo::PushRelabelFIFO;
+    use std::path::PathBuf;
+
+    fn write_temp(name: &str, data: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "maximum_flow_instances_{}_{}",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn error_line(result: Result<GraphInstance, ParseError>) -> usize {
+        match result {
+            Err(ParseError::Syntax { line, .. }) => line,
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("parsed a malformed instance"),
+        }
+    }
+
+    #[test]
+    fn build_solver() {
+        let instance = read_instance_aoj("test_cases/AOJ_GRL_6_A/00_sample_00.in").unwrap();
+        assert_eq!(instance.num_nodes, 4);
+
+        let mut solver: Dinic = instance.build_solver();
+        assert_eq!(solver.solve(instance.source, instance.sink), 3);
+        assert_eq!(instance.solve::<PushRelabelFIFO>(), 3);
+        assert_eq!(instance.build_graph().num_edges, instance.edges.len());
+    }
+
+    #[test]
+    fn missing_columns() {
+        let path = write_temp("missing_columns_aoj", "3 2\n0 1 5\n1 2\n");
+        assert_eq!(error_line(read_instance_aoj(&path)), 3);
+        let path = write_temp("missing_columns_libreoj", "3 1 1\n1 2 5\n");
+        assert_eq!(error_line(read_instance_libreoj(&path)), 1);
+    }
+
+    #[test]
+    fn non_numeric_capacity() {
+        let path = write_temp("non_numeric_aoj", "3 2\n0 1 5\n1 2 seven\n");
+        assert_eq!(error_line(read_instance_aoj(&path)), 3);
+        let path = write_temp("non_numeric_libreoj", "3 2 1 3\n1 2 5.5\n2 3 7\n");
+        assert_eq!(error_line(read_instance_libreoj(&path)), 2);
+    }
+
+    #[test]
+    fn empty_file() {
+        let path = write_temp("empty_aoj", "");
+        assert_eq!(error_line(read_instance_aoj(&path)), 0);
+        let path = write_temp("empty_libreoj", "\n");
+        assert_eq!(error_line(read_instance_libreoj(&path)), 0);
+    }
+
+    #[test]
+    fn missing_file() {
+        assert!(matches!(
+            read_instance_aoj("test_cases/does_not_exist.in"),
+            Err(ParseError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+use crate::graph::Flow;
+use crate::instances::GraphInstance;
+use crate::io::{read_dimacs_max, ParseError};
+use network_flows_instances::MAX_RESERVED_EDGES;
+use std::io::{BufRead, Read, Write};
+
+// little-endian layout
+// header: magic (4 bytes), version (u32), num_nodes (u64), num_edges (u64), source (u64), sink (u64)
+// records: from (u32), to (u32), capacity (i64)
+pub const MAGIC: &[u8; 4] = b"MFLW";
+pub const VERSION: u32 = 1;
+pub const HEADER_SIZE: usize = 40;
+pub const RECORD_SIZE: usize = 16;
+
+struct Header {
+    num_nodes: usize,
+    num_edges: usize,
+    source: usize,
+    sink: usize,
+}
+
+fn error(offset: usize, message: impl Into<String>) -> ParseError {
+    ParseError::Binary {
+        offset,
+        message: message.into(),
+    }
+}
+
+fn parse_header(bytes: &[u8]) -> Result<Header, ParseError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(error(0, "truncated header"));
+    }
+    if &bytes[0..4] != MAGIC {
+        return Err(error(0, "bad magic number"));
+    }
+    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    if version != VERSION {
+        return Err(error(4, format!("unsupported version {}", version)));
+    }
+    let field = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap()) as usize;
+    let header = Header {
+        num_nodes: field(8),
+        num_edges: field(16),
+        source: field(24),
+        sink: field(32),
+    };
+    if header.source >= header.num_nodes || header.sink >= header.num_nodes {
+        return Err(error(24, "source or sink is out of range"));
+    }
+    Ok(header)
+}
+
+// without the checks of parse_record
+fn decode_record(bytes: &[u8]) -> (usize, usize, Flow) {
+    let from = u32::from_le_bytes(bytes[0..4].try_into().unwrap()) as usize;
+    let to = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+    let capacity = Flow::from_le_bytes(bytes[8..16].try_into().unwrap());
+    (from, to, capacity)
+}
+
+fn parse_record(
+    bytes: &[u8],
+    offset: usize,
+    num_nodes: usize,
+) -> Result<(usize, usize, Flow), ParseError> {
+    let (from, to, capacity) = decode_record(bytes);
+    if from >= num_nodes || to >= num_nodes {
+        return Err(error(offset, "node id is out of range"));
+    }
+    if capacity < 0 {
+        return Err(error(offset + 8, "capacity must be non-negative"));
+    }
+    Ok((from, to, capacity))
+}
+
+pub fn write_binary<W: Write>(mut w: W, instance: &GraphInstance) -> std::io::Result<()> {
+    if instance.num_nodes > u32::MAX as usize {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "node ids must fit in u32",
+        ));
+    }
+    w.write_all(MAGIC)?;
+    w.write_all(&VERSION.to_le_bytes())?;
+    for value in [
+        instance.num_nodes,
+        instance.edges.len(),
+        instance.source,
+        instance.sink,
+    ] {
+        w.write_all(&(value as u64).to_le_bytes())?;
+    }
+    for &(from, to, capacity) in instance.edges.iter() {
+        w.write_all(&(from as u32).to_le_bytes())?;
+        w.write_all(&(to as u32).to_le_bytes())?;
+        w.write_all(&capacity.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn read_binary<R: Read>(mut r: R) -> Result<GraphInstance, ParseError> {
+    let mut header = [0; HEADER_SIZE];
+    r.read_exact(&mut header)
+        .map_err(|_| error(0, "truncated header"))?;
+    let header = parse_header(&header)?;
+
+    let mut edges = Vec::with_capacity(header.num_edges.min(MAX_RESERVED_EDGES));
+    let mut record = [0; RECORD_SIZE];
+    for i in 0..header.num_edges {
+        let offset = HEADER_SIZE + i * RECORD_SIZE;
+        r.read_exact(&mut record)
+            .map_err(|_| error(offset, format!("truncated record {}", i)))?;
+        edges.push(parse_record(&record, offset, header.num_nodes)?);
+    }
+
+    Ok(GraphInstance {
+        num_nodes: header.num_nodes,
+        edges,
+        source: header.source,
+        sink: header.sink,
+    })
+}
+
+pub fn dimacs_to_binary<R: BufRead, W: Write>(r: R, w: W) -> Result<(), ParseError> {
+    let instance = read_dimacs_max(r)?;
+    write_binary(w, &instance)?;
+    Ok(())
+}
+
+#[cfg(feature = "mmap")]
+pub use mapped::MappedInstance;
+
+#[cfg(feature = "mmap")]
+mod mapped {
+    use super::{decode_record, parse_header, parse_record, HEADER_SIZE, RECORD_SIZE};
+    use crate::graph::{Flow, Graph};
+    use crate::io::ParseError;
+    use memmap2::Mmap;
+    use std::fs::File;
+    use std::path::Path;
+
+    // a binary instance mapped into memory, edges are decoded on the fly
+    pub struct MappedInstance {
+        mmap: Mmap,
+        pub num_nodes: usize,
+        pub num_edges: usize,
+        pub source: usize,
+        pub sink: usize,
+    }
+
+    impl MappedInstance {
+        pub fn open(path: &Path) -> Result<Self, ParseError> {
+            let file = File::open(path)?;
+            // SAFETY: the file must not be modified while it is mapped
+            let mmap = unsafe { Mmap::map(&file)? };
+            let header = parse_header(&mmap)?;
+            let size = header
+                .num_edges
+                .checked_mul(RECORD_SIZE)
+                .and_then(|records| records.checked_add(HEADER_SIZE));
+            if size != Some(mmap.len()) {
+                return Err(super::error(
+                    HEADER_SIZE,
+                    "file size does not match the edge count",
+                ));
+            }
+            Ok(MappedInstance {
+                mmap,
+                num_nodes: header.num_nodes,
+                num_edges: header.num_edges,
+                source: header.source,
+                sink: header.sink,
+            })
+        }
+
+        pub fn edges(&self) -> impl Iterator<Item = Result<(usize, usize, Flow), ParseError>> + '_ {
+            self.mmap[HEADER_SIZE..]
+                .chunks_exact(RECORD_SIZE)
+                .enumerate()
+                .map(|(i, record)| {
+                    parse_record(record, HEADER_SIZE + i * RECORD_SIZE, self.num_nodes)
+                })
+        }
+
+        // O(n + m)
+        // a lean graph built straight from the mapped buffer, for Dinic::from_graph and the like:
+        // the records are checked in a first pass, then Graph::build_lean decodes them again
+        pub fn build_graph(&self) -> Result<Graph, ParseError> {
+            for edge in self.edges() {
+                edge?;
+            }
+            let records = || {
+                self.mmap[HEADER_SIZE..]
+                    .chunks_exact(RECORD_SIZE)
+                    .map(decode_record)
+            };
+            Ok(Graph::build_lean(records))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::binary::{dimacs_to_binary, read_binary, write_binary, HEADER_SIZE};
+    use crate::io::ParseError;
+    use crate::test_utility::{read_instance_libreoj, GraphInstance};
+    use std::path::PathBuf;
+
+    fn sample() -> GraphInstance {
+        read_instance_libreoj(&PathBuf::from("test_cases/LibreOJ_101/1.in"))
+    }
+
+    #[test]
+    fn round_trip() {
+        let instance = sample();
+        let mut buffer = Vec::new();
+        write_binary(&mut buffer, &instance).unwrap();
+        assert_eq!(buffer.len(), HEADER_SIZE + 16 * instance.edges.len());
+
+        let restored = read_binary(buffer.as_slice()).unwrap();
+        assert_eq!(restored.num_nodes, instance.num_nodes);
+        assert_eq!(restored.source, instance.source);
+        assert_eq!(restored.sink, instance.sink);
+        assert_eq!(restored.edges, instance.edges);
+    }
+
+    #[test]
+    fn from_dimacs() {
+        let data = "p max 3 2\nn 1 s\nn 3 t\na 1 2 5\na 2 3 7\n";
+        let mut buffer = Vec::new();
+        dimacs_to_binary(data.as_bytes(), &mut buffer).unwrap();
+        let instance = read_binary(buffer.as_slice()).unwrap();
+        assert_eq!(instance.edges, vec![(0, 1, 5), (1, 2, 7)]);
+        assert_eq!((instance.source, instance.sink), (0, 2));
+    }
+
+    #[test]
+    fn errors() {
+        let mut buffer = Vec::new();
+        write_binary(&mut buffer, &sample()).unwrap();
+
+        let offset = |bytes: &[u8]| match read_binary(bytes) {
+            Err(ParseError::Binary { offset, .. }) => offset,
+            _ => panic!("expected an error"),
+        };
+        assert_eq!(offset(&buffer[..10]), 0);
+        assert_eq!(offset(&buffer[..HEADER_SIZE + 20]), HEADER_SIZE + 16);
+
+        let mut bad_magic = buffer.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(offset(&bad_magic), 0);
+
+        let mut bad_node = buffer.clone();
+        bad_node[HEADER_SIZE..HEADER_SIZE + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(offset(&bad_node), HEADER_SIZE);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped() {
+        use crate::dinic::Dinic;
+        use crate::io::binary::MappedInstance;
+
+        let path =
+            std::env::temp_dir().join(format!("maximum_flow_mapped_{}.bin", std::process::id()));
+        write_binary(std::fs::File::create(&path).unwrap(), &sample()).unwrap();
+
+        let mapped = MappedInstance::open(&path).unwrap();
+        let graph = mapped.build_graph().unwrap();
+        assert!(graph.lean);
+        assert_eq!(graph.num_edges, sample().edges.len());
+        let mut solver = Dinic::from_graph(graph);
+        assert_eq!(solver.solve(mapped.source, mapped.sink), 729);
+        drop(mapped);
+
+        // a header alone, whose edge count overflows the file size to HEADER_SIZE
+        let mut header = Vec::new();
+        write_binary(&mut header, &sample()).unwrap();
+        header.truncate(HEADER_SIZE);
+        header[16..24].copy_from_slice(&(1u64 << 60).to_le_bytes());
+        std::fs::write(&path, &header).unwrap();
+        assert!(matches!(
+            MappedInstance::open(&path),
+            Err(ParseError::Binary {
+                offset: HEADER_SIZE,
+                ..
+            })
+        ));
+
+        // a record out of range is reported before the graph is built
+        let mut buffer = Vec::new();
+        write_binary(&mut buffer, &sample()).unwrap();
+        buffer[HEADER_SIZE..HEADER_SIZE + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &buffer).unwrap();
+        let mapped = MappedInstance::open(&path).unwrap();
+        assert!(matches!(
+            mapped.build_graph(),
+            Err(ParseError::Binary {
+                offset: HEADER_SIZE,
+                ..
+            })
+        ));
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
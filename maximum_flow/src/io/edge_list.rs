@@ -0,0 +1,211 @@
+use crate::graph::Flow;
+use crate::instances::GraphInstance;
+use crate::io::ParseError;
+use std::io::BufRead;
+
+#[derive(Debug, Clone)]
+pub struct EdgeListOptions {
+    // None splits on any whitespace
+    pub delimiter: Option<char>,
+    pub has_header: bool,
+    pub one_based: bool,
+    pub from_column: usize,
+    pub to_column: usize,
+    pub capacity_column: usize,
+    // node ids as written in the file, None picks the minimum/maximum id
+    pub source: Option<usize>,
+    pub sink: Option<usize>,
+    pub skip_malformed: bool,
+}
+
+impl Default for EdgeListOptions {
+    fn default() -> Self {
+        EdgeListOptions {
+            delimiter: None,
+            has_header: false,
+            one_based: false,
+            from_column: 0,
+            to_column: 1,
+            capacity_column: 2,
+            source: None,
+            sink: None,
+            skip_malformed: false,
+        }
+    }
+}
+
+impl EdgeListOptions {
+    pub fn csv() -> Self {
+        EdgeListOptions {
+            delimiter: Some(','),
+            has_header: true,
+            ..Default::default()
+        }
+    }
+
+    fn to_index(&self, id: usize, line: usize) -> Result<usize, ParseError> {
+        // the node count is the largest index + 1
+        if id == usize::MAX {
+            return Err(ParseError::Syntax {
+                line,
+                message: format!("node id {} is too large", id),
+            });
+        }
+        if !self.one_based {
+            return Ok(id);
+        }
+        id.checked_sub(1).ok_or_else(|| ParseError::Syntax {
+            line,
+            message: "node id 0 in a 1-based file".to_string(),
+        })
+    }
+
+    fn parse_line(
+        &self,
+        line: &str,
+        line_number: usize,
+    ) -> Result<(usize, usize, Flow), ParseError> {
+        let fields: Vec<&str> = match self.delimiter {
+            Some(delimiter) => line.split(delimiter).map(|f| f.trim()).collect(),
+            None => line.split_whitespace().collect(),
+        };
+        let field = |column: usize, name: &str| {
+            fields.get(column).ok_or_else(|| ParseError::Syntax {
+                line: line_number,
+                message: format!("missing {} column {}", name, column),
+            })
+        };
+        let parse_id = |column: usize, name: &str| -> Result<usize, ParseError> {
+            let token = field(column, name)?;
+            let id = token.parse().map_err(|_| ParseError::Syntax {
+                line: line_number,
+                message: format!("invalid {} `{}`", name, token),
+            })?;
+            self.to_index(id, line_number)
+        };
+
+        let from = parse_id(self.from_column, "from")?;
+        let to = parse_id(self.to_column, "to")?;
+        let token = field(self.capacity_column, "capacity")?;
+        let capacity: Flow = token.parse().map_err(|_| ParseError::Syntax {
+            line: line_number,
+            message: format!("invalid capacity `{}`", token),
+        })?;
+        Ok((from, to, capacity))
+    }
+}
+
+// blank lines and lines starting with `#` are ignored
+pub fn read_edge_list<R: BufRead>(
+    r: R,
+    options: &EdgeListOptions,
+) -> Result<GraphInstance, ParseError> {
+    let mut edges = Vec::new();
+    let mut header_skipped = !options.has_header;
+    for (i, line) in r.lines().enumerate() {
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !header_skipped {
+            header_skipped = true;
+            continue;
+        }
+
+        match options.parse_line(trimmed, i + 1) {
+            Ok(edge) => edges.push(edge),
+            Err(ParseError::Syntax { .. }) if options.skip_malformed => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    let source = match options.source {
+        Some(id) => options.to_index(id, 0)?,
+        None => edges.iter().map(|&(u, v, _)| u.min(v)).min().unwrap_or(0),
+    };
+    let sink = match options.sink {
+        Some(id) => options.to_index(id, 0)?,
+        None => edges.iter().map(|&(u, v, _)| u.max(v)).max().unwrap_or(0),
+    };
+    let num_nodes = edges
+        .iter()
+        .map(|&(u, v, _)| u.max(v) + 1)
+        .chain([source + 1, sink + 1])
+        .max()
+        .unwrap();
+
+    Ok(GraphInstance {
+        num_nodes,
+        edges,
+        source,
+        sink,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::edge_list::{read_edge_list, EdgeListOptions};
+    use crate::io::ParseError;
+
+    #[test]
+    fn csv() {
+        let data = "capacity,from,to\n5,1,2\n7, 2, 3\n\n3,1,3\n";
+        let options = EdgeListOptions {
+            one_based: true,
+            from_column: 1,
+            to_column: 2,
+            capacity_column: 0,
+            ..EdgeListOptions::csv()
+        };
+        let instance = read_edge_list(data.as_bytes(), &options).unwrap();
+        assert_eq!(instance.edges, vec![(0, 1, 5), (1, 2, 7), (0, 2, 3)]);
+        assert_eq!(
+            (instance.num_nodes, instance.source, instance.sink),
+            (3, 0, 2)
+        );
+    }
+
+    #[test]
+    fn whitespace() {
+        let data = "# comment\n1 2 5\n2\t3   7 extra columns\n  3 4 2\n";
+        let options = EdgeListOptions {
+            source: Some(2),
+            sink: Some(6),
+            ..Default::default()
+        };
+        let instance = read_edge_list(data.as_bytes(), &options).unwrap();
+        assert_eq!(instance.edges, vec![(1, 2, 5), (2, 3, 7), (3, 4, 2)]);
+        assert_eq!(
+            (instance.num_nodes, instance.source, instance.sink),
+            (7, 2, 6)
+        );
+    }
+
+    #[test]
+    fn ragged_lines() {
+        let data = "1 2 5\n2 3\n3 x 1\n3 4 2\n";
+        match read_edge_list(data.as_bytes(), &EdgeListOptions::default()) {
+            Err(ParseError::Syntax { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("expected a syntax error"),
+        }
+
+        let options = EdgeListOptions {
+            skip_malformed: true,
+            ..Default::default()
+        };
+        let instance = read_edge_list(data.as_bytes(), &options).unwrap();
+        assert_eq!(instance.edges, vec![(1, 2, 5), (3, 4, 2)]);
+        assert_eq!((instance.source, instance.sink), (1, 4));
+
+        let data = "from,to,capacity\n1,2,5\n0,1,1\n";
+        let options = EdgeListOptions {
+            one_based: true,
+            ..EdgeListOptions::csv()
+        };
+        match read_edge_list(data.as_bytes(), &options) {
+            Err(ParseError::Syntax { line, .. }) => assert_eq!(line, 3),
+            _ => panic!("expected a syntax error"),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::graph::{Flow, Graph, FLOW_MAX};
+use std::io::Write;
+
+fn write_terms<W: Write>(w: &mut W, terms: &[(bool, usize)]) -> std::io::Result<()> {
+    if terms.is_empty() {
+        return write!(w, " 0");
+    }
+    for (k, &(positive, i)) in terms.iter().enumerate() {
+        match (k, positive) {
+            (0, true) => write!(w, " x{}", i)?,
+            (0, false) => write!(w, " -x{}", i)?,
+            (_, true) => write!(w, " + x{}", i)?,
+            (_, false) => write!(w, " - x{}", i)?,
+        }
+    }
+    Ok(())
+}
+
+// CPLEX LP format
+// one variable x{i} per edge bounded by its capacity (unbounded edges only from below), conservation at every node except the terminals,
+// and the objective is the net flow into sink
+pub fn write_lp<W: Write>(
+    mut w: W,
+    graph: &Graph,
+    source: usize,
+    sink: usize,
+) -> std::io::Result<()> {
+    // read from the arcs on a lean graph
+    let edges: Vec<(usize, usize, Flow)> =
+        (0..graph.num_edges).map(|i| graph.edge_ends(i)).collect();
+    let mut terms = vec![Vec::new(); graph.num_nodes.max(sink + 1)];
+    for (i, &(from, to, _)) in edges.iter().enumerate() {
+        terms[to].push((true, i));
+        terms[from].push((false, i));
+    }
+
+    writeln!(w, "\\ maximum flow from node {} to node {}", source, sink)?;
+    writeln!(w, "Maximize")?;
+    write!(w, " obj:")?;
+    write_terms(&mut w, &terms[sink])?;
+    writeln!(w)?;
+
+    writeln!(w, "Subject To")?;
+    for (u, t) in terms.iter().enumerate() {
+        if u == source || u == sink || t.is_empty() {
+            continue;
+        }
+        write!(w, " n{}:", u)?;
+        write_terms(&mut w, t)?;
+        writeln!(w, " = 0")?;
+    }
+
+    writeln!(w, "Bounds")?;
+    for (i, &(_, _, capacity)) in edges.iter().enumerate() {
+        if capacity == FLOW_MAX {
+            writeln!(w, " x{} >= 0", i)?;
+        } else {
+            writeln!(w, " 0 <= x{} <= {}", i, capacity)?;
+        }
+    }
+    writeln!(w, "End")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::graph::{Graph, FLOW_MAX};
+    use crate::io::lp::write_lp;
+
+    #[test]
+    fn sample() {
+        let mut graph = Graph::new();
+        graph.add_directed_edge(0, 1, 2);
+        graph.add_directed_edge(0, 2, 1);
+        graph.add_directed_edge(1, 2, 1);
+        graph.add_directed_edge(1, 3, 1);
+        graph.add_directed_edge(2, 3, 2);
+        graph.add_directed_edge(3, 1, 4);
+
+        let mut buffer = Vec::new();
+        write_lp(&mut buffer, &graph, 0, 3).unwrap();
+        let lp = String::from_utf8(buffer).unwrap();
+
+        let expected = "\\ maximum flow from node 0 to node 3
+Maximize
+ obj: x3 + x4 - x5
+Subject To
+ n1: x0 - x2 - x3 + x5 = 0
+ n2: x1 + x2 - x4 = 0
+Bounds
+ 0 <= x0 <= 2
+ 0 <= x1 <= 1
+ 0 <= x2 <= 1
+ 0 <= x3 <= 1
+ 0 <= x4 <= 2
+ 0 <= x5 <= 4
+End
+";
+        assert_eq!(lp, expected);
+
+        let section = |name: &str| {
+            lp.lines()
+                .skip_while(|l| *l != name)
+                .skip(1)
+                .take_while(|l| l.starts_with(' '))
+                .count()
+        };
+        assert_eq!(section("Subject To"), 2);
+        assert_eq!(section("Bounds"), graph.num_edges);
+    }
+
+    #[test]
+    fn unbounded() {
+        let mut graph = Graph::new();
+        graph.add_directed_edge_unbounded(0, 1);
+        graph.add_directed_edge(1, 2, 3);
+
+        let mut buffer = Vec::new();
+        write_lp(&mut buffer, &graph, 0, 2).unwrap();
+        let lp = String::from_utf8(buffer).unwrap();
+        assert!(lp.ends_with("Bounds\n x0 >= 0\n 0 <= x1 <= 3\nEnd\n"));
+    }
+
+    #[test]
+    fn lean() {
+        let edges = [(0, 1, 2), (0, 2, 1), (1, 2, 1), (1, 3, FLOW_MAX), (2, 3, 2)];
+        let mut graph = Graph::new();
+        for &(from, to, capacity) in edges.iter() {
+            graph.add_directed_edge(from, to, capacity);
+        }
+        let lean = Graph::build_lean(|| edges);
+
+        let lp = |graph: &Graph| {
+            let mut buffer = Vec::new();
+            write_lp(&mut buffer, graph, 0, 3).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        assert!(lp(&lean).contains(" x3 >= 0\n"));
+        assert_eq!(lp(&lean), lp(&graph));
+    }
+}
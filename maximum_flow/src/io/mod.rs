@@ -0,0 +1,211 @@
+use crate::instances::GraphInstance;
+use network_flows_instances as shared;
+use std::fmt;
+use std::io::{BufRead, Write};
+
+mod binary;
+mod edge_list;
+mod lp;
+#[cfg(feature = "mmap")]
+pub use binary::MappedInstance;
+pub use binary::{dimacs_to_binary, read_binary, write_binary};
+pub use edge_list::{read_edge_list, EdgeListOptions};
+pub use lp::write_lp;
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(std::io::Error),
+    Syntax { line: usize, message: String },
+    Binary { offset: usize, message: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "io error: {}", e),
+            ParseError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            ParseError::Binary { offset, message } => {
+                write!(f, "byte offset {}: {}", offset, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            ParseError::Syntax { .. } | ParseError::Binary { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ParseError {
+    fn from(e: std::io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+impl From<shared::ParseError> for ParseError {
+    fn from(e: shared::ParseError) -> Self {
+        match e {
+            shared::ParseError::Io(e) => ParseError::Io(e),
+            shared::ParseError::Syntax { line, message } => ParseError::Syntax { line, message },
+        }
+    }
+}
+
+// the text formats are read and written by network_flows_instances, which generalized_maximum_flow shares
+
+// header: `num_nodes num_edges`, edges: `from to capacity` (0-indexed, source = 0, sink = num_nodes - 1)
+pub fn read_aoj<R: BufRead>(r: R) -> Result<GraphInstance, ParseError> {
+    Ok(shared::read_aoj(r)?.into())
+}
+
+pub fn parse_aoj(data: &str) -> Result<GraphInstance, ParseError> {
+    read_aoj(data.as_bytes())
+}
+
+// header: `num_nodes num_edges source sink`, edges: `from to capacity` (1-indexed)
+pub fn read_libreoj<R: BufRead>(r: R) -> Result<GraphInstance, ParseError> {
+    Ok(shared::read_libreoj(r)?.into())
+}
+
+pub fn parse_libreoj(data: &str) -> Result<GraphInstance, ParseError> {
+    read_libreoj(data.as_bytes())
+}
+
+// DIMACS `p max`, node ids are 1-indexed in the file and 0-indexed in the instance
+pub fn read_dimacs_max<R: BufRead>(r: R) -> Result<GraphInstance, ParseError> {
+    Ok(shared::read_dimacs(r)?.into())
+}
+
+pub fn write_dimacs_max<W: Write>(w: W, instance: &GraphInstance) -> std::io::Result<()> {
+    shared::write_dimacs(w, &instance.into())
+}
+
+// the AOJ format has no source and sink, so they must be 0 and num_nodes - 1
+pub fn write_aoj<W: Write>(w: W, instance: &GraphInstance) -> std::io::Result<()> {
+    shared::write_aoj(w, &instance.into())
+}
+
+pub fn write_libreoj<W: Write>(w: W, instance: &GraphInstance) -> std::io::Result<()> {
+    shared::write_libreoj(w, &instance.into())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::io::{
+        parse_aoj, parse_libreoj, read_aoj, read_dimacs_max, read_libreoj, write_aoj,
+        write_dimacs_max, write_libreoj, ParseError,
+    };
+    use std::io::BufReader;
+
+    fn syntax_error_line(data: &str) -> usize {
+        match read_dimacs_max(data.as_bytes()) {
+            Err(ParseError::Syntax { line, .. }) => line,
+            _ => panic!("expected a syntax error"),
+        }
+    }
+
+    #[test]
+    fn read() {
+        let data = "c sample\np max 4 5\nn 1 s\nn 4 t\nc arcs\na 1 2 2\na 1 3 1\na 2 3 1\na 2 4 1\n\na 3 4 2\n";
+        let instance = read_dimacs_max(data.as_bytes()).unwrap();
+        assert_eq!(instance.num_nodes, 4);
+        assert_eq!(instance.source, 0);
+        assert_eq!(instance.sink, 3);
+        assert_eq!(
+            instance.edges,
+            vec![(0, 1, 2), (0, 2, 1), (1, 2, 1), (1, 3, 1), (2, 3, 2)]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = "p max 3 2\nn 1 s\nn 3 t\na 1 2 5\na 2 3 7\n";
+        let instance = read_dimacs_max(data.as_bytes()).unwrap();
+        let mut buffer = Vec::new();
+        write_dimacs_max(&mut buffer, &instance).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), data);
+
+        let mut buffer = Vec::new();
+        write_libreoj(&mut buffer, &instance).unwrap();
+        assert_eq!(
+            parse_libreoj(std::str::from_utf8(&buffer).unwrap()).unwrap(),
+            instance
+        );
+        let mut buffer = Vec::new();
+        write_aoj(&mut buffer, &instance).unwrap();
+        assert_eq!(
+            parse_aoj(std::str::from_utf8(&buffer).unwrap()).unwrap(),
+            instance
+        );
+
+        let mut swapped = instance.clone();
+        swapped.sink = 1;
+        assert!(write_aoj(Vec::new(), &swapped).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(syntax_error_line("c only comments\n"), 0);
+        assert_eq!(syntax_error_line("a 1 2 3\n"), 1);
+        assert_eq!(syntax_error_line("p max 2 1\np max 2 1\n"), 2);
+        assert_eq!(syntax_error_line("p min 2 1\n"), 1);
+        assert_eq!(syntax_error_line("p max 2 1\nn 1 s\nn 2 t\na 1 3 5\n"), 4);
+        assert_eq!(syntax_error_line("p max 2 1\nn 1 s\nn 2 t\na 1 2 x\n"), 4);
+        assert_eq!(syntax_error_line("p max 2 1\nn 1 s\nn 2 t\na 1 2\n"), 4);
+        assert_eq!(syntax_error_line("p max 2 1\nn 1 s\nn 2 t\na 1 2 -1\n"), 4);
+        assert_eq!(syntax_error_line("p max 2 1\nn 1 s\nn 2 s\n"), 3);
+        assert_eq!(syntax_error_line("p max 2 1\nn 1 s\nx 2 t\n"), 3);
+        assert_eq!(syntax_error_line("p max 2 2\nn 1 s\nn 2 t\na 1 2 5\n"), 0);
+        assert_eq!(syntax_error_line("p max 2 1\nn 1 s\na 1 2 5\n"), 0);
+    }
+
+    #[test]
+    fn aoj_and_libreoj() {
+        let instance = parse_aoj("3 2\n0 1 5\n1 2 7\n").unwrap();
+        assert_eq!((instance.source, instance.sink), (0, 2));
+        assert_eq!(instance.edges, vec![(0, 1, 5), (1, 2, 7)]);
+
+        let instance = parse_libreoj("3 2 1 3\n1 2 5\n2 3 7\n").unwrap();
+        assert_eq!((instance.source, instance.sink), (0, 2));
+        assert_eq!(instance.edges, vec![(0, 1, 5), (1, 2, 7)]);
+
+        assert!(matches!(
+            parse_aoj(""),
+            Err(ParseError::Syntax { line: 0, .. })
+        ));
+        assert!(matches!(
+            parse_aoj("3 2\n0 1\n"),
+            Err(ParseError::Syntax { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse_libreoj("3 1 1 3\n1 2 x\n"),
+            Err(ParseError::Syntax { line: 2, .. })
+        ));
+
+        // lines spanning several buffer fills, blank lines still count towards line numbers
+        let data = "3 2 1 3\n\n1 2 123456789\r\n2 3 7\n";
+        let instance = read_libreoj(BufReader::with_capacity(4, data.as_bytes())).unwrap();
+        assert_eq!(instance.edges, vec![(0, 1, 123456789), (1, 2, 7)]);
+        let data = "3 2\n\n0 1 5\n1 2 x\n";
+        assert!(matches!(
+            read_aoj(BufReader::with_capacity(4, data.as_bytes())),
+            Err(ParseError::Syntax { line: 4, .. })
+        ));
+        assert!(matches!(
+            read_libreoj(BufReader::new(&b"3 2 1 3\n\xff\n"[..])),
+            Err(ParseError::Io(_))
+        ));
+        assert!(matches!(
+            read_libreoj("3 18446744073709551615 1 3\n1 3 5\n".as_bytes()),
+            Err(ParseError::Syntax { line: 0, .. })
+        ));
+        assert!(matches!(
+            parse_aoj("3 3\n0 1 5\n1 2 7\n"),
+            Err(ParseError::Syntax { line: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,332 @@
+use crate::graph::{Edge, Flow, Graph, InsideEdge, FLOW_MAX};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+// returned by Graph::try_get_directed_edge and the other try_ readers of the edges on a graph of Graph::build_lean,
+// which keeps no edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoEdgeList {
+    pub edge_index: usize,
+}
+
+impl fmt::Display for NoEdgeList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "edge {} can't be read back, the graph was built by build_lean without its edges",
+            self.edge_index
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NoEdgeList {}
+
+impl Graph {
+    // O(n + m) in two passes over the edges (from, to, capacity), which edges has to give the same both times
+    // the residual network of build, made right away without the edge list: for the flow value and the min cut
+    // only, the edges can't be read back, nor added, and build clears the flow
+    // as with add_directed_edge, the edges of capacity <= 0 are skipped and the edge indices shift
+    pub fn build_lean<I: IntoIterator<Item = (usize, usize, Flow)>>(
+        edges: impl Fn() -> I,
+    ) -> Graph {
+        let mut graph = Graph {
+            lean: true,
+            ..Default::default()
+        };
+        let mut degree: Vec<usize> = Vec::new();
+        let mut finite_capacity: Flow = 0;
+        for (from, to, capacity) in edges() {
+            if capacity <= 0 {
+                continue;
+            }
+            if degree.len() <= from.max(to) {
+                degree.resize(from.max(to) + 1, 0);
+            }
+            degree[from] += 1;
+            degree[to] += 1;
+            if capacity == FLOW_MAX {
+                graph.lean_unbounded_edges.push(graph.num_edges);
+            } else {
+                finite_capacity = finite_capacity.saturating_add(capacity);
+            }
+            graph.num_edges += 1;
+        }
+        graph.num_nodes = degree.len();
+
+        // as CsrGraph::build, with the capacity of Graph::build for the unbounded edges
+        let mut start = vec![0; graph.num_nodes + 1];
+        for u in 0..graph.num_nodes {
+            start[u + 1] = start[u] + degree[u];
+        }
+        let mut next = degree;
+        next.copy_from_slice(&start[..graph.num_nodes]);
+        let unbounded_capacity = finite_capacity.saturating_add(1);
+        graph.inside_edge_list = (0..2 * graph.num_edges)
+            .map(|_| InsideEdge::default())
+            .collect();
+        graph.edge_index_to_inside_edge_index = Vec::with_capacity(graph.num_edges);
+        for (from, to, capacity) in edges() {
+            if capacity <= 0 {
+                continue;
+            }
+            let edge = Edge {
+                from,
+                to,
+                flow: 0,
+                capacity,
+            };
+            let (mut forward, mut reverse) = edge.arcs(unbounded_capacity);
+            let i = next[from];
+            next[from] += 1;
+            let j = next[to];
+            next[to] += 1;
+            forward.rev = j;
+            reverse.rev = i;
+            graph.inside_edge_list[i] = forward;
+            graph.inside_edge_list[j] = reverse;
+            graph.edge_index_to_inside_edge_index.push(i);
+        }
+        assert_eq!(
+            graph.edge_index_to_inside_edge_index.len(),
+            graph.num_edges,
+            "the edges changed between the passes"
+        );
+        graph.start = start;
+        graph.excesses = vec![0; graph.num_nodes];
+        graph.distance = vec![0; graph.num_nodes];
+        graph
+    }
+
+    // get_directed_edge, or an error on a lean graph
+    pub fn try_get_directed_edge(&self, edge_index: usize) -> Result<Edge, NoEdgeList> {
+        if self.lean {
+            return Err(NoEdgeList { edge_index });
+        }
+        Ok(self.get_directed_edge(edge_index))
+    }
+
+    // decompose_flow, or an error on a lean graph
+    pub fn try_decompose_flow(
+        &self,
+        source: usize,
+        sink: usize,
+    ) -> Result<Vec<(Vec<usize>, Flow)>, NoEdgeList> {
+        self.no_edge_list()?;
+        Ok(self.decompose_flow(source, sink))
+    }
+
+    // the error of the readers of the edges on a lean graph, for the first edge they would read
+    pub(crate) fn no_edge_list(&self) -> Result<(), NoEdgeList> {
+        if self.lean {
+            return Err(NoEdgeList { edge_index: 0 });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::dot::DotOptions;
+    use crate::generators::{random_graph, Rng};
+    use crate::graph::{Flow, FlowViolation, Graph, FLOW_MAX};
+    use crate::lean::NoEdgeList;
+    use crate::push_relabel_fifo::PushRelabelFIFO;
+    use crate::push_relabel_highest_label::PushRelabelHighestLabel;
+    use crate::solver::MaxFlowSolver;
+
+    fn lean(edges: &[(usize, usize, Flow)]) -> Graph {
+        Graph::build_lean(|| edges.iter().copied())
+    }
+
+    #[test]
+    fn same_layout_as_build() {
+        let mut rng = Rng::new(8);
+        for _ in 0..100 {
+            let n = rng.gen_range(1, 20) as usize;
+            // with self loops, unbounded edges and capacities the graph rejects
+            let edges: Vec<(usize, usize, Flow)> = (0..rng.gen_range(0, 60))
+                .map(|_| {
+                    let capacity = match rng.gen_range(0, 10) {
+                        0 => FLOW_MAX,
+                        1 => 0,
+                        _ => rng.gen_capacity(10),
+                    };
+                    (
+                        rng.gen_range(0, n as u64) as usize,
+                        rng.gen_range(0, n as u64) as usize,
+                        capacity,
+                    )
+                })
+                .collect();
+            let mut graph = Graph::new();
+            for &(from, to, capacity) in edges.iter() {
+                graph.add_directed_edge(from, to, capacity);
+            }
+            graph.build();
+            let lean = lean(&edges);
+            assert_eq!(
+                (lean.num_nodes, lean.num_edges),
+                (graph.num_nodes, graph.num_edges)
+            );
+            assert!(lean.edge_list.is_empty());
+            assert_eq!(lean.start, graph.start);
+            assert_eq!(
+                lean.edge_index_to_inside_edge_index,
+                graph.edge_index_to_inside_edge_index
+            );
+            let arcs = |g: &Graph| -> Vec<(usize, Flow, Flow, usize)> {
+                g.inside_edge_list
+                    .iter()
+                    .map(|a| (a.to, a.flow, a.capacity, a.rev))
+                    .collect()
+            };
+            assert_eq!(arcs(&lean), arcs(&graph));
+        }
+    }
+
+    #[test]
+    fn solvers_on_a_lean_graph() {
+        for seed in 0..20 {
+            let instance = random_graph(50, 300, 30, seed);
+            let (source, sink) = (instance.source, instance.sink);
+            let mut solver: Dinic = instance.build_solver();
+            let expected = solver.solve(source, sink);
+            let mut dinic = Dinic::from_graph(lean(&instance.edges));
+            assert_eq!(dinic.solve(source, sink), expected);
+            // build clears the flow of the first solve
+            assert_eq!(dinic.solve(source, sink), expected);
+            let mut fifo = PushRelabelFIFO::from_graph(lean(&instance.edges));
+            assert_eq!(fifo.solve(source, sink), expected);
+            let mut highest_label = PushRelabelHighestLabel::from_graph(lean(&instance.edges));
+            assert_eq!(highest_label.solve(source, sink), expected);
+            assert_eq!(
+                highest_label.graph().min_cut(sink),
+                solver.graph().min_cut(sink)
+            );
+        }
+    }
+
+    #[test]
+    fn no_edges_to_read_back() {
+        let mut dinic = Dinic::from_graph(lean(&[(0, 1, FLOW_MAX), (1, 2, 3), (0, 2, 2)]));
+        assert_eq!(dinic.solve(0, 2), 5);
+        assert_eq!(dinic.solve(0, 1), FLOW_MAX);
+        let graph = dinic.graph();
+        assert_eq!(
+            graph.try_get_directed_edge(1).unwrap_err(),
+            NoEdgeList { edge_index: 1 }
+        );
+        let mut graph = Graph::new();
+        graph.add_directed_edge(0, 1, 4);
+        assert_eq!(graph.try_get_directed_edge(0).map(|e| e.capacity), Ok(4));
+    }
+
+    // read from the arcs, with the parallel edges of the same ends and an unbounded one among them
+    #[test]
+    fn cut_capacity_and_unbounded_edges() {
+        let edges = [(0, 1, 2), (0, 1, FLOW_MAX), (1, 2, 3), (1, 2, 1), (0, 2, 2)];
+        let mut dinic = Dinic::from_graph(lean(&edges));
+        assert_eq!(dinic.solve(0, 2), 6);
+        let graph = dinic.graph();
+        let unbounded: Vec<bool> = (0..edges.len()).map(|i| graph.is_unbounded(i)).collect();
+        assert_eq!(unbounded, [false, true, false, false, false]);
+        assert_eq!(graph.cut_capacity(&graph.min_cut(2)), Some(6));
+        assert_eq!(graph.cut_capacity(&[true, false, false]), None);
+        assert_eq!(graph.cut_capacity(&[true, true, false]), Some(6));
+        assert!(graph.has_unbounded_path(0, 1) && !graph.has_unbounded_path(0, 2));
+
+        for seed in 0..10 {
+            let instance = random_graph(40, 200, 20, seed);
+            let mut solver: Dinic = instance.build_solver();
+            let expected = solver.solve(instance.source, instance.sink);
+            let mut dinic = Dinic::from_graph(lean(&instance.edges));
+            dinic.solve(instance.source, instance.sink);
+            let graph = dinic.graph();
+            assert_eq!(
+                graph.cut_capacity(&graph.min_cut(instance.sink)),
+                Some(expected)
+            );
+        }
+    }
+
+    // the readers of the edges give an error, and the ones that change them leave the graph as it is
+    #[test]
+    fn readers_of_the_edges() {
+        let mut dinic = Dinic::from_graph(lean(&[(0, 1, 2), (1, 2, 3)]));
+        assert_eq!(dinic.solve(0, 2), 2);
+        let error = NoEdgeList { edge_index: 0 };
+        let graph = dinic.graph();
+        assert_eq!(graph.try_snapshot(2).unwrap_err(), error);
+        assert_eq!(graph.try_to_dot(&DotOptions::default()).unwrap_err(), error);
+        assert_eq!(graph.try_decompose_flow(0, 2).unwrap_err(), error);
+        assert_eq!(graph.validate_flow(0, 2), Err(FlowViolation::NoEdgeList));
+
+        let mut graph = lean(&[(0, 1, 2), (1, 2, 3)]);
+        assert_eq!(
+            graph.try_preprocess(0, 2),
+            Err(NoEdgeList { edge_index: 0 })
+        );
+        assert_eq!(
+            graph.try_reduce_series_parallel(0, 2),
+            Err(NoEdgeList { edge_index: 0 })
+        );
+        assert_eq!((graph.num_edges, graph.inside_edge_list.len()), (2, 4));
+        assert_eq!(PushRelabelFIFO::from_graph(graph).solve(0, 2), 2);
+
+        let mut graph = Graph::new();
+        graph.add_directed_edge(0, 1, 2);
+        graph.add_directed_edge(1, 2, 3);
+        assert!(graph.try_to_dot(&DotOptions::default()).is_ok());
+        assert_eq!(graph.try_snapshot(2).map(|s| s.num_edges()), Ok(2));
+        assert_eq!(graph.try_decompose_flow(0, 2), Ok(Vec::new()));
+        assert_eq!(
+            graph
+                .try_reduce_series_parallel(0, 2)
+                .map(|m| m.edges.len()),
+            Ok(1)
+        );
+        assert_eq!(
+            graph.try_preprocess(0, 2).map(|r| r.kept_edges),
+            Ok(vec![0])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "keeps no edges")]
+    fn snapshot_panics() {
+        lean(&[(0, 1, 1)]).snapshot(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "keeps no edges")]
+    fn to_dot_panics() {
+        lean(&[(0, 1, 1)]).to_dot(&DotOptions::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "keeps no edges")]
+    fn decompose_flow_panics() {
+        lean(&[(0, 1, 1)]).decompose_flow(0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "keeps no edges")]
+    fn preprocess_panics() {
+        lean(&[(0, 1, 1)]).preprocess(0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "keeps no edges")]
+    fn reduce_series_parallel_panics() {
+        lean(&[(0, 1, 1)]).reduce_series_parallel(0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "keeps no edges")]
+    fn get_directed_edge_panics() {
+        lean(&[(0, 1, 1)]).get_directed_edge(0);
+    }
+}
@@ -1,7 +1,42 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod bipartite_matching;
 pub mod capacity_scaling;
+pub mod control;
+pub mod cycle_canceling;
+#[cfg(feature = "std")]
+pub mod debugging;
 pub mod dinic;
+#[cfg(feature = "std")]
+pub mod dot;
+pub mod edmonds_karp;
+pub mod flows_over_time;
 pub mod ford_fulkerson;
+#[cfg(feature = "std")]
+pub mod fuzzing;
+#[cfg(feature = "std")]
+pub mod generators;
 pub mod graph;
+#[cfg(feature = "std")]
+pub mod instances;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod lean;
+pub mod min_cost_flow;
+#[cfg(feature = "rayon")]
+mod parallel_build;
+pub mod preprocessing;
+#[cfg(feature = "tracing")]
+pub mod profiling;
 pub mod push_relabel_fifo;
 pub mod push_relabel_highest_label;
+#[cfg(feature = "serde")]
+pub mod serialization;
+pub mod snapshot;
+pub mod solver;
+#[cfg(feature = "std")]
 pub mod test_utility;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,377 @@
+use crate::graph::{decompose_flow, Flow};
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use network_flows_core::{CsrGraph, EdgePayload};
+
+pub type Cost = i64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostEdge {
+    pub from: usize,
+    pub to: usize,
+    pub flow: Flow,
+    pub capacity: Flow,
+    pub cost: Cost,
+}
+
+// the reverse arc of an edge has cost -cost, and flow capacity - flow like the arcs of Graph
+#[derive(Debug, Default)]
+pub struct CostArc {
+    pub to: usize,
+    pub flow: Flow,
+    pub capacity: Flow,
+    pub cost: Cost,
+    pub rev: usize,
+}
+
+impl CostArc {
+    #[inline]
+    pub fn residual_capacity(&self) -> Flow {
+        self.capacity - self.flow
+    }
+}
+
+impl EdgePayload for CostArc {
+    fn to(&self) -> usize {
+        self.to
+    }
+
+    fn rev(&self) -> usize {
+        self.rev
+    }
+
+    fn set_rev(&mut self, rev: usize) {
+        self.rev = rev;
+    }
+}
+
+// the residual network of edges that carry their flow already, see Graph::build
+pub(crate) fn build_cost_graph(num_nodes: usize, edges: &[CostEdge]) -> CsrGraph<CostArc> {
+    CsrGraph::build(
+        num_nodes,
+        edges.iter().map(|e| {
+            (
+                e.from,
+                e.to,
+                CostArc {
+                    to: e.to,
+                    flow: e.flow,
+                    capacity: e.capacity,
+                    cost: e.cost,
+                    rev: usize::MAX,
+                },
+                CostArc {
+                    to: e.from,
+                    flow: e.capacity - e.flow,
+                    capacity: e.capacity,
+                    cost: -e.cost,
+                    rev: usize::MAX,
+                },
+            )
+        }),
+    )
+}
+
+// successive shortest paths: Dijkstra on the costs reduced by node potentials, which Bellman-Ford initializes if
+// there are negative costs
+// the network must not have a cycle of negative cost
+#[derive(Default)]
+pub struct MinCostFlow {
+    num_nodes: usize,
+    edge_list: Vec<CostEdge>,
+    graph: CsrGraph<CostArc>,
+    potential: Vec<Cost>,
+    augmentations: usize,
+}
+
+impl MinCostFlow {
+    pub fn new() -> Self {
+        MinCostFlow::default()
+    }
+
+    pub fn add_directed_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        capacity: Flow,
+        cost: Cost,
+    ) -> Option<usize> {
+        if capacity <= 0 {
+            return None;
+        }
+        self.edge_list.push(CostEdge {
+            from,
+            to,
+            flow: 0,
+            capacity,
+            cost,
+        });
+        self.num_nodes = self.num_nodes.max(from.max(to) + 1);
+        Some(self.edge_list.len() - 1)
+    }
+
+    // the flow is read from the residual network once the graph is built
+    pub fn get_directed_edge(&self, edge_index: usize) -> CostEdge {
+        let mut edge = self.edge_list[edge_index].clone();
+        if edge_index < self.graph.edge_index.len() {
+            edge.flow = self.graph.forward(edge_index).flow;
+        }
+        edge
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.edge_list.len()
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    // the shortest paths of the last solve
+    pub fn augmentations(&self) -> usize {
+        self.augmentations
+    }
+
+    // a maximum flow of minimum cost, (flow, cost)
+    pub fn solve(&mut self, source: usize, sink: usize) -> (Flow, Cost) {
+        self.solve_with_limit(source, sink, Flow::MAX, Cost::MAX)
+    }
+
+    // O(flow_limit * m log n), and O(nm) more with negative costs
+    // augments along shortest paths while they cost less than path_cost_limit per unit, and until flow_limit units
+    // are sent: the result is a flow of minimum cost among those of its value
+    pub fn solve_with_limit(
+        &mut self,
+        source: usize,
+        sink: usize,
+        flow_limit: Flow,
+        path_cost_limit: Cost,
+    ) -> (Flow, Cost) {
+        self.build();
+        self.augmentations = 0;
+        let (mut flow, mut cost) = (0, 0);
+        if source == sink || source >= self.num_nodes || sink >= self.num_nodes {
+            return (flow, cost);
+        }
+        self.initialize_potential(source);
+
+        while flow < flow_limit {
+            let parent = self.dijkstra(source);
+            if parent[sink] == usize::MAX {
+                break;
+            }
+            let path_cost = self.potential[sink] - self.potential[source];
+            if path_cost >= path_cost_limit {
+                break;
+            }
+
+            let mut delta = flow_limit - flow;
+            let mut v = sink;
+            while v != source {
+                let arc = &self.graph.arcs[parent[v]];
+                delta = delta.min(arc.residual_capacity());
+                v = self.graph.arcs[arc.rev].to;
+            }
+            let mut v = sink;
+            while v != source {
+                let i = parent[v];
+                let rev = self.graph.arcs[i].rev;
+                self.graph.arcs[i].flow += delta;
+                self.graph.arcs[rev].flow -= delta;
+                v = self.graph.arcs[rev].to;
+            }
+            self.augmentations += 1;
+            flow += delta;
+            cost += delta * path_cost;
+        }
+        (flow, cost)
+    }
+
+    // O(nm), see Graph::decompose_flow
+    pub fn decompose_flow(&self, source: usize, sink: usize) -> Vec<(Vec<usize>, Flow)> {
+        let edges: Vec<(usize, usize, Flow)> = (0..self.num_edges())
+            .map(|i| {
+                let e = self.get_directed_edge(i);
+                (e.from, e.to, e.flow)
+            })
+            .collect();
+        decompose_flow(self.num_nodes, &edges, source, sink)
+    }
+
+    fn build(&mut self) {
+        self.graph = build_cost_graph(self.num_nodes, &self.edge_list);
+    }
+
+    // O(nm) Bellman-Ford from the source if a cost is negative, so that the reduced costs are not
+    fn initialize_potential(&mut self, source: usize) {
+        self.potential = vec![0; self.num_nodes];
+        if self.edge_list.iter().all(|e| e.cost >= 0) {
+            return;
+        }
+        let mut distance = vec![Cost::MAX; self.num_nodes];
+        distance[source] = 0;
+        for round in 0..self.num_nodes {
+            let mut updated = false;
+            for e in self.edge_list.iter() {
+                if distance[e.from] != Cost::MAX && distance[e.from] + e.cost < distance[e.to] {
+                    distance[e.to] = distance[e.from] + e.cost;
+                    updated = true;
+                }
+            }
+            if !updated {
+                break;
+            }
+            assert!(round + 1 < self.num_nodes, "negative cycle");
+        }
+        // the nodes the source can't reach stay unreachable
+        for (p, d) in self.potential.iter_mut().zip(distance) {
+            if d != Cost::MAX {
+                *p = d;
+            }
+        }
+    }
+
+    // Dijkstra on the reduced costs, the arc into each node on a shortest path (usize::MAX if unreachable) after
+    // adding the distances to the potentials
+    fn dijkstra(&mut self, source: usize) -> Vec<usize> {
+        let mut distance = vec![Cost::MAX; self.num_nodes];
+        let mut parent = vec![usize::MAX; self.num_nodes];
+        let mut heap = BinaryHeap::new();
+        distance[source] = 0;
+        heap.push(Reverse((0, source)));
+        while let Some(Reverse((d, u))) = heap.pop() {
+            if d > distance[u] {
+                continue;
+            }
+            for i in self.graph.start[u]..self.graph.start[u + 1] {
+                let arc = &self.graph.arcs[i];
+                if arc.residual_capacity() == 0 {
+                    continue;
+                }
+                let reduced_cost = arc.cost + self.potential[u] - self.potential[arc.to];
+                debug_assert!(reduced_cost >= 0);
+                if d + reduced_cost < distance[arc.to] {
+                    distance[arc.to] = d + reduced_cost;
+                    parent[arc.to] = i;
+                    heap.push(Reverse((distance[arc.to], arc.to)));
+                }
+            }
+        }
+        for (p, d) in self.potential.iter_mut().zip(distance) {
+            if d != Cost::MAX {
+                *p += d;
+            }
+        }
+        parent
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::generators::Rng;
+    use crate::graph::Flow;
+    use crate::min_cost_flow::{Cost, MinCostFlow};
+
+    fn example() -> MinCostFlow {
+        let mut solver = MinCostFlow::new();
+        for (from, to, capacity, cost) in [
+            (0, 1, 2, 1),
+            (0, 2, 1, 2),
+            (1, 2, 1, 1),
+            (1, 3, 1, 3),
+            (2, 3, 2, 1),
+        ] {
+            solver.add_directed_edge(from, to, capacity, cost);
+        }
+        solver
+    }
+
+    // optimality: no cycle of negative cost in the residual network, by Bellman-Ford from every node at once
+    fn assert_no_negative_cycle(solver: &MinCostFlow) {
+        let graph = &solver.graph;
+        let mut distance = vec![0 as Cost; solver.num_nodes()];
+        for _ in 0..=solver.num_nodes() {
+            let mut updated = false;
+            for u in 0..solver.num_nodes() {
+                for arc in graph.neighbors(u) {
+                    if arc.residual_capacity() > 0 && distance[u] + arc.cost < distance[arc.to] {
+                        distance[arc.to] = distance[u] + arc.cost;
+                        updated = true;
+                    }
+                }
+            }
+            if !updated {
+                return;
+            }
+        }
+        panic!("negative cycle in the residual network");
+    }
+
+    #[test]
+    fn known_cost() {
+        let mut solver = example();
+        assert_eq!(solver.solve(0, 3), (3, 10));
+        assert_no_negative_cycle(&solver);
+        assert_eq!(solver.get_directed_edge(3).flow, 1);
+        let paths = solver.decompose_flow(0, 3);
+        let cost: Cost = paths
+            .iter()
+            .map(|(edges, flow)| {
+                flow * edges
+                    .iter()
+                    .map(|&i| solver.get_directed_edge(i).cost)
+                    .sum::<Cost>()
+            })
+            .sum();
+        assert_eq!(cost, 10);
+
+        assert_eq!(solver.solve_with_limit(0, 3, 1, Cost::MAX), (1, 3));
+        assert_eq!(solver.solve_with_limit(0, 3, 2, Cost::MAX), (2, 6));
+        // only the paths of cost 3
+        assert_eq!(solver.solve_with_limit(0, 3, Flow::MAX, 4), (2, 6));
+        assert_eq!(solver.solve_with_limit(0, 3, Flow::MAX, 3), (0, 0));
+        assert_eq!(solver.solve(3, 0), (0, 0));
+        assert_eq!(solver.add_directed_edge(0, 3, 0, 1), None);
+    }
+
+    #[test]
+    fn negative_costs() {
+        // the path 0 -> 2 -> 3 of cost -1 first
+        let mut solver = example();
+        solver.add_directed_edge(0, 2, 1, -2);
+        assert_eq!(solver.solve_with_limit(0, 3, 1, Cost::MAX), (1, -1));
+        assert_eq!(solver.solve(0, 3), (3, 6));
+        assert_no_negative_cycle(&solver);
+    }
+
+    #[test]
+    fn random_dags() {
+        let mut rng = Rng::new(3);
+        for _ in 0..200 {
+            let n = rng.gen_range(2, 8) as usize;
+            let mut solver = MinCostFlow::new();
+            let mut dinic = Dinic::new();
+            for _ in 0..rng.gen_range(0, 20) {
+                // from < to, so that negative costs make no cycle
+                let from = rng.gen_range(0, n as u64 - 1) as usize;
+                let to = rng.gen_range(from as u64 + 1, n as u64) as usize;
+                let capacity = rng.gen_capacity(10);
+                solver.add_directed_edge(from, to, capacity, rng.gen_range(0, 15) as Cost - 5);
+                dinic.add_directed_edge(from, to, capacity);
+            }
+            let (flow, cost) = solver.solve(0, n - 1);
+            assert_eq!(flow, dinic.solve(0, n - 1));
+            assert_no_negative_cycle(&solver);
+            let edge_cost: Cost = (0..solver.num_edges())
+                .map(|i| {
+                    let e = solver.get_directed_edge(i);
+                    e.flow * e.cost
+                })
+                .sum();
+            assert_eq!(edge_cost, cost);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+// Graph::build on the threads of rayon, for the layout of CsrGraph::build: the edges are split into chunks, each
+// counts the arcs it puts at each node, the prefix sums over the nodes and then over the chunks give each chunk the
+// position of its first arc at each node, and each chunk places its arcs from there in the order of its edges
+// the positions are written into a slot per arc, from which the arcs are made with their revs
+use crate::graph::{Edge, Flow, InsideEdge};
+use network_flows_core::CsrGraph;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// from this many edges on, with more than one thread
+pub(crate) const PARALLEL_BUILD_MIN_EDGES: usize = 1 << 16;
+
+// the nodes a thread takes at once
+const BLOCK: usize = 1 << 14;
+
+pub(crate) fn build(
+    num_nodes: usize,
+    edges: &[Edge],
+    unbounded_capacity: Flow,
+) -> CsrGraph<InsideEdge> {
+    // the counts take chunks * num_nodes, which stays below the arcs
+    let chunks = rayon::current_num_threads().min(1 + 4 * edges.len() / num_nodes.max(1));
+    build_in_chunks(num_nodes, edges, unbounded_capacity, chunks)
+}
+
+// O(chunks * n + m) work
+fn build_in_chunks(
+    num_nodes: usize,
+    edges: &[Edge],
+    unbounded_capacity: Flow,
+    chunks: usize,
+) -> CsrGraph<InsideEdge> {
+    let chunk_len = edges.len().div_ceil(chunks).max(1);
+    let mut counts: Vec<Vec<usize>> = edges
+        .par_chunks(chunk_len)
+        .map(|chunk| {
+            let mut count = vec![0; num_nodes];
+            for e in chunk.iter() {
+                count[e.from] += 1;
+                count[e.to] += 1;
+            }
+            count
+        })
+        .collect();
+    let degree: Vec<usize> = (0..num_nodes)
+        .into_par_iter()
+        .map(|u| counts.iter().map(|count| count[u]).sum())
+        .collect();
+    let start = prefix_sums(&degree);
+
+    // the counts become the positions of the first arcs of the chunks, a block of nodes of every chunk at a time
+    let mut blocks: Vec<Vec<&mut [usize]>> =
+        (0..num_nodes.div_ceil(BLOCK)).map(|_| Vec::new()).collect();
+    for count in counts.iter_mut() {
+        for (block, part) in blocks.iter_mut().zip(count.chunks_mut(BLOCK)) {
+            block.push(part);
+        }
+    }
+    blocks
+        .into_par_iter()
+        .enumerate()
+        .for_each(|(b, mut block)| {
+            let nodes = b * BLOCK..num_nodes.min((b + 1) * BLOCK);
+            for (j, &first) in start[nodes].iter().enumerate() {
+                let mut position = first;
+                for part in block.iter_mut() {
+                    let count = part[j];
+                    part[j] = position;
+                    position += count;
+                }
+            }
+        });
+
+    // (forward, reverse) positions of each edge, the forward arc of a self loop first
+    let ends: Vec<(usize, usize)> = edges
+        .par_chunks(chunk_len)
+        .zip(counts.par_iter_mut())
+        .flat_map_iter(|(chunk, next)| {
+            chunk.iter().map(move |e| {
+                let forward = next[e.from];
+                next[e.from] += 1;
+                let reverse = next[e.to];
+                next[e.to] += 1;
+                (forward, reverse)
+            })
+        })
+        .collect();
+
+    // 2 * edge for the forward arc and 2 * edge + 1 for the reverse one, each slot is written once
+    let slots: Vec<AtomicUsize> = (0..2 * edges.len())
+        .into_par_iter()
+        .map(|_| AtomicUsize::new(0))
+        .collect();
+    ends.par_iter()
+        .enumerate()
+        .for_each(|(i, &(forward, reverse))| {
+            slots[forward].store(2 * i, Ordering::Relaxed);
+            slots[reverse].store(2 * i + 1, Ordering::Relaxed);
+        });
+    let arcs = slots
+        .into_par_iter()
+        .map(|slot| {
+            let slot = slot.into_inner();
+            let (forward, reverse) = ends[slot / 2];
+            let (mut forward_arc, mut reverse_arc) = edges[slot / 2].arcs(unbounded_capacity);
+            if slot % 2 == 0 {
+                forward_arc.rev = reverse;
+                forward_arc
+            } else {
+                reverse_arc.rev = forward;
+                reverse_arc
+            }
+        })
+        .collect();
+    let edge_index = ends.par_iter().map(|&(forward, _)| forward).collect();
+
+    CsrGraph {
+        num_nodes,
+        start,
+        arcs,
+        edge_index,
+    }
+}
+
+// the n + 1 sums of the first 0..=n values, the blocks summed on the threads
+fn prefix_sums(values: &[usize]) -> Vec<usize> {
+    let totals: Vec<usize> = values
+        .par_chunks(BLOCK)
+        .map(|block| block.iter().sum())
+        .collect();
+    let offsets: Vec<usize> = totals
+        .iter()
+        .scan(0, |sum, &total| {
+            *sum += total;
+            Some(*sum - total)
+        })
+        .collect();
+    let mut sums = vec![0; values.len() + 1];
+    sums[1..]
+        .par_chunks_mut(BLOCK)
+        .zip(values.par_chunks(BLOCK))
+        .zip(offsets)
+        .for_each(|((sums, values), mut sum)| {
+            for (s, &v) in sums.iter_mut().zip(values) {
+                sum += v;
+                *s = sum;
+            }
+        });
+    sums
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::generators::{random_graph, Rng};
+    use crate::graph::{Edge, InsideEdge, FLOW_MAX};
+    use crate::parallel_build::{build_in_chunks, prefix_sums, BLOCK, PARALLEL_BUILD_MIN_EDGES};
+    use network_flows_core::CsrGraph;
+
+    fn arcs(graph: &CsrGraph<InsideEdge>) -> Vec<(usize, i64, i64, usize)> {
+        graph
+            .arcs
+            .iter()
+            .map(|a| (a.to, a.flow, a.capacity, a.rev))
+            .collect()
+    }
+
+    fn assert_same_layout(num_nodes: usize, edges: &[Edge], chunks: usize) {
+        let expected = CsrGraph::build(
+            num_nodes,
+            edges.iter().map(|e| {
+                let (forward, reverse) = e.arcs(1000);
+                (e.from, e.to, forward, reverse)
+            }),
+        );
+        let graph = build_in_chunks(num_nodes, edges, 1000, chunks);
+        assert_eq!(graph.start, expected.start);
+        assert_eq!(graph.edge_index, expected.edge_index);
+        assert_eq!(arcs(&graph), arcs(&expected));
+    }
+
+    #[test]
+    fn same_layout_as_the_sequential_build() {
+        let mut rng = Rng::new(5);
+        for _ in 0..200 {
+            let num_nodes = rng.gen_range(1, 40) as usize;
+            // with self loops, parallel edges, unbounded edges and nodes without edges
+            let edges: Vec<Edge> = (0..rng.gen_range(0, 100))
+                .map(|_| Edge {
+                    from: rng.gen_range(0, num_nodes as u64) as usize,
+                    to: rng.gen_range(0, num_nodes as u64) as usize,
+                    flow: 0,
+                    capacity: if rng.gen_range(0, 10) == 0 {
+                        FLOW_MAX
+                    } else {
+                        rng.gen_capacity(10)
+                    },
+                })
+                .collect();
+            for chunks in [1, 2, 3, 7, 64] {
+                assert_same_layout(num_nodes, &edges, chunks);
+            }
+        }
+    }
+
+    // the nodes over several blocks
+    #[test]
+    fn blocks_of_nodes() {
+        let instance = random_graph(3 * BLOCK + 5, 10 * BLOCK, 100, 1);
+        let edges: Vec<Edge> = instance
+            .edges
+            .iter()
+            .map(|&(from, to, capacity)| Edge {
+                from,
+                to,
+                flow: 0,
+                capacity,
+            })
+            .collect();
+        assert_same_layout(instance.num_nodes, &edges, 5);
+
+        let values: Vec<usize> = (0..2 * BLOCK + 3).map(|v| v % 7).collect();
+        let mut sum = 0;
+        let expected: Vec<usize> = std::iter::once(0)
+            .chain(values.iter().map(|v| {
+                sum += v;
+                sum
+            }))
+            .collect();
+        assert_eq!(prefix_sums(&values), expected);
+    }
+
+    #[test]
+    fn build_on_four_threads() {
+        let instance = random_graph(10_000, PARALLEL_BUILD_MIN_EDGES + 1, 100, 2);
+        let expected = instance.solve::<Dinic>();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let (flow, graph) = pool.install(|| {
+            let mut graph = instance.build_graph();
+            graph.build();
+            (
+                instance
+                    .build_solver::<Dinic>()
+                    .solve(instance.source, instance.sink),
+                graph,
+            )
+        });
+        assert_eq!(flow, expected);
+        let sequential = CsrGraph::build(
+            graph.num_nodes,
+            graph.edge_list.iter().map(|e| {
+                let (forward, reverse) = e.arcs(FLOW_MAX);
+                (e.from, e.to, forward, reverse)
+            }),
+        );
+        assert_eq!(graph.start, sequential.start);
+        assert_eq!(graph.edge_index_to_inside_edge_index, sequential.edge_index);
+    }
+}
@@ -0,0 +1,583 @@
+use crate::graph::{Edge, Flow, Graph};
+use crate::lean::NoEdgeList;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+
+// what Graph::preprocess removed and clamped, edge ids are those of the graph before
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreprocessReport {
+    pub original_num_edges: usize,
+    // kept_edges[i] is the edge before of edge i after
+    pub kept_edges: Vec<usize>,
+    pub removed_edges: Vec<usize>,
+    // the nodes other than source and sink whose edges were all removed
+    pub removed_nodes: Vec<usize>,
+    // (edge, capacity before, capacity after)
+    pub clamped_edges: Vec<(usize, Flow, Flow)>,
+}
+
+impl PreprocessReport {
+    // the flow of every edge before from a solved graph of the edges after, 0 on the removed ones
+    pub fn expand_flow(&self, graph: &Graph) -> Vec<Flow> {
+        let mut flows = vec![0; self.original_num_edges];
+        for (i, &edge) in self.kept_edges.iter().enumerate() {
+            flows[edge] = graph.get_directed_edge(i).flow;
+        }
+        flows
+    }
+}
+
+// an edge of Graph::reduce_series_parallel, made of the edges before
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reduced {
+    Edge(usize),
+    // a path of two reduced edges, the ids are those of ReductionMap::parts
+    Series(usize, usize),
+    Parallel(usize, usize),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReductionMap {
+    pub original_num_edges: usize,
+    // the parts the reduced edges are built of and their capacities
+    pub parts: Vec<(Reduced, Flow)>,
+    // edges[i] is the part of edge i after
+    pub edges: Vec<usize>,
+}
+
+impl ReductionMap {
+    // the flow of every edge before from a solved graph of the edges after: the flow of a series part goes through
+    // both, that of a parallel part fills the first up to its capacity and the second with the rest
+    pub fn expand_flow(&self, graph: &Graph) -> Vec<Flow> {
+        let mut flows = vec![0; self.original_num_edges];
+        let mut stack: Vec<(usize, Flow)> = self
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(i, &part)| (part, graph.get_directed_edge(i).flow))
+            .collect();
+        while let Some((part, flow)) = stack.pop() {
+            match self.parts[part].0 {
+                Reduced::Edge(edge) => flows[edge] = flow,
+                Reduced::Series(a, b) => stack.extend([(a, flow), (b, flow)]),
+                Reduced::Parallel(a, b) => {
+                    let first = flow.min(self.parts[a].1);
+                    stack.extend([(a, first), (b, flow - first)]);
+                }
+            }
+        }
+        flows
+    }
+}
+
+// the state of Graph::reduce_series_parallel
+struct SeriesParallel {
+    // (from, to, part) of the edges, None once merged into another
+    edges: Vec<Option<(usize, usize, usize)>>,
+    edge_of_pair: BTreeMap<(usize, usize), usize>,
+    in_edges: Vec<Vec<usize>>,
+    out_edges: Vec<Vec<usize>>,
+    in_degree: Vec<usize>,
+    out_degree: Vec<usize>,
+    parts: Vec<(Reduced, Flow)>,
+}
+
+impl SeriesParallel {
+    // a parallel edge is merged into the one there is
+    fn add(&mut self, from: usize, to: usize, part: usize) {
+        if let Some(&j) = self.edge_of_pair.get(&(from, to)) {
+            if let Some((_, _, other)) = self.edges[j] {
+                let capacity = self.parts[other].1.saturating_add(self.parts[part].1);
+                self.parts.push((Reduced::Parallel(other, part), capacity));
+                self.edges[j] = Some((from, to, self.parts.len() - 1));
+                return;
+            }
+        }
+        let i = self.edges.len();
+        self.edge_of_pair.insert((from, to), i);
+        self.in_edges[to].push(i);
+        self.out_edges[from].push(i);
+        self.in_degree[to] += 1;
+        self.out_degree[from] += 1;
+        self.edges.push(Some((from, to, part)));
+    }
+
+    fn remove(&mut self, i: usize) {
+        let (from, to, _) = self.edges[i].take().unwrap();
+        self.in_degree[to] -= 1;
+        self.out_degree[from] -= 1;
+    }
+
+    // the first edge of the list that is not merged
+    fn alive(&self, list: &[usize]) -> usize {
+        *list.iter().find(|&&i| self.edges[i].is_some()).unwrap()
+    }
+}
+
+impl Graph {
+    // O(n(n + m)) at most, usually a few rounds of O(n + m)
+    // removes the edges no flow from source to sink needs, those out of a node the source can't reach, into a node
+    // that can't reach the sink, into the source, out of the sink and the self loops, which takes dead end chains and
+    // the nodes without in- or out-capacity along
+    // then clamps the capacity of every edge to the total capacity into its tail and out of its head, as the flow
+    // through a node other than source and sink is bounded by both: every feasible flow stays feasible, so the
+    // maximum flow value is unchanged
+    // the edges are renumbered and the graph must be built again
+    // panics on a lean graph, see Graph::try_preprocess
+    pub fn preprocess(&mut self, source: usize, sink: usize) -> PreprocessReport {
+        assert!(
+            !self.lean,
+            "a graph built by build_lean keeps no edges to preprocess"
+        );
+        let n = self.num_nodes;
+        let mut capacity: Vec<Flow> = self.edge_list.iter().map(|e| e.capacity).collect();
+        let mut alive: Vec<bool> = self
+            .edge_list
+            .iter()
+            .map(|e| e.from != e.to && e.to != source && e.from != sink)
+            .collect();
+        if source >= n || sink >= n || source == sink {
+            alive.fill(false);
+        } else {
+            let forward = self.reachable(source, &alive, false);
+            let backward = self.reachable(sink, &alive, true);
+            for (i, e) in self.edge_list.iter().enumerate() {
+                alive[i] &= forward[e.from] && backward[e.to];
+            }
+        }
+
+        // a clamp lowers the totals of the nodes around it, so repeat, but only n rounds in case of long cycles
+        for _ in 0..n {
+            let mut in_total = vec![0 as Flow; n];
+            let mut out_total = vec![0 as Flow; n];
+            for (i, e) in self.edge_list.iter().enumerate() {
+                if alive[i] {
+                    out_total[e.from] = out_total[e.from].saturating_add(capacity[i]);
+                    in_total[e.to] = in_total[e.to].saturating_add(capacity[i]);
+                }
+            }
+            let mut clamped = false;
+            for (i, e) in self.edge_list.iter().enumerate() {
+                let mut bound = capacity[i];
+                if e.from != source {
+                    bound = bound.min(in_total[e.from]);
+                }
+                if e.to != sink {
+                    bound = bound.min(out_total[e.to]);
+                }
+                if alive[i] && bound < capacity[i] {
+                    capacity[i] = bound;
+                    clamped = true;
+                }
+            }
+            if !clamped {
+                break;
+            }
+        }
+
+        let mut report = PreprocessReport {
+            original_num_edges: self.num_edges,
+            ..Default::default()
+        };
+        let mut has_edges = vec![false; n];
+        let mut kept_edges_of = vec![false; n];
+        let edge_list = core::mem::take(&mut self.edge_list);
+        for (i, mut e) in edge_list.into_iter().enumerate() {
+            has_edges[e.from] = true;
+            has_edges[e.to] = true;
+            if !alive[i] {
+                report.removed_edges.push(i);
+                continue;
+            }
+            if capacity[i] < e.capacity {
+                report.clamped_edges.push((i, e.capacity, capacity[i]));
+                e.capacity = capacity[i];
+            }
+            kept_edges_of[e.from] = true;
+            kept_edges_of[e.to] = true;
+            report.kept_edges.push(i);
+            self.edge_list.push(e);
+        }
+        report.removed_nodes = (0..n)
+            .filter(|&u| u != source && u != sink && has_edges[u] && !kept_edges_of[u])
+            .collect();
+        self.num_edges = self.edge_list.len();
+        self.unbuild();
+        report
+    }
+
+    // O(m log m)
+    // merges parallel edges into one of the total capacity, and the two edges of a node other than source and sink
+    // with one edge in and one out into one of the smaller capacity, until neither is left: the flows of the reduced
+    // graph are those of the graph before, and a path through such a node back to where it came from is dropped
+    // the edges are renumbered and the graph must be built again
+    // panics on a lean graph, see Graph::try_reduce_series_parallel
+    pub fn reduce_series_parallel(&mut self, source: usize, sink: usize) -> ReductionMap {
+        assert!(
+            !self.lean,
+            "a graph built by build_lean keeps no edges to reduce"
+        );
+        let n = self.num_nodes;
+        let mut reduction = SeriesParallel {
+            edges: Vec::new(),
+            edge_of_pair: BTreeMap::new(),
+            in_edges: vec![Vec::new(); n],
+            out_edges: vec![Vec::new(); n],
+            in_degree: vec![0; n],
+            out_degree: vec![0; n],
+            parts: Vec::new(),
+        };
+        for (i, e) in self.edge_list.iter().enumerate() {
+            reduction.parts.push((Reduced::Edge(i), e.capacity));
+            reduction.add(e.from, e.to, reduction.parts.len() - 1);
+        }
+
+        let mut queue: Vec<usize> = (0..n).collect();
+        while let Some(v) = queue.pop() {
+            if v == source
+                || v == sink
+                || reduction.in_degree[v] != 1
+                || reduction.out_degree[v] != 1
+            {
+                continue;
+            }
+            let a = reduction.alive(&reduction.in_edges[v]);
+            let b = reduction.alive(&reduction.out_edges[v]);
+            if a == b {
+                // a self loop
+                continue;
+            }
+            let ((u, _, part_a), (_, w, part_b)) =
+                (reduction.edges[a].unwrap(), reduction.edges[b].unwrap());
+            reduction.remove(a);
+            reduction.remove(b);
+            if u != w {
+                let capacity = reduction.parts[part_a].1.min(reduction.parts[part_b].1);
+                reduction
+                    .parts
+                    .push((Reduced::Series(part_a, part_b), capacity));
+                reduction.add(u, w, reduction.parts.len() - 1);
+            }
+            queue.extend([u, w]);
+        }
+
+        let mut map = ReductionMap {
+            original_num_edges: self.num_edges,
+            parts: reduction.parts,
+            edges: Vec::new(),
+        };
+        self.edge_list.clear();
+        for &(from, to, part) in reduction.edges.iter().flatten() {
+            self.edge_list.push(Edge {
+                from,
+                to,
+                flow: 0,
+                capacity: map.parts[part].1,
+            });
+            map.edges.push(part);
+        }
+        self.num_edges = self.edge_list.len();
+        self.unbuild();
+        map
+    }
+
+    // preprocess, or an error on a lean graph, which is left as it is
+    pub fn try_preprocess(
+        &mut self,
+        source: usize,
+        sink: usize,
+    ) -> Result<PreprocessReport, NoEdgeList> {
+        self.no_edge_list()?;
+        Ok(self.preprocess(source, sink))
+    }
+
+    // reduce_series_parallel, or an error on a lean graph, which is left as it is
+    pub fn try_reduce_series_parallel(
+        &mut self,
+        source: usize,
+        sink: usize,
+    ) -> Result<ReductionMap, NoEdgeList> {
+        self.no_edge_list()?;
+        Ok(self.reduce_series_parallel(source, sink))
+    }
+
+    // the built residual network is stale once the edge list changes
+    pub(crate) fn unbuild(&mut self) {
+        self.start.clear();
+        self.inside_edge_list.clear();
+        self.edge_index_to_inside_edge_index.clear();
+    }
+
+    // O(n + m) the nodes reachable from start along the alive edges, or reaching it if backward
+    fn reachable(&self, start: usize, alive: &[bool], backward: bool) -> Vec<bool> {
+        let mut adjacency = vec![Vec::new(); self.num_nodes];
+        for (i, e) in self.edge_list.iter().enumerate() {
+            if alive[i] {
+                let (u, v) = if backward {
+                    (e.to, e.from)
+                } else {
+                    (e.from, e.to)
+                };
+                adjacency[u].push(v);
+            }
+        }
+        let mut visited = vec![false; self.num_nodes];
+        let mut queue = VecDeque::from([start]);
+        visited[start] = true;
+        while let Some(u) = queue.pop_front() {
+            for &v in adjacency[u].iter() {
+                if !visited[v] {
+                    visited[v] = true;
+                    queue.push_back(v);
+                }
+            }
+        }
+        visited
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::generators::{random_dag, random_graph, Rng};
+    use crate::graph::{Flow, Graph};
+    use crate::preprocessing::{PreprocessReport, Reduced, ReductionMap};
+    use crate::solver::MaxFlowSolver;
+
+    fn graph(edges: &[(usize, usize, Flow)]) -> Graph {
+        let mut graph = Graph::new();
+        for &(from, to, capacity) in edges.iter() {
+            graph.add_directed_edge(from, to, capacity);
+        }
+        graph
+    }
+
+    fn solve(graph: &Graph, source: usize, sink: usize) -> (Flow, Dinic) {
+        let mut solver = Dinic::new();
+        for e in graph.edge_list.iter() {
+            solver.add_directed_edge(e.from, e.to, e.capacity);
+        }
+        (solver.solve(source, sink), solver)
+    }
+
+    // the flows of the original edges are feasible and of the expected value
+    fn assert_flow(
+        edges: &[(usize, usize, Flow)],
+        flows: &[Flow],
+        source: usize,
+        sink: usize,
+        expected: Flow,
+    ) {
+        let original = graph(edges);
+        let mut balance = vec![0 as Flow; original.num_nodes];
+        for (e, &flow) in original.edge_list.iter().zip(flows.iter()) {
+            assert!(0 <= flow && flow <= e.capacity, "{:?}", edges);
+            balance[e.from] -= flow;
+            balance[e.to] += flow;
+        }
+        for (u, &b) in balance.iter().enumerate() {
+            if u != source && u != sink {
+                assert_eq!(b, 0, "node {} of {:?}", u, edges);
+            }
+        }
+        if source != sink && source < original.num_nodes && sink < original.num_nodes {
+            assert_eq!(balance[sink], expected, "{:?}", edges);
+        }
+    }
+
+    // preprocesses a copy and checks the value and the expanded flow on the original edges
+    fn check(edges: &[(usize, usize, Flow)], source: usize, sink: usize) -> PreprocessReport {
+        let (expected, _) = solve(&graph(edges), source, sink);
+        let mut reduced = graph(edges);
+        let report = reduced.preprocess(source, sink);
+        let (flow, solver) = solve(&reduced, source, sink);
+        assert_eq!(flow, expected, "{:?}", edges);
+        assert_eq!(reduced.num_edges, report.kept_edges.len());
+        assert_flow(
+            edges,
+            &report.expand_flow(solver.graph()),
+            source,
+            sink,
+            expected,
+        );
+        report
+    }
+
+    // the same for reduce_series_parallel, (map, edges after)
+    fn check_reduction(
+        edges: &[(usize, usize, Flow)],
+        source: usize,
+        sink: usize,
+    ) -> (ReductionMap, Vec<(usize, usize, Flow)>) {
+        let (expected, _) = solve(&graph(edges), source, sink);
+        let mut reduced = graph(edges);
+        let map = reduced.reduce_series_parallel(source, sink);
+        let (flow, solver) = solve(&reduced, source, sink);
+        assert_eq!(flow, expected, "{:?}", edges);
+        assert_eq!(reduced.num_edges, map.edges.len());
+        assert_flow(
+            edges,
+            &map.expand_flow(solver.graph()),
+            source,
+            sink,
+            expected,
+        );
+        let after = reduced
+            .edge_list
+            .iter()
+            .map(|e| (e.from, e.to, e.capacity))
+            .collect();
+        (map, after)
+    }
+
+    #[test]
+    fn dead_ends_and_clamps() {
+        // 1 -> 4 -> 5 is a dead end, 6 has no in-capacity, 2 -> 0 goes back into the source, and 1 -> 2 of 100 is
+        // clamped to the 3 that 0 -> 1 brings in, and so are 2 -> 3 and 3 -> 7 after it
+        let edges = [
+            (0, 1, 3),
+            (1, 2, 100),
+            (2, 3, 50),
+            (3, 7, 7),
+            (1, 4, 5),
+            (4, 5, 5),
+            (6, 3, 2),
+            (2, 0, 4),
+            (2, 2, 9),
+        ];
+        let report = check(&edges, 0, 7);
+        assert_eq!(report.removed_edges, [4, 5, 6, 7, 8]);
+        assert_eq!(report.removed_nodes, [4, 5, 6]);
+        assert_eq!(report.kept_edges, [0, 1, 2, 3]);
+        assert_eq!(report.clamped_edges, [(1, 100, 3), (2, 50, 3), (3, 7, 3)]);
+    }
+
+    #[test]
+    fn terminals_without_edges() {
+        assert_eq!(check(&[(0, 1, 2)], 0, 2).removed_edges, [0]);
+        assert_eq!(check(&[(0, 1, 2)], 1, 1).removed_edges, [0]);
+        assert_eq!(check(&[], 0, 1), PreprocessReport::default());
+    }
+
+    #[test]
+    fn random_instances() {
+        let mut rng = Rng::new(8);
+        let (mut edges_before, mut edges_after) = (0, 0);
+        for seed in 0..300 {
+            let n = rng.gen_range(2, 30) as usize;
+            let m = rng.gen_range(0, 2 * n as u64) as usize;
+            let instance = if seed % 2 == 0 {
+                random_graph(n, m, 20, seed)
+            } else {
+                random_dag(n, m, 20, seed)
+            };
+            let report = check(&instance.edges, instance.source, instance.sink);
+            edges_before += instance.edges.len();
+            edges_after += report.kept_edges.len();
+        }
+        // sparse random graphs are mostly dead ends
+        assert!(
+            edges_after * 2 < edges_before,
+            "{} of {}",
+            edges_after,
+            edges_before
+        );
+    }
+
+    #[test]
+    fn repeated_preprocessing_is_stable() {
+        let instance = random_graph(40, 100, 50, 3);
+        let mut graph = graph(&instance.edges);
+        graph.preprocess(instance.source, instance.sink);
+        let edges: Vec<_> = graph
+            .edge_list
+            .iter()
+            .map(|e| (e.from, e.to, e.capacity))
+            .collect();
+        let report = graph.preprocess(instance.source, instance.sink);
+        assert!(report.removed_edges.is_empty() && report.clamped_edges.is_empty());
+        assert_eq!(report.kept_edges, (0..edges.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn series_and_parallel() {
+        // two parallel pipelines 0 -> 1 -> 2 -> 5 and 0 -> 3 -> 4 -> 5, with a second edge 3 -> 4, become one edge
+        // of min(4, 6, 5) + min(2, 1 + 2, 9) = 6
+        let edges = [
+            (0, 1, 4),
+            (1, 2, 6),
+            (2, 5, 5),
+            (0, 3, 2),
+            (3, 4, 1),
+            (3, 4, 2),
+            (4, 5, 9),
+        ];
+        let (map, after) = check_reduction(&edges, 0, 5);
+        assert_eq!(after, [(0, 5, 6)]);
+        let mut leaves: Vec<usize> = map
+            .parts
+            .iter()
+            .filter_map(|part| match part.0 {
+                Reduced::Edge(edge) => Some(edge),
+                _ => None,
+            })
+            .collect();
+        leaves.sort_unstable();
+        assert_eq!(leaves, (0..edges.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn terminals_stay() {
+        // a cycle 0 -> 1 -> 3 -> 2 -> 0 with two edges 3 -> 2, source and sink have one edge in and one out
+        let edges = [(2, 0, 3), (0, 1, 5), (1, 3, 2), (3, 2, 4), (3, 2, 1)];
+        let (_, after) = check_reduction(&edges, 0, 1);
+        assert_eq!(after, [(0, 1, 5), (1, 0, 2)]);
+        let (_, after) = check_reduction(&edges, 2, 3);
+        assert_eq!(after, [(3, 2, 5), (2, 3, 2)]);
+        // without terminals on it the cycle is dropped
+        let (map, after) = check_reduction(&edges, 4, 5);
+        assert_eq!(after, []);
+        assert_eq!(map.original_num_edges, 5);
+    }
+
+    #[test]
+    fn random_series_parallel() {
+        let mut rng = Rng::new(9);
+        let (mut edges_before, mut edges_after) = (0, 0);
+        for round in 0..300 {
+            let n = rng.gen_range(2, 20) as usize;
+            // pipelines: chains of random length between random nodes of a small core, and some parallel copies
+            let core = rng.gen_range(2, n as u64 + 1) as usize;
+            let mut edges = Vec::new();
+            let mut next = core;
+            for _ in 0..rng.gen_range(1, 12) {
+                let from = rng.gen_range(0, core as u64) as usize;
+                let to = rng.gen_range(0, core as u64) as usize;
+                let mut u = from;
+                for _ in 0..rng.gen_range(0, 4) {
+                    edges.push((u, next, rng.gen_capacity(10)));
+                    u = next;
+                    next += 1;
+                }
+                for _ in 0..rng.gen_range(1, 3) {
+                    edges.push((u, to, rng.gen_capacity(10)));
+                }
+            }
+            let (source, sink) = if round % 3 == 0 {
+                (0, next - 1)
+            } else {
+                (0, core - 1)
+            };
+            let (_, after) = check_reduction(&edges, source, sink);
+            edges_before += edges.len();
+            edges_after += after.len();
+        }
+        assert!(
+            edges_after * 2 < edges_before,
+            "{} of {}",
+            edges_after,
+            edges_before
+        );
+    }
+}
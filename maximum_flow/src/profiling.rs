@@ -0,0 +1,161 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+// time spent in the spans with one name, e.g. "bfs", "blocking_flow", "global_relabeling"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseTiming {
+    pub name: &'static str,
+    // 0 for the phases of the solve itself, 1 for the subroutines nested in them, ...
+    pub depth: usize,
+    pub calls: usize,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub total: Duration,
+    // in order of first entry
+    pub phases: Vec<PhaseTiming>,
+}
+
+impl PhaseTimings {
+    pub fn get(&self, name: &str) -> Option<&PhaseTiming> {
+        self.phases.iter().find(|p| p.name == name)
+    }
+
+    // the nested phases are already part of the ones containing them
+    pub fn top_level(&self) -> Duration {
+        self.phases
+            .iter()
+            .filter(|p| p.depth == 0)
+            .map(|p| p.duration)
+            .sum()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    phases: Vec<PhaseTiming>,
+    // (phase index, entered at)
+    stack: Vec<(usize, Instant)>,
+}
+
+// a subscriber that only sums up the span durations, one id per span name
+#[derive(Default)]
+struct Recorder {
+    state: Mutex<State>,
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_span()
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let name = attributes.metadata().name();
+        let mut state = self.state.lock().unwrap();
+        let index = match state.phases.iter().position(|p| p.name == name) {
+            Some(index) => index,
+            None => {
+                let depth = state.stack.len();
+                state.phases.push(PhaseTiming {
+                    name,
+                    depth,
+                    calls: 0,
+                    duration: Duration::ZERO,
+                });
+                state.phases.len() - 1
+            }
+        };
+        Id::from_u64(index as u64 + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, id: &Id) {
+        let index = id.into_u64() as usize - 1;
+        self.state
+            .lock()
+            .unwrap()
+            .stack
+            .push((index, Instant::now()));
+    }
+
+    fn exit(&self, _: &Id) {
+        let mut state = self.state.lock().unwrap();
+        if let Some((index, entered)) = state.stack.pop() {
+            let phase = &mut state.phases[index];
+            phase.calls += 1;
+            phase.duration += entered.elapsed();
+        }
+    }
+}
+
+// runs f with a recording subscriber for the current thread only
+pub fn profile<T>(f: impl FnOnce() -> T) -> (T, PhaseTimings) {
+    let recorder = std::sync::Arc::new(Recorder::default());
+    let dispatch = tracing::Dispatch::from(recorder.clone());
+    let start = Instant::now();
+    let result = tracing::dispatcher::with_default(&dispatch, f);
+    let total = start.elapsed();
+
+    let phases = std::mem::take(&mut recorder.state.lock().unwrap().phases);
+    (result, PhaseTimings { total, phases })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::generators::{genrmf, random_graph};
+    use crate::push_relabel_fifo::PushRelabelFIFO;
+    use crate::push_relabel_highest_label::PushRelabelHighestLabel;
+    use crate::solver::MaxFlowSolver;
+
+    #[test]
+    fn dinic() {
+        let instance = random_graph(2_000, 20_000, 1_000, 1);
+        let mut solver: Dinic = instance.build_solver();
+        let (flow, timings) = solver.solve_profiled(instance.source, instance.sink);
+        assert_eq!(flow, instance.solve::<Dinic>());
+
+        let phases = solver.stats().phases.unwrap();
+        assert_eq!(timings.get("build").unwrap().calls, 1);
+        assert_eq!(timings.get("bfs").unwrap().calls, phases);
+        assert_eq!(timings.get("blocking_flow").unwrap().calls, phases - 1);
+        // most of the time is spent inside the phases
+        let top_level = timings.top_level();
+        assert!(top_level <= timings.total);
+        assert!(top_level >= timings.total / 2, "{:?}", timings);
+    }
+
+    #[test]
+    fn push_relabel() {
+        let instance = genrmf(6, 12, 1, 1_000, 2);
+        let expected = instance.solve::<Dinic>();
+
+        let mut fifo: PushRelabelFIFO = instance.build_solver();
+        let mut highest_label: PushRelabelHighestLabel = instance.build_solver();
+        for (flow, timings) in [
+            fifo.solve_profiled(instance.source, instance.sink),
+            highest_label.solve_profiled(instance.source, instance.sink),
+        ] {
+            assert_eq!(flow, expected);
+            assert_eq!(timings.get("global_relabeling").unwrap().depth, 0);
+            let discharge = timings.get("discharge").unwrap();
+            assert_eq!(discharge.calls, 1);
+            if let Some(gap) = timings.get("gap_relabeling") {
+                assert_eq!(gap.depth, 1);
+                assert!(gap.duration <= discharge.duration);
+            }
+            let top_level = timings.top_level();
+            assert!(top_level <= timings.total);
+            assert!(top_level >= timings.total / 2, "{:?}", timings);
+        }
+    }
+}
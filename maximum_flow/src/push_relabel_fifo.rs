@@ -1,7 +1,12 @@
-use crate::graph::{Flow, Graph};
-use std::collections::VecDeque;
+use crate::control::{Cancelled, SolveControl};
+use crate::graph::{count, Flow, Graph, SolveStats, FLOW_MAX};
+use crate::solver::MaxFlowSolver;
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
 
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PushRelabelFIFO {
     graph: Graph,
 
@@ -9,6 +14,8 @@ pub struct PushRelabelFIFO {
     current_edge: Vec<usize>,
 
     num_distance: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stats: SolveStats,
 }
 
 impl PushRelabelFIFO {
@@ -16,21 +23,62 @@ impl PushRelabelFIFO {
         PushRelabelFIFO::default()
     }
 
+    // a solver of a graph built already, e.g. by Graph::build_lean
+    pub fn from_graph(graph: Graph) -> Self {
+        PushRelabelFIFO {
+            graph,
+            ..Default::default()
+        }
+    }
+
     pub fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow) -> Option<usize> {
         self.graph.add_directed_edge(from, to, capacity)
     }
 
+    pub fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
     pub fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.solve_with_control(source, sink, &SolveControl::new())
+            .unwrap_or_else(|cancelled| cancelled.flow_so_far)
+    }
+
+    pub fn solve_with_control(
+        &mut self,
+        source: usize,
+        sink: usize,
+        control: &SolveControl,
+    ) -> Result<Flow, Cancelled> {
         self.graph.build();
+        self.stats = SolveStats::push_relabel();
 
-        if source == sink || self.graph.num_nodes == 0 || self.graph.num_edges == 0 {
-            return 0;
+        // a source or sink without incident edges is not part of the graph
+        if source == sink || source >= self.graph.num_nodes || sink >= self.graph.num_nodes {
+            return Ok(0);
+        }
+        // no cut separates them
+        if self.graph.has_unbounded_path(source, sink) {
+            return Ok(FLOW_MAX);
         }
         assert!(source < self.graph.num_nodes && sink < self.graph.num_nodes);
 
         self.pre_process(source, sink);
 
-        while let Some(u) = self.active_nodes.pop_front() {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("discharge").entered();
+        // a pass discharges the nodes that were active when it started
+        let mut pass_remaining = 0;
+        loop {
+            if pass_remaining == 0 {
+                control.checkpoint(self.graph.excesses[sink], &self.stats)?;
+                pass_remaining = self.active_nodes.len();
+            }
+            let Some(u) = self.active_nodes.pop_front() else {
+                break;
+            };
+            pass_remaining -= 1;
+
             if u == sink || self.graph.distance[u] >= self.graph.num_nodes {
                 assert_ne!(u, source);
                 continue;
@@ -38,7 +86,7 @@ impl PushRelabelFIFO {
             self.discharge(u);
         }
 
-        self.graph.excesses[sink]
+        Ok(self.graph.excesses[sink])
     }
 
     fn pre_process(&mut self, source: usize, sink: usize) {
@@ -55,6 +103,9 @@ impl PushRelabelFIFO {
 
         for i in self.graph.start[source]..self.graph.start[source + 1] {
             let delta = self.graph.inside_edge_list[i].residual_capacity();
+            if delta > 0 {
+                count(&mut self.stats.saturating_pushes);
+            }
             self.graph.push_flow(source, i, delta);
         }
 
@@ -94,8 +145,14 @@ impl PushRelabelFIFO {
     // push from u
     fn push(&mut self, u: usize, i: usize) {
         let to = self.graph.inside_edge_list[i].to;
-        let delta = self.graph.excesses[u].min(self.graph.inside_edge_list[i].residual_capacity());
+        let residual_capacity = self.graph.inside_edge_list[i].residual_capacity();
+        let delta = self.graph.excesses[u].min(residual_capacity);
         if self.graph.is_admissible_edge(u, to) && delta > 0 {
+            if delta == residual_capacity {
+                count(&mut self.stats.saturating_pushes);
+            } else {
+                count(&mut self.stats.non_saturating_pushes);
+            }
             self.graph.push_flow(u, i, delta);
             if self.graph.excesses[to] == delta {
                 self.active_nodes.push_back(to);
@@ -104,6 +161,7 @@ impl PushRelabelFIFO {
     }
 
     fn relabel(&mut self, u: usize) {
+        count(&mut self.stats.relabels);
         self.num_distance[self.graph.distance[u]] -= 1;
 
         self.graph.distance[u] = self
@@ -121,11 +179,17 @@ impl PushRelabelFIFO {
     // global relabeling heuristic
     // O(n + m)
     fn global_relabeling(&mut self, sink: usize) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("global_relabeling").entered();
+        count(&mut self.stats.global_relabels);
         self.graph.distance = self.graph.calculate_distance_to_sink(sink);
     }
 
     // gap relabeling heuristic
     fn gap_relabeling(&mut self, k: usize) {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("gap_relabeling").entered();
+        count(&mut self.stats.gaps);
         for u in 0..self.graph.num_nodes {
             if self.graph.distance[u] >= k {
                 self.num_distance[self.graph.distance[u]] -= 1;
@@ -136,12 +200,44 @@ impl PushRelabelFIFO {
     }
 }
 
+impl MaxFlowSolver for PushRelabelFIFO {
+    fn new() -> Self {
+        PushRelabelFIFO::new()
+    }
+
+    fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow) -> Option<usize> {
+        self.add_directed_edge(from, to, capacity)
+    }
+
+    fn solve(&mut self, source: usize, sink: usize) -> Flow {
+        self.solve(source, sink)
+    }
+
+    fn solve_with_control(
+        &mut self,
+        source: usize,
+        sink: usize,
+        control: &SolveControl,
+    ) -> Result<Flow, Cancelled> {
+        self.solve_with_control(source, sink, control)
+    }
+
+    fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    fn stats(&self) -> &SolveStats {
+        self.stats()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::graph::Flow;
     use crate::push_relabel_fifo::PushRelabelFIFO;
     use crate::test_utility::{
-        read_expected, read_instance_aoj, read_instance_libreoj, GraphInstance,
+        read_expected, read_instance_aoj, read_instance_dimacs, read_instance_libreoj,
+        GraphInstance,
     };
     use rstest::*;
     use std::path::PathBuf;
@@ -176,6 +272,32 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[rstest]
+    fn dimacs(#[files("test_cases/**/*.max")] path: PathBuf) {
+        let mut expected_file_path = path.clone();
+        expected_file_path.set_extension("out");
+
+        let actual = execute(read_instance_dimacs(&path));
+        let expected = read_expected(&expected_file_path);
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    fn stats(#[files("test_cases/AOJ_GRL_6_A/*.in")] path: PathBuf) {
+        let instance = read_instance_aoj(&path);
+        let mut solver: PushRelabelFIFO = instance.build_solver();
+        let flow = solver.solve(instance.source, instance.sink);
+        let stats = solver.stats();
+        let n = instance.num_nodes;
+        // no global relabeling if source or sink has no incident edges
+        assert!(stats.global_relabels.unwrap() <= 1);
+        assert!(stats.relabels.unwrap() <= 2 * n * n);
+        assert!(stats.gaps.unwrap() <= n);
+        assert!(flow == 0 || stats.saturating_pushes.unwrap() > 0);
+        assert_eq!(stats.phases, None);
+        assert_eq!(stats.augmenting_paths, None);
+    }
+
     fn execute(instance: GraphInstance) -> Flow {
         let mut solver = PushRelabelFIFO::new();
         for (from, to, capacity) in instance.edges {
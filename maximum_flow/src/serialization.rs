@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// bump when the layout of the serialized structs changes
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionMismatch {
+    pub expected: u32,
+    pub found: u32,
+}
+
+impl fmt::Display for VersionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unsupported format version {} (expected {})",
+            self.found, self.expected
+        )
+    }
+}
+
+impl std::error::Error for VersionMismatch {}
+
+impl<T> Versioned<T> {
+    pub fn new(data: T) -> Self {
+        Versioned {
+            version: FORMAT_VERSION,
+            data,
+        }
+    }
+
+    pub fn into_inner(self) -> Result<T, VersionMismatch> {
+        if self.version != FORMAT_VERSION {
+            return Err(VersionMismatch {
+                expected: FORMAT_VERSION,
+                found: self.version,
+            });
+        }
+        Ok(self.data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::graph::Graph;
+    use crate::push_relabel_highest_label::PushRelabelHighestLabel;
+    use crate::serialization::{VersionMismatch, Versioned, FORMAT_VERSION};
+    use crate::solver::MaxFlowSolver;
+    use crate::test_utility::read_instance_aoj;
+    use rstest::*;
+    use std::path::PathBuf;
+
+    #[rstest]
+    fn graph_round_trip(#[files("test_cases/AOJ_GRL_6_A/*.in")] path: PathBuf) {
+        let instance = read_instance_aoj(&path);
+        let mut solver = Dinic::new();
+        for (from, to, capacity) in instance.edges {
+            solver.add_directed_edge(from, to, capacity);
+        }
+        solver.solve(instance.source, instance.sink);
+
+        let json = serde_json::to_string(&Versioned::new(solver.graph())).unwrap();
+        let graph: Graph = serde_json::from_str::<Versioned<Graph>>(&json)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+
+        let original = solver.graph();
+        assert_eq!(
+            graph.min_cut(instance.sink),
+            original.min_cut(instance.sink)
+        );
+        assert_eq!(
+            graph.decompose_flow(instance.source, instance.sink),
+            original.decompose_flow(instance.source, instance.sink)
+        );
+        assert_eq!(graph.excesses, original.excesses);
+    }
+
+    #[test]
+    fn solver_round_trip() {
+        let mut solver = PushRelabelHighestLabel::new();
+        solver.add_directed_edge(0, 1, 2);
+        solver.add_directed_edge(0, 2, 1);
+        solver.add_directed_edge(1, 2, 1);
+        solver.add_directed_edge(1, 3, 1);
+        solver.add_directed_edge(2, 3, 2);
+        assert_eq!(solver.solve(0, 3), 3);
+
+        let json = serde_json::to_string(&Versioned::new(&solver)).unwrap();
+        let restored: PushRelabelHighestLabel =
+            serde_json::from_str::<Versioned<PushRelabelHighestLabel>>(&json)
+                .unwrap()
+                .into_inner()
+                .unwrap();
+        assert_eq!(restored.graph().min_cut(3), solver.graph().min_cut(3));
+        assert_eq!(restored.graph().excesses[3], 3);
+    }
+
+    #[test]
+    fn dinic_without_incremental_levels() {
+        let mut solver = Dinic::new().incremental_levels(true);
+        solver.add_directed_edge(0, 1, 2);
+        solver.add_directed_edge(1, 2, 1);
+        assert_eq!(solver.solve(0, 2), 1);
+
+        // the layout before incremental_levels, the repair scratch isn't serialized
+        let mut value = serde_json::to_value(&solver).unwrap();
+        let fields = value.as_object_mut().unwrap();
+        assert!(!fields.contains_key("saturated") && !fields.contains_key("pending"));
+        fields.remove("incremental_levels");
+        let mut restored: Dinic = serde_json::from_value(value).unwrap();
+        assert_eq!(restored.graph().excesses[2], 1);
+        restored.add_directed_edge(0, 2, 3);
+        assert_eq!(restored.solve(0, 2), 4);
+    }
+
+    #[test]
+    fn version_mismatch() {
+        let json = format!(
+            "{{\"version\":{},\"data\":{}}}",
+            FORMAT_VERSION + 1,
+            serde_json::to_string(&Graph::new()).unwrap()
+        );
+        let versioned: Versioned<Graph> = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            versioned.into_inner().err(),
+            Some(VersionMismatch {
+                expected: FORMAT_VERSION,
+                found: FORMAT_VERSION + 1
+            })
+        );
+    }
+}
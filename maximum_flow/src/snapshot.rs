@@ -0,0 +1,261 @@
+use crate::graph::{Edge, Flow, Graph, FLOW_MAX};
+use crate::lean::NoEdgeList;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use network_flows_core::CsrGraph;
+
+// the flows, the residual network and the minimum cut of a graph at the time of Graph::snapshot, which later solves
+// and edges of the graph leave as they were
+// clones share the vectors, so a snapshot can be handed to many threads
+#[derive(Debug, Clone)]
+pub struct FlowSnapshot {
+    sink: usize,
+    flow_value: Flow,
+    edges: Arc<[Edge]>,
+    // the arcs of u are arcs[start[u]..start[u + 1]], (head, residual capacity) in the layout of Graph::build
+    start: Arc<[usize]>,
+    arcs: Arc<[(usize, Flow)]>,
+    source_side: Arc<[bool]>,
+}
+
+impl Graph {
+    // O(n + m)
+    // works on graphs that are not built, as with no flow
+    // panics on a lean graph, see Graph::try_snapshot
+    pub fn snapshot(&self, sink: usize) -> FlowSnapshot {
+        let edges: Vec<Edge> = (0..self.num_edges)
+            .map(|i| self.get_directed_edge(i))
+            .collect();
+        let layout = CsrGraph::build(
+            self.num_nodes,
+            edges.iter().map(|e| {
+                let (mut forward, mut reverse) = e.arcs(FLOW_MAX);
+                forward.flow = e.flow;
+                reverse.flow = e.capacity - e.flow;
+                (e.from, e.to, forward, reverse)
+            }),
+        );
+
+        // the nodes that reach the sink in the residual network, as Graph::min_cut
+        let mut source_side = vec![true; self.num_nodes];
+        if sink < self.num_nodes {
+            source_side[sink] = false;
+            let mut queue = VecDeque::from([sink]);
+            while let Some(u) = queue.pop_front() {
+                for arc in layout.neighbors(u) {
+                    if arc.flow > 0 && source_side[arc.to] {
+                        source_side[arc.to] = false;
+                        queue.push_back(arc.to);
+                    }
+                }
+            }
+        }
+
+        let flow_value = edges
+            .iter()
+            .map(|e| {
+                if e.to == sink {
+                    e.flow
+                } else if e.from == sink {
+                    -e.flow
+                } else {
+                    0
+                }
+            })
+            .sum();
+        let mut arcs: Vec<(usize, Flow)> = layout
+            .arcs
+            .iter()
+            .map(|a| (a.to, a.residual_capacity()))
+            .collect();
+        for (e, &i) in edges.iter().zip(layout.edge_index.iter()) {
+            if e.capacity == FLOW_MAX {
+                arcs[i].1 = FLOW_MAX;
+            }
+        }
+        FlowSnapshot {
+            sink,
+            flow_value,
+            edges: edges.into(),
+            arcs: arcs.into(),
+            start: layout.start.into(),
+            source_side: source_side.into(),
+        }
+    }
+
+    // snapshot, or an error on a lean graph
+    pub fn try_snapshot(&self, sink: usize) -> Result<FlowSnapshot, NoEdgeList> {
+        self.no_edge_list()?;
+        Ok(self.snapshot(sink))
+    }
+}
+
+impl FlowSnapshot {
+    pub fn num_nodes(&self) -> usize {
+        self.source_side.len()
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn sink(&self) -> usize {
+        self.sink
+    }
+
+    // the net inflow into the sink
+    pub fn flow_value(&self) -> Flow {
+        self.flow_value
+    }
+
+    pub fn edge(&self, edge_index: usize) -> &Edge {
+        &self.edges[edge_index]
+    }
+
+    pub fn flow(&self, edge_index: usize) -> Flow {
+        self.edges[edge_index].flow
+    }
+
+    pub fn capacity(&self, edge_index: usize) -> Flow {
+        self.edges[edge_index].capacity
+    }
+
+    // (head, residual capacity) of the arcs of u, an arc per edge out of u and per edge into u
+    pub fn residual_arcs(&self, u: usize) -> &[(usize, Flow)] {
+        if u >= self.num_nodes() {
+            return &[];
+        }
+        &self.arcs[self.start[u]..self.start[u + 1]]
+    }
+
+    // O(degree of u) what more can flow from u to v, over all the edges between them, FLOW_MAX through an unbounded
+    // edge
+    pub fn residual_capacity(&self, u: usize, v: usize) -> Flow {
+        self.residual_arcs(u)
+            .iter()
+            .filter(|&&(to, _)| to == v)
+            .fold(0, |total, &(_, residual)| total.saturating_add(residual))
+    }
+
+    // whether u cannot reach the sink in the residual network, see Graph::min_cut
+    pub fn is_source_side(&self, u: usize) -> bool {
+        self.source_side.get(u).copied().unwrap_or(true)
+    }
+
+    pub fn source_side(&self) -> &[bool] {
+        &self.source_side
+    }
+
+    // O(m) the edges from the source side to the sink side
+    pub fn cut_edges(&self) -> Vec<usize> {
+        (0..self.num_edges())
+            .filter(|&i| {
+                let e = &self.edges[i];
+                self.source_side[e.from] && !self.source_side[e.to]
+            })
+            .collect()
+    }
+
+    // O(m) their capacity, the flow value once the flow is maximum, None if one of them is unbounded
+    pub fn cut_capacity(&self) -> Option<Flow> {
+        self.cut_edges()
+            .into_iter()
+            .try_fold(0 as Flow, |total, i| {
+                let capacity = self.edges[i].capacity;
+                (capacity != FLOW_MAX).then(|| total.saturating_add(capacity))
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dinic::Dinic;
+    use crate::generators::random_graph;
+    use crate::graph::Graph;
+    use crate::snapshot::FlowSnapshot;
+    use crate::solver::MaxFlowSolver;
+
+    fn example() -> Dinic {
+        let mut solver = Dinic::new();
+        for (from, to, capacity) in [(0, 1, 2), (1, 2, 1), (0, 2, 4), (2, 1, 3)] {
+            solver.add_directed_edge(from, to, capacity);
+        }
+        solver
+    }
+
+    #[test]
+    fn queries() {
+        let mut solver = example();
+        let before = solver.graph().snapshot(2);
+        assert_eq!(before.flow_value(), 0);
+        assert_eq!(before.residual_capacity(0, 2), 4);
+        // all reach the sink without flow
+        assert_eq!(before.source_side(), &[false, false, false]);
+        assert!(before.cut_edges().is_empty());
+
+        assert_eq!(solver.solve(0, 2), 5);
+        let snapshot = solver.graph().snapshot(2);
+        assert_eq!(snapshot.flow_value(), 5);
+        assert_eq!((snapshot.flow(0), snapshot.capacity(0)), (1, 2));
+        assert_eq!((snapshot.edge(2).from, snapshot.edge(2).to), (0, 2));
+        // 1 more on 0 -> 1, 1 back on the edge 1 -> 2 and 2 on 2 -> 1
+        assert_eq!(snapshot.residual_capacity(0, 1), 1);
+        assert_eq!(snapshot.residual_capacity(2, 1), 4);
+        assert_eq!(snapshot.residual_capacity(1, 2), 0);
+        assert_eq!(snapshot.residual_capacity(1, 7), 0);
+        assert_eq!(snapshot.source_side(), &[true, true, false]);
+        assert!(snapshot.is_source_side(1) && !snapshot.is_source_side(2));
+        assert_eq!(snapshot.cut_edges(), vec![1, 2]);
+        assert_eq!(snapshot.cut_capacity(), Some(5));
+        // the snapshot taken before is not affected
+        assert_eq!(before.flow(0), 0);
+
+        let empty = Graph::new().snapshot(0);
+        assert_eq!((empty.num_nodes(), empty.num_edges()), (0, 0));
+        assert!(empty.residual_arcs(0).is_empty());
+    }
+
+    #[test]
+    fn send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<FlowSnapshot>();
+    }
+
+    // readers query snapshots of the first solve while the graph gets more edges and is solved again
+    #[test]
+    fn query_while_solving() {
+        let instance = random_graph(200, 2000, 50, 4);
+        let mut solver: Dinic = instance.build_solver();
+        let flow = solver.solve(instance.source, instance.sink);
+        let snapshot = solver.graph().snapshot(instance.sink);
+        let flows: Vec<i64> = (0..snapshot.num_edges())
+            .map(|i| solver.graph().get_directed_edge(i).flow)
+            .collect();
+        let source_side = solver.graph().min_cut(instance.sink);
+
+        std::thread::scope(|scope| {
+            for reader in 0..4 {
+                let snapshot = snapshot.clone();
+                let (flows, source_side) = (&flows, &source_side);
+                scope.spawn(move || {
+                    for round in 0..200 {
+                        assert_eq!(snapshot.flow_value(), flow);
+                        assert_eq!(snapshot.cut_capacity(), Some(flow));
+                        let i = (reader * 31 + round * 7) % flows.len();
+                        assert_eq!(snapshot.flow(i), flows[i]);
+                        assert_eq!(snapshot.source_side(), &source_side[..]);
+                    }
+                });
+            }
+            for i in 0..20 {
+                solver.add_directed_edge(instance.source, i + 1, 100);
+                solver.add_directed_edge(i + 1, instance.sink, 100);
+                assert!(solver.solve(instance.source, instance.sink) > flow);
+            }
+        });
+        assert!(solver.graph().snapshot(instance.sink).flow_value() > flow);
+        assert_eq!(snapshot.flow_value(), flow);
+    }
+}
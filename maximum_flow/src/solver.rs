@@ -0,0 +1,135 @@
+use crate::control::{Cancelled, SolveControl};
+pub use crate::graph::SolveStats;
+use crate::graph::{Flow, Graph, FLOW_MAX};
+
+pub trait MaxFlowSolver {
+    fn new() -> Self
+    where
+        Self: Sized;
+    fn add_directed_edge(&mut self, from: usize, to: usize, capacity: Flow) -> Option<usize>;
+    // see Graph::add_directed_edge_unbounded
+    fn add_directed_edge_unbounded(&mut self, from: usize, to: usize) -> Option<usize> {
+        self.add_directed_edge(from, to, FLOW_MAX)
+    }
+    fn solve(&mut self, source: usize, sink: usize) -> Flow;
+    fn solve_with_control(
+        &mut self,
+        source: usize,
+        sink: usize,
+        control: &SolveControl,
+    ) -> Result<Flow, Cancelled>;
+    fn graph(&self) -> &Graph;
+    // counters of the last solve
+    fn stats(&self) -> &SolveStats;
+
+    // durations of the traced phases, without a tracing subscriber of your own
+    #[cfg(feature = "tracing")]
+    fn solve_profiled(
+        &mut self,
+        source: usize,
+        sink: usize,
+    ) -> (Flow, crate::profiling::PhaseTimings)
+    where
+        Self: Sized,
+    {
+        crate::profiling::profile(|| self.solve(source, sink))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::capacity_scaling::CapacityScaling;
+    use crate::dinic::Dinic;
+    use crate::edmonds_karp::EdmondsKarp;
+    use crate::ford_fulkerson::FordFulkerson;
+    use crate::graph::{Flow, FLOW_MAX};
+    use crate::push_relabel_fifo::PushRelabelFIFO;
+    use crate::push_relabel_highest_label::PushRelabelHighestLabel;
+    use crate::solver::MaxFlowSolver;
+    use proptest::prelude::*;
+
+    type Instance = (usize, Vec<(usize, usize, Flow)>, usize, usize);
+
+    // 64 cases by default, PROPTEST_CASES=N for more
+    fn config() -> ProptestConfig {
+        let cases = std::env::var("PROPTEST_CASES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(64);
+        ProptestConfig::with_cases(cases)
+    }
+
+    // capacity 0 is included to cover the edges the solvers reject
+    fn instance() -> impl Strategy<Value = Instance> {
+        instance_with(0..=100 as Flow)
+    }
+
+    // and one edge in ten unbounded
+    fn instance_with_unbounded() -> impl Strategy<Value = Instance> {
+        instance_with(prop_oneof![9 => 0..=100 as Flow, 1 => Just(FLOW_MAX)])
+    }
+
+    fn instance_with(
+        capacity: impl Strategy<Value = Flow> + Clone,
+    ) -> impl Strategy<Value = Instance> {
+        (2..12_usize).prop_flat_map(move |n| {
+            (
+                Just(n),
+                prop::collection::vec((0..n, 0..n, capacity.clone()), 0..40),
+                0..n,
+                0..n,
+            )
+        })
+    }
+
+    // returns the flow value after checking that it matches the extracted min cut
+    fn solve<S: MaxFlowSolver>(
+        instance: &Instance,
+        true_flow: bool,
+    ) -> Result<Flow, TestCaseError> {
+        let (_, edges, source, sink) = instance;
+        let mut solver = S::new();
+        for &(from, to, capacity) in edges.iter() {
+            solver.add_directed_edge(from, to, capacity);
+        }
+        let flow = solver.solve(*source, *sink);
+
+        let graph = solver.graph();
+        if flow == FLOW_MAX {
+            prop_assert!(graph.has_unbounded_path(*source, *sink));
+            return Ok(flow);
+        }
+        if source != sink {
+            let cut = graph.min_cut(*sink);
+            prop_assert_eq!(graph.cut_capacity(&cut), Some(flow), "min cut capacity");
+        }
+        if true_flow {
+            prop_assert_eq!(graph.validate_flow(*source, *sink), Ok(flow));
+        }
+        Ok(flow)
+    }
+
+    proptest! {
+        #![proptest_config(config())]
+
+        #[test]
+        fn solvers_agree(instance in instance()) {
+            let flow = solve::<Dinic>(&instance, true)?;
+            prop_assert_eq!(solve::<FordFulkerson>(&instance, true)?, flow);
+            prop_assert_eq!(solve::<EdmondsKarp>(&instance, true)?, flow);
+            prop_assert_eq!(solve::<CapacityScaling>(&instance, true)?, flow);
+            prop_assert_eq!(solve::<PushRelabelFIFO>(&instance, false)?, flow);
+            prop_assert_eq!(solve::<PushRelabelHighestLabel>(&instance, false)?, flow);
+        }
+
+        #[test]
+        fn solvers_agree_with_unbounded_edges(instance in instance_with_unbounded()) {
+            let flow = solve::<Dinic>(&instance, true)?;
+            prop_assert_eq!(solve::<FordFulkerson>(&instance, true)?, flow);
+            prop_assert_eq!(solve::<EdmondsKarp>(&instance, true)?, flow);
+            prop_assert_eq!(solve::<CapacityScaling>(&instance, true)?, flow);
+            prop_assert_eq!(solve::<PushRelabelFIFO>(&instance, false)?, flow);
+            prop_assert_eq!(solve::<PushRelabelHighestLabel>(&instance, false)?, flow);
+        }
+    }
+}
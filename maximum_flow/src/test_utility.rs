@@ -1,77 +1,28 @@
 use crate::graph::Flow;
-use std::fs::read_to_string;
+pub use crate::instances::GraphInstance;
+#[cfg(not(target_arch = "wasm32"))]
+use network_flows_instances::{read_file, Format};
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::PathBuf;
 
-pub struct GraphInstance {
-    pub num_nodes: usize,
-    pub edges: Vec<(usize, usize, Flow)>,
-    pub source: usize,
-    pub sink: usize,
-}
-
+// the test cases are read from the file system, which wasm32-unknown-unknown does not have
+// through network_flows_instances, like those of generalized_maximum_flow
+#[cfg(not(target_arch = "wasm32"))]
 pub fn read_expected(file_path: &PathBuf) -> Flow {
-    let data = read_to_string(file_path).unwrap();
-    data.trim().parse().unwrap()
+    network_flows_instances::read_expected(file_path).unwrap()
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 pub fn read_instance_aoj(file_path: &PathBuf) -> GraphInstance {
-    let data = read_to_string(file_path).unwrap();
-    let data: Vec<&str> = data.trim().split('\n').collect();
-
-    let (num_nodes, source, sink) = {
-        let header: Vec<&str> = data[0].trim().split(' ').collect();
-        let num_nodes = header[0].parse().unwrap();
-        (num_nodes, 0, num_nodes - 1)
-    };
-
-    let mut edges = Vec::new();
-    for line in data[1..].iter() {
-        let line: Vec<&str> = line.trim().split(' ').collect();
-        let (from, to, capacity) = (
-            line[0].parse().unwrap(),
-            line[1].parse().unwrap(),
-            line[2].parse().unwrap(),
-        );
-        edges.push((from, to, capacity));
-    }
-
-    GraphInstance {
-        num_nodes,
-        edges,
-        source,
-        sink,
-    }
+    read_file(file_path, Format::Aoj).unwrap().into()
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 pub fn read_instance_libreoj(file_path: &PathBuf) -> GraphInstance {
-    let data = read_to_string(file_path).unwrap();
-    let data: Vec<&str> = data.trim().split('\n').collect();
-
-    let (num_nodes, source, sink) = {
-        let header: Vec<&str> = data[0].trim().split(' ').collect();
-        let (num_nodes, source, sink): (usize, usize, usize) = (
-            header[0].parse().unwrap(),
-            header[2].parse().unwrap(),
-            header[3].parse().unwrap(),
-        );
-        (num_nodes, source - 1, sink - 1)
-    };
-
-    let mut edges = Vec::new();
-    for line in data[1..].iter() {
-        let line: Vec<&str> = line.trim().split(' ').collect();
-        let (from, to, capacity): (usize, usize, Flow) = (
-            line[0].parse().unwrap(),
-            line[1].parse().unwrap(),
-            line[2].parse().unwrap(),
-        );
-        edges.push((from - 1, to - 1, capacity));
-    }
+    read_file(file_path, Format::LibreOj).unwrap().into()
+}
 
-    GraphInstance {
-        num_nodes,
-        edges,
-        source,
-        sink,
-    }
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_instance_dimacs(file_path: &PathBuf) -> GraphInstance {
+    read_file(file_path, Format::Dimacs).unwrap().into()
 }
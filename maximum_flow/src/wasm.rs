@@ -0,0 +1,98 @@
+use crate::dinic::Dinic;
+use crate::graph::Flow;
+use crate::solver::MaxFlowSolver;
+use wasm_bindgen::prelude::*;
+
+// JS-facing wrapper around Dinic
+// capacities and flows are plain JS numbers (f64), so they are exact up to 2^53
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct MaxFlow {
+    solver: Dinic,
+    sink: Option<usize>,
+}
+
+#[wasm_bindgen]
+impl MaxFlow {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> MaxFlow {
+        MaxFlow::default()
+    }
+
+    // returns the edge index, or undefined if the capacity is not positive
+    pub fn add_edge(&mut self, from: u32, to: u32, capacity: f64) -> Option<u32> {
+        self.sink = None;
+        self.solver
+            .add_directed_edge(from as usize, to as usize, capacity as Flow)
+            .map(|i| i as u32)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn num_nodes(&self) -> u32 {
+        self.solver.graph().num_nodes as u32
+    }
+
+    // returns undefined if source or sink is not a node of the graph
+    pub fn solve(&mut self, source: u32, sink: u32) -> Option<f64> {
+        let num_nodes = self.solver.graph().num_nodes;
+        if source as usize >= num_nodes || sink as usize >= num_nodes {
+            return None;
+        }
+        let flow = self.solver.solve(source as usize, sink as usize);
+        self.sink = Some(sink as usize);
+        Some(flow as f64)
+    }
+
+    // flow on each edge, indexed by the value returned from add_edge (Float64Array)
+    pub fn edge_flows(&self) -> Vec<f64> {
+        let graph = self.solver.graph();
+        (0..graph.num_edges)
+            .map(|i| match self.sink {
+                Some(_) => graph.get_directed_edge(i).flow as f64,
+                None => 0.0,
+            })
+            .collect()
+    }
+
+    // nodes on the source side of a minimum cut (Uint32Array), empty before solve
+    pub fn min_cut(&self) -> Vec<u32> {
+        let Some(sink) = self.sink else {
+            return Vec::new();
+        };
+        self.solver
+            .graph()
+            .min_cut(sink)
+            .iter()
+            .enumerate()
+            .filter(|(_, &side)| side)
+            .map(|(u, _)| u as u32)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wasm::MaxFlow;
+
+    #[test]
+    fn sample() {
+        let mut max_flow = MaxFlow::new();
+        for (from, to, capacity) in [
+            (0, 1, 2.0),
+            (0, 2, 1.0),
+            (1, 2, 1.0),
+            (1, 3, 1.0),
+            (2, 3, 2.0),
+        ] {
+            assert!(max_flow.add_edge(from, to, capacity).is_some());
+        }
+        assert_eq!(max_flow.add_edge(0, 3, 0.0), None);
+        assert_eq!(max_flow.num_nodes(), 4);
+        assert!(max_flow.min_cut().is_empty());
+
+        assert_eq!(max_flow.solve(0, 7), None);
+        assert_eq!(max_flow.solve(0, 3), Some(3.0));
+        assert_eq!(max_flow.edge_flows(), vec![2.0, 1.0, 1.0, 1.0, 2.0]);
+        assert_eq!(max_flow.min_cut(), vec![0, 1, 2]);
+    }
+}
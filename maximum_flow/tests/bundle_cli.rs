@@ -0,0 +1,68 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const AOJ_MAIN: &str = r#"
+fn main() {
+    let mut input = String::new();
+    std::io::Read::read_to_string(&mut std::io::stdin(), &mut input).unwrap();
+    let mut tokens = input.split_whitespace().map(|t| t.parse::<usize>().unwrap());
+    let (n, m) = (tokens.next().unwrap(), tokens.next().unwrap());
+    let mut solver = Dinic::new();
+    for _ in 0..m {
+        let (u, v, c) = (tokens.next().unwrap(), tokens.next().unwrap(), tokens.next().unwrap());
+        solver.add_directed_edge(u, v, c as Flow);
+    }
+    println!("{}", solver.solve(0, n - 1));
+}
+"#;
+
+#[test]
+fn dinic_bundle_compiles_and_solves_aoj_sample() {
+    let output = Command::new(env!("CARGO_BIN_EXE_bundle"))
+        .arg("dinic")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let bundle = String::from_utf8(output.stdout).unwrap();
+    assert!(bundle.starts_with(&format!("// maximum_flow {}", env!("CARGO_PKG_VERSION"))));
+    assert!(!bundle.contains("mod "));
+    assert!(!bundle.contains("pub "));
+    assert!(!bundle.contains("PushRelabel"));
+
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let source = dir.join("dinic_bundle.rs");
+    let binary = dir.join("dinic_bundle");
+    std::fs::write(&source, bundle + AOJ_MAIN).unwrap();
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let status = Command::new(rustc)
+        .args(["--edition", "2021", "-A", "warnings", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mut child = Command::new(&binary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let input = std::fs::read("test_cases/AOJ_GRL_6_A/00_sample_00.in").unwrap();
+    child.stdin.take().unwrap().write_all(&input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let expected = std::fs::read_to_string("test_cases/AOJ_GRL_6_A/00_sample_00.out").unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        expected.trim()
+    );
+}
+
+#[test]
+fn unknown_algorithm() {
+    let output = Command::new(env!("CARGO_BIN_EXE_bundle"))
+        .arg("simplex")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -0,0 +1,141 @@
+use maximum_flow::ford_fulkerson::FordFulkerson;
+use maximum_flow::instances::{read_instance_aoj, read_instance_libreoj};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn run(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_gen_test_cases"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+}
+
+fn out_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn read_expected(path: &Path) -> i64 {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn aoj_cases() {
+    let dir = out_dir("gen_aoj");
+    let args = [
+        "--out",
+        dir.to_str().unwrap(),
+        "--count",
+        "5",
+        "--seed",
+        "3",
+    ];
+    // the cycle family has its sink in the middle, which the AOJ format has to move
+    assert_eq!(
+        run(&[&args[..], &["washington", "4", "3..9", "1..50"]].concat()),
+        Some(0)
+    );
+
+    for i in 0..5 {
+        let instance = read_instance_aoj(dir.join(format!("gen_{:02}.in", i))).unwrap();
+        assert_eq!(
+            (instance.source, instance.sink),
+            (0, instance.num_nodes - 1)
+        );
+        let expected = read_expected(&dir.join(format!("gen_{:02}.out", i)));
+        assert_eq!(instance.solve::<FordFulkerson>(), expected);
+    }
+
+    // the same seed gives the same files
+    let again = out_dir("gen_aoj_again");
+    let args = [
+        "--out",
+        again.to_str().unwrap(),
+        "--count",
+        "5",
+        "--seed",
+        "3",
+    ];
+    assert_eq!(
+        run(&[&args[..], &["washington", "4", "3..9", "1..50"]].concat()),
+        Some(0)
+    );
+    for i in 0..5 {
+        let name = format!("gen_{:02}.in", i);
+        assert_eq!(
+            std::fs::read(dir.join(&name)).unwrap(),
+            std::fs::read(again.join(&name)).unwrap()
+        );
+    }
+}
+
+#[test]
+fn libreoj_cases_and_budget() {
+    let dir = out_dir("gen_libreoj");
+    let args = [
+        "--out",
+        dir.to_str().unwrap(),
+        "--format",
+        "libreoj",
+        "--prefix",
+        "dag",
+        "--count",
+        "20",
+        "--budget",
+        "100",
+        "dag",
+        "10",
+        "30",
+        "1..1000",
+    ];
+    assert_eq!(run(&args), Some(0));
+
+    // 30 edges per case, so only 3 cases fit in the budget
+    let mut names: Vec<String> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "dag_00.in",
+            "dag_00.out",
+            "dag_01.in",
+            "dag_01.out",
+            "dag_02.in",
+            "dag_02.out"
+        ]
+    );
+    let instance = read_instance_libreoj(dir.join("dag_02.in")).unwrap();
+    assert_eq!(
+        instance.solve::<FordFulkerson>(),
+        read_expected(&dir.join("dag_02.out"))
+    );
+}
+
+#[test]
+fn invalid_arguments() {
+    assert_eq!(run(&[]), Some(1));
+    assert_eq!(run(&["random", "10..5", "20", "5"]), Some(1));
+    assert_eq!(
+        run(&["--format", "dimacs", "random", "10", "20", "5"]),
+        Some(1)
+    );
+    let dir = out_dir("gen_invalid");
+    assert_eq!(
+        run(&["--out", dir.to_str().unwrap(), "simplex", "10"]),
+        Some(1)
+    );
+    assert_eq!(
+        run(&["--out", dir.to_str().unwrap(), "random", "1", "20", "5"]),
+        Some(1)
+    );
+}
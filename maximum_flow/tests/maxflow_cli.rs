@@ -0,0 +1,57 @@
+use std::process::Command;
+
+fn run(args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_maxflow"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn solve_all_formats() {
+    for path in [
+        "test_cases/AOJ_GRL_6_A/00_sample_00.in",
+        "test_cases/DIMACS/aoj_grl_6_a_00_sample_00.max",
+    ] {
+        for algo in ["dinic", "ff", "scaling", "pr-fifo", "pr-hl"] {
+            let (code, stdout) = run(&["--algo", algo, path]);
+            assert_eq!(code, Some(0));
+            assert_eq!(stdout, "flow 3\n");
+        }
+    }
+
+    let (code, stdout) = run(&["test_cases/LibreOJ_101/1.in"]);
+    assert_eq!(code, Some(0));
+    assert_eq!(stdout.trim(), "flow 729");
+}
+
+#[test]
+fn cut_and_flows() {
+    let (code, stdout) = run(&["--cut", "--flows", "test_cases/AOJ_GRL_6_A/00_sample_00.in"]);
+    assert_eq!(code, Some(0));
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "flow 3");
+
+    let cut_capacity: i64 = lines
+        .iter()
+        .filter(|l| l.starts_with("cut "))
+        .map(|l| l.split(' ').nth(4).unwrap().parse::<i64>().unwrap())
+        .sum();
+    assert_eq!(cut_capacity, 3);
+    assert_eq!(lines.iter().filter(|l| l.starts_with("edge ")).count(), 5);
+}
+
+#[test]
+fn exit_codes() {
+    assert_eq!(run(&[]).0, Some(1));
+    assert_eq!(run(&["--algo", "unknown", "x.in"]).0, Some(1));
+    assert_eq!(run(&["does_not_exist.in"]).0, Some(2));
+    assert_eq!(
+        run(&["--format", "aoj", "test_cases/DIMACS/libreoj_101_1.max"]).0,
+        Some(2)
+    );
+}
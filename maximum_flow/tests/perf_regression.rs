@@ -0,0 +1,179 @@
+// wall-clock regression check of every solver on the largest committed instances
+//   cargo test --release --test perf_regression -- --ignored --nocapture
+// fails if an algorithm takes more than TOLERANCE times its time in tests/perf_baseline.json
+// UPDATE_BASELINE=1 rewrites the baseline with the times of this run
+use maximum_flow::capacity_scaling::CapacityScaling;
+use maximum_flow::dinic::Dinic;
+use maximum_flow::ford_fulkerson::FordFulkerson;
+use maximum_flow::graph::Flow;
+use maximum_flow::instances::{read_instance_libreoj, GraphInstance};
+use maximum_flow::push_relabel_fifo::PushRelabelFIFO;
+use maximum_flow::push_relabel_highest_label::PushRelabelHighestLabel;
+use maximum_flow::solver::MaxFlowSolver;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const BASELINE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/perf_baseline.json");
+const NUM_INSTANCES: usize = 5;
+const NUM_RUNS: usize = 3;
+const TOLERANCE: f64 = 3.0;
+// keeps timer noise on fast runs from failing the check
+const SLACK: Duration = Duration::from_millis(20);
+
+// LibreOJ_127 is built against augmenting paths (Dinic needs minutes on each of the largest
+// instances), so like the unit tests only push-relabel runs it
+const AUGMENTING_PATH_INSTANCES: &str = "LibreOJ_101";
+const PUSH_RELABEL_INSTANCES: &str = "LibreOJ_127";
+
+const PROFILE: &str = if cfg!(debug_assertions) {
+    "debug"
+} else {
+    "release"
+};
+
+struct Instance {
+    name: String,
+    instance: GraphInstance,
+    expected: Flow,
+}
+
+// the largest files of the directory, by size
+fn largest_instances(dir: &str) -> Vec<Instance> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test_cases")
+        .join(dir);
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "in"))
+        .collect();
+    paths.sort_by_key(|path| {
+        (
+            std::cmp::Reverse(path.metadata().unwrap().len()),
+            path.clone(),
+        )
+    });
+    paths
+        .into_iter()
+        .take(NUM_INSTANCES)
+        .map(|path| {
+            let expected = std::fs::read_to_string(path.with_extension("out")).unwrap();
+            Instance {
+                name: path.file_name().unwrap().to_string_lossy().into_owned(),
+                instance: read_instance_libreoj(&path).unwrap(),
+                expected: expected.trim().parse().unwrap(),
+            }
+        })
+        .collect()
+}
+
+// best of NUM_RUNS per instance, summed over the instances
+fn measure<S: MaxFlowSolver>(instances: &[Instance]) -> Duration {
+    instances
+        .iter()
+        .map(|instance| {
+            (0..NUM_RUNS)
+                .map(|_| {
+                    let mut solver: S = instance.instance.build_solver();
+                    let start = Instant::now();
+                    let flow = solver.solve(instance.instance.source, instance.instance.sink);
+                    let elapsed = start.elapsed();
+                    assert_eq!(flow, instance.expected, "{}", instance.name);
+                    elapsed
+                })
+                .min()
+                .unwrap()
+        })
+        .sum()
+}
+
+fn instance_names(dir: &str, instances: &[Instance]) -> Vec<String> {
+    instances
+        .iter()
+        .map(|i| format!("{}/{}", dir, i.name))
+        .collect()
+}
+
+fn read_baseline(path: &Path) -> Option<Value> {
+    let data = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+#[test]
+#[ignore]
+fn perf_regression() {
+    let augmenting_path = largest_instances(AUGMENTING_PATH_INSTANCES);
+    let push_relabel = largest_instances(PUSH_RELABEL_INSTANCES);
+    let mut names = instance_names(AUGMENTING_PATH_INSTANCES, &augmenting_path);
+    names.extend(instance_names(PUSH_RELABEL_INSTANCES, &push_relabel));
+    let timings = [
+        ("dinic", measure::<Dinic>(&augmenting_path)),
+        ("ford_fulkerson", measure::<FordFulkerson>(&augmenting_path)),
+        (
+            "capacity_scaling",
+            measure::<CapacityScaling>(&augmenting_path),
+        ),
+        (
+            "push_relabel_fifo",
+            measure::<PushRelabelFIFO>(&push_relabel),
+        ),
+        (
+            "push_relabel_highest_label",
+            measure::<PushRelabelHighestLabel>(&push_relabel),
+        ),
+    ];
+
+    if std::env::var_os("UPDATE_BASELINE").is_some_and(|v| v == "1") {
+        let seconds: serde_json::Map<String, Value> = timings
+            .iter()
+            .map(|&(name, time)| (name.to_string(), json!(time.as_secs_f64())))
+            .collect();
+        let baseline = json!({
+            "profile": PROFILE,
+            "instances": names,
+            "seconds": seconds,
+        });
+        let data = serde_json::to_string_pretty(&baseline).unwrap();
+        std::fs::write(BASELINE, data + "\n").unwrap();
+        println!("wrote {}", BASELINE);
+        return;
+    }
+
+    let baseline = read_baseline(Path::new(BASELINE))
+        .unwrap_or_else(|| panic!("cannot read {}, run with UPDATE_BASELINE=1", BASELINE));
+    assert_eq!(
+        baseline["profile"], PROFILE,
+        "the baseline was recorded with another profile, run with --release or UPDATE_BASELINE=1"
+    );
+    assert_eq!(
+        baseline["instances"],
+        json!(names),
+        "the largest instances changed, run with UPDATE_BASELINE=1"
+    );
+
+    println!(
+        "{:<28} {:>12} {:>12} {:>8}",
+        "algorithm", "baseline [s]", "current [s]", "ratio"
+    );
+    let mut regressions = Vec::new();
+    for &(name, time) in timings.iter() {
+        let Some(expected) = baseline["seconds"][name].as_f64() else {
+            regressions.push(format!("{}: no baseline", name));
+            continue;
+        };
+        let expected = Duration::from_secs_f64(expected);
+        let ratio = time.as_secs_f64() / expected.as_secs_f64();
+        println!(
+            "{:<28} {:>12.3} {:>12.3} {:>8.2}",
+            name,
+            expected.as_secs_f64(),
+            time.as_secs_f64(),
+            ratio
+        );
+        if time > expected.mul_f64(TOLERANCE) + SLACK {
+            regressions.push(format!("{}: {:.2}x the baseline", name, ratio));
+        }
+    }
+    assert!(regressions.is_empty(), "{}", regressions.join("\n"));
+}
@@ -0,0 +1,24 @@
+// cargo test --target wasm32-unknown-unknown --features wasm --test wasm
+// (needs wasm-bindgen-test-runner from wasm-bindgen-cli, runs headless on Node.js)
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use maximum_flow::wasm::MaxFlow;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn sample() {
+    let mut max_flow = MaxFlow::new();
+    for (from, to, capacity) in [
+        (0, 1, 2.0),
+        (0, 2, 1.0),
+        (1, 2, 1.0),
+        (1, 3, 1.0),
+        (2, 3, 2.0),
+    ] {
+        assert!(max_flow.add_edge(from, to, capacity).is_some());
+    }
+
+    assert_eq!(max_flow.solve(0, 3), Some(3.0));
+    assert_eq!(max_flow.edge_flows(), vec![2.0, 1.0, 1.0, 1.0, 2.0]);
+    assert_eq!(max_flow.min_cut(), vec![0, 1, 2]);
+}
@@ -0,0 +1,393 @@
+use generalized_maximum_flow::graph::Flow as GeneralizedFlow;
+use generalized_maximum_flow::{
+    GainScalingPushRelabel, GeneralizedMaxFlowSolver, HighestGainPathMethod, RoundedPrimalDual,
+};
+use maximum_flow::capacity_scaling::CapacityScaling;
+use maximum_flow::dinic::Dinic;
+use maximum_flow::ford_fulkerson::FordFulkerson;
+use maximum_flow::graph::{Flow, SolveStats};
+use maximum_flow::push_relabel_fifo::PushRelabelFIFO;
+use maximum_flow::push_relabel_highest_label::PushRelabelHighestLabel;
+use maximum_flow::solver::MaxFlowSolver;
+use network_flows::instances::{Format, Instance};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "usage: compare [--format aoj|libreoj|dimacs|gain_random] [--repeat N] [--epsilon E] [--csv] FILE
+
+Runs every solver that applies to the instance and prints its value, median solve time and counters as a Markdown
+table (CSV with --csv). The maximum flow solvers need integer capacities and no gains, the generalized ones run with
+the gains of the file or gain 1, and get within 1 - epsilon of the maximum. Exits with 4 if the values disagree.";
+
+const EXIT_USAGE: u8 = 1;
+const EXIT_PARSE: u8 = 2;
+const EXIT_SOLVER: u8 = 3;
+const EXIT_DISAGREE: u8 = 4;
+
+// the capacities of an f64 instance are exact integers up to 2^53
+const MAX_EXACT_CAPACITY: f64 = (1u64 << 53) as f64;
+
+struct Options {
+    path: String,
+    format: Option<Format>,
+    repeat: usize,
+    epsilon: f64,
+    csv: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: String::new(),
+        format: None,
+        repeat: 1,
+        epsilon: 0.01,
+        csv: false,
+    };
+    let mut path = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                options.format = Some(iter.next().ok_or("--format needs a value")?.parse()?)
+            }
+            "--repeat" => {
+                let value = iter.next().ok_or("--repeat needs a value")?;
+                options.repeat = value
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or(format!("invalid repeat count `{}`", value))?;
+            }
+            "--epsilon" => {
+                let value = iter.next().ok_or("--epsilon needs a value")?;
+                options.epsilon = value
+                    .parse()
+                    .ok()
+                    .filter(|&e: &f64| e > 0.0 && e < 1.0)
+                    .ok_or(format!("invalid epsilon `{}`", value))?;
+            }
+            "--csv" => options.csv = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+    options.path = path.ok_or("missing input file")?;
+    Ok(options)
+}
+
+fn read_instance(options: &Options) -> Result<Instance<f64>, String> {
+    let path = Path::new(&options.path);
+    let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", options.path, e))?;
+    let format = match options.format {
+        Some(format) => format,
+        None => {
+            Format::detect(path, &data).ok_or("cannot detect the input format, use --format")?
+        }
+    };
+    format
+        .read(data.as_bytes())
+        .map_err(|e| format!("{}: {}", options.path, e))
+}
+
+// the instance for the maximum flow solvers, None if it has gains or capacities that aren't integers
+fn integral(instance: &Instance<f64>) -> Option<Instance<Flow>> {
+    let exact = |c: f64| c.fract() == 0.0 && c.abs() <= MAX_EXACT_CAPACITY;
+    if instance.has_gains() || !instance.edges.iter().all(|e| exact(e.capacity)) {
+        return None;
+    }
+    Some(instance.clone().map_capacity(|c| c as Flow))
+}
+
+// what one run of a solver found, value as f64 to compare the families
+struct Run {
+    value: f64,
+    display: String,
+    time: Duration,
+    stats: String,
+}
+
+fn max_flow_stats(stats: &SolveStats) -> String {
+    let counters = [
+        ("phases", stats.phases),
+        ("advances", stats.advances),
+        ("retreats", stats.retreats),
+        ("augmenting_paths", stats.augmenting_paths),
+        ("visited_nodes", stats.visited_nodes),
+        ("saturating_pushes", stats.saturating_pushes),
+        ("non_saturating_pushes", stats.non_saturating_pushes),
+        ("relabels", stats.relabels),
+        ("global_relabels", stats.global_relabels),
+        ("gaps", stats.gaps),
+    ];
+    counters
+        .iter()
+        .filter_map(|(name, c)| c.map(|c| format!("{}={}", name, c)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn generalized_stats(stats: &generalized_maximum_flow::graph::SolveStats) -> String {
+    let mut counters = vec![
+        format!("rounds={}", stats.rounds),
+        format!("dijkstras={}", stats.dijkstras),
+        format!("bellman_fords={}", stats.bellman_fords),
+    ];
+    if let Some(augmentations) = stats.augmentations {
+        counters.push(format!("augmentations={}", augmentations));
+    }
+    counters.push(format!("stalled_rounds={}", stats.stalled_rounds));
+    counters.join(" ")
+}
+
+fn run_max_flow<S: MaxFlowSolver>(instance: &Instance<Flow>) -> Result<Run, String> {
+    let mut solver = S::new();
+    // the solvers reject the edges of capacity 0, which carry no flow anyway
+    for (from, to, capacity) in instance.capacities() {
+        solver.add_directed_edge(from, to, capacity);
+    }
+    let timer = Instant::now();
+    let value = solver.solve(instance.source, instance.sink);
+    let time = timer.elapsed();
+    Ok(Run {
+        value: value as f64,
+        display: value.to_string(),
+        time,
+        stats: max_flow_stats(solver.stats()),
+    })
+}
+
+fn run_generalized<S: GeneralizedMaxFlowSolver>(
+    instance: &Instance<f64>,
+    epsilon: f64,
+    stats: fn(&S) -> String,
+) -> Result<Run, String> {
+    let mut solver = S::new(instance.num_nodes, epsilon);
+    // edges of capacity 0 and self loops carry no flow from the source to the sink, the generalized solvers reject them
+    for (from, to, capacity, gain) in instance.gains().filter(|e| e.2 != 0.0 && e.0 != e.1) {
+        solver
+            .add_directed_edge(from, to, capacity, gain)
+            .map_err(|e| format!("edge {} -> {}: {}", from, to, e))?;
+    }
+    let timer = Instant::now();
+    let value: GeneralizedFlow = solver
+        .try_solve(instance.source, instance.sink)
+        .map_err(|e| e.to_string())?;
+    let time = timer.elapsed();
+    Ok(Run {
+        value,
+        display: value.to_string(),
+        time,
+        stats: stats(&solver),
+    })
+}
+
+// the registered solvers, the maximum flow ones first: (name, exact, run)
+type Solver<'a> = (
+    &'static str,
+    bool,
+    Box<dyn Fn() -> Result<Run, String> + 'a>,
+);
+
+fn solvers<'a>(
+    instance: &'a Instance<f64>,
+    integral: Option<&'a Instance<Flow>>,
+    epsilon: f64,
+) -> Vec<Solver<'a>> {
+    let mut solvers: Vec<Solver> = Vec::new();
+    if let Some(instance) = integral {
+        solvers.push(("dinic", true, Box::new(|| run_max_flow::<Dinic>(instance))));
+        solvers.push((
+            "ff",
+            true,
+            Box::new(|| run_max_flow::<FordFulkerson>(instance)),
+        ));
+        solvers.push((
+            "scaling",
+            true,
+            Box::new(|| run_max_flow::<CapacityScaling>(instance)),
+        ));
+        solvers.push((
+            "pr-fifo",
+            true,
+            Box::new(|| run_max_flow::<PushRelabelFIFO>(instance)),
+        ));
+        solvers.push((
+            "pr-hl",
+            true,
+            Box::new(|| run_max_flow::<PushRelabelHighestLabel>(instance)),
+        ));
+    }
+    solvers.push((
+        "rounded-primal-dual",
+        false,
+        Box::new(move || {
+            run_generalized::<RoundedPrimalDual>(instance, epsilon, |s| {
+                generalized_stats(s.stats())
+            })
+        }),
+    ));
+    solvers.push((
+        "highest-gain-path",
+        false,
+        Box::new(move || {
+            run_generalized::<HighestGainPathMethod>(instance, epsilon, |s| {
+                generalized_stats(s.stats())
+            })
+        }),
+    ));
+    solvers.push((
+        "gain-scaling-push-relabel",
+        false,
+        Box::new(move || {
+            run_generalized::<GainScalingPushRelabel>(instance, epsilon, |s| {
+                format!(
+                    "pushes={} relabels={} global_relabels={}",
+                    s.pushes(),
+                    s.relabels(),
+                    s.global_relabels()
+                )
+            })
+        }),
+    ));
+    solvers
+}
+
+fn median(times: &mut [Duration]) -> Duration {
+    times.sort();
+    let middle = times.len() / 2;
+    if times.len().is_multiple_of(2) {
+        (times[middle - 1] + times[middle]) / 2
+    } else {
+        times[middle]
+    }
+}
+
+// one row of the table
+struct Row {
+    name: &'static str,
+    exact: bool,
+    run: Run,
+    // the value of a later repetition, if it differs from the first
+    unstable: Option<String>,
+    agrees: bool,
+}
+
+// the exact values have to be equal, the approximate ones within 1 - epsilon of the largest value
+fn check_agreement(rows: &mut [Row], epsilon: f64) {
+    let best = rows.iter().map(|r| r.run.value).fold(0.0, f64::max);
+    let exact = rows.iter().find(|r| r.exact).map(|r| r.run.value);
+    let tolerance = 1e-9 * best.max(1.0);
+    for row in rows.iter_mut() {
+        row.agrees = row.unstable.is_none()
+            && match exact {
+                Some(exact) if row.exact => row.run.value == exact,
+                Some(exact) => {
+                    (1.0 - epsilon) * exact - tolerance <= row.run.value
+                        && row.run.value <= exact + tolerance
+                }
+                None => (1.0 - epsilon) * best - tolerance <= row.run.value,
+            };
+    }
+}
+
+fn print_table(rows: &[Row], csv: bool) {
+    let header = ["solver", "value", "median time (ms)", "counters", "agrees"];
+    let cells = |row: &Row| {
+        let value = match &row.unstable {
+            Some(other) => format!("{} (then {})", row.run.display, other),
+            None => row.run.display.clone(),
+        };
+        [
+            row.name.to_string(),
+            value,
+            format!("{:.3}", row.run.time.as_secs_f64() * 1000.0),
+            row.run.stats.clone(),
+            (if row.agrees { "yes" } else { "NO" }).to_string(),
+        ]
+    };
+    if csv {
+        println!("{}", header.join(","));
+        for row in rows {
+            println!("{}", cells(row).join(","));
+        }
+    } else {
+        println!("| {} |", header.join(" | "));
+        println!("|{}", "---|".repeat(header.len()));
+        for row in rows {
+            println!("| {} |", cells(row).join(" | "));
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n{}", message, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let instance = match read_instance(&options) {
+        Ok(instance) => instance,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            return ExitCode::from(EXIT_PARSE);
+        }
+    };
+    if instance.source >= instance.num_nodes
+        || instance.sink >= instance.num_nodes
+        || instance.source == instance.sink
+    {
+        eprintln!("error: source and sink need to be two different nodes of the instance");
+        return ExitCode::from(EXIT_SOLVER);
+    }
+
+    let integral = integral(&instance);
+    let mut rows = Vec::new();
+    for (name, exact, run) in solvers(&instance, integral.as_ref(), options.epsilon) {
+        let mut runs = Vec::new();
+        for _ in 0..options.repeat {
+            match catch_unwind(AssertUnwindSafe(&run)) {
+                Ok(Ok(run)) => runs.push(run),
+                Ok(Err(message)) => {
+                    eprintln!("error: {}: {}", name, message);
+                    return ExitCode::from(EXIT_SOLVER);
+                }
+                Err(_) => {
+                    eprintln!("error: {} failed on this instance", name);
+                    return ExitCode::from(EXIT_SOLVER);
+                }
+            }
+        }
+        let unstable = runs
+            .iter()
+            .find(|r| r.value != runs[0].value)
+            .map(|r| r.display.clone());
+        let mut times: Vec<Duration> = runs.iter().map(|r| r.time).collect();
+        let mut run = runs.swap_remove(0);
+        run.time = median(&mut times);
+        rows.push(Row {
+            name,
+            exact,
+            run,
+            unstable,
+            agrees: false,
+        });
+    }
+
+    check_agreement(&mut rows, options.epsilon);
+    print_table(&rows, options.csv);
+    let disagreeing: Vec<&str> = rows.iter().filter(|r| !r.agrees).map(|r| r.name).collect();
+    if !disagreeing.is_empty() {
+        eprintln!(
+            "error: the values of {} disagree with the others",
+            disagreeing.join(", ")
+        );
+        return ExitCode::from(EXIT_DISAGREE);
+    }
+    ExitCode::SUCCESS
+}
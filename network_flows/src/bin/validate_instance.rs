@@ -0,0 +1,109 @@
+use network_flows::instances::{lint_with, normalize, write_file, Capacity, Format, LintOptions};
+use std::ops::Add;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: validate_instance [--format aoj|libreoj|dimacs|gain_random] [--gains-above-one] [--normalize OUT] FILE
+
+Reports duplicate edges, self loops, capacities of at most 0, gains outside (0, 1], nodes without edges and an
+unreachable sink, one per line with the line of the edge where there is one. Node and edge ids are 0-indexed. With
+--normalize, writes the instance with the unused nodes removed, the edges sorted and the duplicates merged to OUT in
+the format of FILE. --gains-above-one accepts gains above 1. Exits with 3 if there are findings.";
+
+const EXIT_USAGE: u8 = 1;
+const EXIT_IO: u8 = 2;
+const EXIT_FINDINGS: u8 = 3;
+
+struct Options {
+    path: String,
+    format: Option<Format>,
+    lint: LintOptions,
+    normalize: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: String::new(),
+        format: None,
+        lint: LintOptions::default(),
+        normalize: None,
+    };
+    let mut path = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                options.format = Some(iter.next().ok_or("--format needs a value")?.parse()?)
+            }
+            "--gains-above-one" => options.lint.gains_above_one = true,
+            "--normalize" => {
+                options.normalize = Some(iter.next().ok_or("--normalize needs a value")?.clone())
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}`", arg)),
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+    options.path = path.ok_or("missing input file")?;
+    Ok(options)
+}
+
+// prints the findings and writes the normalized instance, Ok(number of findings)
+fn validate<C: Capacity + Add<Output = C>>(
+    options: &Options,
+    format: Format,
+    data: &str,
+) -> Result<usize, String> {
+    let instance = format
+        .read::<C, _>(data.as_bytes())
+        .map_err(|e| format!("{}: {}", options.path, e))?;
+    let lints = lint_with(&instance, &options.lint);
+    let lines = format.edge_lines(data);
+    for lint in lints.iter() {
+        match lint.edge().and_then(|edge| lines.get(edge)) {
+            Some(line) => println!("{}:{}: {}: {}", options.path, line, lint.name(), lint),
+            None => println!("{}: {}: {}", options.path, lint.name(), lint),
+        }
+    }
+    if let Some(out) = &options.normalize {
+        write_file(out, format, &normalize(&instance)).map_err(|e| format!("{}: {}", out, e))?;
+    }
+    Ok(lints.len())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n{}", message, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let path = Path::new(&options.path);
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("error: {}: {}", options.path, e);
+            return ExitCode::from(EXIT_IO);
+        }
+    };
+    let Some(format) = options.format.or_else(|| Format::detect(path, &data)) else {
+        eprintln!("error: cannot detect the input format, use --format");
+        return ExitCode::from(EXIT_IO);
+    };
+
+    // integer capacities for the formats without gains, so that normalizing keeps them exact
+    let findings = match format {
+        Format::GainRandom => validate::<f64>(&options, format, &data),
+        _ => validate::<i64>(&options, format, &data),
+    };
+    match findings {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::from(EXIT_FINDINGS),
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(EXIT_IO)
+        }
+    }
+}
@@ -0,0 +1,395 @@
+#[cfg(feature = "generalized_maximum_flow")]
+use generalized_maximum_flow::{
+    graph::{EdgeError, GeneralizedFlowError},
+    verify::Violation,
+};
+#[cfg(feature = "maximum_flow")]
+use maximum_flow::{control::Cancelled, flows_over_time::Unreachable, graph::FlowViolation};
+use std::fmt;
+
+// the errors of both sub-crates in one type, the crate-level enums convert into it with ? and Error::kind sorts them
+// into the categories below
+#[derive(Debug)]
+pub enum Error {
+    // an edge the graph rejected, ends is None if the error came without them, see Error::edge
+    #[cfg(feature = "generalized_maximum_flow")]
+    InvalidEdge {
+        ends: Option<(usize, usize)>,
+        error: EdgeError,
+    },
+    // terminals, supplies, lower bounds and flow generating cycles a generalized solve can't work with
+    #[cfg(feature = "generalized_maximum_flow")]
+    Generalized(GeneralizedFlowError),
+    // a flow that fails the checks of maximum_flow::graph::Graph::validate_flow or of verify::check
+    #[cfg(feature = "maximum_flow")]
+    FlowViolation(FlowViolation),
+    #[cfg(feature = "generalized_maximum_flow")]
+    Violation(Violation),
+    // an instance file that doesn't parse
+    Parse {
+        location: Location,
+        message: String,
+    },
+    Io(std::io::Error),
+    // a solve stopped by its SolveControl
+    #[cfg(feature = "maximum_flow")]
+    Cancelled(Cancelled),
+    // a demand of maximum_flow::flows_over_time::quickest_flow that no horizon meets
+    #[cfg(feature = "maximum_flow")]
+    Unreachable(Unreachable),
+}
+
+// where a parse error is, in the units of the format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    // 1-based, 0 for a file without a header
+    Line(usize),
+    ByteOffset(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    GraphConstruction,
+    Precondition,
+    // NaN or infinite values, and flows the rounding of the solver pushed out of their bounds
+    Numerical,
+    Parse,
+    Io,
+    // cancellation and limits
+    Interrupted,
+}
+
+impl Error {
+    // an EdgeError with the edge it was returned for
+    #[cfg(feature = "generalized_maximum_flow")]
+    pub fn edge(from: usize, to: usize, error: EdgeError) -> Self {
+        Error::InvalidEdge {
+            ends: Some((from, to)),
+            error,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(feature = "generalized_maximum_flow")]
+            Error::InvalidEdge {
+                error:
+                    EdgeError::NonFiniteCapacity | EdgeError::NonFiniteGain | EdgeError::NonFiniteCost,
+                ..
+            } => ErrorKind::Numerical,
+            #[cfg(feature = "generalized_maximum_flow")]
+            Error::InvalidEdge { .. } => ErrorKind::GraphConstruction,
+            #[cfg(feature = "generalized_maximum_flow")]
+            Error::Generalized(_) => ErrorKind::Precondition,
+            #[cfg(feature = "maximum_flow")]
+            Error::FlowViolation(_) => ErrorKind::Numerical,
+            #[cfg(feature = "generalized_maximum_flow")]
+            Error::Violation(_) => ErrorKind::Numerical,
+            Error::Parse { .. } => ErrorKind::Parse,
+            Error::Io(_) => ErrorKind::Io,
+            #[cfg(feature = "maximum_flow")]
+            Error::Cancelled(_) => ErrorKind::Interrupted,
+            #[cfg(feature = "maximum_flow")]
+            Error::Unreachable(_) => ErrorKind::Precondition,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Line(line) => write!(f, "line {}", line),
+            Location::ByteOffset(offset) => write!(f, "byte offset {}", offset),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "generalized_maximum_flow")]
+            Error::InvalidEdge {
+                ends: Some((from, to)),
+                error,
+            } => write!(f, "edge {} -> {}: {}", from, to, error),
+            #[cfg(feature = "generalized_maximum_flow")]
+            Error::InvalidEdge { ends: None, error } => write!(f, "invalid edge: {}", error),
+            #[cfg(feature = "generalized_maximum_flow")]
+            Error::Generalized(e) => e.fmt(f),
+            #[cfg(feature = "maximum_flow")]
+            Error::FlowViolation(e) => e.fmt(f),
+            #[cfg(feature = "generalized_maximum_flow")]
+            Error::Violation(e) => e.fmt(f),
+            Error::Parse { location, message } => write!(f, "{}: {}", location, message),
+            Error::Io(e) => write!(f, "io error: {}", e),
+            #[cfg(feature = "maximum_flow")]
+            Error::Cancelled(e) => e.fmt(f),
+            #[cfg(feature = "maximum_flow")]
+            Error::Unreachable(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+// the parse errors of the text formats of both crates, generalized_maximum_flow::instances::ParseError among them
+impl From<network_flows_instances::ParseError> for Error {
+    fn from(e: network_flows_instances::ParseError) -> Self {
+        use network_flows_instances::ParseError;
+        match e {
+            ParseError::Io(e) => Error::Io(e),
+            ParseError::Syntax { line, message } => Error::Parse {
+                location: Location::Line(line),
+                message,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "generalized_maximum_flow")]
+impl From<EdgeError> for Error {
+    fn from(error: EdgeError) -> Self {
+        Error::InvalidEdge { ends: None, error }
+    }
+}
+
+#[cfg(feature = "generalized_maximum_flow")]
+impl From<GeneralizedFlowError> for Error {
+    fn from(e: GeneralizedFlowError) -> Self {
+        Error::Generalized(e)
+    }
+}
+
+#[cfg(feature = "generalized_maximum_flow")]
+impl From<Violation> for Error {
+    fn from(e: Violation) -> Self {
+        Error::Violation(e)
+    }
+}
+
+#[cfg(feature = "maximum_flow")]
+impl From<FlowViolation> for Error {
+    fn from(e: FlowViolation) -> Self {
+        Error::FlowViolation(e)
+    }
+}
+
+#[cfg(feature = "maximum_flow")]
+impl From<Cancelled> for Error {
+    fn from(e: Cancelled) -> Self {
+        Error::Cancelled(e)
+    }
+}
+
+#[cfg(feature = "maximum_flow")]
+impl From<Unreachable> for Error {
+    fn from(e: Unreachable) -> Self {
+        Error::Unreachable(e)
+    }
+}
+
+#[cfg(feature = "maximum_flow")]
+impl From<maximum_flow::io::ParseError> for Error {
+    fn from(e: maximum_flow::io::ParseError) -> Self {
+        use maximum_flow::io::ParseError;
+        match e {
+            ParseError::Io(e) => Error::Io(e),
+            ParseError::Syntax { line, message } => Error::Parse {
+                location: Location::Line(line),
+                message,
+            },
+            ParseError::Binary { offset, message } => Error::Parse {
+                location: Location::ByteOffset(offset),
+                message,
+            },
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, ErrorKind, Location};
+
+    #[cfg(feature = "maximum_flow")]
+    #[test]
+    fn maximum_flow() {
+        use super::Result;
+        use maximum_flow::control::{Cancelled, SolveControl};
+        use maximum_flow::dinic::Dinic;
+        use maximum_flow::flows_over_time::{quickest_flow, TimeExpandedBuilder, Unreachable};
+        use maximum_flow::graph::FlowViolation;
+        use maximum_flow::io::read_aoj;
+        use std::sync::atomic::AtomicBool;
+
+        fn read(text: &str) -> Result<usize> {
+            Ok(read_aoj(text.as_bytes())?.num_nodes)
+        }
+        assert_eq!(read("2 1\n0 1 3\n").unwrap(), 2);
+        let error = read("2 1\n0 1 x\n").unwrap_err();
+        assert!(
+            matches!(
+                error,
+                Error::Parse {
+                    location: Location::Line(2),
+                    ..
+                }
+            ),
+            "{:?}",
+            error
+        );
+        assert_eq!(error.kind(), ErrorKind::Parse);
+        assert!(error.to_string().starts_with("line 2: "), "{}", error);
+
+        let error = Error::from(FlowViolation::Conservation { node: 7, excess: 2 });
+        assert_eq!(error.kind(), ErrorKind::Numerical);
+        assert!(error.to_string().contains("node 7"), "{}", error);
+        let error = Error::from(FlowViolation::Capacity {
+            edge: 4,
+            flow: 3,
+            capacity: 2,
+        });
+        assert!(error.to_string().contains("edge 4"), "{}", error);
+
+        let cancel = AtomicBool::new(true);
+        let mut solver = Dinic::new();
+        solver.add_directed_edge(0, 1, 3);
+        let solve = |solver: &mut Dinic| -> Result<i64> {
+            Ok(solver.solve_with_control(0, 1, &SolveControl::new().cancel_on(&cancel))?)
+        };
+        let error = solve(&mut solver).unwrap_err();
+        assert!(
+            matches!(error, Error::Cancelled(Cancelled { .. })),
+            "{:?}",
+            error
+        );
+        assert_eq!(error.kind(), ErrorKind::Interrupted);
+
+        let mut network = TimeExpandedBuilder::new(2, 0);
+        network.add_edge(1, 0, 3, 1);
+        let error = Error::from(quickest_flow(&network, 0, 1, 5).unwrap_err());
+        assert!(
+            matches!(error, Error::Unreachable(Unreachable { demand: 5 })),
+            "{:?}",
+            error
+        );
+        assert_eq!(error.kind(), ErrorKind::Precondition);
+    }
+
+    #[cfg(feature = "generalized_maximum_flow")]
+    #[test]
+    fn generalized_maximum_flow() {
+        use super::Result;
+        use generalized_maximum_flow::graph::{EdgeError, GeneralizedFlowError};
+        use generalized_maximum_flow::instances::read_instance;
+        use generalized_maximum_flow::verify::Violation;
+        use generalized_maximum_flow::RoundedPrimalDual;
+
+        let mut solver = RoundedPrimalDual::new(3, 0.01);
+        let mut add = |from: usize, to: usize, gain: f64| -> Result<usize> {
+            solver
+                .add_directed_edge(from, to, 1.0, gain)
+                .map_err(|e| Error::edge(from, to, e))
+        };
+        assert!(add(0, 1, 0.5).is_ok());
+        let error = add(2, 2, 0.5).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                Error::InvalidEdge {
+                    ends: Some((2, 2)),
+                    error: EdgeError::SelfLoop
+                }
+            ),
+            "{:?}",
+            error
+        );
+        assert_eq!(error.kind(), ErrorKind::GraphConstruction);
+        assert!(error.to_string().starts_with("edge 2 -> 2: "), "{}", error);
+        let error = add(0, 2, f64::NAN).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Numerical);
+        assert_eq!(
+            Error::from(EdgeError::NonPositiveGain).kind(),
+            ErrorKind::GraphConstruction
+        );
+
+        let error = Error::from(solver.try_solve(0, 5).unwrap_err());
+        assert!(
+            matches!(
+                error,
+                Error::Generalized(GeneralizedFlowError::InvalidTerminal(5))
+            ),
+            "{:?}",
+            error
+        );
+        assert_eq!(error.kind(), ErrorKind::Precondition);
+        assert!(error.to_string().contains("node 5"), "{}", error);
+
+        let error = Error::from(Violation::Capacity {
+            edge: 3,
+            flow: 2.0,
+            capacity: 1.0,
+        });
+        assert_eq!(error.kind(), ErrorKind::Numerical);
+        assert!(error.to_string().contains("edge 3"), "{}", error);
+
+        let error =
+            Error::from(read_instance("3 2 0 2\n0 1 5 NaN\n1 2 1 0.9\n".as_bytes()).unwrap_err());
+        assert!(
+            matches!(
+                error,
+                Error::Parse {
+                    location: Location::Line(2),
+                    ..
+                }
+            ),
+            "{:?}",
+            error
+        );
+        assert!(error.to_string().starts_with("line 2: "), "{}", error);
+    }
+
+    #[test]
+    fn io_and_parse() {
+        use std::error::Error as _;
+
+        let error = Error::from(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "truncated",
+        ));
+        assert_eq!(error.kind(), ErrorKind::Io);
+        assert!(error.source().is_some());
+        assert_eq!(error.to_string(), "io error: truncated");
+
+        let error = Error::from(
+            network_flows_instances::read_aoj::<i64, _>("3 2\n0 1 5\n1 3 7\n".as_bytes())
+                .unwrap_err(),
+        );
+        assert!(
+            matches!(
+                error,
+                Error::Parse {
+                    location: Location::Line(3),
+                    ..
+                }
+            ),
+            "{:?}",
+            error
+        );
+        assert_eq!(error.to_string(), "line 3: to 3 is not below num_nodes 3");
+    }
+}
@@ -0,0 +1,67 @@
+//! The solvers of `maximum_flow` and `generalized_maximum_flow` behind one dependency.
+//!
+//! Each sub-crate is behind the feature of the same name, both are on by default. The sub-crates themselves are
+//! re-exported for everything the top level leaves out, e.g. `network_flows::maximum_flow::graph::Flow`, and
+//! `prelude` brings in the solvers, their traits and the graph types at once. The errors of both convert into
+//! `Error`.
+
+mod error;
+
+pub use error::{Error, ErrorKind, Location, Result};
+
+#[cfg(feature = "generalized_maximum_flow")]
+pub use generalized_maximum_flow;
+#[cfg(feature = "maximum_flow")]
+pub use maximum_flow;
+pub use network_flows_instances as instances;
+
+/// ```
+/// use network_flows::prelude::*;
+///
+/// let mut solver = Dinic::new();
+/// solver.add_directed_edge(0, 1, 2);
+/// let edge = solver.add_directed_edge(1, 2, 3).unwrap();
+/// solver.add_directed_edge(0, 2, 1);
+/// assert_eq!(solver.solve(0, 2), 3);
+/// assert_eq!(solver.graph().get_directed_edge(edge).flow, 2);
+/// ```
+#[cfg(feature = "maximum_flow")]
+pub use maximum_flow::dinic::Dinic;
+#[cfg(feature = "maximum_flow")]
+pub use maximum_flow::{
+    capacity_scaling::CapacityScaling, ford_fulkerson::FordFulkerson, graph::Graph,
+    push_relabel_fifo::PushRelabelFIFO, push_relabel_highest_label::PushRelabelHighestLabel,
+    solver::MaxFlowSolver,
+};
+
+/// ```
+/// use network_flows::prelude::*;
+///
+/// // half of what enters 0 -> 1 is lost on the way
+/// let epsilon = 0.01;
+/// let mut solver = RoundedPrimalDual::new(3, epsilon);
+/// let edge = solver.add_directed_edge(0, 1, 4.0, 0.5).unwrap();
+/// solver.add_directed_edge(1, 2, 10.0, 1.0).unwrap();
+/// let value = solver.try_solve(0, 2).unwrap();
+/// assert!((1.0 - epsilon) * 2.0 <= value && value <= 2.0 + 1e-9);
+/// assert!(solver.graph().get_flow(edge).0 <= 4.0 + 1e-9);
+/// ```
+#[cfg(feature = "generalized_maximum_flow")]
+pub use generalized_maximum_flow::RoundedPrimalDual;
+#[cfg(feature = "generalized_maximum_flow")]
+pub use generalized_maximum_flow::{
+    graph::ScalingGraph, GainScalingPushRelabel, GeneralizedMaxFlowSolver, HighestGainPathMethod,
+};
+
+pub mod prelude {
+    #[cfg(feature = "maximum_flow")]
+    pub use crate::{
+        CapacityScaling, Dinic, FordFulkerson, Graph, MaxFlowSolver, PushRelabelFIFO,
+        PushRelabelHighestLabel,
+    };
+    #[cfg(feature = "generalized_maximum_flow")]
+    pub use crate::{
+        GainScalingPushRelabel, GeneralizedMaxFlowSolver, HighestGainPathMethod, RoundedPrimalDual,
+        ScalingGraph,
+    };
+}
@@ -0,0 +1,102 @@
+use std::process::Command;
+
+fn run(args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_compare"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+// the rows of a Markdown table, without its header
+fn rows(stdout: &str) -> Vec<Vec<String>> {
+    stdout
+        .lines()
+        .skip(2)
+        .map(|line| {
+            line.trim_matches('|')
+                .split('|')
+                .map(|cell| cell.trim().to_string())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn aoj_sample() {
+    let (code, stdout) = run(&["../maximum_flow/test_cases/AOJ_GRL_6_A/00_sample_00.in"]);
+    assert_eq!(code, Some(0), "{}", stdout);
+    let rows = rows(&stdout);
+    let names: Vec<&str> = rows.iter().map(|row| row[0].as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "dinic",
+            "ff",
+            "scaling",
+            "pr-fifo",
+            "pr-hl",
+            "rounded-primal-dual",
+            "highest-gain-path",
+            "gain-scaling-push-relabel"
+        ]
+    );
+    for row in rows.iter() {
+        assert_eq!(row[1].parse::<f64>().unwrap(), 3.0, "{:?}", row);
+        assert_eq!(row[4], "yes", "{:?}", row);
+    }
+}
+
+#[test]
+fn repeat_and_csv() {
+    let (code, stdout) = run(&[
+        "--repeat",
+        "3",
+        "--csv",
+        "../maximum_flow/test_cases/DIMACS/aoj_grl_6_a_00_sample_00.max",
+    ]);
+    assert_eq!(code, Some(0), "{}", stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines[0], "solver,value,median time (ms),counters,agrees");
+    assert_eq!(lines.len(), 9);
+    assert!(lines[1].starts_with("dinic,3,"), "{}", lines[1]);
+    assert!(
+        lines.iter().skip(1).all(|line| line.ends_with(",yes")),
+        "{}",
+        stdout
+    );
+}
+
+// only the generalized solvers apply, and agree within epsilon
+#[test]
+fn gain_random() {
+    let (code, stdout) = run(&[
+        "--epsilon",
+        "0.01",
+        "../generalized_maximum_flow/test_cases/gain_random/00.in",
+    ]);
+    assert_eq!(code, Some(0), "{}", stdout);
+    let names: Vec<String> = rows(&stdout)
+        .into_iter()
+        .map(|row| row[0].clone())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "rounded-primal-dual",
+            "highest-gain-path",
+            "gain-scaling-push-relabel"
+        ]
+    );
+}
+
+#[test]
+fn usage() {
+    assert_eq!(run(&[]).0, Some(1));
+    assert_eq!(run(&["--repeat", "0", "x.in"]).0, Some(1));
+    assert_eq!(run(&["--format", "csv", "x.in"]).0, Some(1));
+    assert_eq!(run(&["does_not_exist.in"]).0, Some(2));
+}
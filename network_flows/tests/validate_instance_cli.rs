@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+fn run(args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_validate_instance"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+// a file of the test's own in the temporary directory
+fn temp_file(name: &str, data: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("validate_instance_{}_{}", std::process::id(), name));
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+#[test]
+fn clean_test_cases() {
+    for path in [
+        "../maximum_flow/test_cases/AOJ_GRL_6_A/00_sample_00.in",
+        "../maximum_flow/test_cases/DIMACS/aoj_grl_6_a_00_sample_00.max",
+        "../generalized_maximum_flow/test_cases/gain_random/00.in",
+    ] {
+        assert_eq!(run(&[path]), (Some(0), String::new()), "{}", path);
+    }
+}
+
+#[test]
+fn findings_with_lines() {
+    let path = temp_file(
+        "bad.in",
+        "5 6\n0 1 5\n1 1 2\n0 1 5\n1 2 -3\n\n1 4 0\n2 1 1\n",
+    );
+    let (code, stdout) = run(&[path.to_str().unwrap()]);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(code, Some(3));
+    let findings: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.strip_prefix(path.to_str().unwrap()).unwrap())
+        .collect();
+    assert_eq!(
+        findings,
+        [
+            ":3: self_loop: edge 1 is a self loop",
+            ":4: duplicate_edge: edge 2 duplicates edge 0",
+            ":5: non_positive_capacity: edge 3 has a capacity of at most 0",
+            ":7: non_positive_capacity: edge 4 has a capacity of at most 0",
+            ": unused_node: node 3 has no edges",
+            ": unreachable_sink: the sink is unreachable from the source",
+        ]
+    );
+}
+
+#[test]
+fn gains_above_one() {
+    let path = temp_file("gains.in", "3 2 0 2\n0 1 5 1.5\n1 2 1 0.9\n");
+    let path = path.to_str().unwrap();
+    let (code, stdout) = run(&[path]);
+    assert_eq!(code, Some(3));
+    assert!(
+        stdout.ends_with(":2: gain_out_of_range: edge 0 has gain 1.5 outside (0, 1]\n"),
+        "{}",
+        stdout
+    );
+    assert_eq!(run(&["--gains-above-one", path]), (Some(0), String::new()));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn normalize() {
+    let path = temp_file(
+        "duplicates.max",
+        "p max 5 4\nn 2 s\nn 4 t\na 2 4 1\na 5 4 2\na 2 5 1\na 2 4 3\n",
+    );
+    let out = std::env::temp_dir().join(format!(
+        "validate_instance_{}_normalized.max",
+        std::process::id()
+    ));
+    let (code, _) = run(&["--normalize", out.to_str().unwrap(), path.to_str().unwrap()]);
+    assert_eq!(code, Some(3));
+    let normalized = std::fs::read_to_string(&out).unwrap();
+    assert_eq!(
+        normalized,
+        "p max 3 3\nn 1 s\nn 2 t\na 1 2 4\na 1 3 1\na 3 2 2\n"
+    );
+    assert_eq!(run(&[out.to_str().unwrap()]), (Some(0), String::new()));
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn usage() {
+    assert_eq!(run(&[]).0, Some(1));
+    assert_eq!(run(&["--normalize"]).0, Some(1));
+    assert_eq!(run(&["--format", "csv", "x.in"]).0, Some(1));
+    assert_eq!(run(&["does_not_exist.in"]).0, Some(2));
+}
@@ -0,0 +1,174 @@
+// the compressed sparse row layout of the residual networks of maximum_flow and generalized_maximum_flow: each edge
+// becomes a forward arc among the arcs of its tail and a reverse arc among the arcs of its head, in the order of the
+// edges, and each arc knows the position of the other
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+// an arc of a CsrGraph, whatever else it carries
+pub trait EdgePayload {
+    fn to(&self) -> usize;
+    fn rev(&self) -> usize;
+    fn set_rev(&mut self, rev: usize);
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CsrGraph<E> {
+    pub num_nodes: usize,
+    // the arcs of u are arcs[start[u]..start[u + 1]]
+    pub start: Vec<usize>,
+    pub arcs: Vec<E>,
+    // position of the forward arc of each edge
+    pub edge_index: Vec<usize>,
+}
+
+impl<E: EdgePayload + Default> CsrGraph<E> {
+    // (from, to, forward arc, reverse arc) for each edge, build wires the revs of the two arcs
+    pub fn build(num_nodes: usize, edges: impl IntoIterator<Item = (usize, usize, E, E)>) -> Self {
+        let mut degree = vec![0; num_nodes];
+        let mut ends = Vec::new();
+        let mut unplaced = Vec::new();
+        for (from, to, forward, reverse) in edges {
+            // the positions among the arcs of from and to, made absolute below
+            ends.push((from, degree[from], to, degree[to] + usize::from(from == to)));
+            degree[from] += 1;
+            degree[to] += 1;
+            unplaced.push((from, forward));
+            unplaced.push((to, reverse));
+        }
+
+        let mut start = vec![0; num_nodes + 1];
+        for u in 0..num_nodes {
+            start[u + 1] = start[u] + degree[u];
+        }
+        let mut arcs: Vec<E> = (0..unplaced.len()).map(|_| E::default()).collect();
+        let mut counter = start.clone();
+        for (u, e) in unplaced {
+            arcs[counter[u]] = e;
+            counter[u] += 1;
+        }
+
+        let mut edge_index = Vec::with_capacity(ends.len());
+        for (from, forward, to, reverse) in ends {
+            let (forward, reverse) = (start[from] + forward, start[to] + reverse);
+            arcs[forward].set_rev(reverse);
+            arcs[reverse].set_rev(forward);
+            edge_index.push(forward);
+        }
+        CsrGraph {
+            num_nodes,
+            start,
+            arcs,
+            edge_index,
+        }
+    }
+}
+
+impl<E> CsrGraph<E> {
+    pub fn neighbors(&self, u: usize) -> core::slice::Iter<'_, E> {
+        self.arcs[self.start[u]..self.start[u + 1]].iter()
+    }
+
+    // the forward arc of edge_index, see build
+    pub fn forward(&self, edge_index: usize) -> &E {
+        &self.arcs[self.edge_index[edge_index]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CsrGraph, EdgePayload};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[derive(Debug, Default, Clone, PartialEq)]
+    struct Arc {
+        to: usize,
+        rev: usize,
+        // the edge and whether this is its forward arc
+        edge: (usize, bool),
+    }
+
+    impl EdgePayload for Arc {
+        fn to(&self) -> usize {
+            self.to
+        }
+        fn rev(&self) -> usize {
+            self.rev
+        }
+        fn set_rev(&mut self, rev: usize) {
+            self.rev = rev;
+        }
+    }
+
+    fn build(num_nodes: usize, edges: &[(usize, usize)]) -> CsrGraph<Arc> {
+        CsrGraph::build(
+            num_nodes,
+            edges.iter().enumerate().map(|(i, &(from, to))| {
+                (
+                    from,
+                    to,
+                    Arc {
+                        to,
+                        rev: usize::MAX,
+                        edge: (i, true),
+                    },
+                    Arc {
+                        to: from,
+                        rev: usize::MAX,
+                        edge: (i, false),
+                    },
+                )
+            }),
+        )
+    }
+
+    #[test]
+    fn layout() {
+        let graph = build(4, &[(0, 1), (0, 2), (1, 2), (2, 0), (2, 3)]);
+        assert_eq!(graph.start, vec![0, 3, 5, 9, 10]);
+        let edges: Vec<(usize, bool)> = graph.arcs.iter().map(|a| a.edge).collect();
+        assert_eq!(
+            edges,
+            vec![
+                (0, true),
+                (1, true),
+                (3, false),
+                (0, false),
+                (2, true),
+                (1, false),
+                (2, false),
+                (3, true),
+                (4, true),
+                (4, false)
+            ]
+        );
+        for (i, arc) in graph.arcs.iter().enumerate() {
+            assert_eq!(graph.arcs[arc.rev].rev, i);
+            assert_eq!(graph.arcs[arc.rev].edge, (arc.edge.0, !arc.edge.1));
+        }
+        for i in 0..5 {
+            assert_eq!(graph.forward(i).edge, (i, true));
+        }
+        assert_eq!(
+            graph.neighbors(1).map(|a| a.to()).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert_eq!(
+            graph.neighbors(3).map(|a| a.to()).collect::<Vec<_>>(),
+            vec![2]
+        );
+    }
+
+    // both arcs of a self loop among the arcs of its node, the forward one first
+    #[test]
+    fn self_loop() {
+        let graph = build(2, &[(0, 0), (0, 1)]);
+        assert_eq!(graph.start, vec![0, 3, 4]);
+        assert_eq!((graph.arcs[0].rev, graph.arcs[1].rev), (1, 0));
+        assert_eq!(graph.forward(1).to, 1);
+    }
+}
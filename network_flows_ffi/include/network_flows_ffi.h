@@ -0,0 +1,87 @@
+#ifndef NETWORK_FLOWS_FFI_H
+#define NETWORK_FLOWS_FFI_H
+
+/* generated by cbindgen from network_flows_ffi/src/lib.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define NF_OK 0
+
+#define NF_NULL_POINTER -1
+
+#define NF_INVALID_ARGUMENT -2
+
+#define NF_NOT_SOLVED -3
+
+#define NF_BUFFER_TOO_SMALL -4
+
+#define NF_PANIC -5
+
+/**
+ * Opaque handle to a max-flow instance solved with Dinic.
+ */
+typedef struct NfGraph NfGraph;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates an empty graph. Release it with `nf_graph_free`.
+ */
+struct NfGraph *nf_graph_new(void);
+
+/**
+ * Adds the edge `from -> to`. `edge_index` may be null.
+ *
+ * # Safety
+ * `graph` must come from `nf_graph_new`, and `edge_index` must be null or valid for writes.
+ */
+int32_t nf_graph_add_edge(struct NfGraph *graph,
+                          size_t from,
+                          size_t to,
+                          int64_t capacity,
+                          size_t *edge_index);
+
+/**
+ * Solves the maximum flow from `source` to `sink` and writes its value to `flow`.
+ *
+ * # Safety
+ * `graph` must come from `nf_graph_new`, and `flow` must be valid for writes.
+ */
+int32_t nf_graph_solve_dinic(struct NfGraph *graph, size_t source, size_t sink, int64_t *flow);
+
+/**
+ * Writes 1 for nodes on the source side of a minimum cut and 0 otherwise.
+ * `buffer` must hold at least `nf_graph_num_nodes` entries.
+ *
+ * # Safety
+ * `graph` must come from `nf_graph_new`, and `buffer` must be valid for `len` writes.
+ */
+int32_t nf_graph_min_cut(const struct NfGraph *graph, uint8_t *buffer, size_t len);
+
+/**
+ * Returns the number of nodes (the largest node id plus one).
+ *
+ * # Safety
+ * `graph` must be null or come from `nf_graph_new`.
+ */
+size_t nf_graph_num_nodes(const struct NfGraph *graph);
+
+/**
+ * Releases a graph created by `nf_graph_new`. Null is ignored.
+ *
+ * # Safety
+ * `graph` must be null or come from `nf_graph_new`, and must not be used afterwards.
+ */
+void nf_graph_free(struct NfGraph *graph);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NETWORK_FLOWS_FFI_H */
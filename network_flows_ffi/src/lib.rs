@@ -0,0 +1,201 @@
+use maximum_flow::dinic::Dinic;
+use maximum_flow::solver::MaxFlowSolver;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+pub const NF_OK: i32 = 0;
+pub const NF_NULL_POINTER: i32 = -1;
+pub const NF_INVALID_ARGUMENT: i32 = -2;
+pub const NF_NOT_SOLVED: i32 = -3;
+pub const NF_BUFFER_TOO_SMALL: i32 = -4;
+pub const NF_PANIC: i32 = -5;
+
+/// Opaque handle to a max-flow instance solved with Dinic.
+pub struct NfGraph {
+    solver: Dinic,
+    num_nodes: usize,
+    sink: Option<usize>,
+}
+
+fn guard<F: FnOnce() -> i32>(f: F) -> i32 {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or(NF_PANIC)
+}
+
+/// Creates an empty graph. Release it with `nf_graph_free`.
+#[no_mangle]
+pub extern "C" fn nf_graph_new() -> *mut NfGraph {
+    Box::into_raw(Box::new(NfGraph {
+        solver: Dinic::new(),
+        num_nodes: 0,
+        sink: None,
+    }))
+}
+
+/// Adds the edge `from -> to`. `edge_index` may be null.
+///
+/// # Safety
+/// `graph` must come from `nf_graph_new`, and `edge_index` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nf_graph_add_edge(
+    graph: *mut NfGraph,
+    from: usize,
+    to: usize,
+    capacity: i64,
+    edge_index: *mut usize,
+) -> i32 {
+    let Some(graph) = graph.as_mut() else {
+        return NF_NULL_POINTER;
+    };
+    guard(
+        || match graph.solver.add_directed_edge(from, to, capacity) {
+            None => NF_INVALID_ARGUMENT,
+            Some(index) => {
+                graph.num_nodes = graph.num_nodes.max(from.max(to) + 1);
+                graph.sink = None;
+                if let Some(edge_index) = edge_index.as_mut() {
+                    *edge_index = index;
+                }
+                NF_OK
+            }
+        },
+    )
+}
+
+/// Solves the maximum flow from `source` to `sink` and writes its value to `flow`.
+///
+/// # Safety
+/// `graph` must come from `nf_graph_new`, and `flow` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nf_graph_solve_dinic(
+    graph: *mut NfGraph,
+    source: usize,
+    sink: usize,
+    flow: *mut i64,
+) -> i32 {
+    let (Some(graph), Some(flow)) = (graph.as_mut(), flow.as_mut()) else {
+        return NF_NULL_POINTER;
+    };
+    if source >= graph.num_nodes || sink >= graph.num_nodes {
+        return NF_INVALID_ARGUMENT;
+    }
+    guard(|| {
+        *flow = graph.solver.solve(source, sink);
+        graph.sink = Some(sink);
+        NF_OK
+    })
+}
+
+/// Writes 1 for nodes on the source side of a minimum cut and 0 otherwise.
+/// `buffer` must hold at least `nf_graph_num_nodes` entries.
+///
+/// # Safety
+/// `graph` must come from `nf_graph_new`, and `buffer` must be valid for `len` writes.
+#[no_mangle]
+pub unsafe extern "C" fn nf_graph_min_cut(
+    graph: *const NfGraph,
+    buffer: *mut u8,
+    len: usize,
+) -> i32 {
+    let Some(graph) = graph.as_ref() else {
+        return NF_NULL_POINTER;
+    };
+    if buffer.is_null() {
+        return NF_NULL_POINTER;
+    }
+    let Some(sink) = graph.sink else {
+        return NF_NOT_SOLVED;
+    };
+    if len < graph.num_nodes {
+        return NF_BUFFER_TOO_SMALL;
+    }
+    guard(|| {
+        let buffer = std::slice::from_raw_parts_mut(buffer, len);
+        for (b, &side) in buffer
+            .iter_mut()
+            .zip(graph.solver.graph().min_cut(sink).iter())
+        {
+            *b = side as u8;
+        }
+        NF_OK
+    })
+}
+
+/// Returns the number of nodes (the largest node id plus one).
+///
+/// # Safety
+/// `graph` must be null or come from `nf_graph_new`.
+#[no_mangle]
+pub unsafe extern "C" fn nf_graph_num_nodes(graph: *const NfGraph) -> usize {
+    graph.as_ref().map_or(0, |graph| graph.num_nodes)
+}
+
+/// Releases a graph created by `nf_graph_new`. Null is ignored.
+///
+/// # Safety
+/// `graph` must be null or come from `nf_graph_new`, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nf_graph_free(graph: *mut NfGraph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sample() {
+        unsafe {
+            let graph = nf_graph_new();
+            for (from, to, capacity) in [(0, 1, 2), (0, 2, 1), (1, 2, 1), (1, 3, 1), (2, 3, 2)] {
+                assert_eq!(
+                    nf_graph_add_edge(graph, from, to, capacity, std::ptr::null_mut()),
+                    NF_OK
+                );
+            }
+
+            let mut cut = [0u8; 4];
+            assert_eq!(
+                nf_graph_min_cut(graph, cut.as_mut_ptr(), cut.len()),
+                NF_NOT_SOLVED
+            );
+
+            let mut flow = 0;
+            assert_eq!(nf_graph_solve_dinic(graph, 0, 3, &mut flow), NF_OK);
+            assert_eq!(flow, 3);
+            assert_eq!(nf_graph_min_cut(graph, cut.as_mut_ptr(), cut.len()), NF_OK);
+            assert_eq!(cut, [1, 1, 1, 0]);
+            assert_eq!(
+                nf_graph_min_cut(graph, cut.as_mut_ptr(), 2),
+                NF_BUFFER_TOO_SMALL
+            );
+            nf_graph_free(graph);
+        }
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        unsafe {
+            let mut flow = 0;
+            assert_eq!(
+                nf_graph_solve_dinic(std::ptr::null_mut(), 0, 1, &mut flow),
+                NF_NULL_POINTER
+            );
+
+            let graph = nf_graph_new();
+            assert_eq!(
+                nf_graph_add_edge(graph, 0, 1, 0, std::ptr::null_mut()),
+                NF_INVALID_ARGUMENT
+            );
+            let mut index = usize::MAX;
+            assert_eq!(nf_graph_add_edge(graph, 0, 1, 5, &mut index), NF_OK);
+            assert_eq!(index, 0);
+            assert_eq!(
+                nf_graph_solve_dinic(graph, 0, 7, &mut flow),
+                NF_INVALID_ARGUMENT
+            );
+            nf_graph_free(graph);
+            nf_graph_free(std::ptr::null_mut());
+        }
+    }
+}
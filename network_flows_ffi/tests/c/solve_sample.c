@@ -0,0 +1,29 @@
+/* solves the AOJ GRL_6_A sample through the C API */
+#include <stdint.h>
+#include <stdio.h>
+
+#include "network_flows_ffi.h"
+
+int main(void) {
+    const size_t edges[][3] = {{0, 1, 2}, {0, 2, 1}, {1, 2, 1}, {1, 3, 1}, {2, 3, 2}};
+    NfGraph *graph = nf_graph_new();
+    for (size_t i = 0; i < sizeof(edges) / sizeof(edges[0]); i++) {
+        if (nf_graph_add_edge(graph, edges[i][0], edges[i][1], (int64_t)edges[i][2], NULL) != NF_OK) {
+            return 1;
+        }
+    }
+
+    int64_t flow = 0;
+    if (nf_graph_solve_dinic(graph, 0, 3, &flow) != NF_OK || flow != 3) {
+        return 2;
+    }
+
+    uint8_t cut[4];
+    if (nf_graph_min_cut(graph, cut, nf_graph_num_nodes(graph)) != NF_OK || cut[0] != 1 || cut[3] != 0) {
+        return 3;
+    }
+
+    nf_graph_free(graph);
+    printf("%lld\n", (long long)flow);
+    return 0;
+}
@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+// compiles tests/c/solve_sample.c against the static library and runs it
+// skipped when no C compiler is available
+#[test]
+fn solve_sample() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // target/<profile>/deps/c_program-<hash>
+    let target_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let library = target_dir.join("libnetwork_flows_ffi.a");
+    assert!(library.exists(), "{} is missing", library.display());
+
+    let executable = target_dir.join("solve_sample_c");
+    let compiled = Command::new("cc")
+        .arg(manifest_dir.join("tests/c/solve_sample.c"))
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(&library)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&executable)
+        .status();
+    match compiled {
+        Err(_) => {
+            eprintln!("skipping: no C compiler");
+            return;
+        }
+        Ok(status) => assert!(status.success()),
+    }
+
+    let output = Command::new(&executable).output().unwrap();
+    assert!(output.status.success(), "exit status {:?}", output.status);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "3\n");
+}
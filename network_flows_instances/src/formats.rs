@@ -0,0 +1,676 @@
+use crate::{Capacity, Edge, Instance, ParseError, MAX_RESERVED_EDGES};
+use std::io::{BufRead, Write};
+
+fn parse_node(token: Option<&str>, num_nodes: usize, line: usize) -> Result<usize, ParseError> {
+    let token = token.ok_or_else(|| ParseError::syntax(line, "missing node id"))?;
+    let id: usize = token
+        .parse()
+        .map_err(|_| ParseError::syntax(line, format!("invalid node id `{}`", token)))?;
+    if id == 0 || id > num_nodes {
+        return Err(ParseError::syntax(
+            line,
+            format!("node id {} is out of range 1..={}", id, num_nodes),
+        ));
+    }
+    Ok(id - 1)
+}
+
+fn parse_token<T: std::str::FromStr>(
+    token: Option<&str>,
+    name: &str,
+    line: usize,
+) -> Result<T, ParseError> {
+    let token = token.ok_or_else(|| ParseError::syntax(line, format!("missing {}", name)))?;
+    token
+        .parse()
+        .map_err(|_| ParseError::syntax(line, format!("invalid {} `{}`", name, token)))
+}
+
+// a 0-indexed node id below num_nodes
+fn check_node(u: usize, name: &str, num_nodes: usize, line: usize) -> Result<usize, ParseError> {
+    if u >= num_nodes {
+        return Err(ParseError::syntax(
+            line,
+            format!("{} {} is not below num_nodes {}", name, u, num_nodes),
+        ));
+    }
+    Ok(u)
+}
+
+// calls f for every non-blank line with its 1-indexed line number, reusing a single buffer
+fn for_each_line<R: BufRead>(
+    mut r: R,
+    mut f: impl FnMut(usize, &str) -> Result<(), ParseError>,
+) -> Result<(), ParseError> {
+    let mut buffer = String::new();
+    let mut line_number = 0;
+    loop {
+        buffer.clear();
+        if r.read_line(&mut buffer)? == 0 {
+            return Ok(());
+        }
+        line_number += 1;
+        if !buffer.trim().is_empty() {
+            f(line_number, &buffer)?;
+        }
+    }
+}
+
+// the formats without gains can't keep those of the instance
+fn check_no_gains<C>(instance: &Instance<C>, format: &str) -> std::io::Result<()> {
+    if instance.has_gains() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("the {} format has no gains", format),
+        ));
+    }
+    Ok(())
+}
+
+// the edge count of the header against the edges read
+fn check_edge_count(num_edges: usize, found: usize) -> Result<(), ParseError> {
+    if found != num_edges {
+        return Err(ParseError::syntax(
+            0,
+            format!("expected {} edges, found {}", num_edges, found),
+        ));
+    }
+    Ok(())
+}
+
+// header: `num_nodes num_edges`, edges: `from to capacity` (0-indexed, source = 0, sink = num_nodes - 1)
+pub fn read_aoj<C: Capacity, R: BufRead>(r: R) -> Result<Instance<C>, ParseError> {
+    let mut header: Option<(usize, usize)> = None;
+    let mut edges = Vec::new();
+    for_each_line(r, |line_number, line| {
+        let mut tokens = line.split_whitespace();
+        let Some((num_nodes, _)) = header else {
+            let n: usize = parse_token(tokens.next(), "node count", line_number)?;
+            if n == 0 {
+                return Err(ParseError::syntax(
+                    line_number,
+                    "node count must be positive",
+                ));
+            }
+            let num_edges: usize = parse_token(tokens.next(), "edge count", line_number)?;
+            edges.reserve(num_edges.min(MAX_RESERVED_EDGES));
+            header = Some((n, num_edges));
+            return Ok(());
+        };
+
+        let from = parse_token(tokens.next(), "from", line_number)?;
+        let to = parse_token(tokens.next(), "to", line_number)?;
+        let capacity = parse_token(tokens.next(), "capacity", line_number)?;
+        let from = check_node(from, "from", num_nodes, line_number)?;
+        let to = check_node(to, "to", num_nodes, line_number)?;
+        edges.push(Edge::new(from, to, capacity));
+        Ok(())
+    })?;
+
+    let (num_nodes, num_edges) = header.ok_or_else(|| ParseError::syntax(0, "empty instance"))?;
+    check_edge_count(num_edges, edges.len())?;
+    Ok(Instance {
+        num_nodes,
+        edges,
+        source: 0,
+        sink: num_nodes - 1,
+    })
+}
+
+// header: `num_nodes num_edges source sink`, edges: `from to capacity` (1-indexed)
+pub fn read_libreoj<C: Capacity, R: BufRead>(r: R) -> Result<Instance<C>, ParseError> {
+    let mut header: Option<(usize, usize, usize, usize)> = None;
+    let mut edges = Vec::new();
+    for_each_line(r, |line_number, line| {
+        let mut tokens = line.split_whitespace();
+        let Some((num_nodes, _, _, _)) = header else {
+            let num_nodes: usize = parse_token(tokens.next(), "node count", line_number)?;
+            let num_edges: usize = parse_token(tokens.next(), "edge count", line_number)?;
+            let source = parse_node(tokens.next(), num_nodes, line_number)?;
+            let sink = parse_node(tokens.next(), num_nodes, line_number)?;
+            edges.reserve(num_edges.min(MAX_RESERVED_EDGES));
+            header = Some((num_nodes, num_edges, source, sink));
+            return Ok(());
+        };
+
+        let from = parse_node(tokens.next(), num_nodes, line_number)?;
+        let to = parse_node(tokens.next(), num_nodes, line_number)?;
+        let capacity = parse_token(tokens.next(), "capacity", line_number)?;
+        edges.push(Edge::new(from, to, capacity));
+        Ok(())
+    })?;
+
+    let (num_nodes, num_edges, source, sink) =
+        header.ok_or_else(|| ParseError::syntax(0, "empty instance"))?;
+    check_edge_count(num_edges, edges.len())?;
+    Ok(Instance {
+        num_nodes,
+        edges,
+        source,
+        sink,
+    })
+}
+
+// DIMACS `p max`, node ids are 1-based in the file and 0-based in the returned instance
+pub fn read_dimacs<C: Capacity, R: BufRead>(r: R) -> Result<Instance<C>, ParseError> {
+    let mut header: Option<(usize, usize)> = None;
+    let mut source = None;
+    let mut sink = None;
+    let mut edges = Vec::new();
+
+    for (i, line) in r.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        let kind = match tokens.next() {
+            None | Some("c") => continue,
+            Some(kind) => kind,
+        };
+
+        if kind == "p" {
+            if header.is_some() {
+                return Err(ParseError::syntax(line_number, "duplicate problem line"));
+            }
+            if tokens.next() != Some("max") {
+                return Err(ParseError::syntax(
+                    line_number,
+                    "expected `p max NODES ARCS`",
+                ));
+            }
+            let mut count = || -> Result<usize, ParseError> {
+                let token = tokens.next().ok_or_else(|| {
+                    ParseError::syntax(line_number, "expected `p max NODES ARCS`")
+                })?;
+                token.parse().map_err(|_| {
+                    ParseError::syntax(line_number, format!("invalid count `{}`", token))
+                })
+            };
+            let num_nodes = count()?;
+            let num_arcs = count()?;
+            header = Some((num_nodes, num_arcs));
+            edges.reserve(num_arcs.min(MAX_RESERVED_EDGES));
+            continue;
+        }
+
+        let (num_nodes, _) = header.ok_or_else(|| {
+            ParseError::syntax(
+                line_number,
+                format!("`{}` line before the problem line", kind),
+            )
+        })?;
+        match kind {
+            "n" => {
+                let u = parse_node(tokens.next(), num_nodes, line_number)?;
+                match tokens.next() {
+                    Some("s") if source.is_none() => source = Some(u),
+                    Some("t") if sink.is_none() => sink = Some(u),
+                    Some("s") => return Err(ParseError::syntax(line_number, "duplicate source")),
+                    Some("t") => return Err(ParseError::syntax(line_number, "duplicate sink")),
+                    _ => {
+                        return Err(ParseError::syntax(
+                            line_number,
+                            "expected `n ID s` or `n ID t`",
+                        ))
+                    }
+                }
+            }
+            "a" => {
+                let from = parse_node(tokens.next(), num_nodes, line_number)?;
+                let to = parse_node(tokens.next(), num_nodes, line_number)?;
+                let capacity: C = parse_token(tokens.next(), "capacity", line_number)?;
+                if capacity < C::default() {
+                    return Err(ParseError::syntax(
+                        line_number,
+                        "capacity must be non-negative",
+                    ));
+                }
+                edges.push(Edge::new(from, to, capacity));
+            }
+            _ => {
+                return Err(ParseError::syntax(
+                    line_number,
+                    format!("unknown line type `{}`", kind),
+                ))
+            }
+        }
+    }
+
+    let (num_nodes, num_arcs) =
+        header.ok_or_else(|| ParseError::syntax(0, "missing problem line"))?;
+    if edges.len() != num_arcs {
+        return Err(ParseError::syntax(
+            0,
+            format!("expected {} arcs, found {}", num_arcs, edges.len()),
+        ));
+    }
+
+    Ok(Instance {
+        num_nodes,
+        edges,
+        source: source.ok_or_else(|| ParseError::syntax(0, "missing source line"))?,
+        sink: sink.ok_or_else(|| ParseError::syntax(0, "missing sink line"))?,
+    })
+}
+
+// the fields of a line, which has exactly names.len() of them
+fn fields<'a>(
+    line: &'a str,
+    line_number: usize,
+    names: &[&str],
+) -> Result<Vec<&'a str>, ParseError> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < names.len() {
+        return Err(ParseError::syntax(
+            line_number,
+            format!("missing {} column {}", names[fields.len()], fields.len()),
+        ));
+    }
+    if fields.len() > names.len() {
+        return Err(ParseError::syntax(
+            line_number,
+            format!(
+                "unexpected column {} `{}`",
+                names.len(),
+                fields[names.len()]
+            ),
+        ));
+    }
+    Ok(fields)
+}
+
+// what a generalized solver would reject, NaN and infinite values would end up in its labels
+fn check_gain_edge<C: Capacity>(capacity: C, gain: f64) -> Result<(), &'static str> {
+    if !capacity.is_finite() {
+        return Err("capacity needs to be finite");
+    }
+    if !gain.is_finite() {
+        return Err("gain needs to be finite");
+    }
+    if capacity <= C::default() {
+        return Err("capacity needs to be greater than 0");
+    }
+    if gain <= 0.0 {
+        return Err("gain needs to be greater than 0");
+    }
+    Ok(())
+}
+
+// header: `num_nodes num_edges source sink`, edges: `from to capacity gain` (0-indexed)
+// blank lines and comments, lines starting with `#` or a `c` of its own, are ignored, and so are CRLF endings
+pub fn read_gain_random<C: Capacity, R: BufRead>(r: R) -> Result<Instance<C>, ParseError> {
+    // num_nodes and num_edges, from header_line
+    let mut header: Option<(usize, usize)> = None;
+    let mut header_line = 0;
+    let mut instance = Instance {
+        num_nodes: 0,
+        edges: Vec::new(),
+        source: 0,
+        sink: 0,
+    };
+    for (i, line) in r.lines().enumerate() {
+        let line = line?;
+        let line_number = i + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.split_whitespace().next() == Some("c")
+        {
+            continue;
+        }
+
+        let Some((num_nodes, _)) = header else {
+            let names = ["num_nodes", "num_edges", "source", "sink"];
+            let fields = fields(trimmed, line_number, &names)?;
+            let num_nodes: usize = parse_token(Some(fields[0]), "num_nodes", line_number)?;
+            let num_edges: usize = parse_token(Some(fields[1]), "num_edges", line_number)?;
+            let source = parse_token(Some(fields[2]), "source", line_number)?;
+            let sink = parse_token(Some(fields[3]), "sink", line_number)?;
+            let source = check_node(source, "source", num_nodes, line_number)?;
+            let sink = check_node(sink, "sink", num_nodes, line_number)?;
+            header = Some((num_nodes, num_edges));
+            header_line = line_number;
+            instance = Instance {
+                num_nodes,
+                edges: Vec::with_capacity(num_edges.min(MAX_RESERVED_EDGES)),
+                source,
+                sink,
+            };
+            continue;
+        };
+
+        let fields = fields(trimmed, line_number, &["from", "to", "capacity", "gain"])?;
+        let from = parse_token(Some(fields[0]), "from", line_number)?;
+        let to = parse_token(Some(fields[1]), "to", line_number)?;
+        let capacity: C = parse_token(Some(fields[2]), "capacity", line_number)?;
+        let gain: f64 = parse_token(Some(fields[3]), "gain", line_number)?;
+        let from = check_node(from, "from", num_nodes, line_number)?;
+        let to = check_node(to, "to", num_nodes, line_number)?;
+        check_gain_edge(capacity, gain).map_err(|e| ParseError::syntax(line_number, e))?;
+        instance
+            .edges
+            .push(Edge::new(from, to, capacity).with_gain(gain));
+    }
+
+    let Some((_, num_edges)) = header else {
+        return Err(ParseError::syntax(0, "missing header"));
+    };
+    if instance.edges.len() != num_edges {
+        return Err(ParseError::syntax(
+            header_line,
+            format!(
+                "the header announces {} edges, the file has {}",
+                num_edges,
+                instance.edges.len()
+            ),
+        ));
+    }
+    Ok(instance)
+}
+
+// the AOJ format has no source and sink, so they must be 0 and num_nodes - 1
+pub fn write_aoj<C: Capacity, W: Write>(mut w: W, instance: &Instance<C>) -> std::io::Result<()> {
+    check_no_gains(instance, "AOJ")?;
+    if instance.source != 0 || instance.sink + 1 != instance.num_nodes {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "the AOJ format needs source = 0 and sink = num_nodes - 1",
+        ));
+    }
+    writeln!(w, "{} {}", instance.num_nodes, instance.edges.len())?;
+    for e in instance.edges.iter() {
+        writeln!(w, "{} {} {}", e.from, e.to, e.capacity)?;
+    }
+    Ok(())
+}
+
+pub fn write_libreoj<C: Capacity, W: Write>(
+    mut w: W,
+    instance: &Instance<C>,
+) -> std::io::Result<()> {
+    check_no_gains(instance, "LibreOJ")?;
+    writeln!(
+        w,
+        "{} {} {} {}",
+        instance.num_nodes,
+        instance.edges.len(),
+        instance.source + 1,
+        instance.sink + 1
+    )?;
+    for e in instance.edges.iter() {
+        writeln!(w, "{} {} {}", e.from + 1, e.to + 1, e.capacity)?;
+    }
+    Ok(())
+}
+
+pub fn write_dimacs<C: Capacity, W: Write>(
+    mut w: W,
+    instance: &Instance<C>,
+) -> std::io::Result<()> {
+    check_no_gains(instance, "DIMACS")?;
+    writeln!(w, "p max {} {}", instance.num_nodes, instance.edges.len())?;
+    writeln!(w, "n {} s", instance.source + 1)?;
+    writeln!(w, "n {} t", instance.sink + 1)?;
+    for e in instance.edges.iter() {
+        writeln!(w, "a {} {} {}", e.from + 1, e.to + 1, e.capacity)?;
+    }
+    Ok(())
+}
+
+// the capacities and gains parse back to the same values
+pub fn write_gain_random<C: Capacity, W: Write>(
+    mut w: W,
+    instance: &Instance<C>,
+) -> std::io::Result<()> {
+    writeln!(
+        w,
+        "{} {} {} {}",
+        instance.num_nodes,
+        instance.edges.len(),
+        instance.source,
+        instance.sink
+    )?;
+    for (from, to, capacity, gain) in instance.gains() {
+        writeln!(w, "{} {} {} {}", from, to, capacity, gain)?;
+    }
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{read_expected, read_file, write_file, Edge, Format, Instance, ParseError};
+    use std::io::BufReader;
+    use std::path::{Path, PathBuf};
+
+    fn error_line<C: crate::Capacity>(format: Format, data: &str) -> usize {
+        match format.read::<C, _>(data.as_bytes()) {
+            Err(ParseError::Syntax { line, .. }) => line,
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("parsed a malformed instance"),
+        }
+    }
+
+    fn test_cases(pattern: &str) -> Vec<PathBuf> {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let (dir, extension) = pattern.split_once("/*.").unwrap();
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(root.join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|e| e == extension))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn dimacs() {
+        let data = "c sample\np max 4 5\nn 1 s\nn 4 t\nc arcs\na 1 2 2\na 1 3 1\na 2 3 1\na 2 4 1\n\na 3 4 2\n";
+        let instance = Format::Dimacs.read::<i64, _>(data.as_bytes()).unwrap();
+        assert_eq!(
+            (instance.num_nodes, instance.source, instance.sink),
+            (4, 0, 3)
+        );
+        assert_eq!(
+            instance.capacities().collect::<Vec<_>>(),
+            vec![(0, 1, 2), (0, 2, 1), (1, 2, 1), (1, 3, 1), (2, 3, 2)]
+        );
+
+        for (data, line) in [
+            ("c only comments\n", 0),
+            ("a 1 2 3\n", 1),
+            ("p max 2 1\np max 2 1\n", 2),
+            ("p min 2 1\n", 1),
+            ("p max 2 1\nn 1 s\nn 2 t\na 1 3 5\n", 4),
+            ("p max 2 1\nn 1 s\nn 2 t\na 1 2 -1\n", 4),
+            ("p max 2 1\nn 1 s\nn 2 s\n", 3),
+            ("p max 2 2\nn 1 s\nn 2 t\na 1 2 5\n", 0),
+            ("p max 2 1\nn 1 s\na 1 2 5\n", 0),
+        ] {
+            assert_eq!(error_line::<i64>(Format::Dimacs, data), line, "{}", data);
+        }
+    }
+
+    #[test]
+    fn aoj_and_libreoj() {
+        let instance = Format::Aoj
+            .read::<i64, _>("3 2\n0 1 5\n1 2 7\n".as_bytes())
+            .unwrap();
+        assert_eq!((instance.source, instance.sink), (0, 2));
+        assert_eq!(instance.edges, vec![Edge::new(0, 1, 5), Edge::new(1, 2, 7)]);
+        let data = "3 2 1 3\n\n1 2 123456789\r\n2 3 7\n";
+        let instance: Instance<i64> =
+            crate::read_libreoj(BufReader::with_capacity(4, data.as_bytes())).unwrap();
+        assert_eq!((instance.source, instance.sink), (0, 2));
+        assert_eq!(
+            instance.edges,
+            vec![Edge::new(0, 1, 123456789), Edge::new(1, 2, 7)]
+        );
+
+        assert_eq!(error_line::<i64>(Format::Aoj, ""), 0);
+        assert_eq!(error_line::<i64>(Format::Aoj, "3 2\n0 1\n"), 2);
+        assert_eq!(error_line::<i64>(Format::Aoj, "3 2\n\n0 1 5\n1 2 x\n"), 4);
+        // the node ids of an AOJ file are checked like those of the other formats
+        assert_eq!(error_line::<i64>(Format::Aoj, "3 2\n0 1 5\n1 3 7\n"), 3);
+        assert_eq!(error_line::<i64>(Format::LibreOj, "3 1 1 3\n1 2 x\n"), 2);
+        assert_eq!(
+            error_line::<i64>(Format::LibreOj, "3 2 1 3\n1 2 5.5\n2 3 7\n"),
+            2
+        );
+    }
+
+    #[test]
+    fn gain_random() {
+        let data = "# generated\r\nc 3 nodes\r\n\r\n3 2 0 2  \r\n0 1 5 0.5\t\r\n  c\r\n1 2 1.5 0.9\r\n\r\n";
+        let instance = Format::GainRandom.read::<f64, _>(data.as_bytes()).unwrap();
+        let edges = vec![
+            Edge::new(0, 1, 5.0).with_gain(0.5),
+            Edge::new(1, 2, 1.5).with_gain(0.9),
+        ];
+        assert_eq!(
+            instance,
+            Instance {
+                num_nodes: 3,
+                edges,
+                source: 0,
+                sink: 2
+            }
+        );
+
+        for (data, line) in [
+            ("", 0),
+            ("3 2 0\n", 1),
+            ("3 2 0 3\n", 1),
+            ("3 3 0 2\n0 1 5 0.5\n1 2 1 0.9\n", 1),
+            ("3 2 0 2\n0 1 5 0.5 1\n1 2 1 0.9\n", 2),
+            ("3 2 0 2\n\n0 1 five 0.5\n1 2 1 0.9\n", 3),
+            ("3 2 0 2\n0 3 5 0.5\n1 2 1 0.9\n", 2),
+            ("3 2 0 2\n0 1 inf 0.5\n1 2 1 0.9\n", 2),
+            ("3 2 0 2\n0 1 5 -0.5\n1 2 1 0.9\n", 2),
+        ] {
+            assert_eq!(
+                error_line::<f64>(Format::GainRandom, data),
+                line,
+                "{}",
+                data
+            );
+        }
+        let message = Format::GainRandom
+            .read::<f64, _>("3 2 0 2\n0 1 5 0.5\n1 2 1 0\n".as_bytes())
+            .unwrap_err()
+            .to_string();
+        assert_eq!(message, "line 3: gain needs to be greater than 0");
+    }
+
+    // every test case of both crates reads back the same after writing it in its format, and the maximum flow ones
+    // in the other formats that can hold them
+    #[test]
+    fn round_trip() {
+        let mut cases = Vec::new();
+        for (pattern, format) in [
+            ("maximum_flow/test_cases/AOJ_GRL_6_A/*.in", Format::Aoj),
+            ("maximum_flow/test_cases/LibreOJ_101/*.in", Format::LibreOj),
+            ("maximum_flow/test_cases/DIMACS/*.max", Format::Dimacs),
+        ] {
+            for path in test_cases(pattern) {
+                let instance: Instance<i64> = read_file(&path, format).unwrap();
+                assert!(read_expected::<i64>(path.with_extension("out")).is_ok());
+                cases.push((path, instance));
+            }
+        }
+        assert!(cases.len() >= 10);
+        for (path, instance) in cases.iter() {
+            for format in Format::ALL {
+                let mut data = Vec::new();
+                match format.write(&mut data, instance) {
+                    Ok(()) => {}
+                    // the AOJ format can't say where the source and the sink are
+                    Err(_) if format == Format::Aoj => continue,
+                    Err(e) => panic!("{}: {}", path.display(), e),
+                }
+                let read = format.read::<i64, _>(data.as_slice());
+                // nor can gain_random hold an edge of capacity 0
+                if format == Format::GainRandom && instance.edges.iter().any(|e| e.capacity == 0) {
+                    assert!(read.is_err());
+                    continue;
+                }
+                let read = read.unwrap();
+                assert_eq!(
+                    read.capacities().collect::<Vec<_>>(),
+                    instance.capacities().collect::<Vec<_>>(),
+                    "{} {}",
+                    path.display(),
+                    format
+                );
+                assert_eq!(
+                    (read.num_nodes, read.source, read.sink),
+                    (instance.num_nodes, instance.source, instance.sink)
+                );
+            }
+        }
+
+        for path in test_cases("generalized_maximum_flow/test_cases/gain_random/*.in") {
+            let instance: Instance<f64> = read_file(&path, Format::GainRandom).unwrap();
+            assert!(instance.has_gains());
+            let copy = std::env::temp_dir().join(format!(
+                "network_flows_instances_{}_{}",
+                std::process::id(),
+                path.file_name().unwrap().to_string_lossy()
+            ));
+            write_file(&copy, Format::GainRandom, &instance).unwrap();
+            assert_eq!(
+                read_file::<f64>(&copy, Format::GainRandom).unwrap(),
+                instance
+            );
+            std::fs::remove_file(copy).unwrap();
+            assert!(Format::Dimacs.write(Vec::new(), &instance).is_err());
+            assert!(read_expected::<f64>(path.with_extension("out")).is_ok());
+        }
+    }
+
+    #[test]
+    fn detect() {
+        let detect = |path: &str, data: &str| Format::detect(Path::new(path), data);
+        assert_eq!(detect("a.max", ""), Some(Format::Dimacs));
+        assert_eq!(
+            detect("a.in", "c comment\np max 2 1\n"),
+            Some(Format::Dimacs)
+        );
+        assert_eq!(detect("a.in", "\n3 2\n0 1 5\n"), Some(Format::Aoj));
+        assert_eq!(detect("a.in", "3 2 1 3\n1 2 5\n"), Some(Format::LibreOj));
+        assert_eq!(
+            detect("a.in", "# gains\nc\n3 2 0 2\n0 1 5 0.5\n"),
+            Some(Format::GainRandom)
+        );
+        assert_eq!(detect("a.in", "3 2 0\n"), None);
+        assert_eq!(detect("a.in", ""), None);
+        for (pattern, format) in [
+            ("maximum_flow/test_cases/AOJ_GRL_6_A/*.in", Format::Aoj),
+            ("maximum_flow/test_cases/LibreOJ_101/*.in", Format::LibreOj),
+            (
+                "generalized_maximum_flow/test_cases/gain_random/*.in",
+                Format::GainRandom,
+            ),
+        ] {
+            for path in test_cases(pattern) {
+                let data = std::fs::read_to_string(&path).unwrap();
+                assert_eq!(
+                    Format::detect(&path, &data),
+                    Some(format),
+                    "{}",
+                    path.display()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn format_names() {
+        for format in Format::ALL {
+            assert_eq!(format.name().parse(), Ok(format));
+        }
+        assert!("max".parse::<Format>().is_err());
+        assert!(matches!(
+            read_file::<i64>("does_not_exist.in", Format::Aoj),
+            Err(ParseError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,283 @@
+// the instances of the test cases of maximum_flow and generalized_maximum_flow, one type for both and the readers and
+// writers of their formats, see formats.rs
+use std::fmt;
+use std::fs::{read_to_string, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+mod formats;
+mod lint;
+
+pub use formats::{
+    read_aoj, read_dimacs, read_gain_random, read_libreoj, write_aoj, write_dimacs,
+    write_gain_random, write_libreoj,
+};
+pub use lint::{lint, lint_with, normalize, Lint, LintOptions};
+
+// the edge count in a header is only a hint, so don't trust it with an unbounded allocation
+pub const MAX_RESERVED_EDGES: usize = 1 << 24;
+
+// i64 for the maximum flow formats, f64 for gain_random
+pub trait Capacity: Copy + PartialOrd + Default + FromStr + fmt::Display {
+    fn is_finite(self) -> bool {
+        true
+    }
+}
+
+impl Capacity for i64 {}
+
+impl Capacity for f64 {
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+}
+
+// gain and cost are None where the format has no column for them, which the solvers read as gain 1 and cost 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge<C> {
+    pub from: usize,
+    pub to: usize,
+    pub capacity: C,
+    pub gain: Option<f64>,
+    pub cost: Option<f64>,
+}
+
+impl<C> Edge<C> {
+    pub fn new(from: usize, to: usize, capacity: C) -> Self {
+        Edge {
+            from,
+            to,
+            capacity,
+            gain: None,
+            cost: None,
+        }
+    }
+
+    pub fn with_gain(self, gain: f64) -> Self {
+        Edge {
+            gain: Some(gain),
+            ..self
+        }
+    }
+}
+
+// node ids are 0-indexed, edges are kept in input order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Instance<C> {
+    pub num_nodes: usize,
+    pub edges: Vec<Edge<C>>,
+    pub source: usize,
+    pub sink: usize,
+}
+
+impl<C> Instance<C> {
+    // e.g. the integer capacities of an AOJ file for a generalized solver
+    pub fn map_capacity<D>(self, f: impl Fn(C) -> D) -> Instance<D> {
+        Instance {
+            num_nodes: self.num_nodes,
+            edges: self
+                .edges
+                .into_iter()
+                .map(|e| Edge {
+                    from: e.from,
+                    to: e.to,
+                    capacity: f(e.capacity),
+                    gain: e.gain,
+                    cost: e.cost,
+                })
+                .collect(),
+            source: self.source,
+            sink: self.sink,
+        }
+    }
+
+    // an edge with a gain other than 1, which a maximum flow solver would ignore
+    pub fn has_gains(&self) -> bool {
+        self.edges.iter().any(|e| e.gain.is_some_and(|g| g != 1.0))
+    }
+
+    // (from, to, capacity) in input order
+    pub fn capacities(&self) -> impl Iterator<Item = (usize, usize, C)> + '_
+    where
+        C: Copy,
+    {
+        self.edges.iter().map(|e| (e.from, e.to, e.capacity))
+    }
+
+    // (from, to, capacity, gain) in input order, gain 1 for an edge without one
+    pub fn gains(&self) -> impl Iterator<Item = (usize, usize, C, f64)> + '_
+    where
+        C: Copy,
+    {
+        self.edges
+            .iter()
+            .map(|e| (e.from, e.to, e.capacity, e.gain.unwrap_or(1.0)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    // header `num_nodes num_edges`, edges `from to capacity`, 0-indexed, source 0 and sink num_nodes - 1
+    Aoj,
+    // header `num_nodes num_edges source sink`, edges `from to capacity`, 1-indexed
+    LibreOj,
+    // DIMACS `p max`, 1-indexed
+    Dimacs,
+    // header `num_nodes num_edges source sink`, edges `from to capacity gain`, 0-indexed
+    GainRandom,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [
+        Format::Aoj,
+        Format::LibreOj,
+        Format::Dimacs,
+        Format::GainRandom,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Aoj => "aoj",
+            Format::LibreOj => "libreoj",
+            Format::Dimacs => "dimacs",
+            Format::GainRandom => "gain_random",
+        }
+    }
+
+    // the .max extension of DIMACS, or else the shape of the first lines: a `p` line, a header of 2 fields, or one of
+    // 4 followed by edges of 3 (LibreOJ) or 4 (gain_random) fields, comments and blank lines skipped
+    pub fn detect(path: &Path, data: &str) -> Option<Format> {
+        if path.extension().is_some_and(|e| e == "max") {
+            return Some(Format::Dimacs);
+        }
+        let mut lines = data.lines().map(str::trim).filter(|l| {
+            !l.is_empty() && !l.starts_with('#') && l.split_whitespace().next() != Some("c")
+        });
+        let header: Vec<&str> = lines.next()?.split_whitespace().collect();
+        match header.as_slice() {
+            ["p", ..] => Some(Format::Dimacs),
+            [_, _] => Some(Format::Aoj),
+            [_, _, _, _] => match lines.next().map(|l| l.split_whitespace().count()) {
+                Some(4) => Some(Format::GainRandom),
+                _ => Some(Format::LibreOj),
+            },
+            _ => None,
+        }
+    }
+
+    // the 1-indexed line of each edge of data, in the order of Instance::edges
+    pub fn edge_lines(self, data: &str) -> Vec<usize> {
+        let lines = data.lines().enumerate().filter(|(_, l)| {
+            let l = l.trim();
+            !l.is_empty() && !l.starts_with('#') && l.split_whitespace().next() != Some("c")
+        });
+        match self {
+            Format::Dimacs => lines
+                .filter(|(_, l)| l.split_whitespace().next() == Some("a"))
+                .map(|(i, _)| i + 1)
+                .collect(),
+            _ => lines.skip(1).map(|(i, _)| i + 1).collect(),
+        }
+    }
+
+    pub fn read<C: Capacity, R: BufRead>(self, r: R) -> Result<Instance<C>, ParseError> {
+        match self {
+            Format::Aoj => read_aoj(r),
+            Format::LibreOj => read_libreoj(r),
+            Format::Dimacs => read_dimacs(r),
+            Format::GainRandom => read_gain_random(r),
+        }
+    }
+
+    pub fn write<C: Capacity, W: Write>(self, w: W, instance: &Instance<C>) -> std::io::Result<()> {
+        match self {
+            Format::Aoj => write_aoj(w, instance),
+            Format::LibreOj => write_libreoj(w, instance),
+            Format::Dimacs => write_dimacs(w, instance),
+            Format::GainRandom => write_gain_random(w, instance),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::ALL
+            .into_iter()
+            .find(|f| f.name() == s)
+            .ok_or_else(|| format!("unknown format `{}`", s))
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(std::io::Error),
+    // line 0 for a file without a header
+    Syntax { line: usize, message: String },
+}
+
+impl ParseError {
+    pub(crate) fn syntax(line: usize, message: impl Into<String>) -> Self {
+        ParseError::Syntax {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "io error: {}", e),
+            ParseError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            ParseError::Syntax { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ParseError {
+    fn from(e: std::io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+pub fn read_file<C: Capacity>(
+    path: impl AsRef<Path>,
+    format: Format,
+) -> Result<Instance<C>, ParseError> {
+    format.read(BufReader::new(File::open(path)?))
+}
+
+pub fn write_file<C: Capacity>(
+    path: impl AsRef<Path>,
+    format: Format,
+    instance: &Instance<C>,
+) -> std::io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    format.write(&mut w, instance)?;
+    w.flush()
+}
+
+// the answer next to a test case, a single number
+pub fn read_expected<T: FromStr>(path: impl AsRef<Path>) -> Result<T, ParseError> {
+    let data = read_to_string(path)?;
+    data.trim()
+        .parse()
+        .map_err(|_| ParseError::syntax(1, format!("invalid answer `{}`", data.trim())))
+}
@@ -0,0 +1,346 @@
+// findings in an instance that the readers accept but that usually mean a broken benchmark file, and the normalized
+// form of an instance
+use crate::{Capacity, Edge, Instance};
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::ops::Add;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LintOptions {
+    // the instance generates flow on purpose, so gains above 1 are fine
+    pub gains_above_one: bool,
+}
+
+// edges are indices into Instance::edges, nodes are 0-indexed whatever the format
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    // the same ends, gain and cost as the earlier edge first
+    DuplicateEdge { edge: usize, first: usize },
+    SelfLoop { edge: usize },
+    NonPositiveCapacity { edge: usize },
+    // a gain of at most 0, or above 1 without LintOptions::gains_above_one
+    GainOutOfRange { edge: usize, gain: f64 },
+    // a gap in the node ids, no edge touches the node and it is neither source nor sink
+    UnusedNode { node: usize },
+    // along the edges of positive capacity
+    UnreachableSink,
+}
+
+impl Lint {
+    // the edge the finding is about, for its line in the file
+    pub fn edge(&self) -> Option<usize> {
+        match *self {
+            Lint::DuplicateEdge { edge, .. }
+            | Lint::SelfLoop { edge }
+            | Lint::NonPositiveCapacity { edge }
+            | Lint::GainOutOfRange { edge, .. } => Some(edge),
+            Lint::UnusedNode { .. } | Lint::UnreachableSink => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::DuplicateEdge { .. } => "duplicate_edge",
+            Lint::SelfLoop { .. } => "self_loop",
+            Lint::NonPositiveCapacity { .. } => "non_positive_capacity",
+            Lint::GainOutOfRange { .. } => "gain_out_of_range",
+            Lint::UnusedNode { .. } => "unused_node",
+            Lint::UnreachableSink => "unreachable_sink",
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::DuplicateEdge { edge, first } => {
+                write!(f, "edge {} duplicates edge {}", edge, first)
+            }
+            Lint::SelfLoop { edge } => write!(f, "edge {} is a self loop", edge),
+            Lint::NonPositiveCapacity { edge } => {
+                write!(f, "edge {} has a capacity of at most 0", edge)
+            }
+            Lint::GainOutOfRange { edge, gain } => {
+                write!(f, "edge {} has gain {} outside (0, 1]", edge, gain)
+            }
+            Lint::UnusedNode { node } => write!(f, "node {} has no edges", node),
+            Lint::UnreachableSink => f.write_str("the sink is unreachable from the source"),
+        }
+    }
+}
+
+// what identifies a duplicate, an edge without gain or cost has gain 1 and cost 0
+fn key<C>(e: &Edge<C>) -> (usize, usize, u64, u64) {
+    (
+        e.from,
+        e.to,
+        e.gain.unwrap_or(1.0).to_bits(),
+        e.cost.unwrap_or(0.0).to_bits(),
+    )
+}
+
+pub fn lint<C: Capacity>(instance: &Instance<C>) -> Vec<Lint> {
+    lint_with(instance, &LintOptions::default())
+}
+
+// the edge findings in edge order, then the unused nodes, then the sink
+pub fn lint_with<C: Capacity>(instance: &Instance<C>, options: &LintOptions) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    let mut first = HashMap::new();
+    for (edge, e) in instance.edges.iter().enumerate() {
+        if let Some(&first) = first.get(&key(e)) {
+            lints.push(Lint::DuplicateEdge { edge, first });
+        } else {
+            first.insert(key(e), edge);
+        }
+        if e.from == e.to {
+            lints.push(Lint::SelfLoop { edge });
+        }
+        // NaN included
+        if e.capacity.partial_cmp(&C::default()) != Some(Ordering::Greater) {
+            lints.push(Lint::NonPositiveCapacity { edge });
+        }
+        if let Some(gain) = e.gain {
+            if !(gain > 0.0 && (gain <= 1.0 || options.gains_above_one)) {
+                lints.push(Lint::GainOutOfRange { edge, gain });
+            }
+        }
+    }
+
+    let used = used_nodes(instance);
+    lints.extend(
+        (0..instance.num_nodes)
+            .filter(|&u| !used[u])
+            .map(|node| Lint::UnusedNode { node }),
+    );
+
+    if !reachable(instance)
+        .get(instance.sink)
+        .copied()
+        .unwrap_or(false)
+    {
+        lints.push(Lint::UnreachableSink);
+    }
+    lints
+}
+
+// the ends of the edges, and source and sink
+fn used_nodes<C>(instance: &Instance<C>) -> Vec<bool> {
+    let mut used = vec![false; instance.num_nodes];
+    for e in instance.edges.iter() {
+        used[e.from] = true;
+        used[e.to] = true;
+    }
+    for node in [instance.source, instance.sink] {
+        if let Some(used) = used.get_mut(node) {
+            *used = true;
+        }
+    }
+    used
+}
+
+fn reachable<C: Capacity>(instance: &Instance<C>) -> Vec<bool> {
+    let mut adjacency = vec![Vec::new(); instance.num_nodes];
+    for e in instance.edges.iter() {
+        if e.capacity > C::default() && e.gain.is_none_or(|g| g > 0.0) {
+            adjacency[e.from].push(e.to);
+        }
+    }
+    let mut visited = vec![false; instance.num_nodes];
+    if instance.source >= instance.num_nodes {
+        return visited;
+    }
+    visited[instance.source] = true;
+    let mut queue = VecDeque::from([instance.source]);
+    while let Some(u) = queue.pop_front() {
+        for &v in adjacency[u].iter() {
+            if !visited[v] {
+                visited[v] = true;
+                queue.push_back(v);
+            }
+        }
+    }
+    visited
+}
+
+// the unused nodes removed with the other ids kept in order, the edges sorted by (from, to) and the duplicates merged
+// into one edge with the sum of their capacities; self loops and bad capacities or gains are left to lint
+pub fn normalize<C: Capacity + Add<Output = C>>(instance: &Instance<C>) -> Instance<C> {
+    let mut ids = vec![0; instance.num_nodes];
+    let mut num_nodes = 0;
+    for (u, used) in used_nodes(instance).into_iter().enumerate() {
+        ids[u] = num_nodes;
+        num_nodes += used as usize;
+    }
+
+    let mut edges: Vec<Edge<C>> = instance
+        .edges
+        .iter()
+        .map(|e| Edge {
+            from: ids[e.from],
+            to: ids[e.to],
+            ..*e
+        })
+        .collect();
+    edges.sort_by(|a, b| {
+        let (a, b) = (key(a), key(b));
+        (a.0, a.1)
+            .cmp(&(b.0, b.1))
+            .then(f64::from_bits(a.2).total_cmp(&f64::from_bits(b.2)))
+            .then(f64::from_bits(a.3).total_cmp(&f64::from_bits(b.3)))
+    });
+    let mut merged: Vec<Edge<C>> = Vec::with_capacity(edges.len());
+    for e in edges {
+        match merged.last_mut() {
+            Some(last) if key(last) == key(&e) => last.capacity = last.capacity + e.capacity,
+            _ => merged.push(e),
+        }
+    }
+
+    Instance {
+        num_nodes,
+        edges: merged,
+        source: ids[instance.source],
+        sink: ids[instance.sink],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, lint_with, normalize, Lint, LintOptions};
+    use crate::{Edge, Format, Instance};
+
+    fn with_edges<C>(num_nodes: usize, edges: Vec<Edge<C>>) -> Instance<C> {
+        Instance {
+            num_nodes,
+            edges,
+            source: 0,
+            sink: num_nodes - 1,
+        }
+    }
+
+    #[test]
+    fn clean() {
+        let instance = with_edges(3, vec![Edge::new(0, 1, 5), Edge::new(1, 2, 7)]);
+        assert_eq!(lint(&instance), vec![]);
+        assert_eq!(normalize(&instance), instance);
+    }
+
+    #[test]
+    fn each_lint_fires() {
+        // 0 -> 1 twice, a self loop on 1, capacities 0 and -3, node 3 unused and the sink 4 behind the edge of
+        // capacity 0
+        let data = "5 6\n0 1 5\n1 1 2\n0 1 5\n1 2 -3\n\n1 4 0\n2 1 1\n";
+        let instance = Format::Aoj.read::<i64, _>(data.as_bytes()).unwrap();
+        let lints = lint(&instance);
+        assert_eq!(
+            lints,
+            vec![
+                Lint::SelfLoop { edge: 1 },
+                Lint::DuplicateEdge { edge: 2, first: 0 },
+                Lint::NonPositiveCapacity { edge: 3 },
+                Lint::NonPositiveCapacity { edge: 4 },
+                Lint::UnusedNode { node: 3 },
+                Lint::UnreachableSink,
+            ]
+        );
+        let lines = Format::Aoj.edge_lines(data);
+        assert_eq!(lines, vec![2, 3, 4, 5, 7, 8]);
+        assert_eq!(lines[lints[1].edge().unwrap()], 4);
+        assert_eq!(lints[4].edge(), None);
+        assert_eq!(lints[1].to_string(), "edge 2 duplicates edge 0");
+    }
+
+    #[test]
+    fn gains() {
+        let data = "# generated\n4 4 0 3\n0 1 5 0.5\n1 3 5 1.5\n0 2 1 1\nc\n2 3 1 1\n";
+        let instance = Format::GainRandom.read::<f64, _>(data.as_bytes()).unwrap();
+        assert_eq!(
+            lint(&instance),
+            vec![Lint::GainOutOfRange { edge: 1, gain: 1.5 }]
+        );
+        let options = LintOptions {
+            gains_above_one: true,
+        };
+        assert_eq!(lint_with(&instance, &options), vec![]);
+        assert_eq!(Format::GainRandom.edge_lines(data), vec![3, 4, 5, 7]);
+
+        // same ends but another gain is a parallel edge, not a duplicate
+        let mut instance = instance;
+        instance.edges.push(Edge::new(0, 1, 2.0).with_gain(0.7));
+        instance.edges.push(Edge::new(0, 1, 2.0).with_gain(0.5));
+        instance.edges.push(Edge::new(2, 3, 1.0).with_gain(-1.0));
+        assert_eq!(
+            lint_with(&instance, &options),
+            vec![
+                Lint::DuplicateEdge { edge: 5, first: 0 },
+                Lint::GainOutOfRange {
+                    edge: 6,
+                    gain: -1.0
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn dimacs_lines() {
+        let data = "c sample\np max 4 3\nn 1 s\nn 4 t\nc arcs\na 1 2 2\n\na 2 4 1\na 2 4 3\n";
+        let instance = Format::Dimacs.read::<i64, _>(data.as_bytes()).unwrap();
+        assert_eq!(
+            lint(&instance),
+            vec![
+                Lint::DuplicateEdge { edge: 2, first: 1 },
+                Lint::UnusedNode { node: 2 }
+            ]
+        );
+        assert_eq!(Format::Dimacs.edge_lines(data), vec![6, 8, 9]);
+    }
+
+    #[test]
+    fn normalized() {
+        let instance = Instance {
+            num_nodes: 7,
+            edges: vec![
+                Edge::new(5, 1, 2),
+                Edge::new(1, 3, 4),
+                Edge::new(5, 3, 1),
+                Edge::new(1, 3, 6),
+            ],
+            source: 1,
+            sink: 3,
+        };
+        let normalized = normalize(&instance);
+        assert_eq!(
+            normalized,
+            Instance {
+                num_nodes: 3,
+                edges: vec![Edge::new(0, 1, 10), Edge::new(2, 0, 2), Edge::new(2, 1, 1)],
+                source: 0,
+                sink: 1,
+            }
+        );
+        assert_eq!(lint(&normalized), vec![]);
+        assert_eq!(normalize(&normalized), normalized);
+
+        // an edge without a gain is one of gain 1
+        let gains = with_edges(
+            3,
+            vec![
+                Edge::new(1, 2, 2.0).with_gain(0.9),
+                Edge::new(0, 1, 3.0),
+                Edge::new(0, 1, 1.0).with_gain(0.5),
+                Edge::new(0, 1, 2.0).with_gain(1.0),
+            ],
+        );
+        let normalized = normalize(&gains);
+        assert_eq!(
+            normalized.edges,
+            vec![
+                Edge::new(0, 1, 1.0).with_gain(0.5),
+                Edge::new(0, 1, 5.0),
+                Edge::new(1, 2, 2.0).with_gain(0.9)
+            ]
+        );
+    }
+}
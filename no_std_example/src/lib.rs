@@ -0,0 +1,98 @@
+// uses the solvers with `alloc` only, e.g.
+// cargo build -p no_std_example --target thumbv7em-none-eabihf
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use maximum_flow::dinic::Dinic;
+use maximum_flow::graph::Flow;
+use maximum_flow::solver::MaxFlowSolver;
+
+pub struct Link {
+    pub from: usize,
+    pub to: usize,
+    // kbit/s
+    pub bandwidth: Flow,
+}
+
+// maximum bandwidth from source to sink, and the bandwidth routed over each link
+pub fn route(links: &[Link], source: usize, sink: usize) -> (Flow, Vec<Flow>) {
+    let mut solver = Dinic::new();
+    let edge_indices: Vec<Option<usize>> = links
+        .iter()
+        .map(|link| solver.add_directed_edge(link.from, link.to, link.bandwidth))
+        .collect();
+    let total = solver.solve(source, sink);
+
+    let routed = edge_indices
+        .iter()
+        .map(|index| index.map_or(0, |i| solver.graph().get_directed_edge(i).flow))
+        .collect();
+    (total, routed)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{route, Link};
+
+    #[test]
+    fn controller_ring() {
+        // gateway 0 and uplink 5 connected through two rings of routers
+        let links = [
+            Link {
+                from: 0,
+                to: 1,
+                bandwidth: 100,
+            },
+            Link {
+                from: 0,
+                to: 2,
+                bandwidth: 100,
+            },
+            Link {
+                from: 1,
+                to: 3,
+                bandwidth: 40,
+            },
+            Link {
+                from: 1,
+                to: 2,
+                bandwidth: 20,
+            },
+            Link {
+                from: 2,
+                to: 4,
+                bandwidth: 50,
+            },
+            Link {
+                from: 3,
+                to: 4,
+                bandwidth: 10,
+            },
+            Link {
+                from: 3,
+                to: 5,
+                bandwidth: 30,
+            },
+            Link {
+                from: 4,
+                to: 5,
+                bandwidth: 70,
+            },
+            Link {
+                from: 5,
+                to: 0,
+                bandwidth: 0,
+            },
+        ];
+        let (total, routed) = route(&links, 0, 5);
+
+        assert_eq!(total, 90);
+        assert_eq!(routed[8], 0);
+        assert_eq!(routed[6] + routed[7], total);
+        for (link, &flow) in links.iter().zip(routed.iter()) {
+            assert!(0 <= flow && flow <= link.bandwidth);
+        }
+    }
+}